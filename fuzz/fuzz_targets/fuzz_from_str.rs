@@ -0,0 +1,15 @@
+//! Reading arbitrary input must return a value or an error, but never panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parenthesis::{from_str, Value};
+
+fuzz_target!(|data: &[u8]| {
+    // Input that is not valid UTF-8 can not be read as a string at all.
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = from_str::<Value>(text);
+    let _ = from_str::<Vec<Value>>(text);
+});
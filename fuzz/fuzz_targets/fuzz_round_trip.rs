@@ -0,0 +1,28 @@
+//! Every value that can be read must be read again after it has been printed.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parenthesis::{from_str, to_string_pretty, Value};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let Ok(values) = from_str::<Vec<Value>>(text) else {
+        return;
+    };
+
+    for value in &values {
+        let printed = value.to_string();
+
+        match from_str::<Value>(&printed) {
+            Ok(parsed) => assert_eq!(&parsed, value, "printed as {:?}", printed),
+            Err(error) => panic!("failed to read {:?} again: {}", printed, error),
+        }
+    }
+
+    let pretty = to_string_pretty(&values, 40);
+    let parsed = from_str::<Vec<Value>>(&pretty).expect("pretty output can be read again");
+    assert_eq!(parsed, values);
+});
@@ -1,25 +1,960 @@
-use syn::{Attribute, LitStr};
+use std::collections::HashSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+    meta::ParseNestedMeta, parse_quote, punctuated::Punctuated, Attribute, DataEnum, Field, Fields,
+    GenericArgument, GenericParam, Generics, Ident, LitInt, LitStr, Member, Path, PathArguments,
+    Token, Type, Variant, WherePredicate,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum FieldKind {
     Positional,
+    PositionalOptional,
     NamedRequired,
     NamedOptional,
     NamedRepeated,
+    Variadic,
+    Flatten,
+    Rest,
+}
+
+/// How to construct the value of a field that is absent from the input.
+#[derive(Clone)]
+pub enum FieldDefault {
+    /// Use the `Default` implementation of the field type.
+    Trait,
+    /// Call the function at the given path.
+    Function(Path),
 }
 
 pub struct FieldData {
     pub kind: FieldKind,
     pub rename: Option<String>,
+    /// Other names that are accepted for a named field when parsing.
+    pub aliases: Vec<String>,
+    pub default: Option<FieldDefault>,
+    pub skip_serializing: bool,
+    pub skip_deserializing: bool,
+    pub skip_default: bool,
+    pub serialize_with: Option<Path>,
+    pub deserialize_with: Option<Path>,
+    pub validate: Option<Path>,
+    /// Whether the value is written as a list that starts with the field name
+    /// in place of the tag of its type.
+    pub tagged: bool,
+    /// Whether the field is filled with the span of the parsed form instead of being parsed.
+    pub span: bool,
+    /// Whether the value is written as a single sequence `[...]`.
+    pub seq: bool,
+    /// Whether the value is written as a map `{...}` with alternating keys and values.
+    pub map: bool,
+    /// The minimum number of occurrences of a repeated field.
+    pub min: Option<usize>,
+    /// The maximum number of occurrences of a repeated field.
+    pub max: Option<usize>,
+    /// Whether a named field whose form has no value is parsed as the default value.
+    pub default_if_empty: bool,
+}
+
+/// Data from the `sexpr` attributes on a struct or enum.
+pub struct ContainerData {
+    pub transparent: bool,
+    pub header: Option<String>,
+    pub validate: Option<Path>,
+    pub delimiter: Option<Delimiter>,
+    pub case_insensitive: bool,
+    pub untagged: bool,
+    pub rename_all: Option<RenameRule>,
+    /// Whether forms of unknown named fields are rejected instead of skipped.
+    pub deny_unknown_fields: bool,
+    /// Bounds that replace the inferred bounds of the `FromParens` implementation.
+    pub from_parens_bound: Option<Vec<WherePredicate>>,
+    /// Bounds that replace the inferred bounds of the `ToParens` implementation.
+    pub to_parens_bound: Option<Vec<WherePredicate>>,
+    /// Type that is parsed and then converted into the container with `TryFrom`.
+    pub from: Option<Type>,
+    /// Type that the container is converted into with `Into` before it is printed.
+    pub into: Option<Type>,
+}
+
+/// Delimiters that enclose the fields of a struct.
+#[derive(Debug, Clone, Copy)]
+pub enum Delimiter {
+    /// A list delimited by `()`.
+    List,
+    /// A sequence delimited by `[]`.
+    Seq,
+    /// A map delimited by `{}`.
+    Map,
+}
+
+impl ContainerData {
+    /// The delimiters that enclose the fields, if any.
+    ///
+    /// Fields are enclosed in a list if there is a header but no other delimiter.
+    pub fn delimiter(&self) -> Option<Delimiter> {
+        match (self.delimiter, &self.header) {
+            (Some(delimiter), _) => Some(delimiter),
+            (None, Some(_)) => Some(Delimiter::List),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Parse the `sexpr` attributes on a struct or enum.
+pub fn parse_container_data(attrs: &[Attribute]) -> syn::Result<ContainerData> {
+    let mut container_data = ContainerData {
+        transparent: false,
+        header: None,
+        validate: None,
+        delimiter: None,
+        case_insensitive: false,
+        untagged: false,
+        rename_all: None,
+        deny_unknown_fields: false,
+        from_parens_bound: None,
+        to_parens_bound: None,
+        from: None,
+        into: None,
+    };
+
+    for attr in attrs {
+        if !attr.path().is_ident("sexpr") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            if path.is_ident("transparent") {
+                container_data.transparent = true;
+                Ok(())
+            } else if path.is_ident("header") || path.is_ident("tag") {
+                container_data.header = Some(parse_symbol(&meta)?);
+                Ok(())
+            } else if path.is_ident("validate") {
+                let value = meta.value()?;
+                container_data.validate = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("case_insensitive") {
+                container_data.case_insensitive = true;
+                Ok(())
+            } else if path.is_ident("untagged") {
+                container_data.untagged = true;
+                Ok(())
+            } else if path.is_ident("deny_unknown_fields") {
+                container_data.deny_unknown_fields = true;
+                Ok(())
+            } else if path.is_ident("rename_all") {
+                let value = meta.value()?;
+                let rule: LitStr = value.parse()?;
+                container_data.rename_all = Some(RenameRule::parse(&rule)?);
+                Ok(())
+            } else if path.is_ident("bound") {
+                // Either `bound = "..."` for both derives or
+                // `bound(from_parens = "...", to_parens = "...")`.
+                if meta.input.peek(Token![=]) {
+                    let bound = parse_bound(&meta)?;
+                    container_data.from_parens_bound = Some(bound.clone());
+                    container_data.to_parens_bound = Some(bound);
+                    return Ok(());
+                }
+
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("from_parens") {
+                        container_data.from_parens_bound = Some(parse_bound(&meta)?);
+                        Ok(())
+                    } else if meta.path.is_ident("to_parens") {
+                        container_data.to_parens_bound = Some(parse_bound(&meta)?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `from_parens` or `to_parens`"))
+                    }
+                })
+            } else if path.is_ident("from") {
+                let value = meta.value()?;
+                container_data.from = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("into") {
+                let value = meta.value()?;
+                container_data.into = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("seq") || path.is_ident("map") {
+                if container_data.delimiter.is_some() {
+                    return Err(meta.error("`seq` and `map` can not be combined."));
+                }
+
+                container_data.delimiter = Some(match path.is_ident("seq") {
+                    true => Delimiter::Seq,
+                    false => Delimiter::Map,
+                });
+                Ok(())
+            } else {
+                Err(meta.error("unrecognized sexpr attribute"))
+            }
+        })?;
+    }
+
+    if container_data.transparent && container_data.header.is_some() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`transparent` can not be combined with `header`.",
+        ));
+    }
+
+    if container_data.transparent && container_data.delimiter.is_some() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`transparent` can not be combined with `seq` or `map`.",
+        ));
+    }
+
+    Ok(container_data)
+}
+
+/// Check that the `sexpr` attributes on a struct only use options that apply to structs.
+pub fn check_struct_container_data(container_data: &ContainerData) -> syn::Result<()> {
+    if container_data.untagged {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`untagged` can only be used on enums.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that the `sexpr` attributes on an enum only use options that apply to enums.
+///
+/// The variants of an enum determine how they are delimited,
+/// so the enum itself can not be transparent or have its own delimiters.
+pub fn check_enum_container_data(container_data: &ContainerData) -> syn::Result<()> {
+    if container_data.transparent
+        || container_data.header.is_some()
+        || container_data.delimiter.is_some()
+    {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`transparent`, `header`, `seq` and `map` can not be used on enums.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that a type converted with `from` or `into` has no options for its own syntax.
+///
+/// The syntax is that of the other type, so these options would be ignored.
+pub fn check_conversion_container_data(
+    container_data: &ContainerData,
+    attribute: &str,
+) -> syn::Result<()> {
+    if container_data.transparent
+        || container_data.header.is_some()
+        || container_data.delimiter.is_some()
+        || container_data.case_insensitive
+        || container_data.untagged
+        || container_data.rename_all.is_some()
+        || container_data.deny_unknown_fields
+    {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "`{}` can not be combined with `transparent`, `header`, `seq`, `map`, \
+                `case_insensitive`, `untagged`, `rename_all` or `deny_unknown_fields`.",
+                attribute
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Data from the `sexpr` attributes on an enum variant.
+pub struct VariantData {
+    pub rename: Option<String>,
+    /// Other names that are accepted for the variant when parsing.
+    pub aliases: Vec<String>,
+    /// Whether the variant holds the head and values of lists with an unknown head.
+    pub other: bool,
+}
+
+/// Parse the `sexpr` attributes on an enum variant.
+pub fn parse_variant_data(attrs: &[Attribute]) -> syn::Result<VariantData> {
+    let mut variant_data = VariantData {
+        rename: None,
+        aliases: Vec::new(),
+        other: false,
+    };
+
+    for attr in attrs {
+        if !attr.path().is_ident("sexpr") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                variant_data.rename = Some(parse_symbol(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("alias") {
+                variant_data.aliases.push(parse_symbol(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("other") {
+                variant_data.other = true;
+                Ok(())
+            } else {
+                Err(meta.error("unrecognized sexpr attribute"))
+            }
+        })?;
+    }
+
+    Ok(variant_data)
+}
+
+/// Find the variant marked with `#[sexpr(other)]`, if any, and check that it
+/// is the only one and that it is a tuple variant with a head and the values.
+pub fn find_other_variant<'a>(
+    data_enum: &'a DataEnum,
+    container_data: &ContainerData,
+) -> syn::Result<Option<&'a Variant>> {
+    let mut other = None;
+
+    for variant in &data_enum.variants {
+        let variant_data = parse_variant_data(&variant.attrs)?;
+
+        if !variant_data.other {
+            continue;
+        }
+
+        if other.is_some() {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "Only one variant can be annotated with `other`.",
+            ));
+        }
+
+        if container_data.untagged {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "`other` can not be used in untagged enums.",
+            ));
+        }
+
+        if variant_data.rename.is_some() || !variant_data.aliases.is_empty() {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "`other` can not be combined with `rename` or `alias`.",
+            ));
+        }
+
+        match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 2 => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "A variant annotated with `other` must have two fields for the head and the values.",
+                ))
+            }
+        }
+
+        other = Some(variant);
+    }
+
+    Ok(other)
+}
+
+/// The name of an enum variant.
+///
+/// Unless the variant is renamed, the name is derived from the identifier
+/// using the container's `rename_all` rule, which defaults to kebab case.
+pub fn variant_name(
+    variant: &Variant,
+    variant_data: &VariantData,
+    container_data: &ContainerData,
+) -> String {
+    match &variant_data.rename {
+        Some(rename) => rename.clone(),
+        None => container_data
+            .rename_all
+            .unwrap_or(RenameRule::Kebab)
+            .apply(&variant.ident.to_string()),
+    }
+}
+
+/// The name of a field as it appears in the keyword of a named field.
+///
+/// Unless the field is renamed, the name is derived from the identifier
+/// using the container's `rename_all` rule, if any.
+pub fn field_name(
+    member: &Member,
+    field_data: &FieldData,
+    container_data: &ContainerData,
+) -> String {
+    match (&field_data.rename, member, container_data.rename_all) {
+        (Some(rename), _, _) => rename.clone(),
+        (None, Member::Named(ident), Some(rule)) => rule.apply(&ident.to_string()),
+        (None, member, _) => format!("{}", member.to_token_stream()),
+    }
+}
+
+/// Rules to derive names from Rust identifiers, selected by `rename_all`.
+#[derive(Debug, Clone, Copy)]
+pub enum RenameRule {
+    /// `kebab-case`
+    Kebab,
+    /// `snake_case`
+    Snake,
+    /// `camelCase`
+    Camel,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `lowercase`
+    Lower,
+}
+
+impl RenameRule {
+    /// Parse a rule from its name, which is written in the style of the rule.
+    fn parse(rule: &LitStr) -> syn::Result<Self> {
+        match rule.value().as_str() {
+            "kebab-case" => Ok(Self::Kebab),
+            "snake_case" => Ok(Self::Snake),
+            "camelCase" => Ok(Self::Camel),
+            "SCREAMING_SNAKE_CASE" => Ok(Self::ScreamingSnake),
+            "lowercase" => Ok(Self::Lower),
+            _ => Err(syn::Error::new_spanned(
+                rule,
+                "Unknown rule, expected one of `kebab-case`, `snake_case`, `camelCase`, \
+                `SCREAMING_SNAKE_CASE` or `lowercase`.",
+            )),
+        }
+    }
+
+    /// Apply the rule to an identifier in either `snake_case` or `UpperCamelCase`.
+    pub fn apply(self, name: &str) -> String {
+        let words = split_words(name);
+
+        match self {
+            Self::Kebab => words.join("-"),
+            Self::Snake => words.join("_"),
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| match index {
+                    0 => word.clone(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+            Self::Lower => name.to_lowercase(),
+        }
+    }
+}
+
+/// Split an identifier in `snake_case` or `UpperCamelCase` into lowercase words.
+///
+/// A run of capitals is treated as one word, so that `IOPort` becomes `io` and `port`.
+fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (index, c) in chars.iter().enumerate() {
+        if *c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+
+            continue;
+        }
+
+        if c.is_uppercase() && index > 0 && !word.is_empty() {
+            let prev = chars[index - 1];
+            let next = chars.get(index + 1);
+
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Convert the first character of a word to uppercase.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Parse the string value of an attribute that is used as a symbol, such as a
+/// field name or header.
+///
+/// The symbol must be one that can be written without `|` delimiters, since
+/// the derived code matches and prints it verbatim.
+fn parse_symbol(meta: &ParseNestedMeta) -> syn::Result<String> {
+    let lit: LitStr = meta.value()?.parse()?;
+    let symbol = lit.value();
+
+    if !is_bare_symbol(&symbol) {
+        return Err(syn::Error::new_spanned(
+            lit,
+            format!("`{}` is not a valid symbol.", symbol),
+        ));
+    }
+
+    Ok(symbol)
+}
+
+/// Check whether a string is a symbol that can be written without `|` delimiters.
+///
+/// This follows the rules of the lexer in the `parenthesis` crate: symbols
+/// consist of ASCII letters, digits and the characters `!$%&*/:<=>?^_~+-.@`,
+/// may not start with a digit and may not start with a sign followed by a digit.
+pub fn is_bare_symbol(symbol: &str) -> bool {
+    fn is_initial(c: char) -> bool {
+        c.is_ascii_alphabetic() || "!$%&*/:<=>?^_~.@".contains(c)
+    }
+
+    fn is_subsequent(c: char) -> bool {
+        is_initial(c) || c.is_ascii_digit() || matches!(c, '+' | '-')
+    }
+
+    let mut chars = symbol.chars();
+
+    match chars.next() {
+        Some('+' | '-') => match chars.next() {
+            None => return true,
+            Some(c) if is_initial(c) => {}
+            Some(_) => return false,
+        },
+        Some(c) if is_initial(c) => {}
+        _ => return false,
+    }
+
+    chars.all(is_subsequent)
+}
+
+/// Format a number as an English ordinal such as `1st`, `2nd` or `11th`.
+pub fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", n, suffix)
+}
+
+/// Parse the where predicates of a `bound = "..."` attribute.
+fn parse_bound(meta: &ParseNestedMeta) -> syn::Result<Vec<WherePredicate>> {
+    let bound: LitStr = meta.value()?.parse()?;
+    let predicates = bound.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
+
+/// The bounds of a derived implementation.
+///
+/// Unless the bounds are given explicitly by `custom`, `bound` is required for
+/// every type parameter that occurs in the `bounded` field types and `Default` is
+/// required for every type parameter in the `defaulted` field types.
+pub fn derive_bounds<'a>(
+    generics: &Generics,
+    custom: Option<&[WherePredicate]>,
+    bound: TokenStream,
+    bounded: impl IntoIterator<Item = &'a Type>,
+    defaulted: impl IntoIterator<Item = &'a Type>,
+) -> Vec<WherePredicate> {
+    if let Some(custom) = custom {
+        return custom.to_vec();
+    }
+
+    let bounded = used_type_params(generics, bounded);
+    let defaulted = used_type_params(generics, defaulted);
+
+    let bounded = bounded.iter().map(|ident| parse_quote!(#ident: #bound));
+    let defaulted = defaulted
+        .iter()
+        .map(|ident| parse_quote!(#ident: ::std::default::Default));
+
+    bounded.chain(defaulted).collect()
+}
+
+/// The type parameters of `generics` that occur in the given types, in the order
+/// of their declaration. Like serde, we ignore type parameters within `PhantomData`.
+fn used_type_params<'a>(
+    generics: &Generics,
+    types: impl IntoIterator<Item = &'a Type>,
+) -> Vec<Ident> {
+    let params: HashSet<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+
+    let mut used = HashSet::new();
+
+    for ty in types {
+        collect_type_params(ty, &params, &mut used);
+    }
+
+    generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .filter(|ident| used.contains(ident))
+        .collect()
+}
+
+fn collect_type_params(ty: &Type, params: &HashSet<Ident>, used: &mut HashSet<Ident>) {
+    // Any other occurrence of a type parameter, such as in a trait object or
+    // a macro, is found by looking at the tokens.
+    fn collect_tokens(tokens: TokenStream, params: &HashSet<Ident>, used: &mut HashSet<Ident>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) if params.contains(&ident) => {
+                    used.insert(ident);
+                }
+                TokenTree::Group(group) => collect_tokens(group.stream(), params, used),
+                _ => {}
+            }
+        }
+    }
+
+    match ty {
+        Type::Path(type_path) => {
+            if let Some(qself) = &type_path.qself {
+                collect_type_params(&qself.ty, params, used);
+            }
+
+            let segments = &type_path.path.segments;
+
+            if segments
+                .last()
+                .is_some_and(|segment| segment.ident == "PhantomData")
+            {
+                return;
+            }
+
+            if let Some(first) = segments.first() {
+                if type_path.qself.is_none() && params.contains(&first.ident) {
+                    used.insert(first.ident.clone());
+                }
+            }
+
+            for segment in segments {
+                match &segment.arguments {
+                    PathArguments::None => {}
+                    PathArguments::AngleBracketed(arguments) => {
+                        for argument in &arguments.args {
+                            match argument {
+                                GenericArgument::Type(ty) => collect_type_params(ty, params, used),
+                                argument => {
+                                    collect_tokens(argument.to_token_stream(), params, used)
+                                }
+                            }
+                        }
+                    }
+                    arguments => collect_tokens(arguments.to_token_stream(), params, used),
+                }
+            }
+        }
+        Type::Reference(reference) => collect_type_params(&reference.elem, params, used),
+        Type::Slice(slice) => collect_type_params(&slice.elem, params, used),
+        Type::Array(array) => collect_type_params(&array.elem, params, used),
+        Type::Ptr(ptr) => collect_type_params(&ptr.elem, params, used),
+        Type::Paren(paren) => collect_type_params(&paren.elem, params, used),
+        Type::Group(group) => collect_type_params(&group.elem, params, used),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_type_params(elem, params, used);
+            }
+        }
+        ty => collect_tokens(ty.to_token_stream(), params, used),
+    }
+}
+
+/// The field types that determine the bounds of a derived implementation,
+/// for parsing if `parsing` is set and for printing otherwise.
+///
+/// Returns the types of the fields that are parsed or printed by their trait
+/// implementations, and the types of skipped fields that are parsed as their default.
+pub fn bound_field_types<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    parsing: bool,
+) -> syn::Result<(Vec<&'a Type>, Vec<&'a Type>)> {
+    let mut bounded = Vec::new();
+    let mut defaulted = Vec::new();
+
+    for field in fields {
+        let field_data = parse_field_data(field)?;
+
+        let (skip, with) = match parsing {
+            true => (field_data.skip_deserializing, &field_data.deserialize_with),
+            false => (field_data.skip_serializing, &field_data.serialize_with),
+        };
+
+        if skip {
+            if parsing
+                && !field_data.span
+                && matches!(field_data.default, None | Some(FieldDefault::Trait))
+            {
+                defaulted.push(&field.ty);
+            }
+        } else if with.is_none() {
+            bounded.push(&field.ty);
+        }
+
+        if parsing && field_data.default_if_empty {
+            defaulted.push(&field.ty);
+        }
+    }
+
+    Ok((bounded, defaulted))
+}
+
+/// Check that at most one field is annotated with `#[sexpr(span)]`.
+pub fn check_span_fields(fields: &Fields) -> syn::Result<()> {
+    let mut seen_span = false;
+
+    for field in fields {
+        if !parse_field_data(field)?.span {
+            continue;
+        }
+
+        if seen_span {
+            return Err(syn::Error::new_spanned(
+                field,
+                "Only one field can be annotated with `span`.",
+            ));
+        }
+
+        seen_span = true;
+    }
+
+    Ok(())
+}
+
+/// Add the bounds and a type argument for the stream.
+pub fn add_stream_generics(
+    generics: &Generics,
+    bounds: Vec<WherePredicate>,
+    stream_param: GenericParam,
+) -> Generics {
+    let mut modified_generics = generics.clone();
+    let where_clause = modified_generics.make_where_clause();
+    where_clause.predicates.extend(bounds);
+    modified_generics.params.push(stream_param);
+    modified_generics
+}
+
+/// The member that accesses a field, which is an index for tuple structs.
+///
+/// Fields of tuple structs have no name and therefore can not be named fields.
+pub fn field_member(field: &Field, index: usize, field_data: &FieldData) -> syn::Result<Member> {
+    match &field.ident {
+        Some(ident) => Ok(Member::Named(ident.clone())),
+        None => match field_data.kind {
+            FieldKind::NamedRequired | FieldKind::NamedOptional | FieldKind::NamedRepeated
+                if field_data.rename.is_none() =>
+            {
+                Err(syn::Error::new_spanned(
+                    field,
+                    "Named fields of tuple structs require a `rename`.",
+                ))
+            }
+            _ => Ok(Member::Unnamed(index.into())),
+        },
+    }
+}
+
+/// Parse the data for a field from its type and `sexpr` attributes.
+pub fn parse_field_data(field: &Field) -> syn::Result<FieldData> {
+    let mut field_data = parse_sexpr_attributes(&field.attrs)?;
+
+    // A `Rest` field takes all remaining values, so it can not be parsed in any other way.
+    if is_rest_type(&field.ty) {
+        if !matches!(field_data.kind, FieldKind::Positional)
+            || field_data.seq
+            || field_data.map
+            || field_data.tagged
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`Rest` fields can not be `required`, `default`, `optional`, `positional_optional`, `repeated`, `variadic`, `flatten`, `seq`, `map` or `tagged`.",
+            ));
+        }
+
+        field_data.kind = FieldKind::Rest;
+    }
+
+    Ok(field_data)
+}
+
+impl FieldData {
+    /// The function used to parse a value of the field.
+    pub fn parse_fn(&self) -> TokenStream {
+        match (&self.deserialize_with, self.tagged) {
+            (Some(function), _) => quote! { #function },
+            (None, true) => quote! {
+                <_ as ::parenthesis::from_parens::FromParensTagged<__I>>::from_parens_untagged
+            },
+            (None, false) if self.seq => quote! { ::parenthesis::from_parens::seq },
+            (None, false) if self.map => quote! { ::parenthesis::from_parens::map },
+            (None, false) => {
+                quote! { <_ as ::parenthesis::from_parens::FromParens<__I>>::from_parens }
+            }
+        }
+    }
+
+    /// The code that validates the parsed value of the field, if a validation function is set.
+    pub fn code_validate(&self, value: TokenStream, span: TokenStream) -> TokenStream {
+        code_validate(self.validate.as_ref(), value, span)
+    }
+
+    /// The message for a repeated field that occurs too few or too many times,
+    /// or `None` if the number of occurrences is not bounded.
+    pub fn count_message(&self, field_name: &str) -> Option<String> {
+        let (bounds, count) = match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => (format!("exactly {}", min), min),
+            (Some(min), Some(max)) => (format!("between {} and {}", min, max), max),
+            (Some(min), None) => (format!("at least {}", min), min),
+            (None, Some(max)) => (format!("at most {}", max), max),
+            (None, None) => return None,
+        };
+
+        let noun = match count {
+            1 => "occurrence",
+            _ => "occurrences",
+        };
+
+        Some(format!(
+            "expected {} {} of field `{}`",
+            bounds, noun, field_name
+        ))
+    }
+
+    /// The function used to print a value of the field.
+    pub fn print_fn(&self) -> TokenStream {
+        match (&self.serialize_with, self.tagged) {
+            (Some(function), _) => quote! { #function },
+            (None, true) => quote! {
+                <_ as ::parenthesis::to_parens::ToParensTagged<__O>>::to_parens_untagged
+            },
+            (None, false) if self.seq => quote! { ::parenthesis::to_parens::seq },
+            (None, false) if self.map => quote! { ::parenthesis::to_parens::map },
+            (None, false) => quote! { <_ as ::parenthesis::to_parens::ToParens<__O>>::to_parens },
+        }
+    }
+}
+
+/// The code that calls a validation function on a parsed value and
+/// returns its error converted into a `ParseError` at the given span.
+pub fn code_validate(
+    validate: Option<&Path>,
+    value: TokenStream,
+    span: TokenStream,
+) -> TokenStream {
+    match validate {
+        Some(function) => quote! {
+            if let Err(error) = #function(#value) {
+                return Err(::parenthesis::from_parens::ValidationError::into_parse_error(error, #span));
+            }
+        },
+        None => quote! {},
+    }
+}
+
+/// Check whether a type refers to `parenthesis::Rest`.
+///
+/// Since procedural macros can not resolve types, we match on the name of
+/// the last path segment instead.
+fn is_rest_type(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+
+    type_path.qself.is_none()
+        && type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Rest" && segment.arguments.is_empty())
+}
+
+/// The type of the values of a named field if it is `parenthesis::span::Spanned`,
+/// either directly or wrapped in the `Option` or `Vec` of an optional or repeated field.
+///
+/// As for [`is_rest_type`], we match on the name of the last path segment.
+pub fn spanned_value_type(ty: &Type, kind: FieldKind) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+
+    let segment = type_path.path.segments.last()?;
+
+    match kind {
+        FieldKind::NamedRequired if segment.ident == "Spanned" => Some(ty),
+        FieldKind::NamedOptional | FieldKind::NamedRepeated => {
+            let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+                return None;
+            };
+
+            match arguments.args.first()? {
+                GenericArgument::Type(ty) => spanned_value_type(ty, FieldKind::NamedRequired),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
 /// Parse the `sexpr` attributes on a field.
-pub fn parse_sexpr_attributes(attrs: &[Attribute]) -> syn::Result<FieldData> {
+fn parse_sexpr_attributes(attrs: &[Attribute]) -> syn::Result<FieldData> {
     let mut field_data = FieldData {
         kind: FieldKind::Positional,
         rename: None,
+        aliases: Vec::new(),
+        default: None,
+        skip_serializing: false,
+        skip_deserializing: false,
+        skip_default: false,
+        serialize_with: None,
+        deserialize_with: None,
+        validate: None,
+        tagged: false,
+        span: false,
+        seq: false,
+        map: false,
+        min: None,
+        max: None,
+        default_if_empty: false,
     };
 
+    // Path of the `default` attribute, used to report conflicting attributes.
+    let mut default_path = None;
+    let mut skip_default_path = None;
+    let mut tagged_path = None;
+    let mut alias_path = None;
+    let mut span_path = None;
+    let mut seq_path = None;
+    let mut map_path = None;
+    let mut count_path = None;
+    let mut default_if_empty_path = None;
+
     for attr in attrs {
         if !attr.path().is_ident("sexpr") {
             continue;
@@ -33,13 +968,101 @@ pub fn parse_sexpr_attributes(attrs: &[Attribute]) -> syn::Result<FieldData> {
             } else if path.is_ident("optional") {
                 field_data.kind = FieldKind::NamedOptional;
                 Ok(())
+            } else if path.is_ident("positional_optional") {
+                field_data.kind = FieldKind::PositionalOptional;
+                Ok(())
             } else if path.is_ident("repeated") {
                 field_data.kind = FieldKind::NamedRepeated;
                 Ok(())
+            } else if path.is_ident("variadic") {
+                field_data.kind = FieldKind::Variadic;
+                Ok(())
             } else if path.is_ident("rename") {
+                field_data.rename = Some(parse_symbol(&meta)?);
+                Ok(())
+            } else if path.is_ident("alias") {
+                alias_path = Some(path.clone());
+                field_data.aliases.push(parse_symbol(&meta)?);
+                Ok(())
+            } else if path.is_ident("default") {
+                default_path = Some(path.clone());
+
+                if meta.input.peek(syn::Token![=]) {
+                    let value = meta.value()?;
+                    let function: LitStr = value.parse()?;
+                    field_data.default = Some(FieldDefault::Function(function.parse()?));
+                } else {
+                    field_data.default = Some(FieldDefault::Trait);
+                }
+
+                Ok(())
+            } else if path.is_ident("flatten") {
+                field_data.kind = FieldKind::Flatten;
+                Ok(())
+            } else if path.is_ident("with") {
+                let value = meta.value()?;
+                let module: Path = value.parse::<LitStr>()?.parse()?;
+                field_data.serialize_with = Some(parse_quote!(#module::to_parens));
+                field_data.deserialize_with = Some(parse_quote!(#module::from_parens));
+                Ok(())
+            } else if path.is_ident("serialize_with") {
+                let value = meta.value()?;
+                field_data.serialize_with = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("deserialize_with") {
+                let value = meta.value()?;
+                field_data.deserialize_with = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("validate") {
+                let value = meta.value()?;
+                field_data.validate = Some(value.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else if path.is_ident("skip") {
+                field_data.skip_serializing = true;
+                field_data.skip_deserializing = true;
+                Ok(())
+            } else if path.is_ident("skip_serializing") || path.is_ident("skip_printing") {
+                field_data.skip_serializing = true;
+                Ok(())
+            } else if path.is_ident("skip_deserializing") || path.is_ident("skip_parsing") {
+                field_data.skip_deserializing = true;
+                Ok(())
+            } else if path.is_ident("skip_default") {
+                skip_default_path = Some(path.clone());
+                field_data.skip_default = true;
+                Ok(())
+            } else if path.is_ident("span") {
+                span_path = Some(path.clone());
+                field_data.span = true;
+                field_data.skip_serializing = true;
+                field_data.skip_deserializing = true;
+                Ok(())
+            } else if path.is_ident("seq") {
+                seq_path = Some(path.clone());
+                field_data.seq = true;
+                Ok(())
+            } else if path.is_ident("map") {
+                map_path = Some(path.clone());
+                field_data.map = true;
+                Ok(())
+            } else if path.is_ident("min") || path.is_ident("max") {
+                count_path = Some(path.clone());
                 let value = meta.value()?;
-                let name: LitStr = value.parse()?;
-                field_data.rename = Some(name.value());
+                let count = Some(value.parse::<LitInt>()?.base10_parse()?);
+
+                match path.is_ident("min") {
+                    true => field_data.min = count,
+                    false => field_data.max = count,
+                }
+
+                Ok(())
+            } else if path.is_ident("default_if_empty") {
+                default_if_empty_path = Some(path.clone());
+                field_data.default_if_empty = true;
+                Ok(())
+            } else if path.is_ident("tagged") {
+                tagged_path = Some(path.clone());
+                field_data.tagged = true;
                 Ok(())
             } else {
                 Err(meta.error("unrecognized sexpr attribute"))
@@ -47,5 +1070,155 @@ pub fn parse_sexpr_attributes(attrs: &[Attribute]) -> syn::Result<FieldData> {
         })?;
     }
 
+    if let (Some(skip_default_path), None) = (skip_default_path, &default_path) {
+        return Err(syn::Error::new_spanned(
+            skip_default_path,
+            "`skip_default` requires `default`.",
+        ));
+    }
+
+    // A field with a default value is a named field that may be absent. This includes
+    // repeated fields in a sequence, which are written as a single form.
+    if let Some(default_path) = default_path {
+        match field_data.kind {
+            FieldKind::Positional => field_data.kind = FieldKind::NamedRequired,
+            FieldKind::NamedRequired => {}
+            FieldKind::NamedRepeated if seq_path.is_some() => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    default_path,
+                    "`default` can not be combined with `optional`, `positional_optional`, `repeated` without `seq`, `variadic` or `flatten`.",
+                ))
+            }
+        }
+    }
+
+    if let Some(alias_path) = alias_path {
+        if !matches!(
+            field_data.kind,
+            FieldKind::NamedRequired | FieldKind::NamedOptional | FieldKind::NamedRepeated
+        ) {
+            return Err(syn::Error::new_spanned(
+                alias_path,
+                "`alias` requires `required`, `optional`, `repeated` or `default`.",
+            ));
+        }
+    }
+
+    if let Some(default_if_empty_path) = default_if_empty_path {
+        if !matches!(
+            field_data.kind,
+            FieldKind::NamedRequired | FieldKind::NamedOptional
+        ) {
+            return Err(syn::Error::new_spanned(
+                default_if_empty_path,
+                "`default_if_empty` requires `required`, `optional` or `default`.",
+            ));
+        }
+    }
+
+    if let Some(span_path) = span_path {
+        if !matches!(field_data.kind, FieldKind::Positional)
+            || field_data.default.is_some()
+            || field_data.serialize_with.is_some()
+            || field_data.deserialize_with.is_some()
+            || field_data.validate.is_some()
+        {
+            return Err(syn::Error::new_spanned(
+                span_path,
+                "`span` can not be combined with other field attributes.",
+            ));
+        }
+    }
+
+    if let Some(seq_path) = &seq_path {
+        if matches!(
+            field_data.kind,
+            FieldKind::Variadic | FieldKind::Flatten | FieldKind::Rest
+        ) {
+            return Err(syn::Error::new_spanned(
+                seq_path,
+                "`seq` can not be combined with `variadic` or `flatten`.",
+            ));
+        }
+
+        if field_data.serialize_with.is_some()
+            || field_data.deserialize_with.is_some()
+            || tagged_path.is_some()
+        {
+            return Err(syn::Error::new_spanned(
+                seq_path,
+                "`seq` can not be combined with `tagged`, `with`, `serialize_with` or `deserialize_with`.",
+            ));
+        }
+
+        // A repeated field in a sequence is written as a single form with all values,
+        // which can be absent when there are none. An explicit default is kept.
+        if let FieldKind::NamedRepeated = field_data.kind {
+            field_data.kind = FieldKind::NamedRequired;
+            field_data.default.get_or_insert(FieldDefault::Trait);
+        }
+    }
+
+    if let Some(count_path) = count_path {
+        if !matches!(field_data.kind, FieldKind::NamedRepeated) {
+            return Err(syn::Error::new_spanned(
+                count_path,
+                "`min` and `max` require `repeated` and can not be combined with `seq`.",
+            ));
+        }
+
+        if let (Some(min), Some(max)) = (field_data.min, field_data.max) {
+            if min > max {
+                return Err(syn::Error::new_spanned(
+                    count_path,
+                    "`min` can not be greater than `max`.",
+                ));
+            }
+        }
+    }
+
+    if let Some(map_path) = &map_path {
+        if !matches!(
+            field_data.kind,
+            FieldKind::Positional | FieldKind::NamedRequired | FieldKind::NamedOptional
+        ) {
+            return Err(syn::Error::new_spanned(
+                map_path,
+                "`map` can not be combined with `repeated`, `variadic` or `flatten`.",
+            ));
+        }
+
+        if field_data.serialize_with.is_some()
+            || field_data.deserialize_with.is_some()
+            || tagged_path.is_some()
+            || seq_path.is_some()
+        {
+            return Err(syn::Error::new_spanned(
+                map_path,
+                "`map` can not be combined with `seq`, `tagged`, `with`, `serialize_with` or `deserialize_with`.",
+            ));
+        }
+    }
+
+    if let Some(tagged_path) = tagged_path {
+        if !matches!(
+            field_data.kind,
+            FieldKind::NamedRequired | FieldKind::NamedOptional | FieldKind::NamedRepeated
+        ) {
+            return Err(syn::Error::new_spanned(
+                tagged_path,
+                "`tagged` requires `required`, `optional`, `repeated` or `default`.",
+            ));
+        }
+
+        if field_data.serialize_with.is_some() || field_data.deserialize_with.is_some() {
+            return Err(syn::Error::new_spanned(
+                tagged_path,
+                "`tagged` can not be combined with `with`, `serialize_with` or `deserialize_with`.",
+            ));
+        }
+    }
+
     Ok(field_data)
 }
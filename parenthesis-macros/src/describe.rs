@@ -0,0 +1,291 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, DataEnum, DataStruct, DeriveInput, Field, Fields, Generics, Type};
+
+use crate::common::{
+    bound_field_types, check_conversion_container_data, check_enum_container_data,
+    check_struct_container_data, derive_bounds, field_member, field_name, find_other_variant,
+    parse_container_data, parse_field_data, parse_variant_data, variant_name, ContainerData,
+    Delimiter, FieldKind, RenameRule,
+};
+
+pub fn derive_describe_impl(derive_input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+
+    let (code_syntax, generics) = match (&container_data.from, &derive_input.data) {
+        (Some(from), _) => {
+            check_conversion_container_data(&container_data, "from")?;
+            derive_describe_from(&derive_input, from)
+        }
+        (None, syn::Data::Struct(data_struct)) => {
+            derive_describe_struct(&derive_input, &container_data, data_struct)?
+        }
+        (None, syn::Data::Enum(data_enum)) => {
+            derive_describe_enum(&derive_input, &container_data, data_enum)?
+        }
+        (None, syn::Data::Union(_)) => {
+            return Err(syn::Error::new(
+                derive_input.span(),
+                "Can not derive Describe for unions.",
+            ))
+        }
+    };
+
+    // Other types refer to the derived type by its name, so that the syntax of
+    // recursive types is finite.
+    let reference = RenameRule::Kebab.apply(&ident.to_string());
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::parenthesis::describe::Describe for #ident #ty_generics
+        #where_clause {
+            fn syntax() -> ::parenthesis::describe::Syntax {
+                #code_syntax
+            }
+
+            fn reference() -> ::parenthesis::describe::Syntax {
+                ::parenthesis::describe::Syntax::placeholder(#reference)
+            }
+        }
+    })
+}
+
+/// Describe a type that is parsed as another type and then converted.
+fn derive_describe_from(derive_input: &DeriveInput, from: &Type) -> (TokenStream, Generics) {
+    let mut generics = derive_input.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#from: ::parenthesis::describe::Describe));
+
+    let code_syntax = quote! {
+        <#from as ::parenthesis::describe::Describe>::syntax()
+    };
+
+    (code_syntax, generics)
+}
+
+fn derive_describe_struct(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    data_struct: &DataStruct,
+) -> syn::Result<(TokenStream, Generics)> {
+    check_struct_container_data(container_data)?;
+
+    let generics = add_describe_bounds(derive_input, &data_struct.fields)?;
+
+    if container_data.transparent {
+        let mut fields = data_struct.fields.iter();
+        let (Some(field), None) = (fields.next(), fields.next()) else {
+            return Err(syn::Error::new_spanned(
+                &data_struct.fields,
+                "Transparent structs must have exactly one field.",
+            ));
+        };
+
+        let field_ty = &field.ty;
+        let code_syntax = quote! {
+            <#field_ty as ::parenthesis::describe::Describe>::syntax()
+        };
+
+        return Ok((code_syntax, generics));
+    }
+
+    let code_header = container_data.header.as_ref().map(|header| {
+        quote! { ::parenthesis::describe::Syntax::keyword(#header), }
+    });
+    let code_fields = describe_fields(&data_struct.fields, container_data)?;
+
+    let variant = match container_data.delimiter() {
+        Some(Delimiter::List) => quote!(List),
+        Some(Delimiter::Seq) => quote!(Seq),
+        Some(Delimiter::Map) => quote!(Map),
+        None => quote!(Sequence),
+    };
+
+    let code_syntax = quote! {
+        ::parenthesis::describe::Syntax::#variant(vec![#code_header #(#code_fields,)*])
+    };
+
+    Ok((code_syntax, generics))
+}
+
+/// Describe an enum as the alternatives of its variants.
+fn derive_describe_enum(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    data_enum: &DataEnum,
+) -> syn::Result<(TokenStream, Generics)> {
+    check_enum_container_data(container_data)?;
+    find_other_variant(data_enum, container_data)?;
+
+    let mut code_variants = Vec::new();
+
+    for variant in &data_enum.variants {
+        let variant_data = parse_variant_data(&variant.attrs)?;
+        let variant_name = variant_name(variant, &variant_data, container_data);
+
+        // The `other` variant accepts any list that starts with a symbol.
+        if variant_data.other {
+            code_variants.push(quote! {
+                ::parenthesis::describe::Syntax::List(vec![
+                    ::parenthesis::describe::Syntax::placeholder("symbol"),
+                    ::parenthesis::describe::Syntax::placeholder("value").repeated(),
+                ])
+            });
+
+            continue;
+        }
+
+        let code_fields = describe_fields(&variant.fields, container_data)?;
+
+        code_variants.push(match (container_data.untagged, &variant.fields) {
+            (_, Fields::Unit) => quote! {
+                ::parenthesis::describe::Syntax::keyword(#variant_name)
+            },
+            (true, _) => quote! {
+                ::parenthesis::describe::Syntax::Sequence(vec![#(#code_fields,)*])
+            },
+            (false, _) => quote! {
+                ::parenthesis::describe::Syntax::List(vec![
+                    ::parenthesis::describe::Syntax::keyword(#variant_name),
+                    #(#code_fields,)*
+                ])
+            },
+        });
+    }
+
+    let generics = add_describe_bounds(
+        derive_input,
+        data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields),
+    )?;
+
+    let code_syntax = quote! {
+        ::parenthesis::describe::Syntax::Alternatives(vec![#(#code_variants,)*])
+    };
+
+    Ok((code_syntax, generics))
+}
+
+/// Add the bounds for describing the given fields.
+fn add_describe_bounds<'a>(
+    derive_input: &DeriveInput,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> syn::Result<Generics> {
+    let (bounded, _) = bound_field_types(fields, true)?;
+    let bounds = derive_bounds(
+        &derive_input.generics,
+        None,
+        quote!(::parenthesis::describe::Describe),
+        bounded,
+        [],
+    );
+
+    let mut generics = derive_input.generics.clone();
+    generics.make_where_clause().predicates.extend(bounds);
+    Ok(generics)
+}
+
+/// Generate the code that describes each parsed field of a struct or enum variant.
+fn describe_fields(
+    fields: &Fields,
+    container_data: &ContainerData,
+) -> syn::Result<Vec<TokenStream>> {
+    let mut code_fields = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let field_data = parse_field_data(field)?;
+        let field_ident = field_member(field, index, &field_data)?;
+
+        if field_data.skip_deserializing || field_data.span {
+            continue;
+        }
+
+        let field_ty = &field.ty;
+        let field_name = field_name(&field_ident, &field_data, container_data);
+
+        // Tagged fields take the full syntax of their type, which starts with the tag
+        // that is replaced by the field name. Optional and repeated fields are described
+        // by the syntax of their elements.
+        let code_value = |element: bool| {
+            if field_data.deserialize_with.is_some() {
+                return quote! { ::parenthesis::describe::Syntax::placeholder("value") };
+            }
+
+            let method = match field_data.tagged {
+                true => quote!(syntax),
+                false => quote!(reference),
+            };
+            let mut code_value =
+                quote! { <#field_ty as ::parenthesis::describe::Describe>::#method() };
+
+            if element {
+                code_value = quote! { ::parenthesis::describe::element(#code_value) };
+            }
+
+            match (field_data.seq, field_data.map) {
+                (true, _) => quote! { ::parenthesis::describe::seq(#code_value) },
+                (_, true) => quote! { ::parenthesis::describe::map(#code_value) },
+                _ => code_value,
+            }
+        };
+
+        let code_named = |element: bool| {
+            let mut code_value = code_value(element);
+
+            if field_data.default_if_empty {
+                code_value = quote! { #code_value.optional() };
+            }
+
+            match field_data.tagged {
+                true => quote! { ::parenthesis::describe::tagged(#code_value, #field_name) },
+                false => quote! {
+                    ::parenthesis::describe::Syntax::List(vec![
+                        ::parenthesis::describe::Syntax::keyword(#field_name),
+                        #code_value,
+                    ])
+                },
+            }
+        };
+
+        code_fields.push(match field_data.kind {
+            FieldKind::Positional => code_value(false),
+            FieldKind::PositionalOptional => {
+                let code_value = code_value(true);
+                quote! { #code_value.optional() }
+            }
+            FieldKind::NamedRequired => {
+                let code_named = code_named(false);
+
+                match field_data.default {
+                    Some(_) => quote! { #code_named.optional() },
+                    None => code_named,
+                }
+            }
+            FieldKind::NamedOptional => {
+                let code_named = code_named(true);
+                quote! { #code_named.optional() }
+            }
+            FieldKind::NamedRepeated => {
+                let code_named = code_named(true);
+                quote! { #code_named.repeated() }
+            }
+            FieldKind::Variadic => {
+                let code_value = code_value(true);
+                quote! { #code_value.repeated() }
+            }
+            FieldKind::Flatten => quote! {
+                <#field_ty as ::parenthesis::describe::Describe>::syntax()
+            },
+            FieldKind::Rest => code_value(false),
+        });
+    }
+
+    Ok(code_fields)
+}
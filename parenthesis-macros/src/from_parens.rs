@@ -1,16 +1,29 @@
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_quote, spanned::Spanned, DataStruct, DeriveInput, GenericParam};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parse_quote, spanned::Spanned, DataEnum, DataStruct, DeriveInput, Field, Fields, Generics,
+    Member, Type,
+};
 
-use crate::common::{parse_sexpr_attributes, FieldKind};
+use crate::common::{
+    add_stream_generics, bound_field_types, check_conversion_container_data,
+    check_enum_container_data, check_span_fields, check_struct_container_data, code_validate,
+    derive_bounds, field_member, field_name, find_other_variant, ordinal, parse_container_data,
+    parse_field_data, parse_variant_data, spanned_value_type, variant_name, ContainerData,
+    Delimiter, FieldDefault, FieldKind,
+};
 
 pub fn derive_from_parens_impl(derive_input: DeriveInput) -> syn::Result<TokenStream> {
+    let container_data = parse_container_data(&derive_input.attrs)?;
+
+    if let Some(from) = &container_data.from {
+        check_conversion_container_data(&container_data, "from")?;
+        return derive_from_parens_from(&derive_input, &container_data, from);
+    }
+
     match &derive_input.data {
         syn::Data::Struct(data_struct) => derive_from_parens_struct(&derive_input, data_struct),
-        syn::Data::Enum(_) => Err(syn::Error::new(
-            derive_input.span(),
-            "Can not derive FromParens for enums.",
-        )),
+        syn::Data::Enum(data_enum) => derive_from_parens_enum(&derive_input, data_enum),
         syn::Data::Union(_) => Err(syn::Error::new(
             derive_input.span(),
             "Can not derive FromParens for unions.",
@@ -23,12 +36,522 @@ fn derive_from_parens_struct(
     data_struct: &DataStruct,
 ) -> syn::Result<TokenStream> {
     let struct_ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+    check_struct_container_data(&container_data)?;
+
+    if container_data.transparent {
+        return derive_from_parens_transparent(derive_input, data_struct);
+    }
+
+    let FieldsCode {
+        code_body,
+        code_has_field,
+        code_parse_field,
+        code_field_names,
+        code_construct,
+        flatten_types,
+        flattened_fields_type,
+        code_parse_positional,
+        code_finish_flattened,
+        flattenable,
+        ..
+    } = derive_fields(
+        &data_struct.fields,
+        &container_data,
+        quote!(Self),
+        &derive_input.ident.to_string(),
+    )?;
+
+    // Infer the bounds from the types of the parsed and defaulted fields, unless
+    // they are given with `bound`, and add an `__I` type argument for the input stream.
+    let mut modified_generics =
+        add_input_generics(derive_input, &container_data, &data_struct.fields)?;
+    let where_clause = modified_generics.make_where_clause();
+
+    for ty in &flatten_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::parenthesis::from_parens::FromParensFields<__I>));
+    }
+
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    // Structs without variadic or rest fields can be flattened into other structs.
+    let code_fields_impl = if flattenable && container_data.delimiter().is_none() {
+        quote! {
+            #[automatically_derived]
+            impl #impl_generics ::parenthesis::from_parens::FromParensFields<__I> for #struct_ident #ty_generics
+            #where_clause {
+                type Fields = #flattened_fields_type;
+
+                fn has_field(name: &str) -> bool {
+                    #code_has_field
+                }
+
+                fn field_names() -> ::std::vec::Vec<&'static str> {
+                    #code_field_names
+                }
+
+                fn parse_positional(
+                    fields: &mut Self::Fields,
+                    stream: &mut __I,
+                ) -> ::std::result::Result<(), ::parenthesis::from_parens::ParseError<__I::Span>> {
+                    #code_parse_positional
+                }
+
+                fn parse_field(
+                    fields: &mut Self::Fields,
+                    name: &str,
+                    inner_stream: &mut __I,
+                ) -> ::std::result::Result<bool, ::parenthesis::from_parens::ParseError<__I::Span>> {
+                    let fields = &mut fields.1;
+                    Ok(#code_parse_field)
+                }
+
+                fn finish_fields(
+                    fields: Self::Fields,
+                    stream: &__I,
+                ) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>> {
+                    #code_finish_flattened
+                    #code_construct
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // With a header, the fields are contained in a list that starts with the header symbol.
+    // With `seq` or `map`, the fields are contained in a sequence or map instead.
+    let code_header = match container_data.delimiter() {
+        Some(delimiter) => {
+            let (variant, kind) = match delimiter {
+                Delimiter::List => (quote!(List), "list"),
+                Delimiter::Seq => (quote!(Seq), "sequence"),
+                Delimiter::Map => (quote!(Map), "map"),
+            };
+
+            let code_head = container_data.header.as_ref().map(|header| {
+                let header_expected = format!("`{}`", header);
+                let code_is_header = match container_data.case_insensitive {
+                    true => quote!(head.as_ref().eq_ignore_ascii_case(#header)),
+                    false => quote!(head.as_ref() == #header),
+                };
+
+                quote! {
+                    match stream.next() {
+                        Some(::parenthesis::from_parens::TokenTree::Symbol(head)) if #code_is_header => {}
+                        Some(::parenthesis::from_parens::TokenTree::Symbol(head)) => {
+                            return Err(::parenthesis::from_parens::ParseError::new(
+                                format!("expected {}, found `{}`", #header_expected, head),
+                                stream.span()
+                            ));
+                        }
+                        token_tree => {
+                            return Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                                #header_expected,
+                                token_tree.as_ref(),
+                                stream.span()
+                            ));
+                        }
+                    }
+                }
+            });
+
+            quote! {
+                let mut delimited_stream = match stream.next() {
+                    Some(::parenthesis::from_parens::TokenTree::#variant(delimited_stream)) => {
+                        delimited_stream
+                    }
+                    token_tree => {
+                        return Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                            #kind,
+                            token_tree.as_ref(),
+                            stream.span()
+                        ));
+                    }
+                };
+
+                let stream = &mut delimited_stream;
+                #code_head
+            }
+        }
+        None => quote! {},
+    };
+
+    // Structs in a list with a tag can be parsed from a list whose head has already been read,
+    // so that a named field can take the place of the tag.
+    let code_tagged_impl = match (&container_data.header, container_data.delimiter) {
+        (Some(_), None) => quote! {
+            #[automatically_derived]
+            impl #impl_generics ::parenthesis::from_parens::FromParensTagged<__I> for #struct_ident #ty_generics
+            #where_clause {
+                fn from_parens_untagged(stream: &mut __I) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>> {
+                    #code_body
+                }
+            }
+        },
+        _ => quote! {},
+    };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::parenthesis::from_parens::FromParens<__I> for #struct_ident #ty_generics
+        #where_clause {
+            fn from_parens(stream: &mut __I) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>>
+            where
+                Self: Sized {
+                #code_header
+                #code_body
+            }
+        }
+
+        #code_fields_impl
+        #code_tagged_impl
+    })
+}
+
+/// Derive `FromParens` for an enum whose variants are symbols or lists that start with
+/// the variant name, or for an untagged enum whose variants are tried in turn.
+fn derive_from_parens_enum(
+    derive_input: &DeriveInput,
+    data_enum: &DataEnum,
+) -> syn::Result<TokenStream> {
+    let enum_ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+    check_enum_container_data(&container_data)?;
+
+    // The match arms that parse the fields of each variant
+    let mut code_variants = Vec::new();
+
+    // The match arms for unit variants of a tagged enum, which are bare symbols
+    let mut code_unit_variants = Vec::new();
+
+    // The names of the unit variants and of the variants that are lists
+    let mut unit_names = Vec::new();
+    let mut list_names = Vec::new();
+
+    // The names of all variants, as they are matched
+    let mut variant_names = Vec::new();
+
+    // The types of all flattened fields in any variant
+    let mut flatten_types = Vec::new();
+
+    // The variant that holds lists with an unknown head, if any
+    let other_variant = find_other_variant(data_enum, &container_data)?;
+
+    for variant in &data_enum.variants {
+        let variant_data = parse_variant_data(&variant.attrs)?;
+        let variant_ident = &variant.ident;
+
+        if variant_data.other {
+            continue;
+        }
+
+        // With `case_insensitive`, variant names are matched in lowercase.
+        let match_name = match container_data.case_insensitive {
+            true => variant_name(variant, &variant_data, &container_data).to_ascii_lowercase(),
+            false => variant_name(variant, &variant_data, &container_data),
+        };
+
+        // Aliases are matched like the name of the variant.
+        let match_aliases: Vec<_> = variant_data
+            .aliases
+            .iter()
+            .map(|alias| match container_data.case_insensitive {
+                true => alias.to_ascii_lowercase(),
+                false => alias.clone(),
+            })
+            .collect();
+
+        for name in std::iter::once(&match_name).chain(&match_aliases) {
+            if variant_names.contains(name) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("Duplicate variant name `{}`.", name),
+                ));
+            }
+        }
+
+        let fields_code = derive_fields(
+            &variant.fields,
+            &container_data,
+            quote!(Self::#variant_ident),
+            &variant_ident.to_string(),
+        )?;
+        flatten_types.extend(fields_code.flatten_types);
+
+        // Untagged variants are parsers that are tried in turn, while tagged
+        // variants are match arms for the variant name.
+        if container_data.untagged {
+            let variant_name = variant_name(variant, &variant_data, &container_data);
+
+            let code_body = match (&variant.fields, fields_code.code_body_positional) {
+                (Fields::Unit, _) => {
+                    let names = std::iter::once(&variant_name).chain(&variant_data.aliases);
+                    let code_is_name = match container_data.case_insensitive {
+                        true => quote!(false #(|| name.as_ref().eq_ignore_ascii_case(#names))*),
+                        false => quote!(false #(|| name.as_ref() == #names)*),
+                    };
+                    let name_message = format!("expected `{}`", variant_name);
+
+                    quote! {
+                        match stream.next() {
+                            Some(::parenthesis::from_parens::TokenTree::Symbol(name)) if #code_is_name => {
+                                Ok(Self::#variant_ident)
+                            }
+                            _ => Err(::parenthesis::from_parens::ParseError::new(
+                                #name_message,
+                                stream.span()
+                            )),
+                        }
+                    }
+                }
+                (_, Some(code_body_positional)) => code_body_positional,
+                (_, None) => fields_code.code_body,
+            };
+
+            code_variants.push(quote! {
+                &|stream: &mut __I| -> ::std::result::Result<
+                    Self,
+                    ::parenthesis::from_parens::ParseError<__I::Span>
+                > {
+                    #code_body
+                },
+            });
+        } else if let Fields::Unit = variant.fields {
+            code_unit_variants.push(quote! {
+                #match_name #(| #match_aliases)* => Ok(Self::#variant_ident),
+            });
+
+            unit_names.push(match_name.clone());
+        } else {
+            let code_body = fields_code.code_body;
+
+            code_variants.push(quote! {
+                #match_name #(| #match_aliases)* => {
+                    #code_body
+                }
+            });
+
+            list_names.push(match_name.clone());
+        }
+
+        variant_names.push(match_name);
+        variant_names.extend(match_aliases);
+    }
+
+    // The message for an unknown variant name that lists the expected names.
+    let expected_message = |names: &[String]| {
+        let names: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+        format!("expected {}", names.join(", "))
+    };
+
+    let code_fold_name = match container_data.case_insensitive {
+        true => quote! {
+            let folded_name = head.as_ref().to_ascii_lowercase();
+            let name: &str = &folded_name;
+        },
+        false => quote! {
+            let name: &str = head.as_ref();
+        },
+    };
+
+    let code_unit_arm = (!unit_names.is_empty()).then(|| {
+        let unit_message = expected_message(&unit_names);
+
+        quote! {
+            Some(::parenthesis::from_parens::TokenTree::Symbol(head)) => {
+                #code_fold_name
+
+                match name {
+                    #(#code_unit_variants)*
+                    _ => Err(::parenthesis::from_parens::ParseError::new(
+                        format!("unknown variant `{}`, {}", head, #unit_message),
+                        stream.span()
+                    )),
+                }
+            }
+        }
+    });
+
+    let code_list_arm = (!list_names.is_empty() || other_variant.is_some()).then(|| {
+        let list_message = expected_message(&list_names);
+
+        // Lists with an unknown head are kept in the `other` variant if there is one.
+        let code_unknown = match other_variant {
+            Some(variant) => {
+                let variant_ident = &variant.ident;
+                quote! {
+                    _ => Ok(Self::#variant_ident(
+                        head.clone(),
+                        ::parenthesis::from_parens::FromParens::from_parens(stream)?
+                    )),
+                }
+            }
+            None => quote! {
+                _ => Err(::parenthesis::from_parens::ParseError::new(
+                    format!("unknown variant `{}`, {}", head, #list_message),
+                    stream.span()
+                )),
+            },
+        };
+
+        quote! {
+            Some(::parenthesis::from_parens::TokenTree::List(mut variant_stream)) => {
+                let stream = &mut variant_stream;
+
+                let head = match stream.next() {
+                    Some(::parenthesis::from_parens::TokenTree::Symbol(head)) => head,
+                    token_tree => {
+                        return Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                            "variant name",
+                            token_tree.as_ref(),
+                            stream.parent_span()
+                        ));
+                    }
+                };
+
+                #code_fold_name
+
+                match name {
+                    #(#code_variants)*
+                    #code_unknown
+                }
+            }
+        }
+    });
+
+    let expected_kind = match (
+        unit_names.is_empty(),
+        list_names.is_empty() && other_variant.is_none(),
+    ) {
+        (false, false) => "symbol or list",
+        (false, true) => "symbol",
+        (true, _) => "list",
+    };
+
+    // Unit variants of tagged enums are bare symbols, other variants are
+    // lists that start with the variant name.
+    let code_parse = match container_data.untagged {
+        true => quote! {
+            ::parenthesis::from_parens::alt(stream, &[
+                #(#code_variants)*
+            ])
+        },
+        false => quote! {
+            match stream.next() {
+                #code_unit_arm
+                #code_list_arm
+                token_tree => Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                    #expected_kind,
+                    token_tree.as_ref(),
+                    stream.span()
+                )),
+            }
+        },
+    };
+
+    let mut modified_generics = add_input_generics(
+        derive_input,
+        &container_data,
+        data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields),
+    )?;
+    let where_clause = modified_generics.make_where_clause();
+
+    for ty in &flatten_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::parenthesis::from_parens::FromParensFields<__I>));
+    }
+
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::parenthesis::from_parens::FromParens<__I> for #enum_ident #ty_generics
+        #where_clause {
+            fn from_parens(stream: &mut __I) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>>
+            where
+                Self: Sized {
+                #code_parse
+            }
+        }
+    })
+}
+
+/// Add the bounds for parsing the given fields and an `__I` type argument for the input stream.
+fn add_input_generics<'a>(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> syn::Result<Generics> {
+    let (bounded, defaulted) = bound_field_types(fields, true)?;
+    let bounds = derive_bounds(
+        &derive_input.generics,
+        container_data.from_parens_bound.as_deref(),
+        quote!(::parenthesis::from_parens::FromParens<__I>),
+        bounded,
+        defaulted,
+    );
+
+    Ok(add_stream_generics(
+        &derive_input.generics,
+        bounds,
+        parse_quote!(__I: ::parenthesis::from_parens::InputStream),
+    ))
+}
+
+/// The code generated for the fields of a struct or enum variant.
+struct FieldsCode {
+    /// The code that parses all fields from `stream` and returns the constructed value.
+    code_body: TokenStream,
+    /// The code that parses the fields without consuming the rest of `stream`,
+    /// if all fields are positional.
+    code_body_positional: Option<TokenStream>,
+    /// Expression that checks whether the field `name` is a named field.
+    code_has_field: TokenStream,
+    /// Expression that parses the named field `name` into the accumulator.
+    code_parse_field: TokenStream,
+    /// Expression that evaluates to the names of the named fields.
+    code_field_names: TokenStream,
+    /// The code that constructs and validates the value.
+    code_construct: TokenStream,
+    /// The types of all flattened fields.
+    flatten_types: Vec<syn::Type>,
+    /// The type of the accumulator when the fields are flattened into another struct.
+    /// Besides the named fields, it holds the values of the positional fields.
+    flattened_fields_type: TokenStream,
+    /// The code that parses the positional fields into the flattened accumulator.
+    code_parse_positional: TokenStream,
+    /// The code that takes apart the flattened accumulator and checks for missing fields.
+    code_finish_flattened: TokenStream,
+    /// Whether there are no variadic or rest fields, so that the fields can be flattened into other structs.
+    flattenable: bool,
+}
+
+/// Generate the code that parses the fields of a struct or enum variant,
+/// which is constructed by the path in `constructor`.
+/// The `type_name` of the struct or variant is used in error messages.
+fn derive_fields(
+    fields: &Fields,
+    container_data: &ContainerData,
+    constructor: TokenStream,
+    type_name: &str,
+) -> syn::Result<FieldsCode> {
+    check_span_fields(fields)?;
 
     // The code used to parse positional fields
     let mut code_positional = Vec::new();
 
-    // The setup code for named fields
-    let mut code_field_setup = Vec::new();
+    // The types and variables of the accumulator for named fields
+    let mut fields_types = Vec::new();
+    let mut fields_vars = Vec::new();
 
     // The code that checks if required fields have been set
     let mut code_field_required = Vec::new();
@@ -36,31 +559,172 @@ fn derive_from_parens_struct(
     // The match branch for a named field
     let mut code_named_match = Vec::new();
 
+    // The code that tries to parse a field of a flattened struct
+    let mut code_flatten_match = Vec::new();
+
+    // The code that checks whether a field name belongs to a flattened struct
+    let mut code_flatten_has_field = Vec::new();
+
+    // The code that parses the positional fields of a flattened struct, together with
+    // the index of its variable in the accumulator and whether a named field precedes it
+    let mut code_flatten_positional = Vec::new();
+
+    // The variables, types and names of the positional fields
+    let mut positional_fields = Vec::new();
+
     let mut constr_fields = Vec::new();
 
+    // The names of all named fields, including aliases, as they are matched
+    let mut field_names = Vec::new();
+
+    // The canonical names of all named fields, for error messages
+    let mut keyword_names = Vec::new();
+
+    // The types of all flattened fields
+    let mut flatten_types = Vec::new();
+
     // Whether we have seen a named field so far.
     // This is used to guarantee that positional fields must come before named ones.
     let mut seen_named = false;
 
-    for field in &data_struct.fields {
-        let Some(field_ident) = &field.ident else {
-            return Err(syn::Error::new_spanned(
-                field,
-                "Fields must be named to derive Input.",
-            ));
-        };
+    // The code that parses the field capturing all remaining values, if any
+    let mut code_rest = None;
+
+    // The variable and parse function of the variadic positional field, if any
+    let mut variadic = None;
 
-        let field_data = parse_sexpr_attributes(&field.attrs)?;
+    // The variables, types, parse functions and validations of the optional positional fields
+    let mut optional_positional = Vec::new();
 
-        let field_name = field_data
-            .rename
-            .unwrap_or_else(|| format!("{}", field_ident.to_token_stream()));
+    for (index, field) in fields.iter().enumerate() {
+        let field_data = parse_field_data(field)?;
+        let field_ident = field_member(field, index, &field_data)?;
+        let field_ty = &field.ty;
+        let parse_fn = field_data.parse_fn();
+
+        let field_name = field_name(&field_ident, &field_data, container_data);
+
+        // With `case_insensitive`, field names are matched in lowercase.
+        let match_name = match container_data.case_insensitive {
+            true => field_name.to_ascii_lowercase(),
+            false => field_name.clone(),
+        };
+
+        let match_aliases: Vec<_> = field_data
+            .aliases
+            .iter()
+            .map(|alias| match container_data.case_insensitive {
+                true => alias.to_ascii_lowercase(),
+                false => alias.clone(),
+            })
+            .collect();
 
         let field_ident_var = syn::Ident::new(
             &format!("var_{}", field_ident.to_token_stream()),
             field_ident.span(),
         );
 
+        constr_fields.push(quote! {
+            #field_ident: #field_ident_var
+        });
+
+        // Named fields are validated with the span of their form, other fields
+        // with the span of the value or of the enclosing list.
+        let code_validate_value =
+            field_data.code_validate(quote!(&value), quote!(inner_stream.parent_span()));
+        let code_validate_var =
+            field_data.code_validate(quote!(&#field_ident_var), quote!(stream.parent_span()));
+
+        // Errors within a field are reported together with the field and the type.
+        // The description is only formatted when an error occurs.
+        let field_context = match &field_ident {
+            Member::Unnamed(_) if matches!(field_data.kind, FieldKind::Positional) => {
+                format!("{} field of `{}`", ordinal(index + 1), type_name)
+            }
+            _ => format!("field `{}` of `{}`", field_name, type_name),
+        };
+        let code_context = match field_data.kind {
+            FieldKind::NamedRepeated => quote! {
+                |error| error.context(format!(
+                    "{} occurrence of {}",
+                    ::parenthesis::from_parens::ordinal(#field_ident_var.len() + 1),
+                    #field_context,
+                ))
+            },
+            FieldKind::Variadic => quote! {
+                |error| error.context(format!(
+                    "{} value of {}",
+                    ::parenthesis::from_parens::ordinal(#field_ident_var.len() + 1),
+                    #field_context,
+                ))
+            },
+            _ => quote! {
+                |error| error.context(#field_context)
+            },
+        };
+
+        // With `default_if_empty`, a form that contains only the field name is the default value.
+        let code_parse_value = match field_data.default_if_empty {
+            true => quote! {
+                match ::parenthesis::from_parens::InputStream::is_end(inner_stream) {
+                    true => Ok(::std::default::Default::default()),
+                    false => #parse_fn(inner_stream),
+                }
+            },
+            false => quote! { #parse_fn(inner_stream) },
+        };
+
+        // Named fields of type `Spanned` get the span of their entire form.
+        let code_parse_named = if let Some(value_ty) = spanned_value_type(field_ty, field_data.kind)
+        {
+            quote! {
+                let mut value: #value_ty = #code_parse_value.map_err(#code_context)?;
+                value.span = <__I as ::parenthesis::from_parens::InputStream>::byte_range(
+                    &inner_stream.parent_span()
+                ).unwrap_or_default();
+                #code_validate_value
+            }
+        } else {
+            quote! {
+                let value = #code_parse_value.map_err(#code_context)?;
+                #code_validate_value
+            }
+        };
+
+        // Skipped fields are not parsed but filled with their default value,
+        // except for the span field which gets the span of the enclosing list.
+        if field_data.skip_deserializing {
+            code_field_required.push(match &field_data.default {
+                _ if field_data.span => quote_spanned! { field_ty.span() =>
+                    let #field_ident_var: #field_ty =
+                        <__I as ::parenthesis::from_parens::InputStream>::byte_range(
+                            &stream.parent_span()
+                        ).unwrap_or_default();
+                },
+                Some(FieldDefault::Function(function)) => quote! {
+                    let #field_ident_var: #field_ty = #function();
+                },
+                _ => quote_spanned! { field_ty.span() =>
+                    let #field_ident_var: #field_ty = ::std::default::Default::default();
+                },
+            });
+
+            continue;
+        }
+
+        if !matches!(
+            field_data.kind,
+            FieldKind::Positional
+                | FieldKind::PositionalOptional
+                | FieldKind::Variadic
+                | FieldKind::Flatten
+                | FieldKind::Rest
+        ) {
+            field_names.push(match_name.clone());
+            field_names.extend(match_aliases.iter().cloned());
+            keyword_names.push(field_name.clone());
+        }
+
         match field_data.kind {
             FieldKind::Positional => {
                 if seen_named {
@@ -70,150 +734,815 @@ fn derive_from_parens_struct(
                     ));
                 }
 
+                if variadic.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "A variadic field must be the last positional field.",
+                    ));
+                }
+
+                if !optional_positional.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "Optional positional fields must come after all other positional fields.",
+                    ));
+                }
+
+                let code_validate_positional =
+                    field_data.code_validate(quote!(&#field_ident_var), quote!(stream.span()));
+
+                // Fields of tuple structs have no name, so a missing field is
+                // reported by its position.
+                let code_parse_positional = match &field_ident {
+                    Member::Unnamed(_) => {
+                        let missing_message =
+                            format!("expected {} field of `{}`", ordinal(index + 1), type_name);
+                        quote! {
+                            {
+                                let is_end = stream.is_end();
+
+                                #parse_fn(stream)
+                                    .map_err(|error| match is_end {
+                                        true => ::parenthesis::from_parens::ParseError::new(
+                                            #missing_message,
+                                            stream.span(),
+                                        ),
+                                        false => error,
+                                    })
+                                    .map_err(#code_context)?
+                            }
+                        }
+                    }
+                    Member::Named(_) => quote!(#parse_fn(stream).map_err(#code_context)?),
+                };
+
                 code_positional.push(quote! {
-                    let #field_ident_var = <_ as ::parenthesis::from_parens::FromParens<__I>>::from_parens(stream)?;
+                    let #field_ident_var: #field_ty = #code_parse_positional;
+                    #code_validate_positional
                 });
+
+                positional_fields.push((field_ident_var.clone(), field_ty.clone(), field_name));
+            }
+            FieldKind::PositionalOptional => {
+                if seen_named {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "Positional fields must come before named fields.",
+                    ));
+                }
+
+                if variadic.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "A variadic field must be the last positional field.",
+                    ));
+                }
+
+                let code_validate_positional =
+                    field_data.code_validate(quote!(value), quote!(stream.span()));
+
+                optional_positional.push((
+                    field_ident_var.clone(),
+                    field_ty.clone(),
+                    parse_fn,
+                    code_validate_positional,
+                ));
+            }
+            FieldKind::Variadic => {
+                if seen_named {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "Positional fields must come before named fields.",
+                    ));
+                }
+
+                if variadic.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "A variadic field must be the last positional field.",
+                    ));
+                }
+
+                if !optional_positional.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "Optional positional fields must come after all other positional fields.",
+                    ));
+                }
+
+                variadic = Some((
+                    field_ident_var.clone(),
+                    field_ty.clone(),
+                    parse_fn,
+                    code_context.clone(),
+                    code_validate_var,
+                ));
             }
             FieldKind::NamedRequired => {
                 seen_named = true;
 
-                code_field_setup.push(quote! {
-                    let mut #field_ident_var = None;
-                });
+                fields_types.push(quote! { ::std::option::Option<#field_ty> });
+                fields_vars.push(field_ident_var.clone());
 
-                let missing_field_message = format!("Missing required field `{}`.", field_name);
-
-                code_field_required.push(quote! {
-                    let Some(#field_ident_var) = #field_ident_var else {
-                        return Err(::parenthesis::from_parens::ParseError::new(
-                            #missing_field_message,
-                            stream.parent_span()
-                        ));
-                    };
+                code_field_required.push(match &field_data.default {
+                    None => quote! {
+                        let Some(#field_ident_var) = #field_ident_var else {
+                            return Err(::parenthesis::from_parens::ParseError::MissingField {
+                                field: #field_name,
+                                span: stream.parent_span(),
+                            });
+                        };
+                    },
+                    Some(FieldDefault::Trait) => quote! {
+                        let #field_ident_var = #field_ident_var.unwrap_or_default();
+                    },
+                    Some(FieldDefault::Function(function)) => quote! {
+                        let #field_ident_var = #field_ident_var.unwrap_or_else(#function);
+                    },
                 });
 
-                let duplicate_field_message = format!("Duplicate field `{}`.", field_name);
+                let code_check_duplicate = code_check_duplicate(&field_ident, &field_name);
+                fields_types.push(quote! { ::std::option::Option<__I::Span> });
+                fields_vars.push(field_span_var(&field_ident));
 
                 code_named_match.push(quote! {
-                    #field_name => {
-                        if #field_ident_var.is_some() {
-                            return Err(::parenthesis::from_parens::ParseError::new(
-                                #duplicate_field_message,
-                                inner_stream.parent_span()
-                            ));
-                        }
-
-                        let value = <_ as ::parenthesis::from_parens::FromParens<__I>>::from_parens(&mut inner_stream)?;
-                        #field_ident_var = Some(value);
+                    #match_name #(| #match_aliases)* => {
+                        #code_check_duplicate
+                        #code_parse_named
+                        *#field_ident_var = Some(value);
+                        true
                     },
                 });
             }
             FieldKind::NamedOptional => {
                 seen_named = true;
 
-                code_field_setup.push(quote! {
-                    let mut #field_ident_var = None;
-                });
+                fields_types.push(quote! { #field_ty });
+                fields_vars.push(field_ident_var.clone());
 
-                let duplicate_field_message = format!("Duplicate field `{}`.", field_name);
+                let code_check_duplicate = code_check_duplicate(&field_ident, &field_name);
+                fields_types.push(quote! { ::std::option::Option<__I::Span> });
+                fields_vars.push(field_span_var(&field_ident));
 
                 code_named_match.push(quote! {
-                    #field_name => {
-                        if #field_ident_var.is_some() {
-                            return Err(::parenthesis::from_parens::ParseError::new(
-                                #duplicate_field_message,
-                                inner_stream.parent_span()
-                            ));
-                        }
-
-                        let value = <_ as ::parenthesis::from_parens::FromParens<__I>>::from_parens(&mut inner_stream)?;
-                        #field_ident_var = Some(value);
+                    #match_name #(| #match_aliases)* => {
+                        #code_check_duplicate
+                        #code_parse_named
+                        *#field_ident_var = Some(value);
+                        true
                     }
                 });
             }
             FieldKind::NamedRepeated => {
                 seen_named = true;
 
-                code_field_setup.push(quote! {
-                    let mut #field_ident_var = Vec::new();
+                fields_types.push(quote! { #field_ty });
+                fields_vars.push(field_ident_var.clone());
+
+                // Too many occurrences are reported at the first extra occurrence,
+                // too few at the enclosing list.
+                let count_message = field_data.count_message(&field_name);
+
+                let code_check_max = field_data.max.map(|max| {
+                    quote! {
+                        if #field_ident_var.len() >= #max {
+                            return Err(::parenthesis::from_parens::ParseError::new(
+                                format!("{}, found {}", #count_message, #field_ident_var.len() + 1),
+                                inner_stream.parent_span(),
+                            ));
+                        }
+                    }
                 });
 
+                if let Some(min) = field_data.min {
+                    code_field_required.push(quote! {
+                        if #field_ident_var.len() < #min {
+                            return Err(::parenthesis::from_parens::ParseError::new(
+                                format!("{}, found {}", #count_message, #field_ident_var.len()),
+                                stream.parent_span(),
+                            ));
+                        }
+                    });
+                }
+
                 code_named_match.push(quote! {
-                    #field_name => {
-                        let value = <_ as ::parenthesis::from_parens::FromParens<__I>>::from_parens(&mut inner_stream)?;
+                    #match_name #(| #match_aliases)* => {
+                        #code_check_max
+                        #code_parse_named
                         #field_ident_var.push(value);
+                        true
                     }
                 });
             }
+            FieldKind::Flatten => {
+                let after_named = seen_named;
+                seen_named = true;
+
+                let fields_trait = quote! { ::parenthesis::from_parens::FromParensFields<__I> };
+
+                code_flatten_positional.push((
+                    fields_vars.len(),
+                    quote! {
+                        <#field_ty as #fields_trait>::parse_positional(#field_ident_var, stream)?;
+                    },
+                    after_named,
+                ));
+
+                fields_types.push(quote! { <#field_ty as #fields_trait>::Fields });
+                fields_vars.push(field_ident_var.clone());
+
+                code_flatten_match.push(quote! {
+                    || <#field_ty as #fields_trait>::parse_field(#field_ident_var, name, inner_stream)?
+                });
+
+                code_flatten_has_field.push(quote! {
+                    <#field_ty as #fields_trait>::has_field(name)
+                });
+
+                code_field_required.push(quote! {
+                    let #field_ident_var = <#field_ty as #fields_trait>::finish_fields(#field_ident_var, stream)?;
+                    #code_validate_var
+                });
+
+                flatten_types.push(field_ty.clone());
+            }
+            FieldKind::Rest => {
+                if index + 1 != fields.len() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "A field of type `Rest` must be the last field.",
+                    ));
+                }
+
+                code_rest = Some(quote! {
+                    let #field_ident_var = #parse_fn(stream)?;
+                    #code_validate_var
+                });
+            }
         };
+    }
 
-        constr_fields.push(quote! {
-            #field_ident: #field_ident_var
+    // The named fields are accumulated in a nested tuple, so that the
+    // accumulator has a nameable type which implements `Default`.
+    let fields_type = fields_types
+        .iter()
+        .rev()
+        .fold(quote! { () }, |rest, ty| quote! { (#ty, #rest) });
+    let fields_pattern = fields_vars
+        .iter()
+        .rev()
+        .fold(quote! { () }, |rest, var| quote! { (#var, #rest) });
+
+    // Expression that checks whether the field `name` belongs to this struct.
+    // With `case_insensitive`, field names are converted to lowercase before matching.
+    let code_fold_name = match container_data.case_insensitive {
+        true => quote! {
+            let folded_name = name.to_ascii_lowercase();
+            let name: &str = &folded_name;
+        },
+        false => quote! {},
+    };
+
+    let code_has_field = quote! {
+        {
+            #code_fold_name
+            false #(|| name == #field_names)* #(|| #code_flatten_has_field)*
+        }
+    };
+
+    // An optional positional field takes the next value if it parses and
+    // does not belong to a named field. Otherwise the stream is left as is.
+    let has_optional_positional = !optional_positional.is_empty();
+
+    for (field_ident_var, field_ty, parse_fn, code_validate_positional) in optional_positional {
+        let code_validate_positional = (!code_validate_positional.is_empty()).then(|| {
+            quote! {
+                if let Some(value) = &#field_ident_var {
+                    #code_validate_positional
+                }
+            }
+        });
+
+        code_positional.push(quote! {
+            let is_named = match stream.peek() {
+                Some(::parenthesis::from_parens::TokenTree::List(peeked)) => match peeked.peek() {
+                    Some(::parenthesis::from_parens::TokenTree::Symbol(peeked_head)) => {
+                        let name: &str = peeked_head.as_ref();
+                        #code_has_field
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+
+            let #field_ident_var: #field_ty = if is_named || stream.is_end() {
+                None
+            } else {
+                let checkpoint = stream.checkpoint();
+
+                match #parse_fn(stream) {
+                    Ok(value) => Some(value),
+                    Err(_) => {
+                        stream.restore(checkpoint);
+                        None
+                    }
+                }
+            };
+
+            #code_validate_positional
         });
     }
 
-    let code_named_match: TokenStream = code_named_match.into_iter().collect();
-    let code_named = quote! {
-        while let Some(token_tree) = stream.next() {
-            let ::parenthesis::from_parens::TokenTree::List(mut inner_stream) = token_tree else {
-                return Err(::parenthesis::from_parens::ParseError::new(
-                    "expected field",
-                    stream.span()
-                ));
+    // The variadic field takes positional values until the stream ends or
+    // until it encounters a form that belongs to a named field.
+    let has_variadic = variadic.is_some();
+
+    if let Some((field_ident_var, field_ty, parse_fn, code_context, code_validate_var)) = variadic {
+        code_positional.push(quote! {
+            let mut #field_ident_var: #field_ty = ::std::vec::Vec::new();
+
+            while let Some(peeked) = stream.peek() {
+                if let ::parenthesis::from_parens::TokenTree::List(peeked) = peeked {
+                    if let Some(::parenthesis::from_parens::TokenTree::Symbol(peeked_head)) = peeked.peek() {
+                        let name: &str = peeked_head.as_ref();
+
+                        if #code_has_field {
+                            break;
+                        }
+                    }
+                }
+
+                let checkpoint = ::parenthesis::from_parens::InputStream::checkpoint(stream);
+                let value = (#parse_fn)(stream).map_err(#code_context)?;
+                ::parenthesis::from_parens::check_consumed(stream, checkpoint)
+                    .map_err(#code_context)?;
+                #field_ident_var.push(value);
+            }
+
+            #code_validate_var
+        });
+    }
+
+    // A field name that belongs to more than one flattened struct, or to a flattened
+    // struct and this struct, is ambiguous. We can only detect this when the field
+    // is parsed, since the fields of flattened structs are not known to this macro.
+    let code_check_claims = match code_flatten_has_field.is_empty() {
+        true => quote! {},
+        false => quote! {
+                let claims = 0usize
+                    #(+ usize::from(name == #field_names))*
+                    #(+ usize::from(#code_flatten_has_field))*;
+
+                if claims > 1 {
+                    return Err(::parenthesis::from_parens::ParseError::new(
+                        format!("field `{}` is claimed by more than one flattened field", name),
+                        inner_stream.parent_span()
+                    ));
+                }
+        },
+    };
+
+    // Expression that parses the field `name` from `inner_stream` into
+    // `fields` and evaluates to whether the field belongs to this struct.
+    let code_parse_field = quote! {
+        {
+            #code_fold_name
+            #code_check_claims
+            let #fields_pattern = fields;
+
+            match name {
+                #(#code_named_match)*
+                _ => false #(#code_flatten_match)*,
+            }
+        }
+    };
+
+    // The code that takes apart the accumulator and checks for missing fields.
+    let code_finish = quote! {
+        let #fields_pattern = fields;
+        #(#code_field_required)*
+    };
+
+    // When the remaining values are captured, we stop at the first form that
+    // does not belong to a known field and leave it for the `Rest` field.
+    let code_rest_check = if code_rest.is_some() {
+        quote! {
+            let Some(::parenthesis::from_parens::TokenTree::List(peeked)) = stream.peek() else {
+                break;
+            };
+
+            let Some(::parenthesis::from_parens::TokenTree::Symbol(peeked_head)) = peeked.peek() else {
+                break;
+            };
+
+            let name: &str = peeked_head.as_ref();
+
+            if !(#code_has_field) {
+                break;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The positional fields of flattened structs are parsed in the order in which
+    // the flattened fields are declared. Those of flattened fields that are declared
+    // before any named field follow the positional fields of this struct.
+    // Expects `fields` to be a mutable reference to the accumulator.
+    let flatten_pattern = |index: usize| {
+        fields_vars
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(quote! { () }, |rest, (var_index, var)| {
+                match var_index == index {
+                    true => quote! { (#var, #rest) },
+                    false => quote! { (_, #rest) },
+                }
+            })
+    };
+
+    let code_flatten_positional: Vec<_> = code_flatten_positional
+        .into_iter()
+        .map(|(index, code, after_named)| {
+            let pattern = flatten_pattern(index);
+            let code = quote! {
+                {
+                    let #pattern = &mut *fields;
+                    #code
+                }
+            };
+            (code, after_named)
+        })
+        .collect();
+
+    let code_flatten_early: Vec<_> = code_flatten_positional
+        .iter()
+        .filter(|(_, after_named)| !after_named)
+        .map(|(code, _)| code)
+        .collect();
+    let code_flatten_late: Vec<_> = code_flatten_positional
+        .iter()
+        .filter(|(_, after_named)| *after_named)
+        .map(|(code, _)| code)
+        .collect();
+
+    // The positional fields of flattened structs declared after a named field follow
+    // the forms of the named fields. They are parsed one after the other at the first
+    // value that is not the form of a named field, or at the end of the stream.
+    let (code_flatten_pending_init, code_flatten_pending) = match code_flatten_late.is_empty() {
+        true => (quote! {}, quote! {}),
+        false => {
+            let flatten_count = code_flatten_late.len();
+            let flatten_indices = 0..flatten_count;
+
+            let code_flatten_pending = quote! {
+                if flatten_parsed < #flatten_count {
+                    let is_named = match stream.peek() {
+                        Some(::parenthesis::from_parens::TokenTree::List(peeked)) => match peeked.peek() {
+                            Some(::parenthesis::from_parens::TokenTree::Symbol(peeked_head)) => {
+                                let name: &str = peeked_head.as_ref();
+                                #code_has_field
+                            }
+                            _ => false,
+                        },
+                        _ => false,
+                    };
+
+                    if !is_named {
+                        let fields = &mut fields;
+
+                        match flatten_parsed {
+                            #(#flatten_indices => #code_flatten_late)*
+                            _ => unreachable!(),
+                        }
+
+                        flatten_parsed += 1;
+                        continue;
+                    }
+                }
             };
 
-            let Some(::parenthesis::from_parens::TokenTree::Symbol(head)) = inner_stream.next() else {
-                return Err(::parenthesis::from_parens::ParseError::new(
-                    "expected field name",
+            (
+                quote! { let mut flatten_parsed = 0usize; },
+                code_flatten_pending,
+            )
+        }
+    };
+
+    // Expression that evaluates to the names of all named fields, including those of flattened fields.
+    let code_field_names = quote! {
+        {
+            #[allow(unused_mut)]
+            let mut names: ::std::vec::Vec<&'static str> = ::std::vec![#(#keyword_names),*];
+            #(names.extend(<#flatten_types as ::parenthesis::from_parens::FromParensFields<__I>>::field_names());)*
+            names
+        }
+    };
+
+    // Forms of unknown fields are skipped unless `deny_unknown_fields` is set.
+    let code_field = match container_data.deny_unknown_fields {
+        true => quote! {
+            if !#code_parse_field {
+                return Err(::parenthesis::from_parens::ParseError::unknown_field(
+                    name,
+                    &#code_field_names,
                     inner_stream.parent_span()
                 ));
+            }
+        },
+        false => quote! {
+            let _ = #code_parse_field;
+        },
+    };
+
+    // What is expected in place of an atom after the positional fields.
+    let expected_field = match (keyword_names.as_slice(), flatten_types.is_empty()) {
+        ([], true) => "no more values".to_string(),
+        ([name], true) => format!("named field `({} ...)`", name),
+        (names, true) => format!(
+            "one of the named fields {}",
+            names
+                .iter()
+                .map(|name| format!("`({} ...)`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        (_, false) => "named field".to_string(),
+    };
+
+    let code_named = quote! {
+        let mut fields: #fields_type = ::std::default::Default::default();
+
+        {
+            let fields = &mut fields;
+            #(#code_flatten_early)*
+        }
+
+        #code_flatten_pending_init
+
+        loop {
+            #code_flatten_pending
+            #code_rest_check
+
+            let Some(token_tree) = stream.next() else {
+                break;
             };
 
-            match head.as_ref() {
-                #code_named_match
-                unknown_name => {
-                    return Err(::parenthesis::from_parens::ParseError::new(
-                        format!("unknown field `{}`", unknown_name),
+            let mut inner_stream = match token_tree {
+                ::parenthesis::from_parens::TokenTree::List(inner_stream) => inner_stream,
+                token_tree => {
+                    return Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                        #expected_field,
+                        Some(&token_tree),
+                        stream.span()
+                    ));
+                }
+            };
+
+            let head = match inner_stream.next() {
+                Some(::parenthesis::from_parens::TokenTree::Symbol(head)) => head,
+                token_tree => {
+                    return Err(::parenthesis::from_parens::ParseError::unexpected_token(
+                        "field name",
+                        token_tree.as_ref(),
                         inner_stream.parent_span()
                     ));
                 }
             };
+
+            let name: &str = head.as_ref();
+            let inner_stream = &mut inner_stream;
+            let fields = &mut fields;
+
+            #code_field
         }
     };
 
-    // Add an `Input` bound to every generic type argument and add an `__I`
-    // type argument for the input stream.
-    let mut modified_generics = derive_input.generics.clone();
-    let where_clause = modified_generics.make_where_clause();
+    // The code that constructs the struct and runs the struct's validation function.
+    let code_validate_struct = code_validate(
+        container_data.validate.as_ref(),
+        quote!(&value),
+        quote!(stream.parent_span()),
+    );
+
+    let code_construct = quote! {
+        let value = #constructor {
+            #(#constr_fields),*
+        };
+        #code_validate_struct
+        Ok(value)
+    };
+
+    // Without named fields, the positional fields can be parsed without
+    // checking that the stream ends afterwards.
+    let code_body_positional = (fields_vars.is_empty() && code_rest.is_none()).then(|| {
+        quote! {
+            #(#code_positional)*
+            let fields = ();
+            #code_finish
+            #code_construct
+        }
+    });
+
+    // When flattened into another struct, the values of the positional fields are
+    // stored in the accumulator until the outer struct is complete.
+    let (positional_vars, positional_types, positional_names): (Vec<_>, Vec<_>, Vec<_>) =
+        positional_fields.into_iter().fold(
+            (Vec::new(), Vec::new(), Vec::new()),
+            |(mut vars, mut types, mut names), (var, ty, name)| {
+                vars.push(var);
+                types.push(ty);
+                names.push(name);
+                (vars, types, names)
+            },
+        );
+
+    let positional_type = positional_types.iter().rev().fold(
+        quote! { () },
+        |rest, ty| quote! { (::std::option::Option<#ty>, #rest) },
+    );
+    let positional_pattern = positional_vars
+        .iter()
+        .rev()
+        .fold(quote! { () }, |rest, var| quote! { (#var, #rest) });
+    let positional_values = positional_vars.iter().rev().fold(
+        quote! { () },
+        |rest, var| quote! { (::std::option::Option::Some(#var), #rest) },
+    );
+
+    let flattened_fields_type = quote! { (#positional_type, #fields_type) };
+
+    let code_parse_positional = quote! {
+        #(#code_positional)*
+        fields.0 = #positional_values;
+        let fields = &mut fields.1;
+        // When this struct is itself flattened, the named fields are parsed by the
+        // outer struct, so all positional fields of flattened structs are parsed here.
+        #(#code_flatten_early)*
+        #(#code_flatten_late)*
+        Ok(())
+    };
+
+    let code_finish_flattened = quote! {
+        let (#positional_pattern, fields) = fields;
+
+        #(
+            let Some(#positional_vars) = #positional_vars else {
+                return Err(::parenthesis::from_parens::ParseError::MissingField {
+                    field: #positional_names,
+                    span: stream.parent_span(),
+                });
+            };
+        )*
+
+        #code_finish
+    };
+
+    Ok(FieldsCode {
+        flattenable: !has_variadic && !has_optional_positional && code_rest.is_none(),
+        flattened_fields_type,
+        code_parse_positional,
+        code_finish_flattened,
+        code_body_positional,
+        code_body: quote! {
+            #(#code_positional)*
+            #code_named
+            #code_rest
+            #code_finish
+            #code_construct
+        },
+        code_has_field,
+        code_parse_field,
+        code_field_names,
+        code_construct,
+        flatten_types,
+    })
+}
 
-    for param in &derive_input.generics.params {
-        if let GenericParam::Type(param) = param {
-            let ident = &param.ident;
-            where_clause
-                .predicates
-                .push(parse_quote!(#ident: ::parenthesis::from_parens::FromParens<__I>));
+/// The variable of the accumulator that holds the span of the form of a named field
+/// once it has been parsed. It starts with an underscore since it is not used by
+/// the code that takes apart the accumulator.
+fn field_span_var(field_ident: &Member) -> syn::Ident {
+    syn::Ident::new(
+        &format!("_span_{}", field_ident.to_token_stream()),
+        field_ident.span(),
+    )
+}
+
+/// The code that rejects a named field that occurs more than once,
+/// possibly under different aliases.
+fn code_check_duplicate(field_ident: &Member, field_name: &str) -> TokenStream {
+    let field_span_var = field_span_var(field_ident);
+
+    quote! {
+        if let Some(previous) = #field_span_var.take() {
+            return Err(::parenthesis::from_parens::ParseError::DuplicateField {
+                field: #field_name,
+                span: inner_stream.parent_span(),
+                previous,
+            });
         }
+
+        *#field_span_var = Some(inner_stream.parent_span());
     }
+}
+
+/// Derive `FromParens` by parsing another type and converting it with `TryFrom`.
+fn derive_from_parens_from(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    from: &Type,
+) -> syn::Result<TokenStream> {
+    let ident = &derive_input.ident;
+    let code_validate = code_validate(
+        container_data.validate.as_ref(),
+        quote!(&value),
+        quote!(stream.span()),
+    );
+
+    let bounds = match &container_data.from_parens_bound {
+        Some(bound) => bound.clone(),
+        None => vec![parse_quote!(#from: ::parenthesis::from_parens::FromParens<__I>)],
+    };
+
+    let modified_generics = add_stream_generics(
+        &derive_input.generics,
+        bounds,
+        parse_quote!(__I: ::parenthesis::from_parens::InputStream),
+    );
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::parenthesis::from_parens::FromParens<__I> for #ident #ty_generics
+        #where_clause {
+            fn from_parens(stream: &mut __I) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>>
+            where
+                Self: Sized {
+                let from_value = <#from as ::parenthesis::from_parens::FromParens<__I>>::from_parens(stream)?;
+                let value = match <Self as ::std::convert::TryFrom<#from>>::try_from(from_value) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        return Err(::parenthesis::from_parens::ValidationError::into_parse_error(error, stream.span()));
+                    }
+                };
+                #code_validate
+                Ok(value)
+            }
+        }
+    })
+}
+
+/// Derive `FromParens` for a struct with a single field that is parsed in place of the struct.
+fn derive_from_parens_transparent(
+    derive_input: &DeriveInput,
+    data_struct: &DataStruct,
+) -> syn::Result<TokenStream> {
+    let struct_ident = &derive_input.ident;
+
+    let mut fields = data_struct.fields.iter();
+    let (Some(field), None) = (fields.next(), fields.next()) else {
+        return Err(syn::Error::new_spanned(
+            &data_struct.fields,
+            "Transparent structs must have exactly one field.",
+        ));
+    };
+
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(0.into()),
+    };
+
+    let container_data = parse_container_data(&derive_input.attrs)?;
+    let field_data = parse_field_data(field)?;
+    let parse_fn = field_data.parse_fn();
+    let code_validate_field = field_data.code_validate(quote!(&field_value), quote!(stream.span()));
+    let code_validate_struct = code_validate(
+        container_data.validate.as_ref(),
+        quote!(&value),
+        quote!(stream.span()),
+    );
 
-    let stream_param: GenericParam = parse_quote!(__I: ::parenthesis::from_parens::InputStream);
-    modified_generics.params.push(stream_param);
+    let modified_generics = add_input_generics(derive_input, &container_data, &data_struct.fields)?;
     let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
     let (_, ty_generics, _) = derive_input.generics.split_for_impl();
 
     Ok(quote! {
         #[automatically_derived]
         impl #impl_generics ::parenthesis::from_parens::FromParens<__I> for #struct_ident #ty_generics
-        where #where_clause {
+        #where_clause {
             fn from_parens(stream: &mut __I) -> ::std::result::Result<Self, ::parenthesis::from_parens::ParseError<__I::Span>>
             where
                 Self: Sized {
-                #(#code_positional)*
-                #(#code_field_setup)*
-                #code_named
-                #(#code_field_required)*
-                Ok(Self {
-                    #(#constr_fields),*
-                })
+                let field_value = #parse_fn(stream)?;
+                #code_validate_field
+                let value = Self {
+                    #member: field_value
+                };
+                #code_validate_struct
+                Ok(value)
             }
         }
     })
@@ -4,7 +4,10 @@
 use syn::{parse_macro_input, DeriveInput};
 
 pub(crate) mod common;
+mod describe;
 mod from_parens;
+mod match_sexpr;
+mod sexpr;
 mod to_parens;
 
 /// Derive the [`FromParens`] trait.
@@ -24,3 +27,60 @@ pub fn derive_export(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         .unwrap_or_else(syn::Error::into_compile_error)
         .into()
 }
+
+/// Derive the [`Describe`] trait.
+#[proc_macro_derive(Describe, attributes(sexpr))]
+pub fn derive_describe(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    describe::derive_describe_impl(derive_input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Construct a [`Value`] from an s-expression that is read at compile time.
+///
+/// Syntax errors in the s-expression are reported as compile errors.
+/// The s-expression must also be valid Rust tokens, so the quote shorthand
+/// and strings with escape sequences that Rust does not know can not be used.
+/// Atoms outside of a list are separated by Rust's tokenizer, so that
+/// symbols with special characters such as `input-ports` should be delimited
+/// by `|` when they are not within a list.
+#[proc_macro]
+pub fn sexpr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    sexpr::expand_sexpr(input.into(), false)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Construct a `Vec<Value>` from a sequence of s-expressions that are read at compile time.
+///
+/// See [`sexpr!`] for details.
+#[proc_macro]
+pub fn sexpr_vec(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    sexpr::expand_sexpr(input.into(), true)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Match a [`Value`] against a sequence of s-expression patterns.
+///
+/// The first argument is the value to match, followed by arms of the form
+/// `pattern => expression`, which are tried in order. Patterns are written as
+/// s-expressions with the following additions:
+///
+/// - `_` matches any value.
+/// - `$name:kind` matches a value of the given kind and binds it to `name`.
+///   The kinds are `symbol` (`&Symbol`), `string` (`&str`), `int` (`i64`),
+///   `float` (`f64`), `bool` (`bool`), `list`, `seq` and `map` (`&[Value]`)
+///   and `value` (`&Value`). `$name` is short for `$name:value`.
+/// - `..` at the end of a list, sequence or map matches any remaining elements.
+///
+/// Atoms match values that are equal to them. The last arm must be `_` or `$name`
+/// so that every value is matched.
+#[proc_macro]
+pub fn match_sexpr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as match_sexpr::MatchSexpr);
+    match_sexpr::expand_match_sexpr(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,393 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token};
+
+use crate::sexpr::{input_text, Reader};
+
+/// Input of the `match_sexpr!` macro.
+pub struct MatchSexpr {
+    scrutinee: Expr,
+    arms: Vec<Arm>,
+}
+
+struct Arm {
+    pattern: TokenStream,
+    span: Span,
+    body: Expr,
+}
+
+impl Parse for MatchSexpr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let scrutinee = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut arms = Vec::new();
+
+        while !input.is_empty() {
+            let span = input.span();
+            let mut pattern = TokenStream::new();
+
+            while !input.peek(Token![=>]) {
+                if input.is_empty() {
+                    return Err(input.error("expected `=>`"));
+                }
+
+                pattern.extend([input.parse::<TokenTree>()?]);
+            }
+
+            input.parse::<Token![=>]>()?;
+            let body: Expr = input.parse()?;
+            let is_block = matches!(body, Expr::Block(_));
+
+            arms.push(Arm {
+                pattern,
+                span,
+                body,
+            });
+
+            // As in `match` expressions, arms with a block body need no comma.
+            if input.is_empty() || (is_block && !input.peek(Token![,])) {
+                continue;
+            }
+
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(Self { scrutinee, arms })
+    }
+}
+
+impl Arm {
+    /// Read the pattern of the arm.
+    fn read_pattern(&self) -> syn::Result<Pattern> {
+        let text = input_text(self.pattern.clone());
+        let mut reader = Reader::new(&text);
+        let error = |message| syn::Error::new(self.span, message);
+
+        let pattern = read_pattern(&mut reader).map_err(error)?;
+        reader.skip_whitespace();
+
+        if !reader.rest().is_empty() {
+            return Err(error(format!(
+                "unexpected `{}` after pattern",
+                reader.rest().trim_end()
+            )));
+        }
+
+        Ok(pattern)
+    }
+}
+
+/// A pattern that matches a `Value`.
+enum Pattern {
+    /// Matches any value: `_`.
+    Wildcard,
+    /// Binds the value if it is of the given kind: `$name:kind`.
+    Bind(String, BindKind),
+    /// Matches a value that is equal to the given atom.
+    Literal(TokenStream),
+    /// Matches a list, sequence or map whose elements match the patterns.
+    /// With `..` at the end, the value may contain additional elements.
+    Container {
+        constructor: TokenStream,
+        items: Vec<Pattern>,
+        rest: bool,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum BindKind {
+    Value,
+    Symbol,
+    String,
+    Int,
+    Float,
+    Bool,
+    List,
+    Seq,
+    Map,
+}
+
+impl BindKind {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "value" => Self::Value,
+            "symbol" => Self::Symbol,
+            "string" => Self::String,
+            "int" => Self::Int,
+            "float" => Self::Float,
+            "bool" => Self::Bool,
+            "list" => Self::List,
+            "seq" => Self::Seq,
+            "map" => Self::Map,
+            _ => return None,
+        })
+    }
+}
+
+fn read_pattern(reader: &mut Reader) -> Result<Pattern, String> {
+    reader.skip_whitespace();
+
+    let (constructor, close) = match reader.peek() {
+        None => return Err("expected a pattern".to_string()),
+        Some('(') => (quote!(List), ')'),
+        Some('[') => (quote!(Seq), ']'),
+        Some('{') => (quote!(Map), '}'),
+        Some('$') => return read_binding(reader),
+        Some(_) if reader.peek_atom() == "_" => {
+            reader.advance(1);
+            return Ok(Pattern::Wildcard);
+        }
+        Some(_) => return Ok(Pattern::Literal(reader.read_value()?)),
+    };
+
+    reader.advance(1);
+    let mut items = Vec::new();
+    let mut rest = false;
+
+    loop {
+        reader.skip_whitespace();
+
+        match reader.peek() {
+            None => return Err("unexpected end of input".to_string()),
+            Some(c) if c == close => break,
+            Some(_) if rest => return Err("`..` must be the last pattern in a list".to_string()),
+            Some(_) if reader.peek_atom() == ".." => {
+                reader.advance(2);
+                rest = true;
+            }
+            Some(_) => items.push(read_pattern(reader)?),
+        }
+    }
+
+    reader.advance(1);
+
+    Ok(Pattern::Container {
+        constructor,
+        items,
+        rest,
+    })
+}
+
+/// Read a binding of the form `$name:kind` or `$name`, which binds any value.
+fn read_binding(reader: &mut Reader) -> Result<Pattern, String> {
+    fn read_ident<'a>(reader: &mut Reader<'a>) -> &'a str {
+        reader.skip_whitespace();
+        let rest = reader.rest();
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        reader.advance(end);
+        &rest[..end]
+    }
+
+    reader.advance(1);
+    let name = read_ident(reader);
+
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err("expected a name after `$`".to_string());
+    }
+
+    reader.skip_whitespace();
+
+    if reader.peek() != Some(':') {
+        return Ok(Pattern::Bind(name.to_string(), BindKind::Value));
+    }
+
+    reader.advance(1);
+    let kind = read_ident(reader);
+
+    match BindKind::parse(kind) {
+        Some(kind) => Ok(Pattern::Bind(name.to_string(), kind)),
+        None => Err(format!(
+            "unknown kind `{}`, expected `value`, `symbol`, `string`, `int`, `float`, `bool`, `list`, `seq` or `map`",
+            kind
+        )),
+    }
+}
+
+/// Generate the statements that check whether `value` matches the pattern.
+///
+/// The statements bind the names of the pattern and break out of the block
+/// with the given `label` if the value does not match.
+fn code_pattern(
+    pattern: &Pattern,
+    value: TokenStream,
+    span: Span,
+    label: &syn::Lifetime,
+    code_checks: &mut Vec<TokenStream>,
+    bindings: &mut Vec<Ident>,
+) {
+    match pattern {
+        Pattern::Wildcard => {}
+        Pattern::Bind(name, kind) => {
+            let name = Ident::new(name, span);
+
+            let code_value = match kind {
+                BindKind::Value => quote!(#value),
+                BindKind::Symbol => quote! {
+                    match #value {
+                        ::parenthesis::Value::Symbol(symbol) => symbol,
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::String => quote! {
+                    match #value {
+                        ::parenthesis::Value::String(string) => string.as_str(),
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::Int => quote! {
+                    match #value {
+                        ::parenthesis::Value::Int(int) => *int,
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::Float => quote! {
+                    match #value {
+                        ::parenthesis::Value::Float(float) => float.into_inner(),
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::Bool => quote! {
+                    match #value {
+                        ::parenthesis::Value::Bool(bool) => *bool,
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::List => quote! {
+                    match #value {
+                        ::parenthesis::Value::List(items) => items.as_slice(),
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::Seq => quote! {
+                    match #value {
+                        ::parenthesis::Value::Seq(items) => items.as_slice(),
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+                BindKind::Map => quote! {
+                    match #value {
+                        ::parenthesis::Value::Map(items) => items.as_slice(),
+                        _ => break #label ::std::option::Option::None,
+                    }
+                },
+            };
+
+            code_checks.push(quote! {
+                let #name = #code_value;
+            });
+            bindings.push(name);
+        }
+        Pattern::Literal(literal) => {
+            code_checks.push(quote! {
+                if *#value != #literal {
+                    break #label ::std::option::Option::None;
+                }
+            });
+        }
+        Pattern::Container {
+            constructor,
+            items,
+            rest,
+        } => {
+            let items_var = format_ident!("__items_{}", code_checks.len());
+            let len = items.len();
+
+            let code_len = match rest {
+                true => quote!(#items_var.len() < #len),
+                false => quote!(#items_var.len() != #len),
+            };
+
+            code_checks.push(quote! {
+                let ::parenthesis::Value::#constructor(#items_var) = #value else {
+                    break #label ::std::option::Option::None;
+                };
+
+                if #code_len {
+                    break #label ::std::option::Option::None;
+                }
+            });
+
+            for (index, item) in items.iter().enumerate() {
+                code_pattern(
+                    item,
+                    quote!(&#items_var[#index]),
+                    span,
+                    label,
+                    code_checks,
+                    bindings,
+                );
+            }
+        }
+    }
+}
+
+pub fn expand_match_sexpr(input: MatchSexpr) -> syn::Result<TokenStream> {
+    let scrutinee = &input.scrutinee;
+    let label = syn::Lifetime::new("'__match_sexpr_arm", Span::call_site());
+
+    let Some((last, arms)) = input.arms.split_last() else {
+        return Err(syn::Error::new_spanned(
+            scrutinee,
+            "`match_sexpr!` requires at least one arm",
+        ));
+    };
+
+    // The last arm must match any value, so that the macro is exhaustive.
+    let code_last_pattern = match last.read_pattern()? {
+        Pattern::Wildcard => quote!(_),
+        Pattern::Bind(name, BindKind::Value) => Ident::new(&name, last.span).into_token_stream(),
+        _ => {
+            return Err(syn::Error::new(
+                last.span,
+                "the last arm of `match_sexpr!` must be `_` or `$name` to match any value",
+            ))
+        }
+    };
+
+    let last_body = &last.body;
+    let mut code_match = quote! {
+        match __value {
+            #code_last_pattern => #last_body,
+        }
+    };
+
+    // Each arm checks its pattern in a labeled block that evaluates to the bindings
+    // if the pattern matches. The arm bodies are placed in `match` arms outside of
+    // the labeled blocks so that `break`, `continue` and `return` behave as usual.
+    for arm in arms.iter().rev() {
+        let pattern = arm.read_pattern()?;
+        let mut code_checks = Vec::new();
+        let mut bindings = Vec::new();
+        code_pattern(
+            &pattern,
+            quote!(__value),
+            arm.span,
+            &label,
+            &mut code_checks,
+            &mut bindings,
+        );
+
+        let body = &arm.body;
+
+        code_match = quote! {
+            match #label: {
+                #(#code_checks)*
+                ::std::option::Option::Some((#(#bindings,)*))
+            } {
+                ::std::option::Option::Some((#(#bindings,)*)) => #body,
+                ::std::option::Option::None => #code_match,
+            }
+        };
+    }
+
+    Ok(quote! {
+        {
+            let __value = &(#scrutinee);
+            let __value: &::parenthesis::Value = ::std::borrow::Borrow::borrow(__value);
+            #code_match
+        }
+    })
+}
@@ -0,0 +1,321 @@
+use proc_macro2::{Delimiter, Literal, Spacing, Span, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::common::is_bare_symbol;
+
+/// Expand the `sexpr!` macro, or the `sexpr_vec!` macro if `multiple` is set.
+pub fn expand_sexpr(input: TokenStream, multiple: bool) -> syn::Result<TokenStream> {
+    let span = input
+        .clone()
+        .into_iter()
+        .next()
+        .map_or_else(Span::call_site, |token| token.span());
+
+    let text = input_text(input);
+    let values = Reader::new(&text)
+        .read_all()
+        .map_err(|message| syn::Error::new(span, message))?;
+
+    if multiple {
+        return Ok(quote! {
+            <::std::vec::Vec<::parenthesis::Value>>::from([#(#values),*])
+        });
+    }
+
+    let mut values = values.into_iter();
+
+    match (values.next(), values.next()) {
+        (Some(value), None) => Ok(value),
+        (None, _) => Err(syn::Error::new(span, "expected an s-expression")),
+        (Some(_), Some(_)) => Err(syn::Error::new(
+            span,
+            "expected a single s-expression, use `sexpr_vec!` for multiple s-expressions",
+        )),
+    }
+}
+
+/// Recover the text of the macro input.
+///
+/// Rust's tokenizer splits s-expression atoms such as `input-ports` or `#t` into
+/// several tokens and discards the whitespace between them. We therefore use the
+/// source text of each token tree, so that the contents of delimited groups are read
+/// exactly as written. Between top level tokens we can only guess: punctuation
+/// that can start an atom is attached to the following token, and a `-` between
+/// identifiers joins them into a single symbol.
+///
+/// When the source text is not available, such as for tokens that were generated
+/// by another macro, the contents of groups are recovered in the same way.
+pub fn input_text(input: TokenStream) -> String {
+    let mut text = String::new();
+    write_tokens(&mut text, input);
+    text
+}
+
+fn write_tokens(text: &mut String, input: TokenStream) {
+    let mut attach = true;
+    let mut after_ident = false;
+    let mut tokens = input.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        let hyphen = match &token {
+            TokenTree::Punct(punct) => {
+                punct.as_char() == '-'
+                    && after_ident
+                    && matches!(tokens.peek(), Some(TokenTree::Ident(_)))
+            }
+            _ => false,
+        };
+
+        if !attach && !hyphen {
+            text.push(' ');
+        }
+
+        match (token.span().source_text(), &token) {
+            (Some(source), _) => text.push_str(&source),
+            (None, TokenTree::Group(group)) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+
+                text.push_str(open);
+                write_tokens(text, group.stream());
+                text.push_str(close);
+            }
+            (None, token) => text.push_str(&token.to_string()),
+        }
+
+        attach = match &token {
+            TokenTree::Punct(punct) => {
+                punct.spacing() == Spacing::Joint || matches!(punct.as_char(), '#' | '+' | '-')
+            }
+            _ => false,
+        };
+        after_ident = matches!(token, TokenTree::Ident(_));
+    }
+}
+
+/// Reader for s-expressions that produces the code to construct the values.
+///
+/// This follows the syntax accepted by the reader in the `parenthesis` crate.
+pub struct Reader<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self { text, offset: 0 }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Skip over whitespace and comments.
+    pub fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+
+            if let Some(comment) = trimmed.strip_prefix(';') {
+                let end = comment.find('\n').map_or(comment.len(), |index| index + 1);
+                self.offset += rest.len() - comment.len() + end;
+            } else {
+                self.offset += rest.len() - trimmed.len();
+                return;
+            }
+        }
+    }
+
+    fn read_all(&mut self) -> Result<Vec<TokenStream>, String> {
+        let mut values = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => return Ok(values),
+                Some(c @ (')' | ']' | '}')) => {
+                    return Err(format!("unexpected closing delimiter `{}`", c))
+                }
+                Some(_) => values.push(self.read_value()?),
+            }
+        }
+    }
+
+    pub fn read_value(&mut self) -> Result<TokenStream, String> {
+        let (constructor, close) = match self.peek() {
+            Some('(') => (quote!(List), ')'),
+            Some('[') => (quote!(Seq), ']'),
+            Some('{') => (quote!(Map), '}'),
+            Some('"') => {
+                let string = self.read_delimited('"')?;
+                return Ok(quote! {
+                    ::parenthesis::Value::String(::std::convert::Into::into(#string))
+                });
+            }
+            Some('|') => {
+                let symbol = self.read_delimited('|')?;
+                return Ok(quote! {
+                    ::parenthesis::Value::Symbol(::parenthesis::Symbol::from_static(#symbol))
+                });
+            }
+            _ => return self.read_atom(),
+        };
+
+        self.offset += 1;
+        let mut values = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => return Err("unexpected end of input".to_string()),
+                Some(c) if c == close => break,
+                Some(c @ (')' | ']' | '}')) => {
+                    return Err(format!("unexpected closing delimiter `{}`", c))
+                }
+                Some(_) => values.push(self.read_value()?),
+            }
+        }
+
+        self.offset += 1;
+
+        Ok(quote! {
+            ::parenthesis::Value::#constructor(::std::vec![#(#values),*])
+        })
+    }
+
+    /// Read a string or symbol delimited by `delimiter` and resolve its escape sequences.
+    fn read_delimited(&mut self, delimiter: char) -> Result<Literal, String> {
+        let mut chars = self.rest().char_indices().skip(1);
+        let mut output = String::new();
+
+        let end = loop {
+            match chars.next() {
+                None => return Err("unexpected end of input".to_string()),
+                Some((index, c)) if c == delimiter => break index + 1,
+                Some((_, '\\')) => match chars.next().map(|(_, c)| c) {
+                    Some('n') => output.push('\n'),
+                    Some('r') => output.push('\r'),
+                    Some('t') => output.push('\t'),
+                    Some(c @ ('"' | '|' | '\\')) => output.push(c),
+                    Some('u') => {
+                        let hex: String = chars
+                            .by_ref()
+                            .map(|(_, c)| c)
+                            .skip_while(|c| *c == '{')
+                            .take_while(|c| *c != '}')
+                            .collect();
+
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape `\\u{{{}}}`", hex))?;
+                        output.push(c);
+                    }
+                    Some(c) => return Err(format!("invalid escape sequence `\\{}`", c)),
+                    None => return Err("unexpected end of input".to_string()),
+                },
+                Some((_, c)) => output.push(c),
+            }
+        };
+
+        self.offset += end;
+        self.expect_separator()?;
+        Ok(Literal::string(&output))
+    }
+
+    /// Advance the reader by the given number of bytes.
+    pub fn advance(&mut self, bytes: usize) {
+        self.offset += bytes;
+    }
+
+    /// The text of the symbol, boolean or number at the current position.
+    pub fn peek_atom(&self) -> &'a str {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "()[]{}\";|".contains(c))
+            .unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    /// Read a symbol, boolean or number.
+    fn read_atom(&mut self) -> Result<TokenStream, String> {
+        let atom = self.peek_atom();
+        self.offset += atom.len();
+        self.expect_separator()?;
+
+        let value = match atom {
+            "#t" => quote!(::parenthesis::Value::Bool(true)),
+            "#f" => quote!(::parenthesis::Value::Bool(false)),
+            "#+inf" => quote!(::parenthesis::Value::from(::std::primitive::f64::INFINITY)),
+            "#-inf" => quote!(::parenthesis::Value::from(
+                ::std::primitive::f64::NEG_INFINITY
+            )),
+            "#nan" => quote!(::parenthesis::Value::from(::std::primitive::f64::NAN)),
+            _ if is_bare_symbol(atom) => quote! {
+                ::parenthesis::Value::Symbol(::parenthesis::Symbol::from_static(#atom))
+            },
+            _ if is_int(atom) => {
+                let int: i64 = atom
+                    .parse()
+                    .map_err(|_| format!("integer `{}` out of range", atom))?;
+                let int = Literal::i64_suffixed(int);
+                quote!(::parenthesis::Value::Int(#int))
+            }
+            _ if is_float(atom) => {
+                let float: f64 = atom
+                    .parse()
+                    .map_err(|_| format!("unrecognized syntax `{}`", atom))?;
+                let float = Literal::f64_suffixed(float);
+                quote!(::parenthesis::Value::from(#float))
+            }
+            _ => return Err(format!("unrecognized syntax `{}`", atom)),
+        };
+
+        Ok(value)
+    }
+
+    /// Check that a value is followed by whitespace, a delimiter or the end of the input.
+    pub fn expect_separator(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if !c.is_whitespace() && !"()[]{};".contains(c) => Err(format!(
+                "expected whitespace before `{}`",
+                self.rest().split_whitespace().next().unwrap_or_default()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Check whether an atom is an integer of the form `[+-]?[0-9]+`.
+fn is_int(atom: &str) -> bool {
+    let digits = atom.strip_prefix(['+', '-']).unwrap_or(atom);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Check whether an atom is a float of the form `[+-]?[0-9]+\.[0-9]*([eE][+-]?[0-9]+)?`.
+fn is_float(atom: &str) -> bool {
+    let atom = atom.strip_prefix(['+', '-']).unwrap_or(atom);
+    let (mantissa, exponent) = match atom.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (atom, None),
+    };
+
+    let Some((integer, fraction)) = mantissa.split_once('.') else {
+        return false;
+    };
+
+    is_int(integer)
+        && !integer.starts_with(['+', '-'])
+        && fraction.chars().all(|c| c.is_ascii_digit())
+        && exponent.is_none_or(is_int)
+}
@@ -1,17 +1,28 @@
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{parse_quote, GenericParam};
-use syn::{spanned::Spanned, DataStruct, DeriveInput};
+use syn::parse_quote;
+use syn::{
+    spanned::Spanned, DataEnum, DataStruct, DeriveInput, Field, Fields, Generics, Member, Type,
+};
 
-use crate::common::{parse_sexpr_attributes, FieldKind};
+use crate::common::{
+    add_stream_generics, bound_field_types, check_conversion_container_data,
+    check_enum_container_data, check_span_fields, check_struct_container_data, derive_bounds,
+    field_member, field_name, find_other_variant, parse_container_data, parse_field_data,
+    parse_variant_data, variant_name, ContainerData, Delimiter, FieldDefault, FieldKind,
+};
 
 pub fn derive_to_parens_impl(derive_input: DeriveInput) -> syn::Result<TokenStream> {
+    let container_data = parse_container_data(&derive_input.attrs)?;
+
+    if let Some(into) = &container_data.into {
+        check_conversion_container_data(&container_data, "into")?;
+        return derive_to_parens_into(&derive_input, &container_data, into);
+    }
+
     match &derive_input.data {
         syn::Data::Struct(data_struct) => derive_to_parens_struct(&derive_input, data_struct),
-        syn::Data::Enum(_) => Err(syn::Error::new(
-            derive_input.span(),
-            "Can not derive ToParens for enums.",
-        )),
+        syn::Data::Enum(data_enum) => derive_to_parens_enum(&derive_input, data_enum),
         syn::Data::Union(_) => Err(syn::Error::new(
             derive_input.span(),
             "Can not derive ToParens for unions.",
@@ -24,85 +35,394 @@ fn derive_to_parens_struct(
     data_struct: &DataStruct,
 ) -> syn::Result<TokenStream> {
     let struct_ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+    check_struct_container_data(&container_data)?;
+
+    if container_data.transparent {
+        return derive_to_parens_transparent(derive_input, data_struct);
+    }
+
+    let (code_pattern, code_fields) = derive_fields(&data_struct.fields, &container_data)?;
+
+    // With a header, the fields are wrapped in a list that starts with the header symbol.
+    // With `seq` or `map`, the fields are wrapped in a sequence or map instead.
+    let code_header = container_data.header.as_ref().map(|header| {
+        quote! {
+            output.symbol(#header)?;
+        }
+    });
+
+    let code_body = match container_data.delimiter() {
+        Some(delimiter) => {
+            let method = match delimiter {
+                Delimiter::List => quote!(list),
+                Delimiter::Seq => quote!(seq),
+                Delimiter::Map => quote!(map),
+            };
+
+            quote! {
+                output.#method(|output| {
+                    #code_header
+                    #(#code_fields)*
+                    Ok(())
+                })
+            }
+        }
+        None => quote! {
+            #(#code_fields)*
+            Ok(())
+        },
+    };
+
+    let modified_generics =
+        add_output_generics(derive_input, &container_data, &data_struct.fields)?;
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    // Structs in a list with a tag can print the rest of the list without the tag,
+    // so that a named field can take the place of the tag.
+    let code_tagged_impl = match (&container_data.header, container_data.delimiter) {
+        (Some(_), None) => quote! {
+            impl #impl_generics ::parenthesis::to_parens::ToParensTagged<__O> for #struct_ident #ty_generics
+            #where_clause {
+                fn to_parens_untagged(&self, output: &mut __O) -> std::result::Result<(), __O::Error> {
+                    let Self #code_pattern = self;
+                    #(#code_fields)*
+                    Ok(())
+                }
+            }
+        },
+        _ => quote! {},
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::parenthesis::to_parens::ToParens<__O> for #struct_ident #ty_generics
+        #where_clause {
+            fn to_parens(&self, output: &mut __O) -> std::result::Result<(), __O::Error> {
+                let Self #code_pattern = self;
+                #code_body
+            }
+        }
+
+        #code_tagged_impl
+    })
+}
+
+/// Derive `ToParens` for an enum whose variants are written as symbols or lists that start
+/// with the variant name, or for an untagged enum whose variants are written in place.
+fn derive_to_parens_enum(
+    derive_input: &DeriveInput,
+    data_enum: &DataEnum,
+) -> syn::Result<TokenStream> {
+    let enum_ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+    check_enum_container_data(&container_data)?;
+    find_other_variant(data_enum, &container_data)?;
+
+    let mut code_variants = Vec::new();
+
+    for variant in &data_enum.variants {
+        let variant_data = parse_variant_data(&variant.attrs)?;
+        let variant_name = variant_name(variant, &variant_data, &container_data);
+        let variant_ident = &variant.ident;
+
+        // The `other` variant is written as a list with the stored head and values.
+        if variant_data.other {
+            code_variants.push(quote! {
+                Self::#variant_ident(head, values) => output.list(|output| {
+                    ::parenthesis::to_parens::ToParens::to_parens(head, output)?;
+                    ::parenthesis::to_parens::ToParens::to_parens(values, output)
+                }),
+            });
+
+            continue;
+        }
+        let (code_pattern, code_fields) = derive_fields(&variant.fields, &container_data)?;
+
+        // Unit variants are written as a symbol. Other variants are written as
+        // lists that start with their name, or in place if the enum is untagged.
+        code_variants.push(match (container_data.untagged, &variant.fields) {
+            (_, Fields::Unit) => quote! {
+                Self::#variant_ident => output.symbol(#variant_name),
+            },
+            (true, _) => quote! {
+                Self::#variant_ident #code_pattern => {
+                    #(#code_fields)*
+                    Ok(())
+                }
+            },
+            (false, _) => quote! {
+                Self::#variant_ident #code_pattern => output.list(|output| {
+                    output.symbol(#variant_name)?;
+                    #(#code_fields)*
+                    Ok(())
+                }),
+            },
+        });
+    }
+
+    // A reference to an enum without variants is not considered uninhabited,
+    // so we have to match on the enum itself.
+    let code_match = match code_variants.is_empty() {
+        true => quote! { match *self {} },
+        false => quote! {
+            match self {
+                #(#code_variants)*
+            }
+        },
+    };
+
+    let modified_generics = add_output_generics(
+        derive_input,
+        &container_data,
+        data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields),
+    )?;
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::parenthesis::to_parens::ToParens<__O> for #enum_ident #ty_generics
+        #where_clause {
+            fn to_parens(&self, output: &mut __O) -> std::result::Result<(), __O::Error> {
+                #code_match
+            }
+        }
+    })
+}
+
+/// Add the bounds for printing the given fields and an `__O` type argument for the output stream.
+fn add_output_generics<'a>(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> syn::Result<Generics> {
+    let (bounded, _) = bound_field_types(fields, false)?;
+    let bounds = derive_bounds(
+        &derive_input.generics,
+        container_data.to_parens_bound.as_deref(),
+        quote!(::parenthesis::to_parens::ToParens<__O>),
+        bounded,
+        [],
+    );
+
+    Ok(add_stream_generics(
+        &derive_input.generics,
+        bounds,
+        parse_quote!(__O: ::parenthesis::to_parens::OutputStream),
+    ))
+}
+
+/// Generate the code that prints the fields of a struct or enum variant.
+///
+/// Returns the pattern that binds a reference to each printed field
+/// and the code that prints the bound fields.
+fn derive_fields(
+    fields: &Fields,
+    container_data: &ContainerData,
+) -> syn::Result<(TokenStream, Vec<TokenStream>)> {
+    check_span_fields(fields)?;
 
+    let mut code_pattern = Vec::new();
     let mut code_fields = Vec::new();
 
-    for field in &data_struct.fields {
-        let Some(field_ident) = &field.ident else {
-            // TODO: Derive Export for tuple structs
-            return Err(syn::Error::new_spanned(
-                field,
-                "Fields must be named to derive ToParens.",
-            ));
-        };
+    for (index, field) in fields.iter().enumerate() {
+        let field_data = parse_field_data(field)?;
+        let field_ident = field_member(field, index, &field_data)?;
+        let print_fn = field_data.print_fn();
+
+        if field_data.skip_serializing {
+            continue;
+        }
+
+        let field_var = syn::Ident::new(
+            &format!("var_{}", field_ident.to_token_stream()),
+            field_ident.span(),
+        );
 
-        let field_data = parse_sexpr_attributes(&field.attrs)?;
-        let field_name = field_data
-            .rename
-            .unwrap_or_else(|| format!("{}", field_ident.to_token_stream()));
+        code_pattern.push(quote! { #field_ident: #field_var });
+
+        let field_name = field_name(&field_ident, &field_data, container_data);
 
         match field_data.kind {
             FieldKind::Positional => {
                 code_fields.push(quote! {
-                    <_ as ::parenthesis::to_parens::ToParens<__O>>::to_parens(&self.#field_ident, output)?;
+                    #print_fn(#field_var, output)?;
                 });
             }
-            FieldKind::NamedRequired => {
+            FieldKind::PositionalOptional => {
                 code_fields.push(quote! {
+                    if let Some(field_value) = #field_var {
+                        #print_fn(field_value, output)?;
+                    }
+                });
+            }
+            FieldKind::NamedRequired => {
+                let code_field = quote! {
                     output.list(|output| {
                         output.symbol(#field_name)?;
-                        <_ as ::parenthesis::to_parens::ToParens<__O>>::to_parens(&self.#field_ident, output)
+                        #print_fn(#field_var, output)
                     })?;
+                };
+
+                // With `skip_default` the field is omitted when it equals its default value.
+                code_fields.push(match (field_data.skip_default, &field_data.default) {
+                    (true, Some(FieldDefault::Trait)) => {
+                        let field_ty = &field.ty;
+                        quote! {
+                            if *#field_var != <#field_ty as ::std::default::Default>::default() {
+                                #code_field
+                            }
+                        }
+                    }
+                    (true, Some(FieldDefault::Function(function))) => quote! {
+                        if *#field_var != #function() {
+                            #code_field
+                        }
+                    },
+                    _ => code_field,
                 });
             }
             FieldKind::NamedOptional => {
                 code_fields.push(quote! {
-                    if let Some(field_value) = &self.#field_ident {
+                    if let Some(field_value) = #field_var {
                         output.list(|output| {
                             output.symbol(#field_name)?;
-                            <_ as ::parenthesis::to_parens::ToParens<__O>>::to_parens(field_value, output)
+                            #print_fn(field_value, output)
                         })?;
                     }
                 });
             }
             FieldKind::NamedRepeated => {
+                if let Some(count_message) = field_data.count_message(&field_name) {
+                    let code_min = field_data
+                        .min
+                        .map(|min| quote!(&& #field_var.len() >= #min));
+                    let code_max = field_data
+                        .max
+                        .map(|max| quote!(&& #field_var.len() <= #max));
+
+                    code_fields.push(quote! {
+                        debug_assert!(
+                            true #code_min #code_max,
+                            "{}, found {}",
+                            #count_message,
+                            #field_var.len(),
+                        );
+                    });
+                }
+
                 code_fields.push(quote! {
-                    for field_value in self.#field_ident.iter() {
+                    for field_value in #field_var.iter() {
                         output.list(|output| {
                             output.symbol(#field_name)?;
-                            <_ as ::parenthesis::to_parens::ToParens<__O>>::to_parens(field_value, output)
+                            #print_fn(field_value, output)
                         })?;
                     }
                 });
             }
+            FieldKind::Variadic => {
+                code_fields.push(quote! {
+                    for field_value in #field_var.iter() {
+                        #print_fn(field_value, output)?;
+                    }
+                });
+            }
+            FieldKind::Flatten => {
+                code_fields.push(quote! {
+                    #print_fn(#field_var, output)?;
+                });
+            }
+            FieldKind::Rest => {
+                if index + 1 != fields.len() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "A field of type `Rest` must be the last field.",
+                    ));
+                }
+
+                code_fields.push(quote! {
+                    #print_fn(#field_var, output)?;
+                });
+            }
         }
     }
 
-    // Add an `Output` bound to every generic type argument and add an `__O`
-    // type argument for the output stream.
-    let mut modified_generics = derive_input.generics.clone();
-    let where_clause = modified_generics.make_where_clause();
-
-    for param in &derive_input.generics.params {
-        if let GenericParam::Type(param) = param {
-            let ident = &param.ident;
-            where_clause
-                .predicates
-                .push(parse_quote!(#ident: ::parenthesis::to_parens::ToParens<__O>));
+    Ok((
+        quote! {
+            { #(#code_pattern,)* .. }
+        },
+        code_fields,
+    ))
+}
+
+/// Derive `ToParens` by converting a clone of the value into another type with `Into`.
+fn derive_to_parens_into(
+    derive_input: &DeriveInput,
+    container_data: &ContainerData,
+    into: &Type,
+) -> syn::Result<TokenStream> {
+    let ident = &derive_input.ident;
+
+    let bounds = match &container_data.to_parens_bound {
+        Some(bound) => bound.clone(),
+        None => vec![parse_quote!(#into: ::parenthesis::to_parens::ToParens<__O>)],
+    };
+
+    let modified_generics = add_stream_generics(
+        &derive_input.generics,
+        bounds,
+        parse_quote!(__O: ::parenthesis::to_parens::OutputStream),
+    );
+    let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::parenthesis::to_parens::ToParens<__O> for #ident #ty_generics
+        #where_clause {
+            fn to_parens(&self, output: &mut __O) -> std::result::Result<(), __O::Error> {
+                let into_value: #into = ::std::convert::Into::into(::std::clone::Clone::clone(self));
+                <#into as ::parenthesis::to_parens::ToParens<__O>>::to_parens(&into_value, output)
+            }
         }
-    }
+    })
+}
+
+/// Derive `ToParens` for a struct with a single field that is printed in place of the struct.
+fn derive_to_parens_transparent(
+    derive_input: &DeriveInput,
+    data_struct: &DataStruct,
+) -> syn::Result<TokenStream> {
+    let struct_ident = &derive_input.ident;
+    let container_data = parse_container_data(&derive_input.attrs)?;
+
+    let mut fields = data_struct.fields.iter();
+    let (Some(field), None) = (fields.next(), fields.next()) else {
+        return Err(syn::Error::new_spanned(
+            &data_struct.fields,
+            "Transparent structs must have exactly one field.",
+        ));
+    };
+
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(0.into()),
+    };
+
+    let print_fn = parse_field_data(field)?.print_fn();
 
-    let stream_param: GenericParam = parse_quote!(__O: ::parenthesis::to_parens::OutputStream);
-    modified_generics.params.push(stream_param);
+    let modified_generics =
+        add_output_generics(derive_input, &container_data, &data_struct.fields)?;
     let (impl_generics, _, where_clause) = modified_generics.split_for_impl();
     let (_, ty_generics, _) = derive_input.generics.split_for_impl();
 
     Ok(quote! {
         impl #impl_generics ::parenthesis::to_parens::ToParens<__O> for #struct_ident #ty_generics
-        where #where_clause {
+        #where_clause {
             fn to_parens(&self, output: &mut __O) -> std::result::Result<(), __O::Error> {
-                #(#code_fields)*
-                Ok(())
+                #print_fn(&self.#member, output)
             }
         }
     })
@@ -0,0 +1,310 @@
+//! Values with the comments that were attached to them in the source.
+//!
+//! When reading with [`ReadOptions::with_preserve_comments`], each comment is
+//! attached to the value that follows it. Comments that are not followed by a
+//! value, such as comments before a closing delimiter, are kept as the trailing
+//! comments of the enclosing list, or as the following comments of the last
+//! value at the end of the input. The comments within a list are kept in a
+//! tree of [`Comments`] next to the value, so that the [`Value`] itself stays
+//! free of comments.
+//!
+//! # Examples
+//!
+//! ```
+//! # use parenthesis::{from_str_annotated, to_string_pretty};
+//! let text = "; Adds one.\n; @param x\n(define\n  (f x)\n  ; @return\n  (add x 1))";
+//! let annotated = from_str_annotated(text).unwrap();
+//! assert_eq!(annotated.leading_comments, vec![" Adds one.", " @param x"]);
+//! assert_eq!(annotated.value.to_string(), "(define (f x) (add x 1))");
+//! assert_eq!(annotated.element_comments[2].leading, vec![" @return"]);
+//! assert_eq!(to_string_pretty(&annotated, 80), text);
+//! ```
+//!
+//! [`ReadOptions::with_preserve_comments`]: crate::ReadOptions::with_preserve_comments
+use smol_str::SmolStr;
+
+use crate::from_parens::{FromParens, InputStream, ParseError, TokenTree};
+use crate::read::{from_str_with_opts, ReadError, ReadOptions};
+use crate::to_parens::{OutputStream, ToParens};
+use crate::Value;
+
+/// A [`Value`] together with the comments attached to it and its elements.
+///
+/// Reading an [`AnnotatedValue`] expects the input stream to preserve comments,
+/// as with [`from_str_annotated`]. Printing emits the comments in front of the
+/// values they are attached to, for output streams that support comments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AnnotatedValue {
+    /// The value without any comments.
+    pub value: Value,
+    /// The comments before the value.
+    pub leading_comments: Vec<SmolStr>,
+    /// The comments attached to each element of a list, sequence or map.
+    ///
+    /// This is empty when there are no comments within the value.
+    pub element_comments: Vec<Comments>,
+    /// The comments between the last element and the closing delimiter.
+    pub trailing_comments: Vec<SmolStr>,
+    /// The comments after the value that are not followed by another value.
+    pub following_comments: Vec<SmolStr>,
+}
+
+/// The comments attached to an element of an [`AnnotatedValue`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Comments {
+    /// The comments before the element.
+    pub leading: Vec<SmolStr>,
+    /// The comments attached to each element, if the element is a list,
+    /// sequence or map. This is empty when there are no comments within it.
+    pub elements: Vec<Comments>,
+    /// The comments between the last element and the closing delimiter.
+    pub trailing: Vec<SmolStr>,
+}
+
+impl Comments {
+    /// Whether there are no comments at all.
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.elements.is_empty() && self.trailing.is_empty()
+    }
+}
+
+impl From<Value> for AnnotatedValue {
+    fn from(value: Value) -> Self {
+        Self {
+            value,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<AnnotatedValue> for Value {
+    fn from(annotated: AnnotatedValue) -> Self {
+        annotated.value
+    }
+}
+
+/// Read the first value of an s-expression string together with its comments.
+///
+/// This is [`from_str_with_opts`] with comments preserved, see
+/// [`ReadOptions::with_preserve_comments`].
+pub fn from_str_annotated(str: &str) -> Result<AnnotatedValue, ReadError> {
+    from_str_with_opts(str, &ReadOptions::new().with_preserve_comments(true))
+}
+
+impl<I: InputStream> FromParens<I> for AnnotatedValue {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let leading = read_comments(stream);
+        let (value, comments) = read_value(stream, leading)?;
+
+        // Comments that are followed by another value are attached to that value.
+        let checkpoint = stream.checkpoint();
+        let mut following = read_comments(stream);
+
+        if !stream.is_end() {
+            stream.restore(checkpoint);
+            following.clear();
+        }
+
+        Ok(Self {
+            value,
+            leading_comments: comments.leading,
+            element_comments: comments.elements,
+            trailing_comments: comments.trailing,
+            following_comments: following,
+        })
+    }
+}
+
+fn read_comments<I: InputStream>(stream: &mut I) -> Vec<SmolStr> {
+    let mut comments = Vec::new();
+
+    while let Some(TokenTree::Comment(comment)) = stream.peek() {
+        stream.next();
+        comments.push(comment);
+    }
+
+    comments
+}
+
+/// Read a value that follows the given leading comments and return it
+/// together with all comments attached to it.
+fn read_value<I: InputStream>(
+    stream: &mut I,
+    leading: Vec<SmolStr>,
+) -> Result<(Value, Comments), ParseError<I::Span>> {
+    let (constructor, mut inner): (fn(Vec<Value>) -> Value, I) = match stream.peek() {
+        Some(TokenTree::List(inner)) => (Value::List, inner),
+        Some(TokenTree::Seq(inner)) => (Value::Seq, inner),
+        Some(TokenTree::Map(inner)) => (Value::Map, inner),
+        _ => {
+            let value = Value::from_parens(stream)?;
+            let comments = Comments {
+                leading,
+                ..Comments::default()
+            };
+            return Ok((value, comments));
+        }
+    };
+
+    stream.next();
+
+    let mut values = Vec::new();
+    let mut elements = Vec::new();
+
+    loop {
+        let element_leading = read_comments(&mut inner);
+
+        if inner.is_end() {
+            if elements.iter().all(Comments::is_empty) {
+                elements.clear();
+            }
+
+            let comments = Comments {
+                leading,
+                elements,
+                trailing: element_leading,
+            };
+            return Ok((constructor(values), comments));
+        }
+
+        let (value, comments) = read_value(&mut inner, element_leading)?;
+        values.push(value);
+        elements.push(comments);
+    }
+}
+
+impl<O: OutputStream> ToParens<O> for AnnotatedValue {
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for comment in &self.leading_comments {
+            output.comment(comment)?;
+        }
+
+        write_value(
+            &self.value,
+            &self.element_comments,
+            &self.trailing_comments,
+            output,
+        )?;
+
+        for comment in &self.following_comments {
+            output.comment(comment)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a value with the comments of its elements and its trailing comments.
+///
+/// Elements without corresponding [`Comments`] are written without comments.
+fn write_value<O: OutputStream>(
+    value: &Value,
+    elements: &[Comments],
+    trailing: &[SmolStr],
+    output: &mut O,
+) -> Result<(), O::Error> {
+    let write_elements = |output: &mut O, values: &[Value]| {
+        for (index, value) in values.iter().enumerate() {
+            match elements.get(index) {
+                Some(comments) => {
+                    for comment in &comments.leading {
+                        output.comment(comment)?;
+                    }
+
+                    write_value(value, &comments.elements, &comments.trailing, output)?;
+                }
+                None => value.to_parens(output)?,
+            }
+        }
+
+        for comment in trailing {
+            output.comment(comment)?;
+        }
+
+        Ok(())
+    };
+
+    match value {
+        Value::List(list) => output.list(|output| write_elements(output, list)),
+        Value::Seq(seq) => output.seq(|output| write_elements(output, seq)),
+        Value::Map(map) => output.map(|output| write_elements(output, map)),
+        value => value.to_parens(output),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_str_annotated, AnnotatedValue, Comments};
+    use crate::read::{ReadError, ReadOptions};
+    use crate::{from_str, from_str_with_opts, to_string_compact, to_string_pretty, Value};
+    use rstest::rstest;
+
+    #[test]
+    fn attach_comments() {
+        let text = "; file\n; header\n(a ; first\n (b c ; last\n) [d] ; end\n)";
+        let annotated = from_str_annotated(text).unwrap();
+
+        assert_eq!(annotated.value, from_str::<Value>("(a (b c) [d])").unwrap());
+        assert_eq!(annotated.leading_comments, vec![" file", " header"]);
+        assert_eq!(
+            annotated.element_comments,
+            vec![
+                Comments::default(),
+                Comments {
+                    leading: vec![" first".into()],
+                    elements: Vec::new(),
+                    trailing: vec![" last".into()],
+                },
+                Comments::default(),
+            ]
+        );
+        assert_eq!(annotated.trailing_comments, vec![" end"]);
+        assert!(annotated.following_comments.is_empty());
+    }
+
+    #[test]
+    fn following_comments() {
+        let annotated = from_str_annotated("(x)\n; trailing\n; end\n").unwrap();
+        assert_eq!(annotated.following_comments, vec![" trailing", " end"]);
+        assert_eq!(to_string_pretty(&annotated, 80), "(x)\n; trailing\n; end\n");
+
+        // Comments before the next value are attached to that value instead.
+        let options = ReadOptions::new().with_preserve_comments(true);
+        let annotated: Vec<AnnotatedValue> =
+            from_str_with_opts("a ; about b\nb ; last\n", &options).unwrap();
+        assert!(annotated[0].following_comments.is_empty());
+        assert_eq!(annotated[1].leading_comments, vec![" about b"]);
+        assert_eq!(annotated[1].following_comments, vec![" last"]);
+    }
+
+    #[rstest]
+    #[case("a")]
+    #[case("; comment\na")]
+    #[case("(a b c)")]
+    #[case("(a\n  ; doc\n  {b 1}\n  [; empty\n  ])")]
+    #[case("; outer\n(; inner\n  a\n  ; end\n)")]
+    #[case("(a)\n; after\n")]
+    fn pretty_roundtrip(#[case] text: &str) {
+        let annotated = from_str_annotated(text).unwrap();
+        assert_eq!(to_string_pretty(&annotated, 80), text);
+        assert_eq!(
+            from_str_annotated(&to_string_compact(&annotated)).unwrap(),
+            annotated
+        );
+    }
+
+    #[test]
+    fn without_comments() {
+        // Streams that do not preserve comments produce empty annotations.
+        let annotated: AnnotatedValue = from_str("; comment\n(a (b) c)").unwrap();
+        assert_eq!(annotated, AnnotatedValue::from(annotated.value.clone()));
+
+        let options = ReadOptions::new().with_preserve_comments(true);
+        let Err(ReadError::Parse(error)) =
+            from_str_with_opts::<AnnotatedValue>("; only a comment\n", &options)
+        else {
+            panic!("expected parse error");
+        };
+        assert_eq!(error.to_string(), "expected value, found end of input");
+    }
+}
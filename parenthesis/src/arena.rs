@@ -0,0 +1,148 @@
+//! Values that are allocated in an arena.
+//!
+//! Cloning a [`Value`] allocates every list, string and symbol separately,
+//! which adds up when large trees are cloned repeatedly. An [`ArenaValue`]
+//! instead borrows all of its contents from a [`Bump`] arena, so that cloning
+//! a tree into the arena is cheap and the whole tree is freed at once when the
+//! arena is dropped.
+use bumpalo::Bump;
+use ordered_float::OrderedFloat;
+
+use crate::{Symbol, Value};
+
+/// A value whose contents are allocated in an arena.
+///
+/// This mirrors [`Value`], except that lists, strings and symbols are
+/// borrowed from the arena. Use [`clone_into_arena`] to create an arena value
+/// and [`ArenaValue::to_value`] to convert it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArenaValue<'a> {
+    /// Lists are sequences of zero or more values.
+    List(&'a [ArenaValue<'a>]),
+
+    /// Sequences are lists that are delimited by square brackets.
+    Seq(&'a [ArenaValue<'a>]),
+
+    /// Maps are lists that are delimited by curly braces.
+    Map(&'a [ArenaValue<'a>]),
+
+    /// Strings can be any valid UTF-8 string.
+    String(&'a str),
+
+    /// Symbols represent identifiers such as variables or field names.
+    Symbol(&'a str),
+
+    /// Booleans.
+    Bool(bool),
+
+    /// Signed integers with 64bit precision.
+    Int(i64),
+
+    /// Floating point numbers with 64bit precision.
+    Float(OrderedFloat<f64>),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// Convert this value into a [`Value`] that owns its contents.
+    pub fn to_value(&self) -> Value {
+        match self {
+            ArenaValue::List(values) => Value::List(to_values(values)),
+            ArenaValue::Seq(values) => Value::Seq(to_values(values)),
+            ArenaValue::Map(values) => Value::Map(to_values(values)),
+            ArenaValue::String(string) => Value::String((*string).into()),
+            ArenaValue::Symbol(symbol) => Value::Symbol(Symbol::new(symbol)),
+            ArenaValue::Bool(bool) => Value::Bool(*bool),
+            ArenaValue::Int(int) => Value::Int(*int),
+            ArenaValue::Float(float) => Value::Float(*float),
+        }
+    }
+}
+
+impl<'a> From<&ArenaValue<'a>> for Value {
+    fn from(value: &ArenaValue<'a>) -> Self {
+        value.to_value()
+    }
+}
+
+/// Clone a value into an arena.
+///
+/// # Examples
+///
+/// ```
+/// # use bumpalo::Bump;
+/// # use parenthesis::{arena::{clone_into_arena, ArenaValue}, from_str, Value};
+/// let arena = Bump::new();
+/// let value = from_str::<Value>(r#"(define x "y")"#).unwrap();
+/// let arena_value = clone_into_arena(&value, &arena);
+///
+/// let ArenaValue::List([_, ArenaValue::Symbol(name), _]) = arena_value else {
+///     panic!("expected a list with three values");
+/// };
+/// assert_eq!(*name, "x");
+/// assert_eq!(arena_value.to_value(), value);
+/// ```
+pub fn clone_into_arena<'a>(value: &Value, arena: &'a Bump) -> &'a ArenaValue<'a> {
+    arena.alloc(clone_value(value, arena))
+}
+
+/// Clone a slice of values into an arena.
+pub fn clone_slice_into_arena<'a>(values: &[Value], arena: &'a Bump) -> &'a [ArenaValue<'a>] {
+    arena.alloc_slice_fill_iter(values.iter().map(|value| clone_value(value, arena)))
+}
+
+fn clone_value<'a>(value: &Value, arena: &'a Bump) -> ArenaValue<'a> {
+    match value {
+        Value::List(values) => ArenaValue::List(clone_slice_into_arena(values, arena)),
+        Value::Seq(values) => ArenaValue::Seq(clone_slice_into_arena(values, arena)),
+        Value::Map(values) => ArenaValue::Map(clone_slice_into_arena(values, arena)),
+        Value::String(string) => ArenaValue::String(arena.alloc_str(string)),
+        Value::Symbol(symbol) => ArenaValue::Symbol(arena.alloc_str(symbol.as_ref())),
+        Value::Bool(bool) => ArenaValue::Bool(*bool),
+        Value::Int(int) => ArenaValue::Int(*int),
+        Value::Float(float) => ArenaValue::Float(*float),
+    }
+}
+
+fn to_values(values: &[ArenaValue]) -> Vec<Value> {
+    values.iter().map(ArenaValue::to_value).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{clone_into_arena, clone_slice_into_arena, ArenaValue};
+    use crate::{from_str, Value};
+    use bumpalo::Bump;
+    use ordered_float::OrderedFloat;
+    use proptest::prelude::*;
+
+    #[test]
+    fn structure() {
+        let arena = Bump::new();
+        let value = from_str::<Value>(r#"(a [1 2.5] {"k" #t})"#).unwrap();
+
+        assert_eq!(
+            *clone_into_arena(&value, &arena),
+            ArenaValue::List(&[
+                ArenaValue::Symbol("a"),
+                ArenaValue::Seq(&[ArenaValue::Int(1), ArenaValue::Float(OrderedFloat(2.5))]),
+                ArenaValue::Map(&[ArenaValue::String("k"), ArenaValue::Bool(true)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn strings_and_symbols_differ() {
+        let arena = Bump::new();
+        let values = from_str::<Vec<Value>>(r#""a" a"#).unwrap();
+        let arena_values = clone_slice_into_arena(&values, &arena);
+        assert_ne!(arena_values[0], arena_values[1]);
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_round_trip(value: Value) {
+            let arena = Bump::new();
+            prop_assert_eq!(clone_into_arena(&value, &arena).to_value(), value);
+        }
+    }
+}
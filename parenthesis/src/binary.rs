@@ -0,0 +1,454 @@
+//! Compact binary encoding of values.
+//!
+//! The binary encoding is faster to read than the text format, which makes it
+//! suitable for caching values on disk. An encoded value starts with a version
+//! byte, followed by the value itself. Each value consists of a tag byte and
+//! its contents:
+//!
+//! - Lists, sequences and maps are followed by the number of values they contain
+//!   and then the values themselves.
+//! - Strings and symbols are followed by their length in bytes and their UTF-8 encoding.
+//! - Booleans are followed by a single byte that is either `0` or `1`.
+//! - Integers are followed by their signed LEB128 encoding.
+//! - Floats are followed by the eight bytes of their IEEE 754 representation in little endian.
+//!
+//! Lengths are encoded in unsigned LEB128.
+//!
+//! Like the text format, values may be nested at most [`MAX_NESTING_DEPTH`] lists deep.
+use ordered_float::OrderedFloat;
+use thiserror::Error;
+
+use crate::read::MAX_NESTING_DEPTH;
+use crate::Value;
+
+/// The version of the binary encoding that is written by [`encode`].
+pub const VERSION: u8 = 1;
+
+const TAG_LIST: u8 = 0;
+const TAG_SEQ: u8 = 1;
+const TAG_MAP: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_SYMBOL: u8 = 4;
+const TAG_BOOL: u8 = 5;
+const TAG_INT: u8 = 6;
+const TAG_FLOAT: u8 = 7;
+
+/// Append the binary encoding of a value to a buffer.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{binary::{decode, encode}, from_str, Value};
+/// let value = from_str::<Value>(r#"(define x [1 2.5 "three"])"#).unwrap();
+/// let mut buf = Vec::new();
+/// encode(&value, &mut buf);
+/// assert_eq!(decode(&buf).unwrap(), (value, buf.len()));
+/// ```
+pub fn encode(value: &Value, buf: &mut Vec<u8>) {
+    buf.push(VERSION);
+    encode_value(value, buf);
+}
+
+/// Decode a value from the start of a buffer.
+///
+/// Returns the value together with the number of bytes that were read,
+/// so that values can be decoded one after another from the same buffer.
+///
+/// # Errors
+///
+/// Returns an error if the buffer does not start with a value
+/// that was encoded with a supported version of the encoding,
+/// or if the value is nested more than [`MAX_NESTING_DEPTH`] lists deep.
+pub fn decode(buf: &[u8]) -> Result<(Value, usize), DecodeError> {
+    let mut decoder = Decoder {
+        buf,
+        offset: 0,
+        depth: 0,
+    };
+
+    let version = decoder.byte()?;
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let value = decoder.value()?;
+    Ok((value, decoder.offset))
+}
+
+/// Error while decoding a value from its binary encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    /// The buffer ended before the value was complete.
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    /// The value was encoded with a version of the encoding that is not supported.
+    #[error("unsupported version `{0}`")]
+    UnsupportedVersion(u8),
+    /// A value starts with an unknown tag.
+    #[error("invalid tag `{tag}` at offset {offset}")]
+    InvalidTag {
+        /// The tag that was found.
+        tag: u8,
+        /// The offset of the tag in the buffer.
+        offset: usize,
+    },
+    /// A boolean is neither `0` nor `1`.
+    #[error("invalid boolean `{value}` at offset {offset}")]
+    InvalidBool {
+        /// The byte that was found.
+        value: u8,
+        /// The offset of the byte in the buffer.
+        offset: usize,
+    },
+    /// An integer or length does not fit into 64 bits.
+    #[error("integer overflow at offset {0}")]
+    Overflow(usize),
+    /// A string or symbol is not valid UTF-8.
+    #[error("invalid UTF-8 at offset {0}")]
+    InvalidUtf8(usize),
+    /// Lists, sequences or maps are nested more than [`MAX_NESTING_DEPTH`] deep.
+    #[error("lists nested too deeply at offset {0}")]
+    NestingLimitExceeded(usize),
+}
+
+fn encode_value(value: &Value, buf: &mut Vec<u8>) {
+    match value {
+        Value::List(values) => encode_values(TAG_LIST, values, buf),
+        Value::Seq(values) => encode_values(TAG_SEQ, values, buf),
+        Value::Map(values) => encode_values(TAG_MAP, values, buf),
+        Value::String(string) => encode_str(TAG_STRING, string, buf),
+        Value::Symbol(symbol) => encode_str(TAG_SYMBOL, symbol.as_ref(), buf),
+        Value::Bool(bool) => {
+            buf.push(TAG_BOOL);
+            buf.push(u8::from(*bool));
+        }
+        Value::Int(int) => {
+            buf.push(TAG_INT);
+            encode_signed(*int, buf);
+        }
+        Value::Float(float) => {
+            buf.push(TAG_FLOAT);
+            buf.extend_from_slice(&float.to_le_bytes());
+        }
+    }
+}
+
+fn encode_values(tag: u8, values: &[Value], buf: &mut Vec<u8>) {
+    buf.push(tag);
+    encode_unsigned(values.len() as u64, buf);
+
+    for value in values {
+        encode_value(value, buf);
+    }
+}
+
+fn encode_str(tag: u8, str: &str, buf: &mut Vec<u8>) {
+    buf.push(tag);
+    encode_unsigned(str.len() as u64, buf);
+    buf.extend_from_slice(str.as_bytes());
+}
+
+fn encode_unsigned(mut value: u64, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+
+        buf.push(byte | 0x80);
+    }
+}
+
+fn encode_signed(mut value: i64, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        // The sign bit of the last byte must agree with the sign of the value.
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            buf.push(byte);
+            return;
+        }
+
+        buf.push(byte | 0x80);
+    }
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    /// The number of lists, sequences and maps that enclose the current value.
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .buf
+            .get(self.offset)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let bytes = &self.buf[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn value(&mut self) -> Result<Value, DecodeError> {
+        let offset = self.offset;
+
+        Ok(match self.byte()? {
+            TAG_LIST => Value::List(self.values(offset)?),
+            TAG_SEQ => Value::Seq(self.values(offset)?),
+            TAG_MAP => Value::Map(self.values(offset)?),
+            TAG_STRING => Value::String(self.str()?.into()),
+            TAG_SYMBOL => Value::Symbol(self.str()?.into()),
+            TAG_BOOL => {
+                let offset = self.offset;
+                match self.byte()? {
+                    0 => Value::Bool(false),
+                    1 => Value::Bool(true),
+                    value => return Err(DecodeError::InvalidBool { value, offset }),
+                }
+            }
+            TAG_INT => Value::Int(self.signed()?),
+            TAG_FLOAT => {
+                let bytes = self.bytes(8)?.try_into().unwrap();
+                Value::Float(OrderedFloat(f64::from_le_bytes(bytes)))
+            }
+            tag => return Err(DecodeError::InvalidTag { tag, offset }),
+        })
+    }
+
+    /// Decode the values of a list, sequence or map whose tag is at the given offset.
+    fn values(&mut self, offset: usize) -> Result<Vec<Value>, DecodeError> {
+        // Decoding recurses for every level, so the depth is limited to protect the stack.
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(DecodeError::NestingLimitExceeded(offset));
+        }
+
+        let len = self.len()?;
+
+        // Every value takes at least one byte, which bounds the allocation
+        // even if the length is corrupted.
+        let mut values = Vec::with_capacity(len.min(self.buf.len() - self.offset));
+
+        self.depth += 1;
+
+        for _ in 0..len {
+            values.push(self.value()?);
+        }
+
+        self.depth -= 1;
+        Ok(values)
+    }
+
+    fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.len()?;
+        let offset = self.offset;
+        let bytes = self.bytes(len)?;
+        std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8(offset))
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let offset = self.offset;
+        let len = self.unsigned()?;
+        usize::try_from(len).map_err(|_| DecodeError::Overflow(offset))
+    }
+
+    fn unsigned(&mut self) -> Result<u64, DecodeError> {
+        let offset = self.offset;
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7f);
+
+            if shift >= 64 || (bits << shift) >> shift != bits {
+                return Err(DecodeError::Overflow(offset));
+            }
+
+            value |= bits << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn signed(&mut self) -> Result<i64, DecodeError> {
+        let offset = self.offset;
+        let mut value = 0i64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+            let bits = byte & 0x7f;
+
+            // Of the tenth byte only the lowest bit is used, the others must repeat the sign.
+            if shift > 63 || (shift == 63 && bits != 0 && bits != 0x7f) {
+                return Err(DecodeError::Overflow(offset));
+            }
+
+            value |= i64::from(bits) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+
+                return Ok(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, DecodeError, VERSION};
+    use crate::{from_str, read::MAX_NESTING_DEPTH, Value};
+    use ordered_float::OrderedFloat;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn round_trip(value: &Value) -> Value {
+        let mut buf = Vec::new();
+        encode(value, &mut buf);
+        let (decoded, len) = decode(&buf).unwrap();
+        assert_eq!(len, buf.len());
+        decoded
+    }
+
+    #[rstest]
+    #[case("()")]
+    #[case("(a (b c) d)")]
+    #[case("[1 2 3]")]
+    #[case("{a 1 b 2}")]
+    #[case(r#""string with \u{1F600} and \n""#)]
+    #[case("|symbol with spaces|")]
+    #[case("#t")]
+    #[case("#f")]
+    #[case("0")]
+    #[case("-1")]
+    #[case("63")]
+    #[case("64")]
+    #[case("-64")]
+    #[case("-65")]
+    #[case("9223372036854775807")]
+    #[case("-9223372036854775808")]
+    #[case("-0.0")]
+    #[case("1.5e300")]
+    fn variants(#[case] text: &str) {
+        let value = from_str::<Value>(text).unwrap();
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn floats() {
+        for float in [f64::INFINITY, f64::NEG_INFINITY, f64::MIN_POSITIVE] {
+            let value = Value::Float(OrderedFloat(float));
+            assert_eq!(round_trip(&value), value);
+        }
+
+        let Value::Float(nan) = round_trip(&Value::Float(OrderedFloat(f64::NAN))) else {
+            panic!("expected float");
+        };
+        assert!(nan.is_nan());
+    }
+
+    #[test]
+    fn encoding() {
+        let value = from_str::<Value>(r#"(a -2 "é")"#).unwrap();
+        let mut buf = Vec::new();
+        encode(&value, &mut buf);
+        assert_eq!(buf, [VERSION, 0, 3, 4, 1, b'a', 6, 0x7e, 3, 2, 0xc3, 0xa9]);
+    }
+
+    #[test]
+    fn consecutive() {
+        let values = from_str::<Vec<Value>>("(a b) [1 2] c").unwrap();
+        let mut buf = Vec::new();
+
+        for value in &values {
+            encode(value, &mut buf);
+        }
+
+        let mut decoded = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let (value, len) = decode(&buf[offset..]).unwrap();
+            decoded.push(value);
+            offset += len;
+        }
+
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn truncated() {
+        let value = from_str::<Value>(r#"(a "bc" [1 -200 2.5] {#t #f})"#).unwrap();
+        let mut buf = Vec::new();
+        encode(&value, &mut buf);
+
+        for len in 0..buf.len() {
+            assert_eq!(decode(&buf[..len]), Err(DecodeError::UnexpectedEnd));
+        }
+    }
+
+    #[rstest]
+    #[case(&[2, 5, 1], DecodeError::UnsupportedVersion(2))]
+    #[case(&[VERSION, 8], DecodeError::InvalidTag { tag: 8, offset: 1 })]
+    #[case(&[VERSION, 5, 2], DecodeError::InvalidBool { value: 2, offset: 2 })]
+    #[case(&[VERSION, 3, 1, 0xff], DecodeError::InvalidUtf8(3))]
+    #[case(&[VERSION, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f], DecodeError::Overflow(2))]
+    #[case(&[VERSION, 6, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f], DecodeError::Overflow(2))]
+    fn invalid(#[case] buf: &[u8], #[case] error: DecodeError) {
+        assert_eq!(decode(buf), Err(error));
+    }
+
+    #[test]
+    fn nesting_limit() {
+        // A list of length one that contains the next list, with an empty list at the bottom.
+        let nested = |depth: usize| {
+            let mut buf = vec![VERSION];
+            buf.extend(std::iter::repeat_n([0, 1], depth - 1).flatten());
+            buf.extend([0, 0]);
+            buf
+        };
+
+        assert!(decode(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            decode(&nested(MAX_NESTING_DEPTH + 1)),
+            Err(DecodeError::NestingLimitExceeded(1 + 2 * MAX_NESTING_DEPTH))
+        );
+        assert!(decode(&nested(100 * MAX_NESTING_DEPTH)).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_round_trip(value: Value) {
+            prop_assert_eq!(round_trip(&value), value);
+        }
+
+        #[test]
+        fn proptest_int(int: i64) {
+            prop_assert_eq!(round_trip(&Value::Int(int)), Value::Int(int));
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Descriptions of the s-expression syntax that a type is read from.
+//!
+//! The [`Describe`] trait produces a [`Syntax`] tree for a type, which can be
+//! displayed as a usage summary in error messages or documentation. Types that
+//! derive [`FromParens`] can derive [`Describe`] with the same `sexpr` attributes.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! # use parenthesis::{Describe, FromParens, Symbol};
+//! #[derive(FromParens, Describe)]
+//! #[sexpr(tag = "operation")]
+//! struct Operation {
+//!     name: Symbol,
+//!     #[sexpr(optional)]
+//!     title: Option<String>,
+//!     #[sexpr(repeated, rename = "input")]
+//!     inputs: Vec<Symbol>,
+//! }
+//!
+//! assert_eq!(
+//!     Operation::syntax().to_string(),
+//!     "(operation <symbol> (title <string>)? (input <symbol>)...)"
+//! );
+//! assert_eq!(Operation::reference().to_string(), "<operation>");
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
+//!
+//! [`FromParens`]: crate::FromParens
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use smol_str::SmolStr;
+
+use crate::span::Spanned;
+use crate::{Bounded, Either, OrDefault, Rest, Symbol, Value};
+
+/// The syntax of the s-expressions that a type is read from.
+///
+/// When displayed, placeholders are written in angle brackets, optional syntax is
+/// followed by `?` and repeated syntax by `...`. Groups of several items that are
+/// optional or repeated, and alternatives within a list, are enclosed in `⟦⟧`,
+/// which can not be confused with the delimiters of lists, sequences and maps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    /// Any value of the named kind or type, such as `<int>` or `<operation>`.
+    Placeholder(String),
+    /// A symbol that must occur verbatim, such as a tag or the name of a field.
+    Keyword(String),
+    /// A list delimited by `()` whose elements follow the given syntax in order.
+    List(Vec<Syntax>),
+    /// A sequence delimited by `[]` whose elements follow the given syntax in order.
+    Seq(Vec<Syntax>),
+    /// A map delimited by `{}` whose elements follow the given syntax in order.
+    Map(Vec<Syntax>),
+    /// Values that follow the given syntax in order, without delimiters.
+    Sequence(Vec<Syntax>),
+    /// Syntax that may be absent.
+    Optional(Box<Syntax>),
+    /// Syntax that may occur any number of times.
+    Repeated(Box<Syntax>),
+    /// Any one of the given alternatives.
+    Alternatives(Vec<Syntax>),
+}
+
+impl Syntax {
+    /// A placeholder for a value of the named kind.
+    pub fn placeholder(name: impl Into<String>) -> Self {
+        Self::Placeholder(name.into())
+    }
+
+    /// A symbol that must occur verbatim.
+    pub fn keyword(name: impl Into<String>) -> Self {
+        Self::Keyword(name.into())
+    }
+
+    /// Make the syntax optional.
+    pub fn optional(self) -> Self {
+        Self::Optional(Box::new(self))
+    }
+
+    /// Make the syntax repeated.
+    pub fn repeated(self) -> Self {
+        Self::Repeated(Box::new(self))
+    }
+}
+
+impl Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Syntax::Placeholder(name) => write!(f, "<{}>", name),
+            Syntax::Keyword(name) => write!(f, "{}", name),
+            Syntax::List(items) => write_delimited(f, "(", items, ")"),
+            Syntax::Seq(items) => write_delimited(f, "[", items, "]"),
+            Syntax::Map(items) => write_delimited(f, "{", items, "}"),
+            Syntax::Sequence(items) => write_items(f, items, false),
+            Syntax::Optional(inner) => {
+                write_grouped(f, inner)?;
+                write!(f, "?")
+            }
+            Syntax::Repeated(inner) => {
+                write_grouped(f, inner)?;
+                write!(f, "...")
+            }
+            Syntax::Alternatives(alternatives) => {
+                for (index, alternative) in alternatives.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " | ")?;
+                    }
+
+                    write!(f, "{}", alternative)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_delimited(
+    f: &mut fmt::Formatter<'_>,
+    open: &str,
+    items: &[Syntax],
+    close: &str,
+) -> fmt::Result {
+    write!(f, "{}", open)?;
+    write_items(f, items, true)?;
+    write!(f, "{}", close)
+}
+
+/// Write items separated by spaces, where nested sequences are written in place
+/// so that empty sequences do not leave stray spaces. Alternatives are enclosed
+/// in `⟦⟧` unless they are the only item outside of a list.
+fn write_items(f: &mut fmt::Formatter<'_>, items: &[Syntax], nested: bool) -> fmt::Result {
+    let mut first = true;
+    write_items_from(f, items, nested || items.len() > 1, &mut first)
+}
+
+fn write_items_from(
+    f: &mut fmt::Formatter<'_>,
+    items: &[Syntax],
+    group: bool,
+    first: &mut bool,
+) -> fmt::Result {
+    for item in items {
+        if let Syntax::Sequence(inner) = item {
+            write_items_from(f, inner, group, first)?;
+            continue;
+        }
+
+        if !*first {
+            write!(f, " ")?;
+        }
+
+        *first = false;
+
+        match item {
+            Syntax::Alternatives(_) if group => write!(f, "⟦{}⟧", item)?,
+            item => write!(f, "{}", item)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the operand of `?` or `...`, enclosed in `⟦⟧` unless it is a single item.
+fn write_grouped(f: &mut fmt::Formatter<'_>, syntax: &Syntax) -> fmt::Result {
+    match syntax {
+        Syntax::Sequence(items) if items.len() == 1 => write_grouped(f, &items[0]),
+        Syntax::Sequence(_) | Syntax::Alternatives(_) => write!(f, "⟦{}⟧", syntax),
+        Syntax::Optional(_) | Syntax::Repeated(_) => write!(f, "⟦{}⟧", syntax),
+        syntax => write!(f, "{}", syntax),
+    }
+}
+
+/// Types that can describe the syntax they are read from.
+///
+/// This trait can be derived for types that derive [`FromParens`], see the
+/// [crate level documentation](crate) for details.
+///
+/// [`FromParens`]: crate::FromParens
+pub trait Describe {
+    /// The syntax of the values of this type.
+    fn syntax() -> Syntax;
+
+    /// The syntax that stands for this type within the syntax of other types.
+    ///
+    /// By default this is the full [`Describe::syntax`]. Derived implementations
+    /// instead return a placeholder with the name of the type, so that recursive
+    /// types can be described and each type is described only once.
+    fn reference() -> Syntax {
+        Self::syntax()
+    }
+}
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use parenthesis_macros::Describe;
+
+/// The syntax of an element of optional or repeated syntax.
+///
+/// This is used by the derive macro to describe the values of optional and
+/// repeated fields, which are written as a list with the field name.
+pub fn element(syntax: Syntax) -> Syntax {
+    match syntax {
+        Syntax::Optional(inner) | Syntax::Repeated(inner) => *inner,
+        syntax => syntax,
+    }
+}
+
+/// The syntax of a value that is written as a list with the given name in place of its tag.
+///
+/// This is used by the derive macro to describe fields with the `tagged` attribute.
+pub fn tagged(syntax: Syntax, name: &str) -> Syntax {
+    match syntax {
+        Syntax::List(mut items) if matches!(items.first(), Some(Syntax::Keyword(_))) => {
+            items[0] = Syntax::keyword(name);
+            Syntax::List(items)
+        }
+        syntax => Syntax::List(vec![Syntax::keyword(name), syntax]),
+    }
+}
+
+/// The syntax of a value that is written as a single sequence, see [`from_parens::seq`].
+///
+/// [`from_parens::seq`]: crate::from_parens::seq
+pub fn seq(syntax: Syntax) -> Syntax {
+    Syntax::Seq(vec![syntax])
+}
+
+/// The syntax of a value that is written as a map, see [`from_parens::map`].
+///
+/// The entries of a map are described as lists of a key and a value,
+/// which are written in place within the map.
+///
+/// [`from_parens::map`]: crate::from_parens::map
+pub fn map(syntax: Syntax) -> Syntax {
+    match syntax {
+        Syntax::Repeated(entry) => match *entry {
+            Syntax::List(items) => Syntax::Map(vec![Syntax::Sequence(items).repeated()]),
+            entry => Syntax::Map(vec![entry.repeated()]),
+        },
+        syntax => Syntax::Map(vec![syntax]),
+    }
+}
+
+/// Implements [`Describe`] for types that are read from a single atom of the given kind.
+macro_rules! impl_describe_placeholder {
+    ($name:literal: $($ty:ty),+) => {
+        $(
+            impl Describe for $ty {
+                fn syntax() -> Syntax {
+                    Syntax::placeholder($name)
+                }
+            }
+        )+
+    };
+}
+
+impl_describe_placeholder!("int": i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_describe_placeholder!("float": f32, f64);
+impl_describe_placeholder!("bool": bool);
+impl_describe_placeholder!("string": String, SmolStr, str);
+impl_describe_placeholder!("char": char);
+impl_describe_placeholder!("symbol": Symbol);
+impl_describe_placeholder!("value": Value);
+
+impl<const MIN: i64, const MAX: i64> Describe for Bounded<MIN, MAX> {
+    fn syntax() -> Syntax {
+        i64::syntax()
+    }
+}
+
+impl Describe for Rest {
+    fn syntax() -> Syntax {
+        Value::syntax().repeated()
+    }
+}
+
+impl<T: Describe> Describe for Option<T> {
+    fn syntax() -> Syntax {
+        T::syntax().optional()
+    }
+
+    fn reference() -> Syntax {
+        T::reference().optional()
+    }
+}
+
+impl<T: Describe> Describe for OrDefault<T> {
+    fn syntax() -> Syntax {
+        T::syntax().optional()
+    }
+
+    fn reference() -> Syntax {
+        T::reference().optional()
+    }
+}
+
+impl<A: Describe, B: Describe> Describe for Either<A, B> {
+    fn syntax() -> Syntax {
+        Syntax::Alternatives(vec![A::reference(), B::reference()])
+    }
+}
+
+/// Implements [`Describe`] for collections that are read from the remaining values.
+macro_rules! impl_describe_repeated {
+    ($($ty:ident),+) => {
+        $(
+            impl<T: Describe> Describe for $ty<T> {
+                fn syntax() -> Syntax {
+                    T::syntax().repeated()
+                }
+
+                fn reference() -> Syntax {
+                    T::reference().repeated()
+                }
+            }
+        )+
+    };
+}
+
+impl_describe_repeated!(Vec, VecDeque, BTreeSet);
+
+impl<T: Describe, S> Describe for HashSet<T, S> {
+    fn syntax() -> Syntax {
+        T::syntax().repeated()
+    }
+
+    fn reference() -> Syntax {
+        T::reference().repeated()
+    }
+}
+
+impl<K: Describe, V: Describe> Describe for BTreeMap<K, V> {
+    fn syntax() -> Syntax {
+        Syntax::List(vec![K::reference(), V::reference()]).repeated()
+    }
+}
+
+impl<K: Describe, V: Describe, S> Describe for HashMap<K, V, S> {
+    fn syntax() -> Syntax {
+        Syntax::List(vec![K::reference(), V::reference()]).repeated()
+    }
+}
+
+/// Implements [`Describe`] for pointers that are read like the type they point to.
+macro_rules! impl_describe_pointer {
+    ($($ty:ident),+) => {
+        $(
+            impl<T: Describe + ?Sized> Describe for $ty<T> {
+                fn syntax() -> Syntax {
+                    T::syntax()
+                }
+
+                fn reference() -> Syntax {
+                    T::reference()
+                }
+            }
+        )+
+    };
+}
+
+impl_describe_pointer!(Box, Rc, Arc);
+
+impl<T: Describe> Describe for Spanned<T> {
+    fn syntax() -> Syntax {
+        T::syntax()
+    }
+
+    fn reference() -> Syntax {
+        T::reference()
+    }
+}
+
+impl<T: Describe + ?Sized> Describe for &T {
+    fn syntax() -> Syntax {
+        T::syntax()
+    }
+
+    fn reference() -> Syntax {
+        T::reference()
+    }
+}
+
+impl<'a, T: Describe + ToOwned + ?Sized> Describe for Cow<'a, T> {
+    fn syntax() -> Syntax {
+        T::syntax()
+    }
+
+    fn reference() -> Syntax {
+        T::reference()
+    }
+}
+
+impl Describe for () {
+    fn syntax() -> Syntax {
+        Syntax::Sequence(Vec::new())
+    }
+}
+
+impl<T: ?Sized> Describe for PhantomData<T> {
+    fn syntax() -> Syntax {
+        Syntax::Sequence(Vec::new())
+    }
+}
+
+/// Implements [`Describe`] for a tuple whose elements are read in order.
+macro_rules! impl_describe_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Describe),+> Describe for ($($name,)+) {
+            fn syntax() -> Syntax {
+                Syntax::Sequence(vec![$($name::reference()),+])
+            }
+        }
+    };
+}
+
+impl_describe_tuple!(A);
+impl_describe_tuple!(A, B);
+impl_describe_tuple!(A, B, C);
+impl_describe_tuple!(A, B, C, D);
+impl_describe_tuple!(A, B, C, D, E);
+impl_describe_tuple!(A, B, C, D, E, F);
+
+#[cfg(test)]
+mod test {
+    use super::{map, tagged, Describe, Syntax};
+    use crate::{Rest, Symbol};
+    use rstest::rstest;
+    use std::collections::BTreeMap;
+
+    #[rstest]
+    #[case(i32::syntax(), "<int>")]
+    #[case(Option::<String>::syntax(), "<string>?")]
+    #[case(Vec::<(Symbol, f64)>::syntax(), "⟦<symbol> <float>⟧...")]
+    #[case(BTreeMap::<Symbol, i64>::syntax(), "(<symbol> <int>)...")]
+    #[case(map(BTreeMap::<Symbol, i64>::syntax()), "{⟦<symbol> <int>⟧...}")]
+    #[case(Option::<char>::syntax(), "<char>?")]
+    #[case(Rest::syntax(), "<value>...")]
+    #[case(<()>::syntax(), "")]
+    #[case(
+        Syntax::List(vec![
+            Syntax::keyword("pair"),
+            Syntax::Sequence(Vec::new()),
+            Syntax::Alternatives(vec![Syntax::placeholder("int"), Syntax::keyword("none")]),
+        ]),
+        "(pair ⟦<int> | none⟧)"
+    )]
+    #[case(
+        tagged(Syntax::List(vec![Syntax::keyword("op"), Syntax::placeholder("int")]), "input"),
+        "(input <int>)"
+    )]
+    fn display(#[case] syntax: Syntax, #[case] expected: &str) {
+        assert_eq!(syntax.to_string(), expected);
+    }
+}
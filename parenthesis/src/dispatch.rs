@@ -0,0 +1,210 @@
+//! Parsing forms by dispatching on their head symbol.
+use std::collections::BTreeMap;
+
+use crate::from_parens::{InputStream, ParseError};
+use crate::Symbol;
+
+/// Parser for the remaining elements of a form after its head symbol.
+pub type FormParser<I, T> = fn(&mut I) -> Result<T, ParseError<<I as InputStream>::Span>>;
+
+/// Parser for forms whose head symbol has no registered parser.
+pub type FallbackParser<I, T> =
+    fn(Symbol, &mut I) -> Result<T, ParseError<<I as InputStream>::Span>>;
+
+/// Registry of parsers keyed by the head symbol of a form.
+///
+/// This allows to parse open sets of forms, which can be extended at runtime
+/// and therefore can not be represented by a closed enum.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_parens::{FromParens, InputStream, ParseError}, Dispatcher, Value};
+/// fn parse_add<I: InputStream>(stream: &mut I) -> Result<i64, ParseError<I::Span>> {
+///     Ok(i64::from_parens(stream)? + i64::from_parens(stream)?)
+/// }
+///
+/// let mut dispatcher = Dispatcher::new();
+/// dispatcher.register("add", parse_add);
+///
+/// let values: Vec<Value> = parenthesis::from_str("(add 1 2)").unwrap();
+/// assert_eq!(dispatcher.parse(&mut values.as_slice()).unwrap(), 3);
+/// ```
+pub struct Dispatcher<I: InputStream, T> {
+    parsers: BTreeMap<Symbol, FormParser<I, T>>,
+    fallback: Option<FallbackParser<I, T>>,
+}
+
+impl<I: InputStream, T> Dispatcher<I, T> {
+    /// Create a new [`Dispatcher`] without any registered parsers.
+    pub fn new() -> Self {
+        Self {
+            parsers: BTreeMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Register a parser for forms with the given head symbol.
+    ///
+    /// The parser is given the stream of the form's elements after the head,
+    /// all of which it must consume. Any parser previously registered for the same head is replaced.
+    pub fn register(&mut self, head: impl Into<Symbol>, parser: FormParser<I, T>) -> &mut Self {
+        self.parsers.insert(head.into(), parser);
+        self
+    }
+
+    /// Set a parser for forms whose head has no registered parser.
+    ///
+    /// The parser is given the head symbol and the stream of the form's elements after the head,
+    /// all of which it must consume.
+    pub fn fallback(&mut self, parser: FallbackParser<I, T>) -> &mut Self {
+        self.fallback = Some(parser);
+        self
+    }
+
+    /// Iterate over the head symbols with a registered parser in order.
+    pub fn heads(&self) -> impl Iterator<Item = &Symbol> {
+        self.parsers.keys()
+    }
+
+    /// Parse the next form in the stream with the parser registered for its head symbol.
+    pub fn parse(&self, stream: &mut I) -> Result<T, ParseError<I::Span>> {
+        let Some(head) = stream.peek_head() else {
+            stream.next();
+            return Err(ParseError::new(
+                "expected form with head symbol",
+                stream.span(),
+            ));
+        };
+
+        let mut list = stream.expect_list()?;
+        list.next();
+
+        let value = if let Some(parser) = self.parsers.get(&head) {
+            parser(&mut list)?
+        } else if let Some(fallback) = self.fallback {
+            fallback(head, &mut list)?
+        } else if self.parsers.is_empty() {
+            return Err(ParseError::new(
+                format!("unknown form `{}`", head),
+                list.parent_span(),
+            ));
+        } else {
+            let known = self
+                .parsers
+                .keys()
+                .map(|head| format!("`{}`", head))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(ParseError::new(
+                format!("unknown form `{}`, expected one of {}", head, known),
+                list.parent_span(),
+            ));
+        };
+
+        if !list.is_end() {
+            let token_tree = list.next();
+            return Err(ParseError::unexpected_token(
+                "no more values",
+                token_tree.as_ref(),
+                list.span(),
+            ));
+        }
+
+        Ok(value)
+    }
+}
+
+impl<I: InputStream, T> Default for Dispatcher<I, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Dispatcher;
+    use crate::from_parens::{FromParens, InputStream, ParseError};
+    use crate::{from_str, Symbol, Value};
+
+    fn parse_int<I: InputStream>(stream: &mut I) -> Result<Value, ParseError<I::Span>> {
+        Ok(Value::Int(i64::from_parens(stream)?))
+    }
+
+    fn parse_string<I: InputStream>(stream: &mut I) -> Result<Value, ParseError<I::Span>> {
+        Ok(Value::String(FromParens::from_parens(stream)?))
+    }
+
+    fn parse_unknown<I: InputStream>(
+        head: Symbol,
+        stream: &mut I,
+    ) -> Result<Value, ParseError<I::Span>> {
+        Vec::<Value>::from_parens(stream)?;
+        Ok(Value::Symbol(head))
+    }
+
+    #[test]
+    fn dispatch_on_head() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .register("int", parse_int)
+            .register("string", parse_string);
+
+        let values: Vec<Value> = from_str(r#"(string "a") (int 1)"#).unwrap();
+        let mut stream = values.as_slice();
+
+        assert_eq!(dispatcher.parse(&mut stream).unwrap(), Value::from("a"));
+        assert_eq!(dispatcher.parse(&mut stream).unwrap(), Value::from(1));
+        assert!(stream.is_end());
+    }
+
+    #[test]
+    fn dispatch_unknown() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .register("string", parse_string)
+            .register("int", parse_int);
+
+        let values: Vec<Value> = from_str(r#"(float 1.0)"#).unwrap();
+        let error = dispatcher.parse(&mut values.as_slice()).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown form `float`, expected one of `int`, `string`"
+        );
+    }
+
+    #[test]
+    fn dispatch_fallback() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .register("int", parse_int)
+            .fallback(parse_unknown);
+
+        let values: Vec<Value> = from_str(r#"(other 1.0)"#).unwrap();
+        let result = dispatcher.parse(&mut values.as_slice()).unwrap();
+
+        assert_eq!(result, Value::Symbol("other".into()));
+    }
+
+    #[test]
+    fn dispatch_leftover_values() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register("int", parse_int);
+
+        let values: Vec<Value> = from_str(r#"(int 1 "a")"#).unwrap();
+        let error = dispatcher.parse(&mut values.as_slice()).unwrap_err();
+
+        assert_eq!(error.to_string(), "expected no more values, found string");
+    }
+
+    #[test]
+    fn dispatch_without_head() {
+        for text in ["1", "(1)", "()"] {
+            let values: Vec<Value> = from_str(text).unwrap();
+            let dispatcher = Dispatcher::<&[Value], Value>::new();
+            assert!(dispatcher.parse(&mut values.as_slice()).is_err());
+        }
+    }
+}
@@ -1,4 +1,7 @@
+//! Escaping of strings and symbols.
 use logos::Logos;
+use std::ops::Range;
+use thiserror::Error;
 
 /// Lexer token for an escaped string or symbol.
 #[derive(Debug, Clone, Logos)]
@@ -12,40 +15,158 @@ enum EscapedToken {
     Escaped(char),
 
     #[regex(r#"\\u\{[a-fA-F0-9]+\}"#, |lex| parse_unicode(lex.slice()))]
-    Unicode(char),
+    Unicode(u32),
 
     #[regex(r#"[^\\]"#)]
     Literal,
 }
 
-/// Parses a unicode escape sequence of the form `\u{HEX}` where `HEX` is a
-/// hexadecimal number representing a unicode codepoint.
-fn parse_unicode(str: &str) -> Option<char> {
+/// Parses the code point of a unicode escape sequence of the form `\u{HEX}` where
+/// `HEX` is a hexadecimal number. Code points that do not fit into a `u32` saturate.
+fn parse_unicode(str: &str) -> u32 {
     // Skip the '\u{' prefix and '}' suffix
-    let hex = str.get(3..str.len() - 1)?;
-    let code = u32::from_str_radix(hex, 16).ok()?;
-    char::from_u32(code)
+    let hex = &str[3..str.len() - 1];
+    u32::from_str_radix(hex, 16).unwrap_or(u32::MAX)
+}
+
+/// Error when replacing the escape sequences of a string or symbol.
+///
+/// The offsets are the byte offsets of the backslash that starts the escape
+/// sequence, relative to the escaped string without its delimiters.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum UnescapeError {
+    /// A backslash is followed by a character that does not start an escape sequence.
+    #[error("unknown escape sequence `\\{char}`")]
+    UnknownEscape {
+        /// The character after the backslash.
+        char: char,
+        /// The offset of the escape sequence.
+        offset: usize,
+    },
+    /// A unicode escape sequence does not stand for a unicode character.
+    #[error("invalid unicode code point `{code:x}`")]
+    InvalidUnicode {
+        /// The code point, which saturates at `u32::MAX`.
+        code: u32,
+        /// The offset of the escape sequence.
+        offset: usize,
+    },
+    /// An escape sequence is incomplete, such as a backslash at the end of
+    /// the string or a unicode escape sequence without its closing brace.
+    #[error("unterminated escape sequence")]
+    UnterminatedEscape {
+        /// The offset of the escape sequence.
+        offset: usize,
+    },
+}
+
+impl UnescapeError {
+    /// The offset of the escape sequence that caused the error.
+    pub fn offset(&self) -> usize {
+        match self {
+            UnescapeError::UnknownEscape { offset, .. } => *offset,
+            UnescapeError::InvalidUnicode { offset, .. } => *offset,
+            UnescapeError::UnterminatedEscape { offset } => *offset,
+        }
+    }
+
+    /// The range of the escape sequence that caused the error within the escaped string.
+    pub fn span(&self, str: &str) -> Range<usize> {
+        let offset = self.offset();
+
+        let end = match self {
+            UnescapeError::UnknownEscape { char, .. } => offset + 1 + char.len_utf8(),
+            UnescapeError::InvalidUnicode { .. } => str[offset..]
+                .find('}')
+                .map_or(str.len(), |index| offset + index + 1),
+            UnescapeError::UnterminatedEscape { .. } => str.len(),
+        };
+
+        offset..end
+    }
 }
 
 /// Replaces escape sequences with their corresponding characters.
-pub fn unescape(str: &str) -> Option<String> {
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::escape::{unescape, UnescapeError};
+/// assert_eq!(unescape(r"a\n\u{e9}").unwrap(), "a\né");
+/// assert_eq!(
+///     unescape(r"a\q"),
+///     Err(UnescapeError::UnknownEscape { char: 'q', offset: 1 })
+/// );
+/// ```
+pub fn unescape(str: &str) -> Result<String, UnescapeError> {
     let mut lexer = EscapedToken::lexer(str);
     let mut output = String::with_capacity(str.len());
 
     while let Some(token) = lexer.next() {
-        let token = token.ok()?;
+        let offset = lexer.span().start;
+
+        // Every character other than a backslash is a literal, so the
+        // lexer can only fail at a backslash that starts an escape sequence.
+        let Ok(token) = token else {
+            let mut rest = str[offset..].chars().skip(1);
+
+            return Err(match rest.next() {
+                None | Some('u') => UnescapeError::UnterminatedEscape { offset },
+                Some(char) => UnescapeError::UnknownEscape { char, offset },
+            });
+        };
 
         match token {
             EscapedToken::Escaped(c) => output.push(c),
-            EscapedToken::Unicode(c) => output.push(c),
+            EscapedToken::Unicode(code) => match char::from_u32(code) {
+                Some(c) => output.push(c),
+                None => return Err(UnescapeError::InvalidUnicode { code, offset }),
+            },
             EscapedToken::Literal => output.push_str(lexer.slice()),
         }
     }
 
-    Some(output)
+    Ok(output)
 }
 
+/// How characters outside of ASCII are written in strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EscapeMode {
+    /// Characters outside of ASCII are written as they are.
+    #[default]
+    Unicode,
+    /// Characters outside of ASCII are escaped as `\u{HHHH}`, so that the
+    /// output consists of ASCII characters only.
+    AsciiOnly,
+}
+
+/// Escape the special characters of a string, without the surrounding quotes.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::escape::escape_string;
+/// assert_eq!(escape_string("a \"b\"\n"), r#"a \"b\"\n"#);
+/// assert_eq!(escape_string("é"), "é");
+/// ```
 pub fn escape_string(str: &str) -> String {
+    escape_string_with_mode(str, EscapeMode::Unicode)
+}
+
+/// Escape the special characters of a string as well as all characters outside of ASCII.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::escape::escape_string_ascii;
+/// assert_eq!(escape_string_ascii("café ☕"), r#"caf\u{00e9} \u{2615}"#);
+/// ```
+pub fn escape_string_ascii(str: &str) -> String {
+    escape_string_with_mode(str, EscapeMode::AsciiOnly)
+}
+
+/// Escape the special characters of a string with the given [`EscapeMode`].
+pub fn escape_string_with_mode(str: &str, mode: EscapeMode) -> String {
     let mut output = String::with_capacity(str.len());
 
     for c in str.chars() {
@@ -55,6 +176,9 @@ pub fn escape_string(str: &str) -> String {
             '\t' => output.push_str(r#"\t"#),
             '"' => output.push_str(r#"\""#),
             '\\' => output.push_str(r#"\\"#),
+            c if mode == EscapeMode::AsciiOnly && !c.is_ascii() => {
+                output.push_str(&format!("\\u{{{:04x}}}", c as u32));
+            }
             c => output.push(c),
         }
     }
@@ -76,16 +200,15 @@ enum BareSymbol {
 
 /// Escape a symbol. If the symbol can occur on its own, it is returned as is.
 /// Otherwise it is escaped and surrounded by `|` characters.
+///
+/// A symbol can occur on its own if the reader would read it as a single symbol.
+/// This excludes the empty symbol and symbols that start with a digit, with `#`,
+/// or with `+` or `-` followed by a digit, since they would be read as numbers,
+/// booleans or several tokens.
 pub fn escape_symbol(str: &str) -> String {
     // If the symbol is fine without escaping, we can return it directly.
-    {
-        let mut lexer = BareSymbol::lexer(str);
-        let first_token = lexer.next();
-        let second_token = lexer.next();
-
-        if matches!(first_token, Some(Ok(_))) && second_token.is_none() {
-            return str.to_string();
-        }
+    if is_bare_symbol(str) {
+        return str.to_string();
     }
 
     let mut output = String::with_capacity(str.len() + 2);
@@ -106,9 +229,41 @@ pub fn escape_symbol(str: &str) -> String {
     output
 }
 
+/// Check if a symbol can be printed without escaping.
+fn is_bare_symbol(str: &str) -> bool {
+    let mut lexer = BareSymbol::lexer(str);
+    let first_token = lexer.next();
+    let second_token = lexer.next();
+    matches!(first_token, Some(Ok(_))) && second_token.is_none()
+}
+
+/// The length in bytes of the result of [`escape_string`], without allocating it.
+pub(crate) fn escaped_string_len(str: &str) -> usize {
+    let escapes = str
+        .bytes()
+        .filter(|byte| matches!(byte, b'\n' | b'\r' | b'\t' | b'"' | b'\\'))
+        .count();
+    str.len() + escapes
+}
+
+/// The length in bytes of the result of [`escape_symbol`], without allocating it.
+pub(crate) fn escaped_symbol_len(str: &str) -> usize {
+    if is_bare_symbol(str) {
+        return str.len();
+    }
+
+    let escapes = str
+        .bytes()
+        .filter(|byte| matches!(byte, b'\n' | b'\r' | b'\t' | b'|' | b'\\'))
+        .count();
+    str.len() + escapes + 2
+}
+
 #[cfg(test)]
 mod test {
-    use super::{escape_string, escape_symbol, unescape};
+    use super::{escape_string, escape_string_ascii, escape_symbol, unescape, UnescapeError};
+    use crate::{from_str, Symbol};
+    use proptest::prelude::*;
     use rstest::rstest;
 
     #[rstest]
@@ -126,6 +281,13 @@ mod test {
     #[case("+any", "+any")]
     #[case("-any", "-any")]
     #[case("#symbol", "|#symbol|")]
+    #[case("123abc", "|123abc|")]
+    #[case("+1a", "|+1a|")]
+    #[case("-1.5", "|-1.5|")]
+    #[case("+.5", "+.5")]
+    #[case("#t", "|#t|")]
+    #[case("a b", "|a b|")]
+    #[case("a;b", "|a;b|")]
     fn test_escape_symbol(#[case] symbol: &str, #[case] expected: &str) {
         assert_eq!(expected, escape_symbol(symbol));
     }
@@ -141,6 +303,18 @@ mod test {
         assert_eq!(expected, escape_string(string));
     }
 
+    #[rstest]
+    #[case("string", "string")]
+    #[case("\n\"", r#"\n\""#)]
+    #[case("é", r"\u{00e9}")]
+    #[case("\u{1F60A}", r"\u{1f60a}")]
+    #[case("\u{7f}", "\u{7f}")]
+    fn test_escape_string_ascii(#[case] string: &str, #[case] expected: &str) {
+        let escaped = escape_string_ascii(string);
+        assert_eq!(expected, escaped);
+        assert_eq!(string, unescape(&escaped).unwrap());
+    }
+
     #[rstest]
     #[case(r#"\""#, r#"""#)]
     #[case(r"\|", "|")]
@@ -148,4 +322,33 @@ mod test {
     fn test_unescape(#[case] escaped: &str, #[case] expected: &str) {
         assert_eq!(expected, unescape(escaped).unwrap());
     }
+
+    #[rstest]
+    #[case(r"\q", UnescapeError::UnknownEscape { char: 'q', offset: 0 })]
+    #[case(r"ab\\\é", UnescapeError::UnknownEscape { char: 'é', offset: 4 })]
+    #[case(r"\u{d800}", UnescapeError::InvalidUnicode { code: 0xd800, offset: 0 })]
+    #[case(r"a\u{110000}", UnescapeError::InvalidUnicode { code: 0x110000, offset: 1 })]
+    #[case(r"\u{123456789}", UnescapeError::InvalidUnicode { code: u32::MAX, offset: 0 })]
+    #[case(r"\u{41", UnescapeError::UnterminatedEscape { offset: 0 })]
+    #[case(r"\u{}", UnescapeError::UnterminatedEscape { offset: 0 })]
+    #[case(r"a\", UnescapeError::UnterminatedEscape { offset: 1 })]
+    fn test_unescape_error(#[case] escaped: &str, #[case] expected: UnescapeError) {
+        assert_eq!(Err(expected), unescape(escaped));
+    }
+
+    proptest! {
+        #[test]
+        fn escape_symbol_round_trip(symbol: Symbol) {
+            let escaped = escape_symbol(symbol.as_ref());
+            prop_assert_eq!(from_str::<Symbol>(&escaped).unwrap(), symbol);
+        }
+
+        /// Symbols made of the characters that are most likely to be confused
+        /// with other tokens, which arbitrary strings rarely produce.
+        #[test]
+        fn escape_symbol_round_trip_ascii(symbol in r#"[0-9a-z+\-.#|\\;"' ()\[\]{}\t\n]{0,8}"#) {
+            let escaped = escape_symbol(&symbol);
+            prop_assert_eq!(from_str::<Symbol>(&escaped).unwrap(), Symbol::new(symbol));
+        }
+    }
 }
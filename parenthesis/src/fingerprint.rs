@@ -0,0 +1,166 @@
+//! Stable fingerprints of values.
+//!
+//! A fingerprint is a 64 bit hash of the contents of a value that is the same
+//! across runs, platforms and versions of Rust. Unlike comparing with `==`,
+//! fingerprints can be stored, for example as keys of a cache or in test snapshots.
+//! Different values may have the same fingerprint, although this is unlikely.
+use siphasher::sip::SipHasher13;
+use std::hash::Hasher;
+
+use crate::Value;
+
+/// Compute the fingerprint of a value.
+///
+/// Values that are equal have the same fingerprint. In particular this holds
+/// for floats, where all NaNs are equal and `0.0` is equal to `-0.0`.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{fingerprint::fingerprint, from_str, Value};
+/// let a = from_str::<Value>("(define x [1 2.0])").unwrap();
+/// let b = from_str::<Value>("(define  x  [1 2.0])").unwrap();
+/// let c = from_str::<Value>("(define x (1 2.0))").unwrap();
+/// assert_eq!(fingerprint(&a), fingerprint(&b));
+/// assert_ne!(fingerprint(&a), fingerprint(&c));
+/// ```
+pub fn fingerprint(value: &Value) -> u64 {
+    let mut hasher = SipHasher13::new();
+    write_value(&mut hasher, value);
+    hasher.finish()
+}
+
+/// Compute the fingerprint of a sequence of values, such as the top level values of a file.
+///
+/// This differs from the fingerprint of a list that contains the values.
+pub fn fingerprint_slice(values: &[Value]) -> u64 {
+    let mut hasher = SipHasher13::new();
+    write_values(&mut hasher, values);
+    hasher.finish()
+}
+
+/// Feed a value into the hasher.
+///
+/// We do not use the `Hash` implementation of [`Value`], since it feeds
+/// lengths and discriminants with a width that depends on the platform.
+fn write_value(hasher: &mut SipHasher13, value: &Value) {
+    match value {
+        Value::List(values) => {
+            hasher.write_u8(0);
+            write_values(hasher, values);
+        }
+        Value::Seq(values) => {
+            hasher.write_u8(1);
+            write_values(hasher, values);
+        }
+        Value::Map(values) => {
+            hasher.write_u8(2);
+            write_values(hasher, values);
+        }
+        Value::String(string) => {
+            hasher.write_u8(3);
+            write_str(hasher, string);
+        }
+        Value::Symbol(symbol) => {
+            hasher.write_u8(4);
+            write_str(hasher, symbol.as_ref());
+        }
+        Value::Bool(bool) => {
+            hasher.write_u8(5);
+            hasher.write_u8(u8::from(*bool));
+        }
+        Value::Int(int) => {
+            hasher.write_u8(6);
+            hasher.write_i64(*int);
+        }
+        Value::Float(float) => {
+            // All NaNs are equal and so are both zeros, so they must agree on their bits.
+            let bits = if float.is_nan() {
+                f64::NAN.to_bits()
+            } else if float.0 == 0.0 {
+                0
+            } else {
+                float.to_bits()
+            };
+
+            hasher.write_u8(7);
+            hasher.write_u64(bits);
+        }
+    }
+}
+
+fn write_values(hasher: &mut SipHasher13, values: &[Value]) {
+    hasher.write_u64(values.len() as u64);
+
+    for value in values {
+        write_value(hasher, value);
+    }
+}
+
+fn write_str(hasher: &mut SipHasher13, str: &str) {
+    hasher.write_u64(str.len() as u64);
+    hasher.write(str.as_bytes());
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fingerprint, fingerprint_slice};
+    use crate::{from_str, Value};
+    use ordered_float::OrderedFloat;
+    use proptest::prelude::*;
+
+    #[test]
+    fn stable() {
+        // The fingerprint must not change between runs, platforms or versions.
+        let value = from_str::<Value>(r#"(a "b" [1 2.5] {#t #f})"#).unwrap();
+        assert_eq!(fingerprint(&value), 15016812978283281702);
+    }
+
+    #[test]
+    fn distinguishes_kinds() {
+        let values = from_str::<Vec<Value>>(r#"(a) [a] {a} "a" a () 1 1.0 #t"#).unwrap();
+
+        for (i, a) in values.iter().enumerate() {
+            for b in &values[i + 1..] {
+                assert_ne!(fingerprint(a), fingerprint(b), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn floats() {
+        let nan = Value::Float(OrderedFloat(f64::NAN));
+        let other_nan = Value::Float(OrderedFloat(f64::from_bits(f64::NAN.to_bits() ^ 1)));
+        assert_eq!(fingerprint(&nan), fingerprint(&other_nan));
+        assert_eq!(
+            fingerprint(&Value::from(0.0)),
+            fingerprint(&Value::from(-0.0))
+        );
+    }
+
+    #[test]
+    fn slice() {
+        let values = from_str::<Vec<Value>>("a b").unwrap();
+        assert_eq!(
+            fingerprint_slice(&values),
+            fingerprint_slice(&values.clone())
+        );
+        assert_ne!(
+            fingerprint_slice(&values),
+            fingerprint(&Value::List(values))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn equal_values(a: Value, b: Value) {
+            prop_assert_eq!(fingerprint(&a), fingerprint(&a.clone()));
+
+            if a == b {
+                prop_assert_eq!(fingerprint(&a), fingerprint(&b));
+            } else {
+                prop_assert_ne!(fingerprint(&a), fingerprint(&b));
+            }
+        }
+    }
+}
@@ -1,24 +1,72 @@
 //! Types that can be constructed from s-expressions.
 use smol_str::SmolStr;
+use std::borrow::Cow;
+use std::collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::num::{
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32,
+    NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
 use std::{error::Error, fmt::Display};
 use thiserror::Error;
 
-use crate::{Symbol, Value};
+use crate::{Bounded, Either, OrDefault, Rest, Symbol, Value};
 
 /// Input stream that emits s-expression tokens.
 pub trait InputStream: Sized {
     /// Span that identifies the location of a token.
     type Span;
 
+    /// Opaque position in the stream, see [`InputStream::checkpoint`].
+    ///
+    /// Two checkpoints of the same stream are equal if and only if
+    /// they refer to the same position.
+    type Checkpoint: Clone + PartialEq;
+
     /// Advance to the next token and return it.
     fn next(&mut self) -> Option<TokenTree<Self>>;
 
     /// Return the next token without advancing.
     fn peek(&self) -> Option<TokenTree<Self>>;
 
+    /// Record the current position of the stream.
+    fn checkpoint(&self) -> Self::Checkpoint;
+
+    /// Reset the stream to a position recorded by [`InputStream::checkpoint`].
+    fn restore(&mut self, checkpoint: Self::Checkpoint);
+
+    /// Return the token `n` positions ahead without advancing,
+    /// where `peek_n(0)` is the same as [`InputStream::peek`].
+    ///
+    /// The default implementation clones the stream and advances the clone.
+    fn peek_n(&self, n: usize) -> Option<TokenTree<Self>>
+    where
+        Self: Clone,
+    {
+        let mut stream = self.clone();
+
+        for _ in 0..n {
+            stream.next()?;
+        }
+
+        stream.peek()
+    }
+
     /// The span of the last token returned by [`InputStream::next`].
     fn span(&self) -> Self::Span;
 
+    /// The byte range in the source text that is covered by a span,
+    /// if the stream has been read from text.
+    fn byte_range(span: &Self::Span) -> Option<Range<usize>> {
+        let _ = span;
+        None
+    }
+
     /// The span of the parent stream, if any.
     ///
     /// For the root stream of some input, this returns the span of the entire input.
@@ -28,10 +76,127 @@ pub trait InputStream: Sized {
     fn is_end(&self) -> bool {
         self.peek().is_none()
     }
+
+    /// Advance past the comments at the current position, if any.
+    ///
+    /// Comments are only returned by streams that preserve them,
+    /// see [`crate::ReadOptions::with_preserve_comments`].
+    fn skip_comments(&mut self) -> &mut Self {
+        while let Some(TokenTree::Comment(_)) = self.peek() {
+            self.next();
+        }
+
+        self
+    }
+
+    /// Return the head symbol of the next token without advancing,
+    /// if the next token is a list that starts with a symbol.
+    fn peek_head(&self) -> Option<Symbol> {
+        let TokenTree::List(list) = self.peek()? else {
+            return None;
+        };
+
+        let TokenTree::Symbol(head) = list.peek()? else {
+            return None;
+        };
+
+        Some(head)
+    }
+
+    /// Construct a custom [`ParseError`] at the span of the last token.
+    ///
+    /// This is intended for semantic errors detected by hand-written parsers.
+    fn error(&self, message: impl Display) -> ParseError<Self::Span> {
+        ParseError::custom(message, self.span())
+    }
+
+    /// Parse a value, or use its default value if there are no more tokens.
+    ///
+    /// See [`OrDefault`] for a type that uses this to parse.
+    fn parse_or_default<T>(&mut self) -> Result<T, ParseError<Self::Span>>
+    where
+        T: FromParens<Self> + Default,
+    {
+        if self.is_end() {
+            Ok(T::default())
+        } else {
+            T::from_parens(self)
+        }
+    }
+
+    /// Parse a value, restoring the stream to its previous position on failure.
+    ///
+    /// This can be used to try several alternatives in turn.
+    fn try_parse<T>(&mut self) -> Result<T, ParseError<Self::Span>>
+    where
+        T: FromParens<Self>,
+    {
+        let checkpoint = self.checkpoint();
+        T::from_parens(self).inspect_err(|_| self.restore(checkpoint))
+    }
+
+    /// Parse a value and report on failure whether the stream was advanced.
+    ///
+    /// Unlike [`InputStream::try_parse`], the stream is not restored.
+    /// See [`RecoverableParseError`] for details. Note that the parsers for
+    /// scalar values advance past a token of the wrong kind.
+    fn parse_recoverable<T>(&mut self) -> Result<T, RecoverableParseError<Self::Span>>
+    where
+        T: FromParens<Self>,
+    {
+        let checkpoint = self.checkpoint();
+        T::from_parens(self).map_err(|error| {
+            if self.checkpoint() == checkpoint {
+                RecoverableParseError::Recoverable(error)
+            } else {
+                RecoverableParseError::Unrecoverable(error)
+            }
+        })
+    }
+
+    /// Advance to the next token, expecting it to be a list,
+    /// and return the stream of the list's elements.
+    fn expect_list(&mut self) -> Result<Self, ParseError<Self::Span>> {
+        match self.next() {
+            Some(TokenTree::List(list)) => Ok(list),
+            token_tree => Err(ParseError::unexpected_token(
+                "list",
+                token_tree.as_ref(),
+                self.span(),
+            )),
+        }
+    }
+
+    /// Advance to the next token, expecting it to be a sequence,
+    /// and return the stream of the sequence's elements.
+    fn expect_seq(&mut self) -> Result<Self, ParseError<Self::Span>> {
+        match self.next() {
+            Some(TokenTree::Seq(seq)) => Ok(seq),
+            token_tree => Err(ParseError::unexpected_token(
+                "sequence",
+                token_tree.as_ref(),
+                self.span(),
+            )),
+        }
+    }
+
+    /// Advance to the next token, expecting it to be a map,
+    /// and return the stream of the map's elements.
+    fn expect_map(&mut self) -> Result<Self, ParseError<Self::Span>> {
+        match self.next() {
+            Some(TokenTree::Map(map)) => Ok(map),
+            token_tree => Err(ParseError::unexpected_token(
+                "map",
+                token_tree.as_ref(),
+                self.span(),
+            )),
+        }
+    }
 }
 
-impl InputStream for &[Value] {
+impl<'a> InputStream for &'a [Value] {
     type Span = ();
+    type Checkpoint = SliceCheckpoint<'a>;
 
     #[inline]
     fn next(&mut self) -> Option<TokenTree<Self>> {
@@ -46,6 +211,22 @@ impl InputStream for &[Value] {
         Some(value_to_token(value))
     }
 
+    #[inline]
+    fn peek_n(&self, n: usize) -> Option<TokenTree<Self>> {
+        let value = self.get(n)?;
+        Some(value_to_token(value))
+    }
+
+    #[inline]
+    fn checkpoint(&self) -> Self::Checkpoint {
+        SliceCheckpoint(self)
+    }
+
+    #[inline]
+    fn restore(&mut self, checkpoint: Self::Checkpoint) {
+        *self = checkpoint.0;
+    }
+
     #[inline]
     fn span(&self) -> Self::Span {}
 
@@ -58,9 +239,21 @@ impl InputStream for &[Value] {
     }
 }
 
+/// Position in a slice of values, see [`InputStream::checkpoint`].
+#[derive(Debug, Clone, Copy)]
+pub struct SliceCheckpoint<'a>(&'a [Value]);
+
+impl<'a> PartialEq for SliceCheckpoint<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
 fn value_to_token(value: &Value) -> TokenTree<&[Value]> {
     match value {
         Value::List(list) => TokenTree::List(list),
+        Value::Seq(seq) => TokenTree::Seq(seq),
+        Value::Map(map) => TokenTree::Map(map),
         Value::String(string) => TokenTree::String(string.clone()),
         Value::Symbol(symbol) => TokenTree::Symbol(symbol.clone()),
         Value::Bool(bool) => TokenTree::Bool(*bool),
@@ -69,6 +262,87 @@ fn value_to_token(value: &Value) -> TokenTree<&[Value]> {
     }
 }
 
+/// Input stream over a slice of already parsed [`Value`]s.
+///
+/// This allows to reinterpret values as a more specific type:
+///
+/// ```
+/// # use parenthesis::{from_str, from_parens::ValueStream, FromParens, Symbol, Value};
+/// let values: Vec<Value> = from_str("a 1 2").unwrap();
+/// let mut stream = ValueStream::new(&values);
+/// let parsed = <(Symbol, Vec<i64>)>::from_parens(&mut stream).unwrap();
+/// assert_eq!(parsed, (Symbol::from("a"), vec![1, 2]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ValueStream<'a> {
+    values: &'a [Value],
+    pos: usize,
+}
+
+impl<'a> ValueStream<'a> {
+    /// Create a new [`ValueStream`] that starts at the first value.
+    pub fn new(values: &'a [Value]) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    fn token(value: &'a Value) -> TokenTree<Self> {
+        match value {
+            Value::List(list) => TokenTree::List(ValueStream::new(list)),
+            Value::Seq(seq) => TokenTree::Seq(ValueStream::new(seq)),
+            Value::Map(map) => TokenTree::Map(ValueStream::new(map)),
+            Value::String(string) => TokenTree::String(string.clone()),
+            Value::Symbol(symbol) => TokenTree::Symbol(symbol.clone()),
+            Value::Bool(bool) => TokenTree::Bool(*bool),
+            Value::Int(int) => TokenTree::Int(*int),
+            Value::Float(float) => TokenTree::Float(float.into_inner()),
+        }
+    }
+}
+
+impl<'a> InputStream for ValueStream<'a> {
+    type Span = ();
+    type Checkpoint = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<TokenTree<Self>> {
+        let value = self.values.get(self.pos)?;
+        self.pos += 1;
+        Some(Self::token(value))
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<TokenTree<Self>> {
+        self.peek_n(0)
+    }
+
+    #[inline]
+    fn peek_n(&self, n: usize) -> Option<TokenTree<Self>> {
+        let value = self.values.get(self.pos.checked_add(n)?)?;
+        Some(Self::token(value))
+    }
+
+    #[inline]
+    fn checkpoint(&self) -> Self::Checkpoint {
+        self.pos
+    }
+
+    #[inline]
+    fn restore(&mut self, checkpoint: Self::Checkpoint) {
+        self.pos = checkpoint;
+    }
+
+    #[inline]
+    fn span(&self) -> Self::Span {}
+
+    #[inline]
+    fn parent_span(&self) -> Self::Span {}
+
+    #[inline]
+    fn is_end(&self) -> bool {
+        self.pos >= self.values.len()
+    }
+}
+
 /// Types that can be constructed from s-expressions.
 pub trait FromParens<I>: Sized
 where
@@ -78,75 +352,426 @@ where
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>>;
 }
 
+/// Types that are constructed from named fields which can be parsed one at a time.
+///
+/// This is used by `#[sexpr(flatten)]` to parse the fields of a nested struct
+/// interleaved with the fields of the outer struct. The derive macro for
+/// [`FromParens`] implements this trait for structs without variadic or rest fields.
+/// The positional fields of a flattened struct follow the positional fields of the
+/// outer struct, in the order in which the flattened fields are declared.
+pub trait FromParensFields<I>: Sized
+where
+    I: InputStream,
+{
+    /// Accumulator for the fields that have been parsed so far.
+    type Fields: Default;
+
+    /// Return whether a field with the given name belongs to this type.
+    fn has_field(name: &str) -> bool;
+
+    /// The names of the named fields of this type, for error messages.
+    fn field_names() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Parse the positional fields of this type into the accumulator.
+    ///
+    /// This is called once, at the point where the flattened field is declared:
+    /// right after the positional fields of the outer type when no named field is
+    /// declared before it, and otherwise at the first value after the forms of
+    /// named fields.
+    fn parse_positional(
+        fields: &mut Self::Fields,
+        stream: &mut I,
+    ) -> Result<(), ParseError<I::Span>> {
+        let _ = (fields, stream);
+        Ok(())
+    }
+
+    /// Parse the field with the given name from the stream of its list,
+    /// positioned right after the field name.
+    ///
+    /// Returns `false` without consuming any tokens when the field does not belong to this type.
+    fn parse_field(
+        fields: &mut Self::Fields,
+        name: &str,
+        stream: &mut I,
+    ) -> Result<bool, ParseError<I::Span>>;
+
+    /// Construct a value from the accumulated fields.
+    ///
+    /// The stream is the one containing the fields and is used to report missing fields.
+    fn finish_fields(fields: Self::Fields, stream: &I) -> Result<Self, ParseError<I::Span>>;
+}
+
+/// Types that are written as a list that starts with a tag symbol.
+///
+/// The derive macro for [`FromParens`] implements this trait for structs with
+/// `#[sexpr(tag = "...")]`. A named field with `#[sexpr(tagged)]` uses this trait
+/// to parse its value, so that the name of the field takes the place of the tag.
+pub trait FromParensTagged<I>: FromParens<I>
+where
+    I: InputStream,
+{
+    /// Parse the value from the stream of its list, positioned right after the tag.
+    fn from_parens_untagged(stream: &mut I) -> Result<Self, ParseError<I::Span>>;
+}
+
+/// Errors that can be returned by a validation function.
+///
+/// The derive macros call the function given by `#[sexpr(validate = "...")]`
+/// after parsing a field or struct. When the function returns an error, it is
+/// converted into a [`ParseError`] with this trait. Messages are reported
+/// at the span of the validated value, while a [`ParseError`] is passed on unchanged.
+pub trait ValidationError<S> {
+    /// Convert the error into a [`ParseError`] at the span of the validated value.
+    fn into_parse_error(self, span: S) -> ParseError<S>;
+}
+
+impl<S> ValidationError<S> for ParseError<S> {
+    #[inline]
+    fn into_parse_error(self, _span: S) -> ParseError<S> {
+        self
+    }
+}
+
+impl<S> ValidationError<S> for String {
+    #[inline]
+    fn into_parse_error(self, span: S) -> ParseError<S> {
+        ParseError::custom(self, span)
+    }
+}
+
+impl<S> ValidationError<S> for &str {
+    #[inline]
+    fn into_parse_error(self, span: S) -> ParseError<S> {
+        ParseError::custom(self, span)
+    }
+}
+
+impl<S> ValidationError<S> for std::convert::Infallible {
+    #[inline]
+    fn into_parse_error(self, _span: S) -> ParseError<S> {
+        match self {}
+    }
+}
+
 impl<I: InputStream> FromParens<I> for SmolStr {
     #[inline]
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
-        let Some(TokenTree::String(string)) = stream.next() else {
-            return Err(ParseError::new("expected string", stream.span()));
-        };
-
-        Ok(string)
+        match stream.next() {
+            Some(TokenTree::String(string)) => Ok(string),
+            token_tree => Err(ParseError::unexpected_token(
+                "string",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
     }
 }
 
 impl<I: InputStream> FromParens<I> for String {
     #[inline]
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
-        let Some(TokenTree::String(string)) = stream.next() else {
-            return Err(ParseError::new("expected string", stream.span()));
-        };
-
-        Ok(string.into())
+        match stream.next() {
+            Some(TokenTree::String(string)) => Ok(string.into()),
+            token_tree => Err(ParseError::unexpected_token(
+                "string",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
     }
 }
 
 impl<I: InputStream> FromParens<I> for Symbol {
     #[inline]
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
-        let Some(TokenTree::Symbol(symbol)) = stream.next() else {
-            return Err(ParseError::new("expected symbol", stream.span()));
-        };
-
-        Ok(symbol)
+        match stream.next() {
+            Some(TokenTree::Symbol(symbol)) => Ok(symbol),
+            token_tree => Err(ParseError::unexpected_token(
+                "symbol",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
     }
 }
 
 impl<I: InputStream> FromParens<I> for Value {
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
         let Some(token_tree) = stream.next() else {
-            return Err(ParseError::new("expected value", stream.span()));
+            return Err(ParseError::unexpected_token::<I>(
+                "value",
+                None,
+                stream.span(),
+            ));
         };
 
         let value = match token_tree {
-            TokenTree::List(mut list) => Value::List(FromParens::from_parens(&mut list)?),
+            TokenTree::List(mut list) => Value::List(parse_values(&mut list)?),
+            TokenTree::Seq(mut seq) => Value::Seq(parse_values(&mut seq)?),
+            TokenTree::Map(mut map) => Value::Map(parse_values(&mut map)?),
             TokenTree::String(string) => Value::from(string),
             TokenTree::Symbol(symbol) => Value::from(symbol),
             TokenTree::Bool(bool) => Value::from(bool),
             TokenTree::Int(int) => Value::from(int),
             TokenTree::Float(float) => Value::from(float),
+            token_tree @ TokenTree::Comment(_) => {
+                return Err(ParseError::unexpected_token(
+                    "value",
+                    Some(&token_tree),
+                    stream.span(),
+                ));
+            }
         };
 
         Ok(value)
     }
 }
 
-impl<I: InputStream> FromParens<I> for i64 {
+/// Parse the remaining values of a stream.
+///
+/// Unlike `Vec<Value>`, this does not check that each value consumes tokens, since
+/// a value always does. This keeps the stack frames of deeply nested values small.
+fn parse_values<I: InputStream>(stream: &mut I) -> Result<Vec<Value>, ParseError<I::Span>> {
+    let mut values = Vec::new();
+
+    while !stream.is_end() {
+        values.push(Value::from_parens(stream)?);
+    }
+
+    Ok(values)
+}
+
+impl<I: InputStream> FromParens<I> for char {
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
-        let Some(TokenTree::Int(int)) = stream.next() else {
-            return Err(ParseError::new("expected int", stream.span()));
+        let string = match stream.next() {
+            Some(TokenTree::String(string)) => string,
+            token_tree => {
+                return Err(ParseError::unexpected_token(
+                    "character",
+                    token_tree.as_ref(),
+                    stream.span(),
+                ))
+            }
+        };
+
+        let mut chars = string.chars();
+
+        let (Some(char), None) = (chars.next(), chars.next()) else {
+            return Err(ParseError::new(
+                "expected string with a single character",
+                stream.span(),
+            ));
         };
 
-        Ok(int)
+        Ok(char)
+    }
+}
+
+/// Implements [`FromParens`] for a network address type by parsing a string.
+macro_rules! impl_from_parens_addr {
+    ($($addr:ty),*) => {
+        $(
+            impl<I: InputStream> FromParens<I> for $addr {
+                fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                    let string = SmolStr::from_parens(stream)?;
+                    string
+                        .parse()
+                        .map_err(|error| ParseError::new(error, stream.span()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_parens_addr!(
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+    SocketAddrV4,
+    SocketAddrV6
+);
+
+/// Parses a UUID from a string in hyphenated, simple, braced or URN form.
+#[cfg(feature = "uuid")]
+#[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+impl<I: InputStream> FromParens<I> for uuid::Uuid {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let string = SmolStr::from_parens(stream)?;
+        uuid::Uuid::parse_str(&string).map_err(|error| ParseError::new(error, stream.span()))
+    }
+}
+
+/// Parses a timestamp from an RFC 3339 string and converts it to UTC.
+///
+/// Sub-second precision is kept up to nanoseconds. A leap second such as
+/// `23:59:60` is accepted and represented as chrono does for leap seconds.
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl<I: InputStream> FromParens<I> for chrono::DateTime<chrono::Utc> {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let string = SmolStr::from_parens(stream)?;
+        chrono::DateTime::parse_from_rfc3339(&string)
+            .map(|date_time| date_time.to_utc())
+            .map_err(|error| ParseError::new(error, stream.span()))
+    }
+}
+
+/// Parses a date from an RFC 3339 full-date string such as `2024-02-29`.
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl<I: InputStream> FromParens<I> for chrono::NaiveDate {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let string = SmolStr::from_parens(stream)?;
+        string
+            .parse()
+            .map_err(|error| ParseError::new(error, stream.span()))
+    }
+}
+
+/// Parses a timestamp from an RFC 3339 string, keeping its offset.
+///
+/// Sub-second precision is kept up to nanoseconds. Since the `time` crate does
+/// not represent leap seconds, a leap second such as `23:59:60` is read as
+/// the last nanosecond of the preceding second.
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl<I: InputStream> FromParens<I> for time::OffsetDateTime {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let string = SmolStr::from_parens(stream)?;
+        time::OffsetDateTime::parse(&string, &time::format_description::well_known::Rfc3339)
+            .map_err(|error| ParseError::new(error, stream.span()))
+    }
+}
+
+impl<I: InputStream> FromParens<I> for bool {
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        match stream.next() {
+            Some(TokenTree::Bool(bool)) => Ok(bool),
+            token_tree => Err(ParseError::unexpected_token(
+                "bool",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
+    }
+}
+
+impl<I: InputStream> FromParens<I> for i64 {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        match stream.next() {
+            Some(TokenTree::Int(int)) => Ok(int),
+            token_tree => Err(ParseError::unexpected_token(
+                "int",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
+    }
+}
+
+/// Implements [`FromParens`] for an integer type via a checked conversion from `i64`.
+macro_rules! impl_from_parens_int {
+    ($($int:ty),*) => {
+        $(
+            impl<I: InputStream> FromParens<I> for $int {
+                fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                    let int = i64::from_parens(stream)?;
+
+                    <$int>::try_from(int).map_err(|_| {
+                        ParseError::new(
+                            format!("integer `{}` out of range for `{}`", int, stringify!($int)),
+                            stream.span(),
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_from_parens_int!(i8, i16, i32, isize, u8, u16, u32, u64, usize);
+
+/// Implements [`FromParens`] for a nonzero integer type via its underlying integer type.
+macro_rules! impl_from_parens_nonzero {
+    ($($nonzero:ty => $int:ty),*) => {
+        $(
+            impl<I: InputStream> FromParens<I> for $nonzero {
+                fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                    let int = <$int>::from_parens(stream)?;
+
+                    <$nonzero>::new(int).ok_or_else(|| {
+                        ParseError::new(
+                            format!("integer `0` out of range for `{}`", stringify!($nonzero)),
+                            stream.span(),
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_from_parens_nonzero!(
+    NonZeroI8 => i8,
+    NonZeroI16 => i16,
+    NonZeroI32 => i32,
+    NonZeroI64 => i64,
+    NonZeroIsize => isize,
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32,
+    NonZeroU64 => u64,
+    NonZeroUsize => usize
+);
+
+impl<I: InputStream, const MIN: i64, const MAX: i64> FromParens<I> for Bounded<MIN, MAX> {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let int = i64::from_parens(stream)?;
+
+        Self::new(int).ok_or_else(|| {
+            ParseError::new(
+                format!("integer `{}` out of range `{}..={}`", int, MIN, MAX),
+                stream.span(),
+            )
+        })
     }
 }
 
 impl<I: InputStream> FromParens<I> for f64 {
     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
-        let Some(TokenTree::Float(float)) = stream.next() else {
-            return Err(ParseError::new("expected float", stream.span()));
-        };
+        match stream.next() {
+            Some(TokenTree::Float(float)) => Ok(float),
+            token_tree => Err(ParseError::unexpected_token(
+                "float",
+                token_tree.as_ref(),
+                stream.span(),
+            )),
+        }
+    }
+}
+
+/// Parses a float and narrows it to `f32`.
+///
+/// Finite floats that exceed the range of `f32` are rejected instead of
+/// being rounded to infinity. Otherwise precision may be lost.
+impl<I: InputStream> FromParens<I> for f32 {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let float = f64::from_parens(stream)?;
+        let narrowed = float as f32;
 
-        Ok(float)
+        if narrowed.is_infinite() && float.is_finite() {
+            return Err(ParseError::new(
+                format!("float `{}` out of range for `f32`", float),
+                stream.span(),
+            ));
+        }
+
+        Ok(narrowed)
     }
 }
 
@@ -158,65 +783,1224 @@ where
         let mut values = Vec::new();
 
         while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
             values.push(V::from_parens(stream)?);
+            check_consumed(stream, checkpoint)?;
         }
 
         Ok(values)
     }
 }
 
-/// Error while parsing a value.
-#[derive(Debug, Error)]
-pub enum ParseError<S> {
-    /// Parse error together with a span.
-    #[error("{message}")]
-    Error {
-        /// Error message.
-        message: String,
-        /// Span that indicates where the error occured.
-        span: S,
-    },
-    /// Custom errors
-    #[error(transparent)]
-    Other(#[from] Box<dyn Error + 'static>),
-}
+impl<I: InputStream, V> FromParens<I> for VecDeque<V>
+where
+    V: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut values = VecDeque::new();
 
-impl<S> ParseError<S> {
-    /// Construct a new [`ParseError`] given a message and span.
-    pub fn new(message: impl Display, span: S) -> Self {
-        Self::Error {
-            message: format!("{}", message),
-            span,
+        while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
+            values.push_back(V::from_parens(stream)?);
+            check_consumed(stream, checkpoint)?;
         }
+
+        Ok(values)
     }
 }
 
-/// Individual token returned by an [`InputStream`].
-#[derive(Debug, Clone)]
-pub enum TokenTree<L> {
-    /// A list with a nested [`InputStream`].
-    List(L),
-    /// A string.
-    String(SmolStr),
-    /// A symbol.
-    Symbol(Symbol),
-    /// A boolean.
-    Bool(bool),
-    /// An integer.
-    Int(i64),
-    /// A float.
-    Float(f64),
-}
+#[cfg(feature = "smallvec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "smallvec")))]
+impl<I: InputStream, A> FromParens<I> for smallvec::SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut values = smallvec::SmallVec::new();
 
-#[cfg(feature = "macros")]
-#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
-pub use parenthesis_macros::FromParens;
+        while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
+            values.push(A::Item::from_parens(stream)?);
+            check_consumed(stream, checkpoint)?;
+        }
+
+        Ok(values)
+    }
+}
+
+/// Arrays read exactly `N` values in order, like tuples read one value per element.
+impl<I: InputStream, V, const N: usize> FromParens<I> for [V; N]
+where
+    V: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut values = Vec::with_capacity(N);
+
+        while values.len() < N {
+            let is_end = stream.is_end();
+
+            match V::from_parens(stream) {
+                Ok(value) => values.push(value),
+                Err(_) if is_end => {
+                    return Err(ParseError::new(
+                        format!("expected {} elements, found {}", N, values.len()),
+                        stream.span(),
+                    ));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        match values.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("exactly `N` values have been read"),
+        }
+    }
+}
+
+/// Parses all remaining values into a set.
+///
+/// Duplicate values are rejected at the span of the second occurrence.
+impl<I: InputStream, V> FromParens<I> for BTreeSet<V>
+where
+    V: FromParens<I> + Ord,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut set = BTreeSet::new();
+
+        while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
+            let value = V::from_parens(stream)?;
+            check_consumed(stream, checkpoint)?;
+
+            if !set.insert(value) {
+                return Err(ParseError::new("duplicate value", stream.span()));
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+/// Parses all remaining values into a set.
+///
+/// Duplicate values are rejected at the span of the second occurrence.
+impl<I: InputStream, V, S> FromParens<I> for HashSet<V, S>
+where
+    V: FromParens<I> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut set = HashSet::default();
+
+        while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
+            let value = V::from_parens(stream)?;
+            check_consumed(stream, checkpoint)?;
+
+            if !set.insert(value) {
+                return Err(ParseError::new("duplicate value", stream.span()));
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+/// Maps are read from a sequence of lists which contain the key followed by the value.
+/// Duplicate keys result in an error.
+impl<I: InputStream, K, V> FromParens<I> for BTreeMap<K, V>
+where
+    K: FromParens<I> + Ord,
+    V: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut map = BTreeMap::new();
+
+        while !stream.is_end() {
+            let (key, value) = parse_map_entry(stream)?;
+
+            match map.entry(key) {
+                btree_map::Entry::Occupied(_) => {
+                    return Err(ParseError::new("duplicate key", stream.span()));
+                }
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Maps are read from a sequence of lists which contain the key followed by the value.
+/// Duplicate keys result in an error.
+impl<I: InputStream, K, V, S> FromParens<I> for HashMap<K, V, S>
+where
+    K: FromParens<I> + Eq + Hash,
+    V: FromParens<I>,
+    S: BuildHasher + Default,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut map = HashMap::default();
+
+        while !stream.is_end() {
+            let (key, value) = parse_map_entry(stream)?;
+
+            match map.entry(key) {
+                hash_map::Entry::Occupied(_) => {
+                    return Err(ParseError::new("duplicate key", stream.span()));
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Parses all remaining map entries in order.
+///
+/// Each entry is a list with the key followed by the value.
+/// Duplicate keys are rejected at the span of the second entry.
+#[cfg(feature = "indexmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexmap")))]
+impl<I: InputStream, K, V, S> FromParens<I> for indexmap::IndexMap<K, V, S>
+where
+    K: FromParens<I> + Eq + Hash,
+    V: FromParens<I>,
+    S: BuildHasher + Default,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut map = indexmap::IndexMap::default();
+
+        while !stream.is_end() {
+            let (key, value) = parse_map_entry(stream)?;
+
+            match map.entry(key) {
+                indexmap::map::Entry::Occupied(_) => {
+                    return Err(ParseError::new("duplicate key", stream.span()));
+                }
+                indexmap::map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Parses all remaining values into a set in order.
+///
+/// Duplicate values are rejected at the span of the second occurrence.
+#[cfg(feature = "indexmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexmap")))]
+impl<I: InputStream, V, S> FromParens<I> for indexmap::IndexSet<V, S>
+where
+    V: FromParens<I> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        let mut set = indexmap::IndexSet::default();
+
+        while !stream.is_end() {
+            let checkpoint = stream.checkpoint();
+            let value = V::from_parens(stream)?;
+            check_consumed(stream, checkpoint)?;
+
+            if !set.insert(value) {
+                return Err(ParseError::new("duplicate value", stream.span()));
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+/// Parse an entry of a map, which is a list that starts with the key
+/// and whose remaining elements make up the value.
+fn parse_map_entry<I: InputStream, K, V>(stream: &mut I) -> Result<(K, V), ParseError<I::Span>>
+where
+    K: FromParens<I>,
+    V: FromParens<I>,
+{
+    let mut entry = stream.expect_list()?;
+    let key = K::from_parens(&mut entry)?;
+    let value = V::from_parens(&mut entry)?;
+
+    if !entry.is_end() {
+        entry.next();
+        return Err(ParseError::new("expected end of map entry", entry.span()));
+    }
+
+    Ok((key, value))
+}
+
+impl<I: InputStream, T> FromParens<I> for Box<T>
+where
+    T: FromParens<I>,
+{
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(Box::new(T::from_parens(stream)?))
+    }
+}
+
+impl<I: InputStream, T> FromParens<I> for Rc<T>
+where
+    T: FromParens<I>,
+{
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(Rc::new(T::from_parens(stream)?))
+    }
+}
+
+impl<I: InputStream, T> FromParens<I> for Arc<T>
+where
+    T: FromParens<I>,
+{
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(Arc::new(T::from_parens(stream)?))
+    }
+}
+
+/// Parses the owned form of the value, so the result is always [`Cow::Owned`].
+///
+/// In particular, a `Cow<'a, str>` is parsed as a string into an owned [`String`].
+/// It can not borrow from the input, since the lifetime `'a` is unrelated to the
+/// input stream. The impl still lets generic code accept both owned strings and
+/// borrowed strings, such as a `&'static str` used as a default.
+impl<'a, I: InputStream, T> FromParens<I> for Cow<'a, T>
+where
+    T: ToOwned + ?Sized,
+    T::Owned: FromParens<I>,
+{
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(Cow::Owned(T::Owned::from_parens(stream)?))
+    }
+}
+
+impl<I: InputStream> FromParens<I> for Rest {
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(Rest(FromParens::from_parens(stream)?))
+    }
+}
+
+impl<I: InputStream, T> FromParens<I> for OrDefault<T>
+where
+    T: FromParens<I> + Default,
+{
+    #[inline]
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(OrDefault(stream.parse_or_default()?))
+    }
+}
+
+/// Options are read in place. When there are no more tokens or the next value can
+/// not be parsed as a `T`, the result is `None` and the stream is left unchanged,
+/// see [`InputStream::try_parse`]. This means that the error of a value that
+/// does not parse is discarded, so that it is reported by whatever parses next.
+/// Collections that read all remaining values reject such a value, see [`check_consumed`].
+impl<I: InputStream, T> FromParens<I> for Option<T>
+where
+    T: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        if stream.is_end() {
+            return Ok(None);
+        }
+
+        Ok(stream.try_parse().ok())
+    }
+}
+
+impl<I: InputStream, A, B> FromParens<I> for Either<A, B>
+where
+    A: FromParens<I>,
+    B: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        alt(
+            stream,
+            &[
+                &|stream: &mut I| A::from_parens(stream).map(Either::Left),
+                &|stream: &mut I| B::from_parens(stream).map(Either::Right),
+            ],
+        )
+    }
+}
+
+/// Parse a value with the first of several parsers that succeeds.
+///
+/// Each parser starts at the same position of the stream. If all parsers fail,
+/// the error of the parser that consumed the most tokens is returned, since
+/// that parser most likely matches what was intended, and the stream is left
+/// where that parser stopped. When several parsers fail at the same token with
+/// [`ParseError::UnexpectedToken`] errors, the errors are combined into a single
+/// [`ParseError::UnexpectedTokenAlternatives`] error that lists all expectations.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_parens::{alt, FromParens, InputStream, ParseError}, from_str, Symbol};
+/// #[derive(Debug, PartialEq)]
+/// enum Size {
+///     Fixed(i64),
+///     Auto,
+/// }
+///
+/// impl<I: InputStream> FromParens<I> for Size {
+///     fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+///         alt(stream, &[
+///             &|stream: &mut I| i64::from_parens(stream).map(Size::Fixed),
+///             &|stream: &mut I| match Symbol::from_parens(stream)?.as_ref() {
+///                 "auto" => Ok(Size::Auto),
+///                 _ => Err(stream.error("expected `auto`")),
+///             },
+///         ])
+///     }
+/// }
+///
+/// assert_eq!(from_str::<Size>("12").unwrap(), Size::Fixed(12));
+/// assert_eq!(from_str::<Size>("auto").unwrap(), Size::Auto);
+/// assert_eq!(
+///     from_str::<Size>("\"12\"").unwrap_err().to_string(),
+///     "expected int or symbol, found string"
+/// );
+/// ```
+pub fn alt<I, T>(stream: &mut I, parsers: &[&AltParser<I, T>]) -> Result<T, ParseError<I::Span>>
+where
+    I: InputStream,
+{
+    let start = stream.checkpoint();
+    let mut best: Option<(usize, I::Checkpoint, ParseError<I::Span>)> = None;
+
+    for parser in parsers {
+        let error = match parser(stream) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        let end = stream.checkpoint();
+        let consumed = consumed_since(stream, start.clone());
+
+        best = Some(match best {
+            Some((best_consumed, best_end, best_error)) if best_consumed > consumed => {
+                (best_consumed, best_end, best_error)
+            }
+            Some((best_consumed, best_end, best_error)) if best_consumed == consumed => {
+                (best_consumed, best_end, combine_errors(best_error, error))
+            }
+            _ => (consumed, end, error),
+        });
+    }
+
+    match best {
+        Some((_, end, error)) => {
+            stream.restore(end);
+            Err(error)
+        }
+        None => Err(ParseError::new("no alternatives to parse", stream.span())),
+    }
+}
+
+/// A parser that can be passed to [`alt`].
+pub type AltParser<I, T> = dyn Fn(&mut I) -> Result<T, ParseError<<I as InputStream>::Span>>;
+
+/// Advance to the next token, expecting it to be the given symbol up to ASCII case.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::from_parens::{expect_symbol_ci, ParseError};
+/// # use parenthesis::Value;
+/// let values: Vec<Value> = parenthesis::from_str("OPERATION").unwrap();
+/// assert!(expect_symbol_ci(&mut values.as_slice(), "operation").is_ok());
+/// assert!(expect_symbol_ci(&mut values.as_slice(), "other").is_err());
+/// ```
+pub fn expect_symbol_ci<I: InputStream>(
+    stream: &mut I,
+    symbol: &str,
+) -> Result<(), ParseError<I::Span>> {
+    match stream.next() {
+        Some(TokenTree::Symbol(found)) if found.as_ref().eq_ignore_ascii_case(symbol) => Ok(()),
+        Some(TokenTree::Symbol(_)) => Err(ParseError::new(
+            format!("expected `{}`", symbol),
+            stream.span(),
+        )),
+        token_tree => Err(ParseError::unexpected_token(
+            "symbol",
+            token_tree.as_ref(),
+            stream.span(),
+        )),
+    }
+}
+
+/// Fail if parsing an element of a collection did not consume any tokens
+/// since the `checkpoint` was taken.
+///
+/// Values such as `None` of an [`Option`] can be parsed without consuming anything,
+/// so without this check, a loop that parses elements until the end of the stream
+/// would never terminate. The derive macros use this for `variadic` fields.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::from_parens::{check_consumed, FromParens, InputStream};
+/// # use parenthesis::Value;
+/// let values: Vec<Value> = parenthesis::from_str("a").unwrap();
+/// let mut stream = values.as_slice();
+/// let checkpoint = stream.checkpoint();
+/// assert_eq!(Option::<i64>::from_parens(&mut stream).unwrap(), None);
+/// let error = check_consumed(&mut stream, checkpoint).unwrap_err();
+/// assert_eq!(error.to_string(), "expected element, found symbol");
+/// ```
+pub fn check_consumed<I: InputStream>(
+    stream: &mut I,
+    checkpoint: I::Checkpoint,
+) -> Result<(), ParseError<I::Span>> {
+    if stream.checkpoint() != checkpoint {
+        return Ok(());
+    }
+
+    let token_tree = stream.next();
+    Err(ParseError::unexpected_token(
+        "element",
+        token_tree.as_ref(),
+        stream.span(),
+    ))
+}
+
+/// Parse a value from the contents of a sequence `[...]`.
+///
+/// This is used by the `seq` attribute of the derive macros, so that a field
+/// such as a `Vec` is read from a single sequence instead of from the values in place.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::from_parens::seq;
+/// # use parenthesis::{Symbol, Value};
+/// let values: Vec<Value> = parenthesis::from_str("[a b]").unwrap();
+/// let symbols: Vec<Symbol> = seq(&mut values.as_slice()).unwrap();
+/// assert_eq!(symbols, [Symbol::new("a"), Symbol::new("b")]);
+/// ```
+pub fn seq<I, T>(stream: &mut I) -> Result<T, ParseError<I::Span>>
+where
+    I: InputStream,
+    T: FromParens<I>,
+{
+    let mut seq = stream.expect_seq()?;
+    let value = T::from_parens(&mut seq)?;
+
+    match seq.next() {
+        None => Ok(value),
+        token_tree => Err(ParseError::unexpected_token(
+            "end of sequence",
+            token_tree.as_ref(),
+            seq.span(),
+        )),
+    }
+}
+
+/// Collections of key value pairs that can be read from a map `{...}` with [`map`].
+pub trait MapEntries: Default {
+    /// The type of the keys.
+    type Key;
+    /// The type of the values.
+    type Value;
+
+    /// Insert an entry unless the key is already present.
+    /// Returns whether the entry was inserted.
+    fn insert_entry(&mut self, key: Self::Key, value: Self::Value) -> bool;
+}
+
+impl<K: Ord, V> MapEntries for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn insert_entry(&mut self, key: K, value: V) -> bool {
+        match self.entry(key) {
+            btree_map::Entry::Occupied(_) => false,
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> MapEntries for HashMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn insert_entry(&mut self, key: K, value: V) -> bool {
+        match self.entry(key) {
+            hash_map::Entry::Occupied(_) => false,
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexmap")))]
+impl<K: Eq + Hash, V, S: BuildHasher + Default> MapEntries for indexmap::IndexMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn insert_entry(&mut self, key: K, value: V) -> bool {
+        match self.entry(key) {
+            indexmap::map::Entry::Occupied(_) => false,
+            indexmap::map::Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+}
+
+/// Parse a collection from a map `{key value ...}` with alternating keys and values.
+///
+/// This is used by the `map` attribute of the derive macros, since maps such as
+/// [`BTreeMap`] are otherwise read from lists that contain a key and a value.
+/// A key without a value and duplicate keys are rejected at the span of the key.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use parenthesis::from_parens::map;
+/// # use parenthesis::{Symbol, Value};
+/// let values: Vec<Value> = parenthesis::from_str("{a 1 b 2}").unwrap();
+/// let entries: BTreeMap<Symbol, i64> = map(&mut values.as_slice()).unwrap();
+/// assert_eq!(entries, BTreeMap::from([(Symbol::new("a"), 1), (Symbol::new("b"), 2)]));
+/// ```
+pub fn map<I, T>(stream: &mut I) -> Result<T, ParseError<I::Span>>
+where
+    I: InputStream,
+    T: MapEntries,
+    T::Key: FromParens<I>,
+    T::Value: FromParens<I>,
+{
+    let mut map_stream = stream.expect_map()?;
+    let mut entries = T::default();
+
+    while !map_stream.is_end() {
+        let checkpoint = map_stream.checkpoint();
+        let key = T::Key::from_parens(&mut map_stream)?;
+        check_consumed(&mut map_stream, checkpoint)?;
+        let key_span = map_stream.span();
+
+        if map_stream.is_end() {
+            return Err(ParseError::new("missing value for key", key_span));
+        }
+
+        let value = T::Value::from_parens(&mut map_stream)?;
+
+        if !entries.insert_entry(key, value) {
+            return Err(ParseError::new("duplicate key", key_span));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Count the tokens between a checkpoint and the current position of the
+/// stream, leaving the stream at the checkpoint.
+fn consumed_since<I: InputStream>(stream: &mut I, start: I::Checkpoint) -> usize {
+    let end = stream.checkpoint();
+    stream.restore(start.clone());
+
+    let mut consumed = 0;
+
+    while stream.checkpoint() != end && stream.next().is_some() {
+        consumed += 1;
+    }
+
+    stream.restore(start);
+    consumed
+}
+
+/// What an [`ParseError::UnexpectedToken`] or [`ParseError::UnexpectedTokenAlternatives`]
+/// error expected and which kind of token it found.
+fn unexpected_token<S>(error: &ParseError<S>) -> Option<(&[&'static str], TokenKind)> {
+    match error.innermost() {
+        ParseError::UnexpectedToken {
+            expected, found, ..
+        } => Some((std::slice::from_ref(expected), *found)),
+        ParseError::UnexpectedTokenAlternatives {
+            expected, found, ..
+        } => Some((expected, *found)),
+        _ => None,
+    }
+}
+
+/// Combine two unexpected token errors at the same position that found the same
+/// kind of token. Otherwise the first error is kept.
+///
+/// The context of the errors is not part of the combined error, since the
+/// alternatives may describe different fields.
+fn combine_errors<S>(first: ParseError<S>, second: ParseError<S>) -> ParseError<S> {
+    let (Some((first_expected, found)), Some((second_expected, second_found))) =
+        (unexpected_token(&first), unexpected_token(&second))
+    else {
+        return first;
+    };
+
+    if found != second_found {
+        return first;
+    }
+
+    let mut expected = first_expected.to_vec();
+
+    for alternative in second_expected {
+        if !expected.contains(alternative) {
+            expected.push(alternative);
+        }
+    }
+
+    match first.into_span() {
+        Some(span) => ParseError::UnexpectedTokenAlternatives {
+            expected,
+            found,
+            span,
+        },
+        None => second,
+    }
+}
+
+/// Reading `()` consumes no tokens and always succeeds.
+impl<I: InputStream> FromParens<I> for () {
+    #[inline]
+    fn from_parens(_stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(())
+    }
+}
+
+/// Like `()`, reading a [`PhantomData`] consumes no tokens and always succeeds,
+/// regardless of `T`.
+impl<I: InputStream, T: ?Sized> FromParens<I> for PhantomData<T> {
+    #[inline]
+    fn from_parens(_stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        Ok(PhantomData)
+    }
+}
+
+/// Implements [`FromParens`] for a tuple whose elements are parsed positionally in order.
+macro_rules! impl_from_parens_tuple {
+    ($($index:tt $name:ident),+) => {
+        impl<I: InputStream, $($name),+> FromParens<I> for ($($name,)+)
+        where
+            $($name: FromParens<I>),+
+        {
+            fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                Ok(($(
+                    {
+                        let is_end = stream.is_end();
+
+                        match $name::from_parens(stream) {
+                            Ok(value) => value,
+                            Err(_) if is_end => {
+                                return Err(ParseError::new(
+                                    format!("expected {} tuple element", ordinal($index + 1)),
+                                    stream.span(),
+                                ));
+                            }
+                            Err(err) => return Err(err),
+                        }
+                    },
+                )+))
+            }
+        }
+    };
+}
+
+impl_from_parens_tuple!(0 A);
+impl_from_parens_tuple!(0 A, 1 B);
+impl_from_parens_tuple!(0 A, 1 B, 2 C);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K, 10 L);
+impl_from_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K, 10 L, 11 M);
+
+/// Format a number as an English ordinal such as `1st`, `2nd` or `11th`.
+///
+/// This is used for error messages that refer to an element by its position.
+pub fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", n, suffix)
+}
+
+/// Error while parsing a value.
+#[derive(Debug, Error)]
+pub enum ParseError<S> {
+    /// Parse error together with a span.
+    #[error("{message}")]
+    Error {
+        /// Error message.
+        message: String,
+        /// Span that indicates where the error occured.
+        span: S,
+    },
+    /// Custom error message together with a span.
+    #[error("{message}")]
+    Custom {
+        /// Error message.
+        message: String,
+        /// Span that indicates where the error occured.
+        span: S,
+    },
+    /// A token of a different kind than the expected one.
+    #[error("expected {expected}, found {found}")]
+    UnexpectedToken {
+        /// Description of what was expected.
+        expected: &'static str,
+        /// Kind of the token that was found instead.
+        found: TokenKind,
+        /// Span that indicates where the error occured.
+        span: S,
+    },
+    /// A token that none of several alternatives expected, see [`alt`].
+    #[error("expected {}, found {found}", .expected.join(" or "))]
+    UnexpectedTokenAlternatives {
+        /// Descriptions of what each alternative expected.
+        expected: Vec<&'static str>,
+        /// Kind of the token that was found instead.
+        found: TokenKind,
+        /// Span that indicates where the error occured.
+        span: S,
+    },
+    /// A required field that is absent.
+    #[error("missing required field `{field}`")]
+    MissingField {
+        /// Name of the field.
+        field: &'static str,
+        /// Span of the list that should contain the field.
+        span: S,
+    },
+    /// A named field that occurs more than once.
+    #[error("duplicate field `{field}`")]
+    DuplicateField {
+        /// Name of the field.
+        field: &'static str,
+        /// Span of the form of the field.
+        span: S,
+        /// Span of the earlier form of the same field.
+        previous: S,
+    },
+    /// An error within a field, together with a description of the field.
+    ///
+    /// The derive macro for [`FromParens`] adds this context to the errors of
+    /// its fields, so that the message names every field that encloses the error.
+    #[error("{}, in {}", .error.as_ref(), .context)]
+    Context {
+        /// Description of the field, such as ``field `name` of `Operation` ``.
+        context: String,
+        /// The error within the field.
+        error: Box<ParseError<S>>,
+    },
+    /// Custom errors
+    #[error(transparent)]
+    Other(#[from] Box<dyn Error + 'static>),
+}
+
+impl<S> ParseError<S> {
+    /// Construct a new [`ParseError`] given a message and span.
+    pub fn new(message: impl Display, span: S) -> Self {
+        Self::Error {
+            message: format!("{}", message),
+            span,
+        }
+    }
+
+    /// Construct a new custom [`ParseError`] given a message and span.
+    pub fn custom(message: impl Display, span: S) -> Self {
+        Self::Custom {
+            message: format!("{}", message),
+            span,
+        }
+    }
+
+    /// Construct an error for a named field that does not belong to the
+    /// type being parsed, listing the names of the fields that are expected.
+    pub fn unknown_field(name: &str, expected: &[&str], span: S) -> Self {
+        if expected.is_empty() {
+            return Self::new(format!("unknown field `{}`", name), span);
+        }
+
+        let expected = expected
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Self::new(
+            format!("unknown field `{}`, expected one of {}", name, expected),
+            span,
+        )
+    }
+
+    /// Add a description of the field in which the error occured.
+    pub fn context(self, context: impl Display) -> Self {
+        Self::Context {
+            context: context.to_string(),
+            error: Box::new(self),
+        }
+    }
+
+    /// The error without the context of the fields that enclose it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::from_parens::ParseError;
+    /// let error = ParseError::new("expected int", 0..1).context("field `x` of `Point`");
+    /// assert_eq!(error.to_string(), "expected int, in field `x` of `Point`");
+    /// assert_eq!(error.innermost().to_string(), "expected int");
+    /// ```
+    pub fn innermost(&self) -> &Self {
+        match self {
+            ParseError::Context { error, .. } => error.innermost(),
+            error => error,
+        }
+    }
+
+    /// The span where the error occured, if known.
+    pub fn span(&self) -> Option<&S> {
+        match self {
+            ParseError::Error { span, .. } => Some(span),
+            ParseError::Custom { span, .. } => Some(span),
+            ParseError::UnexpectedToken { span, .. } => Some(span),
+            ParseError::UnexpectedTokenAlternatives { span, .. } => Some(span),
+            ParseError::MissingField { span, .. } => Some(span),
+            ParseError::DuplicateField { span, .. } => Some(span),
+            ParseError::Context { error, .. } => error.span(),
+            ParseError::Other(_) => None,
+        }
+    }
+
+    /// Convert the span of the error with the given function.
+    pub fn map_span<T>(self, mut f: impl FnMut(S) -> T) -> ParseError<T> {
+        self.map_span_mut(&mut f)
+    }
+
+    fn map_span_mut<T>(self, f: &mut impl FnMut(S) -> T) -> ParseError<T> {
+        match self {
+            ParseError::Error { message, span } => ParseError::Error {
+                message,
+                span: f(span),
+            },
+            ParseError::Custom { message, span } => ParseError::Custom {
+                message,
+                span: f(span),
+            },
+            ParseError::UnexpectedToken {
+                expected,
+                found,
+                span,
+            } => ParseError::UnexpectedToken {
+                expected,
+                found,
+                span: f(span),
+            },
+            ParseError::UnexpectedTokenAlternatives {
+                expected,
+                found,
+                span,
+            } => ParseError::UnexpectedTokenAlternatives {
+                expected,
+                found,
+                span: f(span),
+            },
+            ParseError::MissingField { field, span } => ParseError::MissingField {
+                field,
+                span: f(span),
+            },
+            ParseError::DuplicateField {
+                field,
+                span,
+                previous,
+            } => ParseError::DuplicateField {
+                field,
+                span: f(span),
+                previous: f(previous),
+            },
+            ParseError::Context { context, error } => ParseError::Context {
+                context,
+                error: Box::new(error.map_span_mut(f)),
+            },
+            ParseError::Other(error) => ParseError::Other(error),
+        }
+    }
+
+    /// Consume the error and return its span, if known.
+    fn into_span(self) -> Option<S> {
+        match self {
+            ParseError::Error { span, .. } => Some(span),
+            ParseError::Custom { span, .. } => Some(span),
+            ParseError::UnexpectedToken { span, .. } => Some(span),
+            ParseError::UnexpectedTokenAlternatives { span, .. } => Some(span),
+            ParseError::MissingField { span, .. } => Some(span),
+            ParseError::DuplicateField { span, .. } => Some(span),
+            ParseError::Context { error, .. } => error.into_span(),
+            ParseError::Other(_) => None,
+        }
+    }
+
+    /// Construct a [`ParseError::UnexpectedToken`] from the token that was found,
+    /// where `None` indicates the end of the stream.
+    pub fn unexpected_token<L>(
+        expected: &'static str,
+        found: Option<&TokenTree<L>>,
+        span: S,
+    ) -> Self {
+        Self::UnexpectedToken {
+            expected,
+            found: found.map_or(TokenKind::End, TokenTree::kind),
+            span,
+        }
+    }
+}
+
+/// Error returned by [`InputStream::parse_recoverable`].
+#[derive(Debug, Error)]
+pub enum RecoverableParseError<S> {
+    /// The parser failed without advancing the stream,
+    /// so that another parser can be tried in its place.
+    #[error(transparent)]
+    Recoverable(ParseError<S>),
+    /// The parser failed after advancing the stream.
+    ///
+    /// The stream needs to be restored to a checkpoint before another parser can be tried.
+    #[error(transparent)]
+    Unrecoverable(ParseError<S>),
+}
+
+impl<S> RecoverableParseError<S> {
+    /// Whether the stream was left unchanged by the failed parser.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, RecoverableParseError::Recoverable(_))
+    }
+
+    /// The underlying [`ParseError`].
+    pub fn into_inner(self) -> ParseError<S> {
+        match self {
+            RecoverableParseError::Recoverable(error) => error,
+            RecoverableParseError::Unrecoverable(error) => error,
+        }
+    }
+}
+
+impl<S> From<RecoverableParseError<S>> for ParseError<S> {
+    fn from(error: RecoverableParseError<S>) -> Self {
+        error.into_inner()
+    }
+}
+
+/// Individual token returned by an [`InputStream`].
+#[derive(Debug, Clone)]
+pub enum TokenTree<L> {
+    /// A list with a nested [`InputStream`].
+    List(L),
+    /// A sequence delimited by `[]` with a nested [`InputStream`].
+    Seq(L),
+    /// A map delimited by `{}` with a nested [`InputStream`].
+    Map(L),
+    /// A string.
+    String(SmolStr),
+    /// A symbol.
+    Symbol(Symbol),
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A float.
+    Float(f64),
+    /// A comment, without the leading `;`.
+    ///
+    /// Comments are only returned by streams that preserve them,
+    /// see [`crate::ReadOptions::with_preserve_comments`].
+    Comment(SmolStr),
+}
+
+impl<L> TokenTree<L> {
+    /// The kind of this token.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            TokenTree::List(_) => TokenKind::List,
+            TokenTree::Seq(_) => TokenKind::Seq,
+            TokenTree::Map(_) => TokenKind::Map,
+            TokenTree::String(_) => TokenKind::String,
+            TokenTree::Symbol(_) => TokenKind::Symbol,
+            TokenTree::Bool(_) => TokenKind::Bool,
+            TokenTree::Int(_) => TokenKind::Int,
+            TokenTree::Float(_) => TokenKind::Float,
+            TokenTree::Comment(_) => TokenKind::Comment,
+        }
+    }
+}
+
+/// Kind of a token, used to report unexpected tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A symbol.
+    Symbol,
+    /// A string.
+    String,
+    /// An integer.
+    Int,
+    /// A float.
+    Float,
+    /// A boolean.
+    Bool,
+    /// A list.
+    List,
+    /// A sequence.
+    Seq,
+    /// A map.
+    Map,
+    /// A comment.
+    Comment,
+    /// The end of the stream.
+    End,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TokenKind::Symbol => "symbol",
+            TokenKind::String => "string",
+            TokenKind::Int => "int",
+            TokenKind::Float => "float",
+            TokenKind::Bool => "bool",
+            TokenKind::List => "list",
+            TokenKind::Seq => "sequence",
+            TokenKind::Map => "map",
+            TokenKind::Comment => "comment",
+            TokenKind::End => "end of input",
+        })
+    }
+}
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use parenthesis_macros::FromParens;
 
 #[cfg(test)]
 mod test {
-    use crate::{FromParens, Value};
+    use super::{alt, ParseError, TokenKind};
+    use crate::read::{ReadError, ReaderStream, Span};
+    use crate::{from_str, FromParens, Symbol, Value};
     use proptest::prelude::*;
 
+    fn parse_error<T>(text: &str) -> ParseError<Span>
+    where
+        T: for<'a> FromParens<ReaderStream<'a>> + std::fmt::Debug,
+    {
+        match from_str::<T>(text) {
+            Err(ReadError::Parse(error)) => error,
+            result => panic!("expected parse error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn unexpected_token() {
+        let cases = [
+            (parse_error::<i64>("a"), "expected int, found symbol"),
+            (parse_error::<String>("(a)"), "expected string, found list"),
+            (parse_error::<bool>("1.0"), "expected bool, found float"),
+            (
+                parse_error::<Symbol>(r#""a""#),
+                "expected symbol, found string",
+            ),
+            (parse_error::<f64>("#t"), "expected float, found bool"),
+            (parse_error::<String>("1"), "expected string, found int"),
+            (parse_error::<i64>(""), "expected int, found end of input"),
+        ];
+
+        for (error, message) in cases {
+            assert!(matches!(error, ParseError::UnexpectedToken { .. }));
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn unexpected_token_kind() {
+        let values = [Value::from(1)];
+        let error = Symbol::from_parens(&mut values.as_slice()).unwrap_err();
+
+        assert!(matches!(
+            error,
+            ParseError::UnexpectedToken {
+                expected: "symbol",
+                found: TokenKind::Int,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn alt_combines_unexpected_tokens() {
+        let values = [Value::String("a".into())];
+        let error = alt(
+            &mut values.as_slice(),
+            &[
+                &|stream: &mut &[Value]| i64::from_parens(stream).map(Value::from),
+                &|stream: &mut &[Value]| Symbol::from_parens(stream).map(Value::Symbol),
+                &|stream: &mut &[Value]| i64::from_parens(stream).map(Value::from),
+                &|_: &mut &[Value]| Err(ParseError::new("expected x, found y", ())),
+                &|stream: &mut &[Value]| bool::from_parens(stream).map(Value::Bool),
+            ],
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            &error,
+            ParseError::UnexpectedTokenAlternatives {
+                expected,
+                found: TokenKind::String,
+                ..
+            } if *expected == ["int", "symbol", "bool"]
+        ));
+        assert_eq!(
+            error.to_string(),
+            "expected int or symbol or bool, found string"
+        );
+    }
+
+    #[test]
+    fn missing_field() {
+        let error = ParseError::MissingField {
+            field: "name",
+            span: (),
+        };
+
+        assert_eq!(error.to_string(), "missing required field `name`");
+    }
+
     proptest! {
         #[test]
         fn parse_values_from_values(values: Vec<Value>) {
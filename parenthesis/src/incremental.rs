@@ -0,0 +1,287 @@
+//! Reading s-expressions incrementally from chunks of text.
+//!
+//! This is intended for tooling that receives its input piece by piece, such as
+//! editors that parse the text while it is being typed.
+use crate::read::{from_str_with_opts, ReadError, ReadOptions};
+use crate::Value;
+
+/// The state of the lexer at the end of the text that has been scanned so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LexState {
+    /// Between two tokens.
+    #[default]
+    Between,
+    /// Within a symbol, number or boolean.
+    Atom,
+    /// Within a string, possibly directly after a backslash.
+    String {
+        /// Whether the last character was a backslash that escapes the next one.
+        escape: bool,
+    },
+    /// Within a symbol that is delimited by `|`, possibly directly after a backslash.
+    DelimitedSymbol {
+        /// Whether the last character was a backslash that escapes the next one.
+        escape: bool,
+    },
+    /// Within a comment.
+    Comment,
+}
+
+/// Parser that reads top level values from chunks of text as they arrive.
+///
+/// The chunks may split the input at any character, including within tokens
+/// such as strings. Each chunk returns the top level values that have been
+/// completed by it. Spans in errors are relative to the start of the input.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, incremental::IncrementalParser, Symbol, Value};
+/// let mut parser = IncrementalParser::new();
+/// assert_eq!(parser.push_chunk(r#"(a "b"#).unwrap(), vec![]);
+/// assert_eq!(
+///     parser.push_chunk(r#" c") d"#).unwrap(),
+///     vec![from_str::<Value>(r#"(a "b c")"#).unwrap()]
+/// );
+/// assert_eq!(parser.finish().unwrap(), vec![Value::Symbol(Symbol::new("d"))]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalParser {
+    options: ReadOptions,
+    /// The text that has not been parsed yet.
+    buffer: String,
+    /// The number of bytes of the input before the buffer.
+    offset: usize,
+    /// The number of bytes of the buffer that have been scanned.
+    scanned: usize,
+    /// The length of the prefix of the buffer that consists of complete top level values.
+    complete: usize,
+    /// The nesting depth of the delimiters at the end of the scanned text.
+    depth: usize,
+    state: LexState,
+}
+
+impl IncrementalParser {
+    /// Create a new [`IncrementalParser`] with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`IncrementalParser`] with the given options.
+    pub fn with_options(options: ReadOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// The state of the lexer at the end of the input so far.
+    pub fn lex_state(&self) -> LexState {
+        self.state
+    }
+
+    /// Add a chunk of text to the input and return the top level values that are complete.
+    ///
+    /// A value is complete once it can not be continued by the following input.
+    /// In particular, a symbol or number at the top level is only complete once it
+    /// is followed by whitespace. When the complete values can not be read, the
+    /// error is returned and reading continues after them with the next chunk.
+    pub fn push_chunk(&mut self, chunk: &str) -> Result<Vec<Value>, ReadError> {
+        self.buffer.push_str(chunk);
+        self.scan();
+
+        match std::mem::take(&mut self.complete) {
+            0 => Ok(Vec::new()),
+            complete => self.read_prefix(complete),
+        }
+    }
+
+    /// End the input and return the remaining top level values.
+    ///
+    /// Fails with [`ReadError::EndOfFile`] if a list, string or delimited symbol
+    /// has not been closed. Afterwards the parser can be used for a new input.
+    pub fn finish(&mut self) -> Result<Vec<Value>, ReadError> {
+        let result = match self.state {
+            _ if self.depth > 0 => Err(ReadError::EndOfFile),
+            LexState::String { .. } | LexState::DelimitedSymbol { .. } => Err(ReadError::EndOfFile),
+            // Comments are terminated by a newline.
+            LexState::Comment => {
+                self.buffer.push('\n');
+                self.read_prefix(self.buffer.len())
+            }
+            LexState::Between | LexState::Atom => self.read_prefix(self.buffer.len()),
+        };
+
+        *self = Self::with_options(std::mem::take(&mut self.options));
+        result
+    }
+
+    /// Read the values in the first `end` bytes of the buffer and remove them.
+    fn read_prefix(&mut self, end: usize) -> Result<Vec<Value>, ReadError> {
+        let result = from_str_with_opts(&self.buffer[..end], &self.options)
+            .map_err(|error| error.offset(self.offset));
+
+        self.buffer.drain(..end);
+        self.offset += end;
+        self.scanned -= end;
+        result
+    }
+
+    /// Advance the lexer state over the text that has not been scanned yet
+    /// and record where the complete top level values end.
+    fn scan(&mut self) {
+        for (index, c) in self.buffer[self.scanned..].char_indices() {
+            let end = self.scanned + index + c.len_utf8();
+
+            if self.state == LexState::Atom && (is_whitespace(c) || "()[]{}\";|'".contains(c)) {
+                self.state = LexState::Between;
+            }
+
+            // Whether the character ends a token, so that a value may be complete.
+            let token_end = match self.state {
+                LexState::Between => match c {
+                    '(' | '[' | '{' => {
+                        self.depth += 1;
+                        false
+                    }
+                    ')' | ']' | '}' => {
+                        self.depth = self.depth.saturating_sub(1);
+                        true
+                    }
+                    '"' => {
+                        self.state = LexState::String { escape: false };
+                        false
+                    }
+                    '|' => {
+                        self.state = LexState::DelimitedSymbol { escape: false };
+                        false
+                    }
+                    ';' => {
+                        self.state = LexState::Comment;
+                        false
+                    }
+                    '\'' => false,
+                    _ if is_whitespace(c) => true,
+                    _ => {
+                        self.state = LexState::Atom;
+                        false
+                    }
+                },
+                LexState::Atom => false,
+                LexState::String { escape } => {
+                    self.state = match c {
+                        _ if escape => LexState::String { escape: false },
+                        '\\' => LexState::String { escape: true },
+                        '"' => LexState::Between,
+                        _ => LexState::String { escape: false },
+                    };
+
+                    self.state == LexState::Between
+                }
+                LexState::DelimitedSymbol { escape } => {
+                    self.state = match c {
+                        _ if escape => LexState::DelimitedSymbol { escape: false },
+                        '\\' => LexState::DelimitedSymbol { escape: true },
+                        '|' => LexState::Between,
+                        _ => LexState::DelimitedSymbol { escape: false },
+                    };
+
+                    self.state == LexState::Between
+                }
+                LexState::Comment => {
+                    if c == '\n' {
+                        self.state = LexState::Between;
+                    }
+
+                    c == '\n'
+                }
+            };
+
+            if token_end && self.depth == 0 {
+                self.complete = end;
+            }
+        }
+
+        self.scanned = self.buffer.len();
+    }
+}
+
+/// Whitespace as accepted by the reader.
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0c')
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IncrementalParser, LexState};
+    use crate::read::{from_str, ReadError};
+    use crate::Value;
+
+    const SOURCE: &str = r#"(define (f x) "a \"quoted\" string")
+; a comment (with parens
+[|sym \| bol| 1.5 #t] {k -12}
+"string" last"#;
+
+    #[test]
+    fn chunks_at_every_position() {
+        let expected: Vec<Value> = from_str(SOURCE).unwrap();
+
+        for split in (0..=SOURCE.len()).filter(|i| SOURCE.is_char_boundary(*i)) {
+            let mut parser = IncrementalParser::new();
+            let mut values = parser.push_chunk(&SOURCE[..split]).unwrap();
+            values.extend(parser.push_chunk(&SOURCE[split..]).unwrap());
+            values.extend(parser.finish().unwrap());
+            assert_eq!(values, expected, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn single_characters() {
+        let expected: Vec<Value> = from_str(SOURCE).unwrap();
+        let mut parser = IncrementalParser::new();
+        let mut values = Vec::new();
+
+        for c in SOURCE.chars() {
+            values.extend(parser.push_chunk(c.encode_utf8(&mut [0; 4])).unwrap());
+        }
+
+        assert_eq!(values.len(), expected.len() - 1);
+        values.extend(parser.finish().unwrap());
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn values_are_returned_when_complete() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.push_chunk("(a (b").unwrap(), vec![]);
+        assert_eq!(parser.push_chunk(")").unwrap(), vec![]);
+        assert_eq!(parser.push_chunk(") 12").unwrap().len(), 1);
+        assert_eq!(parser.lex_state(), LexState::Atom);
+        assert_eq!(
+            parser.push_chunk("3 \"x").unwrap(),
+            from_str::<Vec<Value>>("123").unwrap()
+        );
+        assert_eq!(parser.lex_state(), LexState::String { escape: false });
+        assert_eq!(
+            parser.push_chunk("\"").unwrap(),
+            from_str::<Vec<Value>>(r#""x""#).unwrap()
+        );
+        assert_eq!(parser.finish().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn errors() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.push_chunk("(a) ").unwrap().len(), 1);
+        let Err(ReadError::UnexpectedClose { span }) = parser.push_chunk("b ]") else {
+            panic!("expected unexpected close");
+        };
+        assert_eq!(span, 6..7);
+        assert_eq!(parser.push_chunk(" (c").unwrap(), vec![]);
+        assert!(matches!(parser.finish(), Err(ReadError::EndOfFile)));
+
+        parser.push_chunk("\"unclosed").unwrap();
+        assert!(matches!(parser.finish(), Err(ReadError::EndOfFile)));
+        assert_eq!(parser.push_chunk("x ").unwrap().len(), 1);
+    }
+}
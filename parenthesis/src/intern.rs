@@ -0,0 +1,167 @@
+//! Interning of symbols so that equal symbols share their allocation.
+//!
+//! Symbols of up to 23 bytes are stored inline and never allocate, so interning
+//! only saves memory for longer symbols. Interned symbols still compare by their
+//! text, not by pointer.
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::Symbol;
+
+/// A pool of interned symbols.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::intern::InternPool;
+/// let mut pool = InternPool::new();
+/// let a = pool.intern("a-rather-long-symbol-name-that-allocates");
+/// let b = pool.intern("a-rather-long-symbol-name-that-allocates");
+/// assert_eq!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+/// assert_eq!(pool.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InternPool {
+    symbols: HashSet<Symbol>,
+}
+
+impl InternPool {
+    /// Create a new empty [`InternPool`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the interned symbol with the given name, interning it if necessary.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(s) {
+            return symbol.clone();
+        }
+
+        let symbol = Symbol::new(s);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+
+    /// Return the interned symbol equal to the given symbol, interning it if necessary.
+    ///
+    /// Unlike [`InternPool::intern`] this does not allocate when the symbol is new.
+    pub fn intern_symbol(&mut self, symbol: Symbol) -> Symbol {
+        if let Some(interned) = self.symbols.get(&symbol) {
+            return interned.clone();
+        }
+
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+
+    /// The number of interned symbols.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Whether the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+thread_local! {
+    static GLOBAL_POOL: RefCell<Option<InternPool>> = const { RefCell::new(None) };
+}
+
+/// Run a closure with a thread local [`InternPool`].
+///
+/// All symbols read from text within the closure are interned in the pool.
+/// Nested calls share the pool of the outermost call, which is dropped once
+/// the outermost call returns.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, intern::with_global_pool, Symbol};
+/// let (a, b): (Symbol, Symbol) = with_global_pool(|| {
+///     let a = from_str("a-rather-long-symbol-name-that-allocates").unwrap();
+///     let b = from_str("a-rather-long-symbol-name-that-allocates").unwrap();
+///     (a, b)
+/// });
+/// assert_eq!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+/// ```
+pub fn with_global_pool<F: FnOnce() -> T, T>(f: F) -> T {
+    let outermost = GLOBAL_POOL.with_borrow_mut(|pool| match pool {
+        Some(_) => false,
+        None => {
+            *pool = Some(InternPool::new());
+            true
+        }
+    });
+
+    // Remove the pool again even when the closure panics.
+    struct Guard(bool);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            if self.0 {
+                GLOBAL_POOL.with_borrow_mut(|pool| *pool = None);
+            }
+        }
+    }
+
+    let _guard = Guard(outermost);
+    f()
+}
+
+/// Intern a symbol in the thread local pool if there is one.
+pub(crate) fn intern_global(symbol: Symbol) -> Symbol {
+    GLOBAL_POOL.with_borrow_mut(|pool| match pool {
+        Some(pool) => pool.intern_symbol(symbol),
+        None => symbol,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{with_global_pool, InternPool};
+    use crate::{from_str, Symbol, Value};
+
+    const LONG: &str = "a-rather-long-symbol-name-that-allocates";
+
+    #[test]
+    fn intern_shares_allocation() {
+        let mut pool = InternPool::new();
+        let a = pool.intern(LONG);
+        let b = pool.intern_symbol(Symbol::new(LONG));
+        let c = pool.intern("other");
+
+        assert_eq!(a, b);
+        assert_eq!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+        assert_eq!(c, Symbol::new("other"));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn read_with_global_pool() {
+        let source = format!("{LONG} {LONG}");
+
+        let values: Vec<Value> = with_global_pool(|| from_str(&source).unwrap());
+        let [Value::Symbol(a), Value::Symbol(b)] = values.as_slice() else {
+            panic!("expected two symbols");
+        };
+        assert_eq!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+
+        let values: Vec<Value> = from_str(&source).unwrap();
+        let [Value::Symbol(a), Value::Symbol(b)] = values.as_slice() else {
+            panic!("expected two symbols");
+        };
+        assert_ne!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+    }
+
+    #[test]
+    fn nested_global_pool() {
+        let (a, b): (Symbol, Symbol) = with_global_pool(|| {
+            let a = from_str(LONG).unwrap();
+            let b = with_global_pool(|| from_str(LONG).unwrap());
+            (a, b)
+        });
+        assert_eq!(a.as_ref().as_ptr(), b.as_ref().as_ptr());
+    }
+}
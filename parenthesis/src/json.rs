@@ -0,0 +1,179 @@
+//! Conversion between [`Value`]s and JSON values.
+//!
+//! JSON values are converted as follows:
+//!
+//! - `null` is converted to the empty list `()`.
+//! - Booleans, strings and numbers are converted to the corresponding atoms.
+//!   Integers that do not fit into an `i64` are rejected.
+//! - Arrays are converted to sequences `[...]`.
+//! - Objects are converted to maps `{key value ...}` with string keys, in the
+//!   order in which `serde_json` iterates over the object.
+//!
+//! In the other direction, lists are converted to arrays as well, and symbols
+//! are converted to strings. Map keys that are not strings or symbols are
+//! written as s-expressions. Floats that are infinite or NaN become `null`.
+use serde_json::{Map, Number};
+use thiserror::Error;
+
+use crate::Value;
+
+/// Convert a [`Value`] into a JSON value.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, json::value_to_json, Value};
+/// let value = from_str::<Value>(r#"{"name" "x" "tags" [a b] "size" 2}"#).unwrap();
+/// let json = serde_json::json!({ "name": "x", "tags": ["a", "b"], "size": 2 });
+/// assert_eq!(value_to_json(&value), json);
+/// ```
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::List(values) if values.is_empty() => serde_json::Value::Null,
+        Value::List(values) | Value::Seq(values) => {
+            serde_json::Value::Array(values.iter().map(value_to_json).collect())
+        }
+        Value::Map(values) => {
+            let mut map = Map::with_capacity(values.len() / 2);
+
+            for entry in values.chunks(2) {
+                let key = match &entry[0] {
+                    Value::String(string) => string.to_string(),
+                    Value::Symbol(symbol) => symbol.as_ref().to_string(),
+                    key => key.to_string(),
+                };
+                let value = entry.get(1).map_or(serde_json::Value::Null, value_to_json);
+                map.insert(key, value);
+            }
+
+            serde_json::Value::Object(map)
+        }
+        Value::String(string) => serde_json::Value::String(string.to_string()),
+        Value::Symbol(symbol) => serde_json::Value::String(symbol.as_ref().to_string()),
+        Value::Bool(bool) => serde_json::Value::Bool(*bool),
+        Value::Int(int) => serde_json::Value::Number((*int).into()),
+        Value::Float(float) => Number::from_f64(float.into_inner())
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+    }
+}
+
+/// Convert a JSON value into a [`Value`].
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, json::value_from_json, Value};
+/// let json = serde_json::json!({ "name": "x", "tags": ["a", null], "size": 2.5 });
+/// let value = from_str::<Value>(r#"{"name" "x" "size" 2.5 "tags" ["a" ()]}"#).unwrap();
+/// assert_eq!(value_from_json(json).unwrap(), value);
+/// ```
+pub fn value_from_json(json: serde_json::Value) -> Result<Value, JsonConversionError> {
+    let mut path = String::new();
+    from_json(json, &mut path)
+}
+
+fn from_json(json: serde_json::Value, path: &mut String) -> Result<Value, JsonConversionError> {
+    Ok(match json {
+        serde_json::Value::Null => Value::List(Vec::new()),
+        serde_json::Value::Bool(bool) => Value::Bool(bool),
+        serde_json::Value::Number(number) => {
+            if let Some(int) = number.as_i64() {
+                Value::Int(int)
+            } else if number.is_f64() {
+                Value::from(number.as_f64().unwrap_or_default())
+            } else {
+                return Err(JsonConversionError {
+                    path: path.clone(),
+                    message: format!("integer `{}` out of range", number),
+                });
+            }
+        }
+        serde_json::Value::String(string) => Value::String(string.into()),
+        serde_json::Value::Array(values) => {
+            let mut items = Vec::with_capacity(values.len());
+
+            for (index, value) in values.into_iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", index));
+                items.push(from_json(value, path)?);
+                path.truncate(len);
+            }
+
+            Value::Seq(items)
+        }
+        serde_json::Value::Object(map) => {
+            let mut items = Vec::with_capacity(map.len() * 2);
+
+            for (key, value) in map {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                let value = from_json(value, path)?;
+                path.truncate(len);
+                items.push(Value::String(key.into()));
+                items.push(value);
+            }
+
+            Value::Map(items)
+        }
+    })
+}
+
+/// Error while converting a JSON value into a [`Value`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} at JSON pointer `{path}`")]
+pub struct JsonConversionError {
+    path: String,
+    message: String,
+}
+
+impl JsonConversionError {
+    /// The JSON pointer to the value that could not be converted.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::{value_from_json, value_to_json};
+    use crate::{from_str, Value};
+
+    #[rstest]
+    #[case(json!(null), "()")]
+    #[case(json!(true), "#t")]
+    #[case(json!(false), "#f")]
+    #[case(json!(-12), "-12")]
+    #[case(json!(1.5), "1.5")]
+    #[case(json!("a b"), r#""a b""#)]
+    #[case(json!([]), "[]")]
+    #[case(json!({}), "{}")]
+    #[case(json!([1, [null, "x"], { "k": [] }]), r#"[1 [() "x"] {"k" []}]"#)]
+    #[case(json!({ "a": { "b/c": [false] } }), r#"{"a" {"b/c" [#f]}}"#)]
+    fn roundtrip(#[case] json: serde_json::Value, #[case] text: &str) {
+        let value: Value = from_str(text).unwrap();
+        assert_eq!(value_from_json(json.clone()).unwrap(), value);
+        assert_eq!(value_to_json(&value), json);
+    }
+
+    #[test]
+    fn to_json_lossy() {
+        let value: Value = from_str("((a 1) #nan {k} {(x) 2 |y z| 3})").unwrap();
+        let json = json!([["a", 1], null, { "k": null }, { "(x)": 2, "y z": 3 }]);
+        assert_eq!(value_to_json(&value), json);
+    }
+
+    #[test]
+    fn error_path() {
+        let json = json!({ "a/b": [0, { "c": u64::MAX }] });
+        let error = value_from_json(json).unwrap_err();
+        assert_eq!(error.path(), "/a~1b/1/c");
+        assert_eq!(
+            error.to_string(),
+            "integer `18446744073709551615` out of range at JSON pointer `/a~1b/1/c`"
+        );
+    }
+}
@@ -11,6 +11,10 @@
 //! - **Lists** are sequences of values, delimited on the outside by `(` and `)`
 //!   and separated by whitespace.
 //!
+//! - **Sequences** and **maps** are written like lists, but are delimited by
+//!   `[` and `]` or by `{` and `}` respectively. By convention, sequences hold
+//!   homogeneous values and maps hold alternating keys and values.
+//!
 //! - **Strings** are delimited by double quotes `"` on both sides,
 //!   using the following escaping rules:
 //!
@@ -48,7 +52,7 @@
 //! Since s-expressions do not cleanly map onto the serde data model, this crate
 //! comes with its own derive macros instead.
 //! In particular, the [`FromParens`] and [`ToParens`] traits can be derived
-//! automatically for structs with named fields.
+//! automatically for structs.
 //!
 //! ```
 //! # use parenthesis::{FromParens, ToParens};
@@ -89,26 +93,273 @@
 //! # #[cfg(not(feature = "macros"))]
 //! # pub fn main() {}
 //! ```
+//!
+//! Named fields are written after the positional fields and may appear in any
+//! order, while they are printed in the order in which they are declared.
+//! Forms whose head is not the name of a named field are skipped when parsing.
+//! With `#[sexpr(deny_unknown_fields)]` on the struct or enum, they are rejected
+//! with an error that lists the names of the expected fields instead.
+//! The number of occurrences of a `repeated` field can be bounded with
+//! `#[sexpr(repeated, min = 1, max = 4)]`, which is checked when parsing.
+//!
+//! The value of a named field is read from the rest of its form, so a form such as
+//! `(meta)` without further values is accepted for a struct whose fields are all
+//! optional or have defaults. With `#[sexpr(optional, default_if_empty)]` or
+//! `#[sexpr(required, default_if_empty)]`, such an empty form is instead read as
+//! the `Default` value of the field's type, regardless of the fields it requires.
+//!
+//! Fields can have any of the primitive integer types. Integers that do not fit
+//! into the type of their field, such as `300` for a `u8` or `-1` for a `usize`,
+//! are reported at the integer, as are all other errors within a field. The message
+//! names the field and the struct, and those of all enclosing fields.
+//!
+//! The fields of tuple structs are positional fields in the order in which they
+//! are declared. Like other structs without delimiters, a newtype such as
+//! `struct Name(Symbol);` reads its field and then expects only named fields
+//! until the end of the enclosing list. It is not transparent by default; use
+//! `#[sexpr(transparent)]` to read and write a newtype exactly like its field.
+//!
+//! A positional field of type `Option<T>` marked with `#[sexpr(positional_optional)]`
+//! takes the next value if it parses as a `T` and is not the form of a named field,
+//! and is `None` otherwise. Such fields must follow all other positional fields.
+//! Without the attribute, a positional `Option<T>` field is read by the impl of
+//! [`FromParens`] for `Option<T>`, which also takes the next value if it parses
+//! as a `T`, but does not skip the forms of named fields.
+//!
+//! A field marked with `#[sexpr(seq)]` is written as a single sequence `[...]`
+//! that contains its values, which is useful for fields of type `Vec`. Combined
+//! with `repeated`, all values are written in one form `(name [...])` that may
+//! be absent when there are no values, or that takes the value given by `default`
+//! when it is absent. Similarly, a map such as a `BTreeMap` in
+//! a field marked with `#[sexpr(map)]` is written as a map `{key value ...}`
+//! instead of as lists that each contain a key and a value.
+//!
+//! A field of type [`read::Span`] that is marked with `#[sexpr(span)]` is not
+//! read or written, but is filled with the span of the list that encloses the
+//! fields when parsing. At most one field of a struct can be marked this way.
+//!
+//! With `#[sexpr(tag = "...")]`, a struct is written as a list that starts with
+//! the given symbol, followed by its fields. A named field of such a type that is
+//! marked with `#[sexpr(tagged)]` is written without the extra nesting level, as
+//! a list that starts with the field name in place of the tag.
+//!
+//! ```
+//! # use parenthesis::{FromParens, ToParens, Symbol};
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! #[derive(Debug, PartialEq, FromParens, ToParens)]
+//! #[sexpr(tag = "operation")]
+//! pub struct Operation {
+//!     name: Symbol,
+//! }
+//!
+//! #[derive(Debug, PartialEq, FromParens, ToParens)]
+//! pub struct Module {
+//!     #[sexpr(repeated, tagged)]
+//!     operation: Vec<Operation>,
+//! }
+//!
+//! let module = parenthesis::from_str::<Module>("(operation mul) (operation add)").unwrap();
+//! assert_eq!(module.operation[1], Operation { name: "add".into() });
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
+//!
+//! Types whose invariants are enforced by a constructor can be parsed through an
+//! unchecked type with `#[sexpr(from = "Raw")]`, which parses a `Raw` and converts
+//! it with `TryFrom`. A conversion error is reported at the span of the parsed
+//! value, like the error of a `validate` function. Likewise, `#[sexpr(into = "Raw")]`
+//! prints a clone of the value converted with `Into`. Since the syntax is that of
+//! the other type, options such as `tag`, `seq` or `rename_all` can not be combined
+//! with `from` or `into`.
+//!
+//! Types that already implement serde's traits can still be converted to and
+//! from [`Value`]s with the `serde` module, which requires the `serde` feature.
+//! The `json` module, which requires the `json` feature, converts between
+//! [`Value`]s and JSON values.
+//!
+//! The traits can also be derived for enums. Each variant is written as a list
+//! that starts with the name of the variant in kebab case, followed by the
+//! fields of the variant. Unit variants are written as just the name instead.
+//! The name can be changed with `#[sexpr(rename = "...")]`. To derive the names
+//! of all variants or named fields with another rule, use `#[sexpr(rename_all = "...")]`
+//! with one of `kebab-case`, `snake_case`, `camelCase`, `SCREAMING_SNAKE_CASE`
+//! or `lowercase` on the enum or struct. Variants and named fields can also be
+//! given additional names with `#[sexpr(alias = "...")]`, which are accepted
+//! when parsing while the canonical name is always used when printing.
+//! A tuple variant marked with `#[sexpr(other)]`, such as `Other(Symbol, Vec<Value>)`,
+//! receives the head and values of lists whose head is not the name of any
+//! other variant, and writes them back unchanged.
+//!
+//! ```
+//! # use parenthesis::{FromParens, ToParens, Symbol};
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! #[derive(Debug, PartialEq, FromParens, ToParens)]
+//! pub enum Shape {
+//!     Circle(f64),
+//!     NamedPoint {
+//!         name: Symbol,
+//!         #[sexpr(required)]
+//!         x: f64,
+//!     },
+//!     Empty,
+//! }
+//!
+//! let shapes = parenthesis::from_str::<Vec<Shape>>("(circle 1.0) (named-point p (x 2.0)) empty");
+//! let shapes = shapes.unwrap();
+//! assert_eq!(shapes[0], Shape::Circle(1.0));
+//! assert_eq!(shapes[2], Shape::Empty);
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
+//!
+//! Enums marked with `#[sexpr(untagged)]` are distinguished by the shape of
+//! their values instead. The fields of each variant are written in place and
+//! unit variants are written as a symbol of their name. When parsing, the
+//! variants are tried in the order in which they are declared and the first
+//! variant that succeeds is used, so ambiguities are resolved by order.
+//! If no variant succeeds, the error of the variant that got furthest is reported.
+//!
+//! ```
+//! # use parenthesis::{FromParens, ToParens};
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! #[derive(Debug, PartialEq, FromParens, ToParens)]
+//! #[sexpr(untagged)]
+//! pub enum Size {
+//!     Fixed(i64),
+//!     Auto,
+//! }
+//!
+//! let sizes = parenthesis::from_str::<Vec<Size>>("12 auto").unwrap();
+//! assert_eq!(sizes, [Size::Fixed(12), Size::Auto]);
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
+//!
+//! For generic types, the derived implementations require the traits for each
+//! type parameter that occurs in a field, except for skipped fields and type
+//! parameters within `PhantomData`. When these bounds are wrong, they can be
+//! replaced with `#[sexpr(bound = "...")]` or, to give separate bounds for each
+//! trait, `#[sexpr(bound(from_parens = "...", to_parens = "..."))]`. The bounds
+//! may refer to the stream types of the derived implementations as `__I` and `__O`.
+//! Fields of type `()` or `PhantomData<T>` need no attributes, since reading
+//! them consumes no tokens and always succeeds, and writing them produces no tokens.
+//!
+//! Types may also have lifetime parameters. Borrowed fields such as `&'a str`
+//! can be printed, but not parsed, since input streams hand out owned strings
+//! rather than slices of the input, which may contain escape sequences. For a
+//! field that is parsed, use `Cow<'a, str>`, which is always parsed as
+//! [`Cow::Owned`](std::borrow::Cow::Owned), or [`SmolStr`],
+//! which keeps short strings inline without allocating.
+//!
+//! Deriving [`Describe`] alongside [`FromParens`] describes the expected syntax of a
+//! type from the same `sexpr` attributes, for usage messages and documentation.
+//! Derived types appear by name within the syntax of other types, as in
+//! `(node <symbol> (input <port>)...)`, see the [`describe`] module.
+//!
+//! # S-expression Literals
+//!
+//! The [`sexpr!`] macro reads an s-expression at compile time and expands to
+//! the code that constructs the corresponding [`Value`]. The [`sexpr_vec!`]
+//! macro does the same for a sequence of s-expressions.
+//!
+//! ```
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! # use parenthesis::{from_str, sexpr, sexpr_vec, Value};
+//! let value = sexpr!((define input-ports [1 2.5] "text" #t));
+//! assert_eq!(value, from_str::<Value>(r#"(define input-ports [1 2.5] "text" #t)"#).unwrap());
+//!
+//! let values = sexpr_vec!((a) (b c));
+//! assert_eq!(values, from_str::<Vec<Value>>("(a) (b c)").unwrap());
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
+//!
+//! Values can be destructured with s-expression patterns using [`match_sexpr!`].
+//!
+//! ```
+//! # #[cfg(feature = "macros")]
+//! # pub fn main() {
+//! # use parenthesis::{match_sexpr, sexpr};
+//! let value = sexpr!((add 1 2));
+//! let result = match_sexpr!(value,
+//!     (add $x:int $y:int) => x + y,
+//!     (neg $x:int) => -x,
+//!     _ => 0,
+//! );
+//! assert_eq!(result, 3);
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # pub fn main() {}
+//! ```
 use ordered_float::OrderedFloat;
 use smol_str::SmolStr;
 use std::fmt::Display;
-pub(crate) mod escape;
+use std::str::FromStr;
+pub mod annotate;
+#[cfg(feature = "arena")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arena")))]
+pub mod arena;
+#[cfg(feature = "binary")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binary")))]
+pub mod binary;
+pub mod describe;
+pub mod dispatch;
+pub mod escape;
+pub mod fingerprint;
 pub mod from_parens;
+pub mod incremental;
+#[cfg(feature = "interning")]
+#[cfg_attr(docsrs, doc(cfg(feature = "interning")))]
+pub mod intern;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod json;
+#[cfg(feature = "unicode-normalization")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+pub mod normalize;
+pub mod path;
 pub mod pretty;
 pub mod read;
+pub mod schema;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod serde;
+pub mod span;
+pub mod testing;
 pub mod to_parens;
 
+pub use annotate::{from_str_annotated, AnnotatedValue};
+pub use describe::{Describe, Syntax};
+pub use dispatch::Dispatcher;
 pub use from_parens::FromParens;
-pub use pretty::{to_fmt_pretty, to_string_pretty};
-pub use read::from_str;
+pub use pretty::{to_fmt_compact, to_fmt_pretty, to_string_compact, to_string_pretty, PrintValues};
+pub use read::{from_str, from_str_all_errors, from_str_partial, from_str_with_opts, ReadOptions};
 pub use to_parens::{to_values, ToParens};
 
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use parenthesis_macros::{match_sexpr, sexpr, sexpr_vec};
+
 /// A value that can be encoded as an s-expression.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Value {
     /// Lists are sequences of zero or more values.
     List(Vec<Self>),
 
+    /// Sequences are lists that are delimited by square brackets.
+    Seq(Vec<Self>),
+
+    /// Maps are lists that are delimited by curly braces.
+    Map(Vec<Self>),
+
     /// Strings can be any valid UTF-8 string.
     String(SmolStr),
 
@@ -143,6 +394,113 @@ impl Value {
         }
     }
 
+    /// Attempts to cast this value into a sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// assert_eq!(Value::Seq(vec![]).as_seq(), Some(&vec![]));
+    /// assert_eq!(Value::List(vec![]).as_seq(), None);
+    /// ```
+    pub fn as_seq(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Seq(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// Attempts to cast this value into a map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// assert_eq!(Value::Map(vec![]).as_map(), Some(&vec![]));
+    /// assert_eq!(Value::List(vec![]).as_map(), None);
+    /// ```
+    pub fn as_map(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Estimates the number of bytes of heap memory owned by this value.
+    ///
+    /// This sums up the capacities of all nested lists, sequences and maps as
+    /// well as the strings and symbols that are too long to be stored inline.
+    /// Strings and symbols that share their allocation are counted once for
+    /// each value that refers to them. The tree is traversed iteratively,
+    /// so that deeply nested values do not overflow the stack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// assert_eq!(Value::Int(1).heap_size(), 0);
+    /// assert!(Value::List(Vec::with_capacity(4)).heap_size() >= 4 * std::mem::size_of::<Value>());
+    /// ```
+    pub fn heap_size(&self) -> usize {
+        // Strings that are not inline are stored in an `Arc<str>`,
+        // whose allocation includes the strong and weak reference counts.
+        fn string_heap_size(string: &SmolStr) -> usize {
+            match string.is_heap_allocated() {
+                true => string.len() + 2 * std::mem::size_of::<usize>(),
+                false => 0,
+            }
+        }
+
+        let mut size = 0;
+        let mut stack = vec![self];
+
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::List(values) | Value::Seq(values) | Value::Map(values) => {
+                    size += values.capacity() * std::mem::size_of::<Value>();
+                    stack.extend(values);
+                }
+                Value::String(string) => size += string_heap_size(string),
+                Value::Symbol(symbol) => size += string_heap_size(&symbol.0),
+                Value::Bool(_) | Value::Int(_) | Value::Float(_) => {}
+            }
+        }
+
+        size
+    }
+
+    /// Iterates over the elements of a list, sequence or map.
+    ///
+    /// Returns `None` if the value is not a list, sequence or map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// let value: Value = "(1 2 3)".parse().unwrap();
+    /// let sum: i64 = value.iter().unwrap().filter_map(Value::as_int).sum();
+    /// assert_eq!(sum, 6);
+    /// assert!(Value::Int(3).iter().is_none());
+    /// ```
+    pub fn iter(&self) -> Option<std::slice::Iter<'_, Value>> {
+        match self {
+            Value::List(values) | Value::Seq(values) | Value::Map(values) => Some(values.iter()),
+            _ => None,
+        }
+    }
+
+    /// Iterates mutably over the elements of a list, sequence or map.
+    ///
+    /// Returns `None` if the value is not a list, sequence or map.
+    pub fn iter_mut(&mut self) -> Option<std::slice::IterMut<'_, Value>> {
+        match self {
+            Value::List(values) | Value::Seq(values) | Value::Map(values) => {
+                Some(values.iter_mut())
+            }
+            _ => None,
+        }
+    }
+
     /// Attempts to cast this value into a symbol.
     ///
     /// # Examples
@@ -207,12 +565,122 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Pretty prints this value into a string with the given width, as by [`to_string_pretty`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// let value: Value = "(define (f x) (add x 1))".parse().unwrap();
+    /// assert_eq!(value.pretty(80), "(define (f x) (add x 1))");
+    /// assert_eq!(value.pretty(16), "(define\n  (f x)\n  (add x 1))");
+    /// ```
+    pub fn pretty(&self, width: usize) -> String {
+        to_string_pretty(self, width)
+    }
+
+    /// Prints this value into a string on a single line, as by [`to_string_compact`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Value;
+    /// let value: Value = "(define\n  (f x)\n  (add x 1))".parse().unwrap();
+    /// assert_eq!(value.compact(), "(define (f x) (add x 1))");
+    /// ```
+    pub fn compact(&self) -> String {
+        to_string_compact(self)
+    }
 }
 
+/// Values are displayed as s-expressions on a single line.
+/// When a width is given, as in `{:80}`, the value is pretty printed instead.
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let width = f.width().unwrap_or(80);
-        to_fmt_pretty(self, width, f)
+        match f.width() {
+            Some(width) => to_fmt_pretty(self, width, f),
+            None => to_fmt_compact(self, f),
+        }
+    }
+}
+
+/// Values are parsed from the first s-expression in a string, as by [`from_str`].
+/// Use [`from_str`] with `Vec<Value>` to parse all values in a string.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{Symbol, Value};
+/// let value: Value = "(foo 1 2)".parse().unwrap();
+/// assert_eq!(value.as_list().unwrap()[0], Value::from(Symbol::from("foo")));
+/// ```
+impl FromStr for Value {
+    type Err = read::ReadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_str(s)
+    }
+}
+
+/// Iterates over the elements of a list, sequence or map.
+///
+/// # Panics
+///
+/// Panics if the value is not a list, sequence or map.
+/// Use [`Value::iter`] for a non-panicking alternative.
+impl IntoIterator for Value {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Value::List(values) | Value::Seq(values) | Value::Map(values) => values.into_iter(),
+            value => panic!("can not iterate over `{}`", value),
+        }
+    }
+}
+
+/// Iterates over the elements of a list, sequence or map.
+///
+/// # Panics
+///
+/// Panics if the value is not a list, sequence or map.
+/// Use [`Value::iter`] for a non-panicking alternative.
+impl<'a> IntoIterator for &'a Value {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.iter() {
+            Some(iter) => iter,
+            None => panic!("can not iterate over `{}`", self),
+        }
+    }
+}
+
+/// Iterates mutably over the elements of a list, sequence or map.
+///
+/// # Panics
+///
+/// Panics if the value is not a list, sequence or map.
+/// Use [`Value::iter_mut`] for a non-panicking alternative.
+impl<'a> IntoIterator for &'a mut Value {
+    type Item = &'a mut Value;
+    type IntoIter = std::slice::IterMut<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Value::List(values) | Value::Seq(values) | Value::Map(values) => values.iter_mut(),
+            value => panic!("can not iterate over `{}`", value),
+        }
+    }
+}
+
+/// The [`Default`] value is the empty list `()`.
+impl Default for Value {
+    fn default() -> Self {
+        Value::List(Vec::new())
     }
 }
 
@@ -265,6 +733,8 @@ impl From<f64> for Value {
 }
 
 /// A symbol.
+///
+/// The [`Default`] symbol is the empty symbol, which is written as `||`.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Symbol(SmolStr);
 
@@ -273,6 +743,38 @@ impl Symbol {
     pub fn new(string: impl AsRef<str>) -> Self {
         Self(SmolStr::new(string))
     }
+
+    /// Create a new [`Symbol`] from a static string without allocating.
+    ///
+    /// Since this is a `const` function, it can be used to define constant
+    /// symbols. See also the [`sym!`] macro.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parenthesis::Symbol;
+    /// const DEFINE: Symbol = Symbol::from_static("define");
+    /// assert_eq!(DEFINE, Symbol::new("define"));
+    /// ```
+    pub const fn from_static(string: &'static str) -> Self {
+        Self(SmolStr::new_static(string))
+    }
+}
+
+/// Create a [`Symbol`] from a string literal in a `const` context.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{sym, Symbol};
+/// const FOO: Symbol = sym!("foo");
+/// assert_eq!(FOO, Symbol::new("foo"));
+/// ```
+#[macro_export]
+macro_rules! sym {
+    ($name:literal) => {
+        $crate::Symbol::from_static($name)
+    };
 }
 
 impl From<SmolStr> for Symbol {
@@ -305,18 +807,115 @@ impl AsRef<str> for Symbol {
     }
 }
 
+impl std::borrow::Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
 impl From<&str> for Symbol {
     fn from(value: &str) -> Self {
         Self(value.into())
     }
 }
 
+/// Symbols are displayed as they are written in s-expressions,
+/// delimited by `|` if necessary.
 impl Display for Symbol {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.as_ref())
+        f.write_str(&escape::escape_symbol(self.as_ref()))
+    }
+}
+
+/// All remaining values in a stream.
+///
+/// When parsing, [`Rest`] consumes every remaining token tree of the stream.
+/// When printing, the values are written back verbatim. This is useful to
+/// preserve unknown trailing forms of an extensible format for round-tripping.
+///
+/// The derive macros accept a field of type [`Rest`] as the last field of a
+/// struct. Any forms that follow the known fields are collected into it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Rest(pub Vec<Value>);
+
+impl From<Vec<Value>> for Rest {
+    fn from(value: Vec<Value>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Rest> for Vec<Value> {
+    fn from(value: Rest) -> Self {
+        value.0
+    }
+}
+
+/// A value that defaults when it is absent at the end of a stream.
+///
+/// When parsing, [`OrDefault`] uses the [`Default`] value of `T` if there are
+/// no more values in the stream. Otherwise the next value must parse as `T`;
+/// a value of another type is an error and does not fall back to the default.
+/// This makes [`OrDefault`] suitable for trailing positional fields.
+///
+/// When printing, the value is always written. Use
+/// [`to_parens::skip_default`] to omit values that are equal to the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct OrDefault<T>(pub T);
+
+impl<T> From<T> for OrDefault<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+/// An integer that is restricted to the inclusive range `MIN..=MAX`.
+///
+/// Parsing an integer outside of the range fails with an error that points
+/// at the integer. When printing, the integer is written as is.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, Bounded};
+/// let arity: Bounded<1, 8> = from_str("3").unwrap();
+/// assert_eq!(arity.get(), 3);
+/// assert!(from_str::<Bounded<1, 8>>("9").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bounded<const MIN: i64, const MAX: i64>(i64);
+
+impl<const MIN: i64, const MAX: i64> Bounded<MIN, MAX> {
+    /// Create a new [`Bounded`] integer if the value is within the range.
+    pub fn new(value: i64) -> Option<Self> {
+        (MIN..=MAX).contains(&value).then_some(Self(value))
+    }
+
+    /// The value of the integer.
+    pub fn get(self) -> i64 {
+        self.0
     }
 }
 
+impl<const MIN: i64, const MAX: i64> From<Bounded<MIN, MAX>> for i64 {
+    fn from(value: Bounded<MIN, MAX>) -> Self {
+        value.0
+    }
+}
+
+/// A value that is one of two alternatives.
+///
+/// When parsing, `A` is tried first. If that fails, `B` is tried instead
+/// from the same position. If both fail, the error of the alternative that
+/// got further is returned; see [`from_parens::alt`] for details.
+/// When printing, the value of whichever alternative is present is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Either<A, B> {
+    /// The first alternative.
+    Left(A),
+    /// The second alternative.
+    Right(B),
+}
+
 impl proptest::arbitrary::Arbitrary for Symbol {
     type Parameters = ();
     type Strategy = proptest::strategy::SBoxedStrategy<Self>;
@@ -327,11 +926,12 @@ impl proptest::arbitrary::Arbitrary for Symbol {
     }
 }
 
+/// The size of the generated values can be configured with [`testing::ValueParameters`].
 impl proptest::arbitrary::Arbitrary for Value {
-    type Parameters = ();
+    type Parameters = testing::ValueParameters;
     type Strategy = proptest::strategy::BoxedStrategy<Self>;
 
-    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
         use proptest::prelude::*;
 
         let leaf = prop_oneof![
@@ -342,8 +942,14 @@ impl proptest::arbitrary::Arbitrary for Value {
             proptest::num::f64::ANY.prop_map(Value::from)
         ];
 
-        leaf.prop_recursive(8, 256, 10, |inner| {
-            proptest::collection::vec(inner, 0..10).prop_map(Value::List)
+        let branch = args.branch as usize;
+
+        leaf.prop_recursive(args.depth, args.size, args.branch, move |inner| {
+            prop_oneof![
+                proptest::collection::vec(inner.clone(), 0..branch).prop_map(Value::List),
+                proptest::collection::vec(inner.clone(), 0..branch).prop_map(Value::Seq),
+                proptest::collection::vec(inner, 0..branch).prop_map(Value::Map),
+            ]
         })
         .boxed()
     }
@@ -351,8 +957,900 @@ impl proptest::arbitrary::Arbitrary for Value {
 
 #[cfg(test)]
 mod test {
-    use super::{from_str, to_string_pretty, Value};
+    use super::{
+        from_parens::{InputStream, ParseError, TokenTree},
+        from_str, pretty,
+        read::ReadError,
+        to_parens::OutputStream,
+        to_string_compact, to_string_pretty, to_values, Bounded, Either, FromParens, Symbol,
+        ToParens, Value,
+    };
     use proptest::prelude::*;
+    use std::borrow::Cow;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn tuple_round_trip() {
+        type Tuple = (i64, (Symbol, String), Vec<(i64, f64)>, ());
+
+        let tuple: Tuple = (
+            1,
+            (Symbol::new("a"), "b".to_string()),
+            vec![(2, 3.0), (4, 5.0)],
+            (),
+        );
+
+        let values = to_values(&tuple);
+        assert_eq!(
+            values,
+            from_str::<Vec<Value>>(r#"1 a "b" 2 3.0 4 5.0"#).unwrap()
+        );
+
+        let parsed: Tuple = from_str(&to_string_pretty(&tuple, 80)).unwrap();
+        assert_eq!(parsed, tuple);
+    }
+
+    #[test]
+    fn array_round_trip() {
+        let colors = [[1.0, 0.5, 0.0], [0.0, 0.0, 1.0]];
+        let values = to_values(colors.map(|color| Value::List(to_values(color))));
+        assert_eq!(
+            values,
+            from_str::<Vec<Value>>("(1.0 0.5 0.0) (0.0 0.0 1.0)").unwrap()
+        );
+
+        let parsed: [f64; 3] = from_str(&to_string_pretty(colors[0], 80)).unwrap();
+        assert_eq!(parsed, colors[0]);
+
+        let parsed: [f64; 0] = from_str("").unwrap();
+        assert_eq!(parsed, [0.0; 0]);
+    }
+
+    #[test]
+    fn array_wrong_length() {
+        let error = from_str::<[i64; 3]>("1 2").unwrap_err();
+        assert_eq!(error.to_string(), "expected 3 elements, found 2");
+
+        let error = from_str::<[i64; 3]>("1 2 a").unwrap_err();
+        assert_eq!(error.to_string(), "expected int, found symbol");
+
+        // Like tuples, arrays read exactly their length and leave the rest.
+        let (parsed, rest) = super::from_str_partial::<[i64; 3]>("1 2 3 4").unwrap();
+        assert_eq!(parsed, [1, 2, 3]);
+        assert_eq!(rest, "4");
+
+        let parsed: ([i64; 2], String) = from_str(r#"1 2 "a""#).unwrap();
+        assert_eq!(parsed, ([1, 2], "a".to_owned()));
+    }
+
+    #[test]
+    fn map_round_trip() {
+        type Map = BTreeMap<Symbol, BTreeMap<i64, String>>;
+
+        let text = r#"(key-1 (1 "a") (2 "b")) (key-2)"#;
+        let map: Map = from_str(text).unwrap();
+
+        let expected = Map::from([
+            (
+                Symbol::new("key-1"),
+                BTreeMap::from([(1, "a".to_string()), (2, "b".to_string())]),
+            ),
+            (Symbol::new("key-2"), BTreeMap::new()),
+        ]);
+
+        assert_eq!(map, expected);
+        assert_eq!(to_values(&map), from_str::<Vec<Value>>(text).unwrap());
+
+        let map: HashMap<Symbol, String> = from_str(r#"(a "1") (b "2")"#).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            from_str::<HashMap<Symbol, String>>(&to_string_pretty(&map, 80)).unwrap(),
+            map
+        );
+
+        assert!(from_str::<BTreeMap<Symbol, String>>("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn map_duplicate_key() {
+        let result = from_str::<BTreeMap<Symbol, i64>>("(a 1) (b 2) (a 3)");
+        let Err(ReadError::Parse(error)) = result else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "duplicate key");
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (12..17))
+        );
+
+        assert!(from_str::<HashMap<Symbol, i64>>("(a 1) (a 3)").is_err());
+        assert!(from_str::<HashMap<Symbol, i64>>("(a 1 2)").is_err());
+    }
+
+    macro_rules! test_int_bounds {
+        ($($name:ident: $int:ty),*) => {
+            $(
+                #[test]
+                fn $name() {
+                    for bound in [<$int>::MIN, <$int>::MAX] {
+                        let values = to_values(bound);
+                        assert_eq!(values, [Value::Int(bound as i64)]);
+
+                        let parsed: $int = from_str(&to_string_pretty(bound, 80)).unwrap();
+                        assert_eq!(parsed, bound);
+                    }
+
+                    let below = (<$int>::MIN as i128 - 1).to_string();
+                    let above = (<$int>::MAX as i128 + 1).to_string();
+
+                    for out_of_range in [below, above] {
+                        let error = from_str::<$int>(&out_of_range).unwrap_err();
+                        assert_eq!(
+                            error.to_string(),
+                            format!("integer `{}` out of range for `{}`", out_of_range, stringify!($int))
+                        );
+                    }
+                }
+            )*
+        };
+    }
+
+    test_int_bounds!(
+        int_bounds_i8: i8,
+        int_bounds_i16: i16,
+        int_bounds_i32: i32,
+        int_bounds_u8: u8,
+        int_bounds_u16: u16,
+        int_bounds_u32: u32
+    );
+
+    #[test]
+    fn int_bounds_wide() {
+        assert_eq!(
+            from_str::<u64>(&i64::MAX.to_string()).unwrap(),
+            i64::MAX as u64
+        );
+        assert_eq!(from_str::<usize>("0").unwrap(), 0);
+        assert_eq!(
+            from_str::<isize>(&i64::MIN.to_string()).unwrap() as i64,
+            i64::MIN
+        );
+        assert!(from_str::<u64>("-1").is_err());
+        assert!(from_str::<usize>("-1").is_err());
+        assert_eq!(to_values(i64::MAX as u64), [Value::Int(i64::MAX)]);
+    }
+
+    #[test]
+    fn int_write_out_of_range() {
+        let mut output = pretty::Pretty::new();
+        let error = u64::MAX.to_parens(&mut output).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "integer `18446744073709551615` of type `u64` exceeds the range of s-expression integers"
+        );
+
+        let error = (i64::MAX as usize + 1).to_parens(&mut output).unwrap_err();
+        assert!(error.to_string().contains("of type `usize`"));
+
+        let mut string = String::new();
+        assert!(pretty::to_fmt_compact(vec![1, u64::MAX], &mut string).is_err());
+        assert!(string.is_empty());
+    }
+
+    #[test]
+    fn nonzero_int() {
+        use std::num::{NonZeroI8, NonZeroU32, NonZeroU8};
+
+        let parsed: NonZeroU8 = from_str("255").unwrap();
+        assert_eq!(parsed.get(), u8::MAX);
+        assert_eq!(to_values(parsed), [Value::Int(255)]);
+        assert_eq!(from_str::<NonZeroI8>("-128").unwrap().get(), i8::MIN);
+
+        let error = from_str::<(Symbol, NonZeroU32)>("ports 0").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "integer `0` out of range for `NonZeroU32`"
+        );
+        assert!(
+            matches!(error, ReadError::Parse(ParseError::Error { span, .. }) if span == (6..7))
+        );
+
+        let error = from_str::<NonZeroU8>("256").unwrap_err();
+        assert_eq!(error.to_string(), "integer `256` out of range for `u8`");
+    }
+
+    #[test]
+    fn bounded_int() {
+        let parsed: (Bounded<1, 8>, Bounded<-2, 2>) = from_str("8 -2").unwrap();
+        assert_eq!((parsed.0.get(), parsed.1.get()), (8, -2));
+        assert_eq!(to_values(parsed), [Value::Int(8), Value::Int(-2)]);
+
+        for (text, span) in [("0", 0..1), (" 9", 1..2)] {
+            let error = from_str::<Bounded<1, 8>>(text).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("integer `{}` out of range `1..=8`", text.trim())
+            );
+            assert!(
+                matches!(error, ReadError::Parse(ParseError::Error { span: s, .. }) if s == span)
+            );
+        }
+
+        assert_eq!(Bounded::<1, 8>::new(0), None);
+        assert_eq!(i64::from(Bounded::<1, 8>::new(1).unwrap()), 1);
+    }
+
+    #[test]
+    fn value_from_str() {
+        assert_eq!(
+            "symbol".parse::<Value>().unwrap(),
+            Value::Symbol(Symbol::from("symbol"))
+        );
+        assert_eq!(
+            "(foo 1)".parse::<Value>().unwrap(),
+            Value::List(vec![Value::Symbol(Symbol::from("foo")), Value::Int(1)])
+        );
+        assert!(matches!(
+            "(broken".parse::<Value>(),
+            Err(ReadError::EndOfFile)
+        ));
+        assert_eq!(
+            "a b".parse::<Value>().unwrap(),
+            Value::Symbol(Symbol::from("a"))
+        );
+    }
+
+    #[test]
+    fn value_iter() {
+        let mut value: Value = "(1 [2] 3)".parse().unwrap();
+
+        for element in &mut value {
+            if let Value::Int(int) = element {
+                *int *= 2;
+            }
+        }
+
+        let elements: Vec<&Value> = (&value).into_iter().collect();
+        assert_eq!(
+            elements,
+            [
+                &Value::Int(2),
+                &Value::Seq(vec![Value::Int(2)]),
+                &Value::Int(6)
+            ]
+        );
+        assert_eq!(value.iter().unwrap().count(), 3);
+        assert_eq!(value.clone().into_iter().last(), Some(Value::Int(6)));
+
+        for element in value.iter_mut().unwrap() {
+            if let Some(inner) = element.iter_mut() {
+                inner.for_each(|inner| *inner = Value::Bool(true));
+            }
+        }
+
+        assert_eq!(value.to_string(), "(2 [#t] 6)");
+        assert!(Value::Int(1).iter().is_none());
+        assert!(Value::Int(1).iter_mut().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "can not iterate over `sym`")]
+    fn value_into_iter_panics() {
+        for _ in Value::from(Symbol::from("sym")) {}
+    }
+
+    #[test]
+    fn value_heap_size() {
+        let value_size = std::mem::size_of::<Value>();
+        let long = "a".repeat(100);
+        let heap_string = 100 + 2 * std::mem::size_of::<usize>();
+
+        assert_eq!(Value::from("short").heap_size(), 0);
+        assert_eq!(Value::from(long.clone()).heap_size(), heap_string);
+        assert_eq!(Value::from(Symbol::new(&long)).heap_size(), heap_string);
+
+        let mut list = Vec::with_capacity(4);
+        list.push(Value::Int(1));
+        list.push(Value::Seq(vec![Value::from(long.clone())]));
+        assert_eq!(
+            Value::List(list).heap_size(),
+            4 * value_size + value_size + heap_string
+        );
+
+        // Deeply nested values are measured without recursion.
+        let mut value = Value::Int(0);
+
+        for _ in 0..100_000 {
+            value = Value::List(vec![value]);
+        }
+
+        assert_eq!(value.heap_size(), 100_000 * value_size);
+
+        // Avoid a stack overflow from the recursive drop as well.
+        while let Value::List(mut values) = value {
+            value = values.pop().unwrap();
+        }
+    }
+
+    #[test]
+    fn symbol_const() {
+        const FOO: Symbol = crate::sym!("foo");
+        const LONG: Symbol = Symbol::from_static("a-symbol-that-is-too-long-to-be-inline");
+
+        assert_eq!(FOO, Symbol::new("foo"));
+        assert_eq!(LONG, Symbol::new("a-symbol-that-is-too-long-to-be-inline"));
+        assert_eq!(Value::from(LONG).heap_size(), 0);
+    }
+
+    #[test]
+    fn value_default() {
+        let value = Value::default();
+        assert_eq!(value.to_string(), "()");
+        assert_eq!(from_str::<Value>(&value.to_string()).unwrap(), value);
+        assert_ne!(value, Value::Seq(Vec::new()));
+        assert_ne!(value, Value::Map(Vec::new()));
+
+        let symbol = Symbol::default();
+        let text = symbol.to_string();
+        assert_eq!(text, "||");
+        assert_eq!(from_str::<Symbol>(&text).unwrap(), symbol);
+    }
+
+    #[test]
+    fn f32_shortest() {
+        assert_eq!(to_values(0.1f32), [Value::from(0.1)]);
+        assert_eq!(to_values(f32::MAX), [Value::from(3.4028235e38)]);
+        assert_eq!(to_values(f32::INFINITY), [Value::from(f64::INFINITY)]);
+
+        for float in [0.1f32, -1.5, f32::MIN_POSITIVE, f32::MAX, f32::EPSILON] {
+            let parsed: f32 = from_str(&to_string_pretty(float, 80)).unwrap();
+            assert_eq!(parsed, float);
+        }
+    }
+
+    #[test]
+    fn f32_out_of_range() {
+        let error = from_str::<f32>("1.0e39").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("float `{}` out of range for `f32`", 1.0e39)
+        );
+
+        assert_eq!(from_str::<f32>("#-inf").unwrap(), f32::NEG_INFINITY);
+        assert!(from_str::<f32>("#nan").unwrap().is_nan());
+    }
+
+    #[test]
+    fn char_single_character() {
+        assert_eq!(from_str::<char>(r#""λ""#).unwrap(), 'λ');
+        assert!(from_str::<char>(r#""""#).is_err());
+        assert!(from_str::<char>(r#""ab""#).is_err());
+        assert!(from_str::<char>("a").is_err());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Expr {
+        Int(i64),
+        Add(Box<Expr>, Box<Expr>),
+        Mul(Box<Expr>, Box<Expr>),
+    }
+
+    impl<I: InputStream> FromParens<I> for Expr {
+        fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+            if let Some(TokenTree::Int(_)) = stream.peek() {
+                return Ok(Expr::Int(i64::from_parens(stream)?));
+            }
+
+            let Some(head) = stream.peek_head() else {
+                return Err(stream.error("expected expression"));
+            };
+
+            let mut list = stream.expect_list()?;
+            list.next();
+
+            let expr = match head.as_ref() {
+                "add" => Expr::Add(
+                    FromParens::from_parens(&mut list)?,
+                    FromParens::from_parens(&mut list)?,
+                ),
+                "mul" => Expr::Mul(
+                    FromParens::from_parens(&mut list)?,
+                    FromParens::from_parens(&mut list)?,
+                ),
+                _ => return Err(list.error(format!("unknown operation `{}`", head))),
+            };
+
+            Ok(expr)
+        }
+    }
+
+    impl<O: OutputStream> ToParens<O> for Expr {
+        fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+            match self {
+                Expr::Int(int) => output.int(*int),
+                Expr::Add(lhs, rhs) => output.list(|output| {
+                    output.symbol("add")?;
+                    lhs.to_parens(output)?;
+                    rhs.to_parens(output)
+                }),
+                Expr::Mul(lhs, rhs) => output.list(|output| {
+                    output.symbol("mul")?;
+                    lhs.to_parens(output)?;
+                    rhs.to_parens(output)
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn boxed_recursive() {
+        let expr: Expr = from_str("(add 1 (mul 2 3))").unwrap();
+
+        assert_eq!(
+            expr,
+            Expr::Add(
+                Box::new(Expr::Int(1)),
+                Box::new(Expr::Mul(Box::new(Expr::Int(2)), Box::new(Expr::Int(3))))
+            )
+        );
+
+        assert_eq!(to_string_pretty(&expr, 80), "(add 1 (mul 2 3))");
+    }
+
+    #[test]
+    fn shared_and_cow_round_trip() {
+        let shared: (Rc<Symbol>, Arc<Vec<i64>>) = from_str("a 1 2").unwrap();
+        assert_eq!(*shared.0, Symbol::new("a"));
+        assert_eq!(*shared.1, vec![1, 2]);
+        assert_eq!(to_values(&shared), from_str::<Vec<Value>>("a 1 2").unwrap());
+
+        let cow: Cow<str> = from_str(r#""text""#).unwrap();
+        assert!(matches!(cow, Cow::Owned(_)));
+        assert_eq!(cow, "text");
+        assert_eq!(to_values(&cow), [Value::String("text".into())]);
+
+        let cows: Vec<Cow<'static, str>> = from_str(r#""a" "b""#).unwrap();
+        assert_eq!(cows, [Cow::Borrowed("a"), Cow::Borrowed("b")]);
+
+        let error = from_str::<Cow<str>>("symbol").unwrap_err();
+        assert_eq!(error.to_string(), "expected string, found symbol");
+
+        let cow: Cow<Symbol> = from_str("symbol").unwrap();
+        assert_eq!(to_values(&cow), [Value::Symbol("symbol".into())]);
+    }
+
+    #[test]
+    fn addr_round_trip() {
+        let text = r#""10.0.0.1:4000" "[fe80::1%2]:8080" "::1" "127.0.0.1""#;
+        let addrs: (SocketAddr, SocketAddrV6, Ipv6Addr, IpAddr) = from_str(text).unwrap();
+
+        assert_eq!(addrs.0, SocketAddr::from(([10, 0, 0, 1], 4000)));
+        assert_eq!(addrs.1.scope_id(), 2);
+        assert_eq!(addrs.2, Ipv6Addr::LOCALHOST);
+        assert_eq!(addrs.3, IpAddr::from(Ipv4Addr::LOCALHOST));
+
+        assert_eq!(to_values(addrs), from_str::<Vec<Value>>(text).unwrap());
+    }
+
+    #[test]
+    fn addr_invalid() {
+        let Err(ReadError::Parse(error)) = from_str::<(IpAddr, IpAddr)>(r#""::1" "1.2.3""#) else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "invalid IP address syntax");
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (6..13))
+        );
+
+        assert!(from_str::<Ipv4Addr>(r#""::1""#).is_err());
+        assert!(from_str::<SocketAddr>(r#""10.0.0.1""#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn uuid_round_trip() {
+        let uuid = uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+
+        for text in [
+            r#""67e55044-10b1-426f-9247-bb680e5fe0c8""#,
+            r#""67E5504410B1426F9247BB680E5FE0C8""#,
+            r#""urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8""#,
+        ] {
+            assert_eq!(from_str::<uuid::Uuid>(text).unwrap(), uuid);
+        }
+
+        assert_eq!(
+            to_values(uuid),
+            [Value::from("67e55044-10b1-426f-9247-bb680e5fe0c8")]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn uuid_invalid() {
+        let Err(ReadError::Parse(error)) = from_str::<(String, uuid::Uuid)>(r#""a" "67e55044""#)
+        else {
+            panic!("expected parse error");
+        };
+
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (4..14))
+        );
+        assert!(from_str::<uuid::Uuid>("67e55044").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_round_trip() {
+        use chrono::{DateTime, NaiveDate, Timelike, Utc};
+
+        let text = r#""2024-02-29T00:00:00Z""#;
+        let date_time: DateTime<Utc> = from_str(text).unwrap();
+        assert_eq!(
+            date_time.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(to_values(date_time), from_str::<Vec<Value>>(text).unwrap());
+
+        let text = r#""2024-02-29T13:30:00.123456789+01:00""#;
+        let date_time: DateTime<Utc> = from_str(text).unwrap();
+        assert_eq!(date_time.nanosecond(), 123456789);
+        assert_eq!(
+            to_values(date_time),
+            [Value::from("2024-02-29T12:30:00.123456789Z")]
+        );
+
+        let text = r#""2016-12-31T23:59:60.5Z""#;
+        let date_time: DateTime<Utc> = from_str(text).unwrap();
+        assert_eq!(date_time.nanosecond(), 1_500_000_000);
+        assert_eq!(
+            from_str::<DateTime<Utc>>(&to_string_pretty(date_time, 80)).unwrap(),
+            date_time
+        );
+
+        let date: NaiveDate = from_str(r#""2024-02-29""#).unwrap();
+        assert_eq!(to_values(date), [Value::from("2024-02-29")]);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_invalid() {
+        let Err(ReadError::Parse(error)) =
+            from_str::<chrono::DateTime<chrono::Utc>>(r#""2023-02-29T00:00:00Z""#)
+        else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "input is out of range");
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (0..22))
+        );
+
+        assert!(from_str::<chrono::NaiveDate>(r#""2024-02-30""#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_round_trip() {
+        use time::OffsetDateTime;
+
+        let text = r#""2024-02-29T00:00:00Z""#;
+        let date_time: OffsetDateTime = from_str(text).unwrap();
+        assert_eq!(date_time.unix_timestamp(), 1709164800);
+        assert_eq!(to_values(date_time), from_str::<Vec<Value>>(text).unwrap());
+
+        let text = r#""2024-02-29T13:30:00.123456789+01:00""#;
+        let date_time: OffsetDateTime = from_str(text).unwrap();
+        assert_eq!(date_time.nanosecond(), 123456789);
+        assert_eq!(to_values(date_time), from_str::<Vec<Value>>(text).unwrap());
+
+        let date_time: OffsetDateTime = from_str(r#""2016-12-31T23:59:60Z""#).unwrap();
+        assert_eq!(date_time.unix_timestamp(), 1483228799);
+        assert_eq!(date_time.nanosecond(), 999_999_999);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_write_unrepresentable() {
+        use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+        let mut output = pretty::Pretty::new();
+
+        let date_time: OffsetDateTime = from_str(r#""2024-02-29T13:30:00Z""#).unwrap();
+        let date_time = date_time.to_offset(UtcOffset::from_hms(1, 0, 30).unwrap());
+        let error = date_time.to_parens(&mut output).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("timestamp `2024-02-29 14:30:30.0 +01:00:30` can not be represented"));
+
+        let date = Date::from_calendar_date(-1, Month::December, 31).unwrap();
+        let date_time = date.midnight().assume_utc();
+        assert!(date_time.to_parens(&mut output).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_invalid() {
+        let result = from_str::<time::OffsetDateTime>(r#""2024-02-29 00:00:00""#);
+        assert!(matches!(
+            result,
+            Err(ReadError::Parse(ParseError::Error { span, .. })) if span == (0..21)
+        ));
+    }
+
+    #[test]
+    fn set_round_trip() {
+        let set: BTreeSet<Symbol> = from_str("b c a").unwrap();
+        assert_eq!(to_values(&set), from_str::<Vec<Value>>("a b c").unwrap());
+
+        let set: HashSet<i64> = from_str("3 1 2").unwrap();
+        assert_eq!(set, HashSet::from([1, 2, 3]));
+
+        assert!(from_str::<BTreeSet<Symbol>>("").unwrap().is_empty());
+        assert!(from_str::<HashSet<Symbol>>("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_duplicate() {
+        let result = from_str::<BTreeSet<Symbol>>("read write read");
+        let Err(ReadError::Parse(error)) = result else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "duplicate value");
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (11..15))
+        );
+
+        assert!(from_str::<HashSet<Symbol>>("read write read").is_err());
+    }
+
+    #[test]
+    fn vec_deque_round_trip() {
+        let values: VecDeque<i64> = from_str("1 2 3").unwrap();
+        assert_eq!(values, [1, 2, 3]);
+        assert_eq!(to_values(&values), from_str::<Vec<Value>>("1 2 3").unwrap());
+        assert!(from_str::<VecDeque<i64>>("").unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn small_vec_round_trip() {
+        use smallvec::SmallVec;
+
+        let values: SmallVec<[Symbol; 2]> = from_str("a b c").unwrap();
+        assert_eq!(
+            values.as_slice(),
+            [Symbol::new("a"), Symbol::new("b"), Symbol::new("c")]
+        );
+        assert_eq!(to_values(&values), from_str::<Vec<Value>>("a b c").unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn index_map_round_trip() {
+        use indexmap::{IndexMap, IndexSet};
+
+        let text = r#"(c "1") (a "2") (b "3")"#;
+        let map: IndexMap<Symbol, String> = from_str(text).unwrap();
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            [&Symbol::new("c"), &Symbol::new("a"), &Symbol::new("b")]
+        );
+        assert_eq!(to_values(&map), from_str::<Vec<Value>>(text).unwrap());
+
+        let set: IndexSet<i64> = from_str("3 1 2").unwrap();
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), [3, 1, 2]);
+        assert_eq!(to_values(&set), from_str::<Vec<Value>>("3 1 2").unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn index_map_duplicate_key() {
+        let result = from_str::<indexmap::IndexMap<Symbol, i64>>("(a 1) (b 2) (a 3)");
+        let Err(ReadError::Parse(error)) = result else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "duplicate key");
+        assert!(
+            matches!(error, super::from_parens::ParseError::Error { span, .. } if span == (12..17))
+        );
+
+        assert!(from_str::<indexmap::IndexSet<i64>>("1 2 1").is_err());
+    }
+
+    #[test]
+    fn either() {
+        type IntOrSymbol = Either<i64, Symbol>;
+
+        assert_eq!(from_str::<IntOrSymbol>("1").unwrap(), Either::Left(1));
+        assert_eq!(
+            from_str::<IntOrSymbol>("a").unwrap(),
+            Either::Right(Symbol::from("a"))
+        );
+        assert_eq!(
+            from_str::<Vec<IntOrSymbol>>("1 a").unwrap(),
+            vec![Either::Left(1), Either::Right(Symbol::from("a"))]
+        );
+
+        let error = from_str::<IntOrSymbol>("\"a\"").unwrap_err();
+        assert_eq!(error.to_string(), "expected int or symbol, found string");
+        assert_eq!(error.span(), Some(&(0..3)));
+
+        let values = vec![Either::Left(1), Either::Right(Symbol::from("a"))];
+        assert_eq!(to_values(&values), from_str::<Vec<Value>>("1 a").unwrap());
+    }
+
+    #[test]
+    fn either_nested() {
+        type Nested = Either<Either<i64, Symbol>, String>;
+
+        assert_eq!(
+            from_str::<Nested>("a").unwrap(),
+            Either::Left(Either::Right(Symbol::from("a")))
+        );
+        assert_eq!(
+            from_str::<Nested>("\"a\"").unwrap(),
+            Either::Right("a".to_owned())
+        );
+
+        let error = from_str::<Nested>("#t").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected int or symbol or string, found bool"
+        );
+
+        let value: Nested = Either::Left(Either::Left(1));
+        assert_eq!(to_values(&value), vec![Value::Int(1)]);
+    }
+
+    #[test]
+    fn either_prefers_longest() {
+        type PairOrInt = Either<(i64, i64), i64>;
+
+        assert_eq!(from_str::<PairOrInt>("1 2").unwrap(), Either::Left((1, 2)));
+        assert_eq!(
+            from_str::<(PairOrInt, Symbol)>("1 a").unwrap(),
+            (Either::Right(1), Symbol::from("a"))
+        );
+
+        // The pair fails at its second element, after consuming the first one.
+        type PairOrSymbol = Either<(i64, i64), Symbol>;
+        let error = from_str::<PairOrSymbol>("1 a").unwrap_err();
+        assert_eq!(error.to_string(), "expected int, found symbol");
+        assert_eq!(error.span(), Some(&(2..3)));
+    }
+
+    #[test]
+    fn bool() {
+        assert!(from_str::<bool>("#t").unwrap());
+        assert!(!from_str::<bool>("#f").unwrap());
+
+        let error = from_str::<bool>("yes").unwrap_err();
+        assert_eq!(error.to_string(), "expected bool, found symbol");
+        assert_eq!(error.span(), Some(&(0..3)));
+
+        assert_eq!(
+            to_values([true, false]),
+            from_str::<Vec<Value>>("#t #f").unwrap()
+        );
+    }
+
+    #[test]
+    fn unit_and_phantom() {
+        use std::marker::PhantomData;
+
+        assert_eq!(from_str::<()>("").unwrap(), ());
+        assert_eq!(from_str::<((), i64, ())>("1").unwrap(), ((), 1, ()));
+        assert_eq!(
+            from_str::<(PhantomData<String>, i64)>("1").unwrap(),
+            (PhantomData, 1)
+        );
+
+        assert_eq!(to_values(()), Vec::<Value>::new());
+        assert_eq!(to_values((1, PhantomData::<str>)), vec![Value::Int(1)]);
+    }
+
+    #[test]
+    fn option() {
+        assert_eq!(from_str::<Option<i64>>("1").unwrap(), Some(1));
+        assert_eq!(from_str::<Option<i64>>("").unwrap(), None);
+        assert_eq!(
+            from_str::<(Option<i64>, Symbol)>("a").unwrap(),
+            (None, Symbol::from("a"))
+        );
+        assert_eq!(
+            from_str::<(Option<(i64, i64)>, i64)>("1").unwrap(),
+            (None, 1)
+        );
+
+        // The error of the value that does not parse is reported by what follows.
+        let error = from_str::<(Option<i64>, i64)>("a").unwrap_err();
+        assert_eq!(error.to_string(), "expected int, found symbol");
+
+        // Collections fail on a value that no element consumes instead of looping.
+        assert_eq!(
+            from_str::<Vec<Option<i64>>>("1 2").unwrap(),
+            vec![Some(1), Some(2)]
+        );
+        let error = from_str::<Vec<Option<i64>>>("1 a").unwrap_err();
+        assert_eq!(error.to_string(), "expected element, found symbol");
+        assert_eq!(error.span(), Some(&(2..3)));
+        assert!(from_str::<VecDeque<Option<i64>>>("a").is_err());
+        assert!(from_str::<BTreeSet<Option<i64>>>("a").is_err());
+        assert!(from_str::<HashSet<Option<i64>>>("a").is_err());
+
+        assert_eq!(to_values(Some(1)), vec![Value::Int(1)]);
+        assert_eq!(to_values(None::<i64>), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn seq_and_map() {
+        let values = from_str::<Vec<Value>>("[a (b) {c 1}] {}").unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Value::Seq(vec![
+                    Value::Symbol("a".into()),
+                    Value::List(vec![Value::Symbol("b".into())]),
+                    Value::Map(vec![Value::Symbol("c".into()), Value::Int(1)]),
+                ]),
+                Value::Map(vec![]),
+            ]
+        );
+
+        assert_eq!(values[0].to_string(), "[a (b) {c 1}]");
+        assert_eq!(values[1].to_string(), "{}");
+    }
+
+    #[test]
+    fn mismatched_delimiters() {
+        let Err(error) = from_str::<Vec<Value>>("(a]") else {
+            panic!("expected error");
+        };
+        assert!(matches!(error, ReadError::UnexpectedClose { span } if span == (2..3)));
+
+        assert!(from_str::<Vec<Value>>("[a)").is_err());
+        assert!(from_str::<Vec<Value>>("{a]}").is_err());
+        assert!(from_str::<Vec<Value>>("[a]b").is_err());
+    }
+
+    #[test]
+    fn display_compact() {
+        let text = "(define (f x) (long-name-number-one x) (long-name-number-two x) (long-name-number-three x))";
+        let value = from_str::<Value>(text).unwrap();
+
+        assert_eq!(value.to_string(), text);
+        assert!(format!("{:40}", value).contains('\n'));
+        assert_eq!(to_string_compact(vec![Value::Int(1), Value::Int(2)]), "1 2");
+    }
+
+    #[test]
+    fn display_symbol() {
+        assert_eq!(Symbol::new("abc").to_string(), "abc");
+        assert_eq!(Symbol::new("a b").to_string(), "|a b|");
+        assert_eq!(Symbol::new("1").to_string(), "|1|");
+        assert_eq!(Value::Symbol(Symbol::new("a|b")).to_string(), r"|a\|b|");
+    }
+
+    proptest! {
+        #[test]
+        fn display_round_trip(value: Value) {
+            prop_assert_eq!(from_str::<Value>(&value.to_string()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn tuple_missing_element() {
+        let error = from_str::<(i64, i64, i64)>("1 2").unwrap_err();
+        assert_eq!(error.to_string(), "expected 3rd tuple element");
+    }
 
     proptest! {
         #[test]
@@ -362,4 +1860,108 @@ mod test {
             assert_eq!(values, parsed);
         }
     }
+
+    proptest! {
+        #[test]
+        fn io_writer_matches_string(values: Vec<Value>, width in 0..120usize) {
+            let mut bytes = Vec::new();
+            pretty::to_io_writer_pretty(&values, width, &mut bytes).unwrap();
+            prop_assert_eq!(String::from_utf8(bytes).unwrap(), to_string_pretty(&values, width));
+
+            let mut bytes = Vec::new();
+            pretty::to_io_writer_compact(&values, &mut bytes).unwrap();
+            prop_assert_eq!(String::from_utf8(bytes).unwrap(), to_string_compact(&values));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn size_hint_matches_compact(values: Vec<Value>) {
+            prop_assert_eq!(pretty::size_hint(&values), to_string_compact(&values).len());
+        }
+
+        #[test]
+        fn size_hint_pretty_bounds_pretty(values: Vec<Value>, width in 0usize..100) {
+            let size_hint = pretty::size_hint_pretty(&values);
+            prop_assert_eq!(size_hint, to_string_pretty(&values, 0).len());
+            prop_assert!(size_hint >= to_string_pretty(&values, width).len());
+        }
+
+        #[test]
+        fn presized_matches(values: Vec<Value>, width in 0usize..100) {
+            prop_assert_eq!(
+                pretty::to_string_pretty_presized(&values, width),
+                to_string_pretty(&values, width)
+            );
+            prop_assert_eq!(
+                pretty::to_string_compact_presized(&values),
+                to_string_compact(&values)
+            );
+        }
+    }
+
+    /// Values with a depth of at most 5 and at most 50 nodes.
+    fn arb_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            any::<Symbol>().prop_map(Value::from),
+            any::<String>().prop_map(Value::from),
+            proptest::num::f64::ANY.prop_map(Value::from)
+        ];
+
+        leaf.prop_recursive(5, 50, 8, |inner| {
+            prop_oneof![
+                proptest::collection::vec(inner.clone(), 0..8).prop_map(Value::List),
+                proptest::collection::vec(inner.clone(), 0..8).prop_map(Value::Seq),
+                proptest::collection::vec(inner, 0..8).prop_map(Value::Map),
+            ]
+        })
+    }
+
+    /// The bits of a float, with all NaNs normalized to the same bit pattern.
+    fn canonical_bits(float: f64) -> u64 {
+        match float.is_nan() {
+            true => f64::NAN.to_bits(),
+            false => float.to_bits(),
+        }
+    }
+
+    /// Compare values exactly, so that unlike `==` floats must agree in the sign
+    /// of zero, while NaNs of any payload are considered equal.
+    fn same_value(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Float(a), Value::Float(b)) => {
+                canonical_bits(a.into_inner()) == canonical_bits(b.into_inner())
+            }
+            (Value::List(a), Value::List(b))
+            | (Value::Seq(a), Value::Seq(b))
+            | (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_value(a, b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_compact(value in arb_value()) {
+            let parsed: Value = from_str(&to_string_compact(&value)).unwrap();
+            prop_assert!(same_value(&parsed, &value), "{} != {}", parsed, value);
+        }
+
+        #[test]
+        fn roundtrip_pretty(value in arb_value(), width in 0..120usize) {
+            let parsed: Value = from_str(&to_string_pretty(&value, width)).unwrap();
+            prop_assert!(same_value(&parsed, &value), "{} != {}", parsed, value);
+        }
+    }
+
+    #[test]
+    fn same_value_floats() {
+        let nan = f64::from_bits(f64::NAN.to_bits() | 1);
+        assert!(same_value(&Value::from(f64::NAN), &Value::from(nan)));
+        assert!(!same_value(&Value::from(0.0), &Value::from(-0.0)));
+        assert!(!same_value(&Value::List(vec![]), &Value::Seq(vec![])));
+    }
 }
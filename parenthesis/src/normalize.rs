@@ -0,0 +1,39 @@
+//! Unicode normalization of strings and symbols.
+//!
+//! Text that looks the same may be encoded by different sequences of code points,
+//! such as `é` as a single code point or as `e` followed by a combining accent.
+//! Normalizing strings and symbols to one form makes such values compare equal.
+use unicode_normalization::UnicodeNormalization;
+
+/// A unicode normalization form.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::normalize::NormalizationForm;
+/// assert_eq!(NormalizationForm::Nfc.normalize("e\u{301}"), "\u{e9}");
+/// assert_eq!(NormalizationForm::Nfd.normalize("\u{e9}"), "e\u{301}");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    /// Canonical decomposition followed by canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+    /// Compatibility decomposition followed by canonical composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+impl NormalizationForm {
+    /// Normalize a string to this form.
+    pub fn normalize(self, str: &str) -> String {
+        match self {
+            NormalizationForm::Nfc => str.nfc().collect(),
+            NormalizationForm::Nfd => str.nfd().collect(),
+            NormalizationForm::Nfkc => str.nfkc().collect(),
+            NormalizationForm::Nfkd => str.nfkd().collect(),
+        }
+    }
+}
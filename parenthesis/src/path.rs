@@ -0,0 +1,427 @@
+//! Paths that address values nested within other values.
+//!
+//! A [`ValuePath`] is a sequence of steps, each of which either selects an
+//! element of a list, sequence or map by its index, or selects a value by a
+//! symbol key. Paths are written as their steps separated by `.`, such as
+//! `0.field.2`, where steps that consist only of digits are indices. A key that
+//! would otherwise be read as an index or split into several steps is written
+//! between `|`, such as `|2|` or `|a.b|`, where `\|` and `\\` stand for `|` and `\`.
+//!
+//! A key step selects the value that follows the key in a map. In a list or
+//! sequence, it selects the first element that is a list starting with the
+//! key, which is how named fields are commonly written.
+//!
+//! # Examples
+//!
+//! ```
+//! # use parenthesis::{from_str, path::{get, set, ValuePath}, Value};
+//! let mut value = from_str::<Value>("(define x (type (int 64)) {size 2})").unwrap();
+//!
+//! let path: ValuePath = "type.1".parse().unwrap();
+//! assert_eq!(get(&value, &path), Some(&from_str("(int 64)").unwrap()));
+//!
+//! let path: ValuePath = "3.size".parse().unwrap();
+//! set(&mut value, &path, Value::from(3)).unwrap();
+//! assert_eq!(value, from_str("(define x (type (int 64)) {size 3})").unwrap());
+//! ```
+use std::fmt::Display;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::{Symbol, Value};
+
+/// A step of a [`ValuePath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathStep {
+    /// Select the element at the given index of a list, sequence or map.
+    Index(usize),
+    /// Select the value of a map entry with the given key, or the first
+    /// element of a list or sequence that is a list starting with the key.
+    Key(Symbol),
+}
+
+impl Display for PathStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = match self {
+            PathStep::Index(index) => return index.fmt(f),
+            PathStep::Key(key) => key.as_ref(),
+        };
+
+        let needs_quotes = key.is_empty()
+            || key.bytes().all(|byte| byte.is_ascii_digit())
+            || key.contains(['.', '|', '\\']);
+
+        if !needs_quotes {
+            return f.write_str(key);
+        }
+
+        f.write_str("|")?;
+
+        for char in key.chars() {
+            if matches!(char, '|' | '\\') {
+                f.write_str("\\")?;
+            }
+
+            write!(f, "{}", char)?;
+        }
+
+        f.write_str("|")
+    }
+}
+
+/// A path that addresses a value nested within another value.
+///
+/// The empty path addresses the value itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ValuePath(pub Vec<PathStep>);
+
+impl ValuePath {
+    /// Create an empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step to the path.
+    pub fn push(&mut self, step: PathStep) {
+        self.0.push(step);
+    }
+
+    /// The steps of the path.
+    pub fn steps(&self) -> &[PathStep] {
+        &self.0
+    }
+}
+
+impl From<Vec<PathStep>> for ValuePath {
+    fn from(steps: Vec<PathStep>) -> Self {
+        Self(steps)
+    }
+}
+
+impl Display for ValuePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, step) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(".")?;
+            }
+
+            step.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ValuePath {
+    type Err = PathParseError;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        if str.is_empty() {
+            return Ok(Self::new());
+        }
+
+        let error = || PathParseError(str.to_string());
+        let mut steps = Vec::new();
+        let mut chars = str.chars().peekable();
+
+        loop {
+            let step = if chars.next_if_eq(&'|').is_some() {
+                // A quoted key extends to the next `|` that is not escaped.
+                let mut key = String::new();
+
+                loop {
+                    match chars.next().ok_or_else(error)? {
+                        '|' => break,
+                        '\\' => key.push(chars.next().ok_or_else(error)?),
+                        char => key.push(char),
+                    }
+                }
+
+                PathStep::Key(Symbol::new(key))
+            } else {
+                let mut step = String::new();
+
+                while let Some(char) = chars.next_if(|char| *char != '.') {
+                    step.push(char);
+                }
+
+                if step.is_empty() {
+                    return Err(error());
+                } else if step.bytes().all(|byte| byte.is_ascii_digit()) {
+                    PathStep::Index(step.parse().map_err(|_| error())?)
+                } else {
+                    PathStep::Key(Symbol::new(step))
+                }
+            };
+
+            steps.push(step);
+
+            match chars.next() {
+                None => return Ok(Self(steps)),
+                Some('.') => {}
+                Some(_) => return Err(error()),
+            }
+        }
+    }
+}
+
+/// Error when parsing a [`ValuePath`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid path `{0}`")]
+pub struct PathParseError(String);
+
+/// Error when a [`ValuePath`] can not be followed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PathError {
+    /// There is no value at the path.
+    #[error("no value at `{0}`")]
+    NotFound(ValuePath),
+    /// The value at the path is not a list, sequence or map, so it can not be stepped into.
+    #[error("value at `{0}` is not a list, sequence or map")]
+    NotContainer(ValuePath),
+    /// The map at the path ends with a key without a value, so no entry can be appended.
+    #[error("map at `{0}` has a key without a value")]
+    OddMap(ValuePath),
+}
+
+/// Return the value at a path, if there is one.
+pub fn get<'a>(value: &'a Value, path: &ValuePath) -> Option<&'a Value> {
+    path.0
+        .iter()
+        .try_fold(value, |value, step| step_ref(value, step))
+}
+
+/// Return a mutable reference to the value at a path, if there is one.
+pub fn get_mut<'a>(value: &'a mut Value, path: &ValuePath) -> Option<&'a mut Value> {
+    path.0
+        .iter()
+        .try_fold(value, |value, step| step_mut(value, step))
+}
+
+/// Replace the value at a path.
+///
+/// If the last step is a key that is missing from a map, a new entry is
+/// appended to the map, unless the map ends with a key without a value.
+/// Otherwise the value at the path must already exist.
+pub fn set(value: &mut Value, path: &ValuePath, new: Value) -> Result<(), PathError> {
+    let mut target = value;
+
+    for (index, step) in path.0.iter().enumerate() {
+        if !matches!(target, Value::List(_) | Value::Seq(_) | Value::Map(_)) {
+            return Err(PathError::NotContainer(ValuePath(path.0[..index].to_vec())));
+        }
+
+        let is_last = index + 1 == path.0.len();
+
+        if let (Value::Map(values), PathStep::Key(key), true) = (&mut *target, step, is_last) {
+            if map_value_index(values, key).is_none() {
+                if values.len() % 2 != 0 {
+                    return Err(PathError::OddMap(ValuePath(path.0[..index].to_vec())));
+                }
+
+                values.push(Value::Symbol(key.clone()));
+                values.push(new);
+                return Ok(());
+            }
+        }
+
+        target = step_mut(target, step)
+            .ok_or_else(|| PathError::NotFound(ValuePath(path.0[..=index].to_vec())))?;
+    }
+
+    *target = new;
+    Ok(())
+}
+
+fn step_ref<'a>(value: &'a Value, step: &PathStep) -> Option<&'a Value> {
+    match (value, step) {
+        (Value::List(values) | Value::Seq(values) | Value::Map(values), PathStep::Index(index)) => {
+            values.get(*index)
+        }
+        (Value::List(values) | Value::Seq(values), PathStep::Key(key)) => {
+            values.get(form_index(values, key)?)
+        }
+        (Value::Map(values), PathStep::Key(key)) => values.get(map_value_index(values, key)?),
+        _ => None,
+    }
+}
+
+fn step_mut<'a>(value: &'a mut Value, step: &PathStep) -> Option<&'a mut Value> {
+    match (value, step) {
+        (Value::List(values) | Value::Seq(values) | Value::Map(values), PathStep::Index(index)) => {
+            values.get_mut(*index)
+        }
+        (Value::List(values) | Value::Seq(values), PathStep::Key(key)) => {
+            let index = form_index(values, key)?;
+            values.get_mut(index)
+        }
+        (Value::Map(values), PathStep::Key(key)) => {
+            let index = map_value_index(values, key)?;
+            values.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+/// The index of the first list in `values` that starts with the symbol `key`.
+fn form_index(values: &[Value], key: &Symbol) -> Option<usize> {
+    values.iter().position(|value| match value {
+        Value::List(form) => matches!(form.first(), Some(Value::Symbol(head)) if head == key),
+        _ => false,
+    })
+}
+
+/// The index of the value that follows the symbol `key` in the entries of a map.
+fn map_value_index(values: &[Value], key: &Symbol) -> Option<usize> {
+    values
+        .chunks(2)
+        .position(|entry| matches!(&entry[0], Value::Symbol(found) if found == key))
+        .map(|index| index * 2 + 1)
+        .filter(|index| *index < values.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{get, get_mut, set, PathError, PathStep, ValuePath};
+    use crate::{from_str, Symbol, Value};
+    use rstest::rstest;
+
+    const VALUE: &str = "(define x (type (int 64)) {size 2 name \"x\"} [a b])";
+
+    #[rstest]
+    #[case("", VALUE)]
+    #[case("0", "define")]
+    #[case("2.1", "(int 64)")]
+    #[case("type.1", "(int 64)")]
+    #[case("2.0", "type")]
+    #[case("type", "(type (int 64))")]
+    #[case("3.size", "2")]
+    #[case("3.name", "\"x\"")]
+    #[case("3.0", "size")]
+    #[case("4.1", "b")]
+    fn get_value(#[case] path: &str, #[case] expected: &str) {
+        let value = from_str::<Value>(VALUE).unwrap();
+        let path: ValuePath = path.parse().unwrap();
+        let expected = from_str::<Value>(expected).unwrap();
+        assert_eq!(get(&value, &path), Some(&expected));
+    }
+
+    #[rstest]
+    #[case("5")]
+    #[case("0.0")]
+    #[case("3.other")]
+    #[case("4.a")]
+    #[case("missing")]
+    fn get_missing(#[case] path: &str) {
+        let value = from_str::<Value>(VALUE).unwrap();
+        let path: ValuePath = path.parse().unwrap();
+        assert_eq!(get(&value, &path), None);
+    }
+
+    #[test]
+    fn get_value_mut() {
+        let mut value = from_str::<Value>(VALUE).unwrap();
+        let path: ValuePath = "4.0".parse().unwrap();
+        *get_mut(&mut value, &path).unwrap() = Value::from(1);
+        assert_eq!(
+            value,
+            from_str::<Value>("(define x (type (int 64)) {size 2 name \"x\"} [1 b])").unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("1", "(define y (type (int 64)) {size 2 name \"x\"} [a b])")]
+    #[case("type.1", "(define x (type y) {size 2 name \"x\"} [a b])")]
+    #[case("3.size", "(define x (type (int 64)) {size y name \"x\"} [a b])")]
+    #[case(
+        "3.other",
+        "(define x (type (int 64)) {size 2 name \"x\" other y} [a b])"
+    )]
+    #[case("", "y")]
+    fn set_value(#[case] path: &str, #[case] expected: &str) {
+        let mut value = from_str::<Value>(VALUE).unwrap();
+        let path: ValuePath = path.parse().unwrap();
+        set(&mut value, &path, Value::Symbol(Symbol::new("y"))).unwrap();
+        assert_eq!(value, from_str::<Value>(expected).unwrap());
+    }
+
+    #[rstest]
+    #[case("5", PathError::NotFound("5".parse().unwrap()))]
+    #[case("4.2.0", PathError::NotFound("4.2".parse().unwrap()))]
+    #[case("2.missing.0", PathError::NotFound("2.missing".parse().unwrap()))]
+    #[case("0.0", PathError::NotContainer("0".parse().unwrap()))]
+    #[case("3.size.0", PathError::NotContainer("3.size".parse().unwrap()))]
+    fn set_error(#[case] path: &str, #[case] error: PathError) {
+        let mut value = from_str::<Value>(VALUE).unwrap();
+        let path: ValuePath = path.parse().unwrap();
+        assert_eq!(set(&mut value, &path, Value::from(1)), Err(error));
+    }
+
+    #[test]
+    fn set_odd_map() {
+        let mut value = from_str::<Value>("(x {a 1 b})").unwrap();
+        let path: ValuePath = "1.c".parse().unwrap();
+        assert_eq!(
+            set(&mut value, &path, Value::from(2)),
+            Err(PathError::OddMap("1".parse().unwrap()))
+        );
+
+        // Existing keys can still be replaced.
+        set(&mut value, &"1.a".parse().unwrap(), Value::from(2)).unwrap();
+        assert_eq!(value, from_str::<Value>("(x {a 2 b})").unwrap());
+    }
+
+    #[test]
+    fn parse_path() {
+        let path: ValuePath = "0.field-name.2".parse().unwrap();
+        assert_eq!(
+            path,
+            ValuePath(vec![
+                PathStep::Index(0),
+                PathStep::Key(Symbol::new("field-name")),
+                PathStep::Index(2),
+            ])
+        );
+        assert_eq!(path.to_string(), "0.field-name.2");
+        assert_eq!("".parse::<ValuePath>().unwrap(), ValuePath::new());
+        assert!("a..b".parse::<ValuePath>().is_err());
+        assert!("a.".parse::<ValuePath>().is_err());
+        assert!("99999999999999999999999".parse::<ValuePath>().is_err());
+    }
+
+    #[rstest]
+    #[case("12", "|12|")]
+    #[case("a.b", "|a.b|")]
+    #[case("", "||")]
+    #[case("a|b\\c", "|a\\|b\\\\c|")]
+    #[case("a-b", "a-b")]
+    fn quoted_keys(#[case] key: &str, #[case] expected: &str) {
+        let path = ValuePath(vec![PathStep::Key(Symbol::new(key)), PathStep::Index(0)]);
+        let printed = path.to_string();
+        assert_eq!(printed, format!("{}.0", expected));
+        assert_eq!(printed.parse::<ValuePath>().unwrap(), path);
+    }
+
+    #[rstest]
+    #[case("|a")]
+    #[case("|a|b")]
+    #[case("|a\\")]
+    #[case("|a|.")]
+    fn quoted_keys_invalid(#[case] path: &str) {
+        assert!(path.parse::<ValuePath>().is_err());
+    }
+
+    #[test]
+    fn get_quoted_key() {
+        let value = from_str::<Value>("{|12| a |a.b| b}").unwrap();
+        assert_eq!(
+            get(&value, &"|12|".parse().unwrap()),
+            Some(&Value::from(Symbol::new("a")))
+        );
+        assert_eq!(
+            get(&value, &"|a.b|".parse().unwrap()),
+            Some(&Value::from(Symbol::new("b")))
+        );
+    }
+}
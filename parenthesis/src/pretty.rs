@@ -1,88 +1,340 @@
 //! Pretty print s-expressions.
-use std::convert::Infallible;
+use std::borrow::Cow;
+use std::fmt::{Display, Write};
 
+#[cfg(feature = "unicode-normalization")]
+use crate::normalize::NormalizationForm;
 use crate::{
-    escape::{escape_string, escape_symbol},
-    to_parens::{OutputStream, ToParens},
+    escape::{
+        escape_string_with_mode, escape_symbol, escaped_string_len, escaped_symbol_len, EscapeMode,
+    },
+    to_parens::{OutputStream, ToParens, WriteError},
+    Value,
 };
 use pretty::BoxDoc;
 
 /// Pretty prints a value of type `T` into an s-expression by writing into an
 /// [`std::fmt::Write`].
+///
+/// Fails without writing anything if the value can not be written, see [`WriteError`].
 pub fn to_fmt_pretty<W, P>(value: P, width: usize, f: &mut W) -> std::fmt::Result
 where
     W: std::fmt::Write,
     P: ToParens<Pretty>,
 {
-    let mut pretty = Pretty::new();
-    let _ = value.to_parens(&mut pretty);
-    let doc = pretty.finish();
+    let doc = document(value).map_err(|_| std::fmt::Error)?;
     doc.render_fmt(width, f)
 }
 
 /// Pretty prints a value that implements [`ToParens`] into an s-expression string.
+///
+/// # Panics
+///
+/// Panics if the value can not be written, see [`WriteError`].
 pub fn to_string_pretty<T>(value: T, width: usize) -> String
 where
     T: ToParens<Pretty>,
 {
     let mut string = String::new();
-    let _ = to_fmt_pretty(value, width, &mut string);
+    let doc = document(value).unwrap_or_else(|error| panic!("{}", error));
+    let _ = doc.render_fmt(width, &mut string);
     string
 }
 
-/// Output stream used by [`to_string_pretty`] and [`to_fmt_pretty`].
+/// Pretty prints a value into an s-expression string, like [`to_string_pretty`],
+/// but allocates the string upfront with the capacity given by [`size_hint_pretty`].
+///
+/// The value is written twice, once to estimate its size and once to print it.
+/// This pays off for large values, where growing the string would copy it repeatedly.
+///
+/// # Panics
+///
+/// Panics if the value can not be written, see [`WriteError`].
+pub fn to_string_pretty_presized<T>(value: T, width: usize) -> String
+where
+    T: ToParens<Pretty> + ToParens<SizeEstimatingOutputStream>,
+{
+    let mut string = String::with_capacity(size_hint_pretty(&value));
+    let doc = document(value).unwrap_or_else(|error| panic!("{}", error));
+    let _ = doc.render_fmt(width, &mut string);
+    string
+}
+
+/// Prints a value of type `T` into an s-expression on a single line by
+/// writing into an [`std::fmt::Write`].
+///
+/// Fails without writing anything if the value can not be written, see [`WriteError`].
+pub fn to_fmt_compact<W, P>(value: P, f: &mut W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+    P: ToParens<Pretty>,
+{
+    let doc = document(value).map_err(|_| std::fmt::Error)?.group();
+    doc.render_fmt(usize::MAX, f)
+}
+
+/// Prints a value that implements [`ToParens`] into an s-expression string on a single line.
+///
+/// # Panics
+///
+/// Panics if the value can not be written, see [`WriteError`].
+pub fn to_string_compact<T>(value: T) -> String
+where
+    T: ToParens<Pretty>,
+{
+    let mut string = String::new();
+    let doc = document(value).unwrap_or_else(|error| panic!("{}", error));
+    let _ = doc.group().render_fmt(usize::MAX, &mut string);
+    string
+}
+
+/// Prints a value into an s-expression string on a single line, like [`to_string_compact`],
+/// but allocates the string upfront with the capacity given by [`size_hint`].
+///
+/// The value is written twice, once to estimate its size and once to print it.
+///
+/// # Panics
+///
+/// Panics if the value can not be written, see [`WriteError`].
+pub fn to_string_compact_presized<T>(value: T) -> String
+where
+    T: ToParens<Pretty> + ToParens<SizeEstimatingOutputStream>,
+{
+    let mut string = String::with_capacity(size_hint(&value));
+    let doc = document(value).unwrap_or_else(|error| panic!("{}", error));
+    let _ = doc.group().render_fmt(usize::MAX, &mut string);
+    string
+}
+
+/// Write a value into a [`Pretty`] output stream and return its document.
+fn document<P>(value: P) -> Result<BoxDoc<'static>, WriteError>
+where
+    P: ToParens<Pretty>,
+{
+    let mut pretty = Pretty::new();
+    value.to_parens(&mut pretty)?;
+    Ok(pretty.finish())
+}
+
+/// Convenience methods to print a `Vec<Value>`.
+///
+/// The values are separated by line breaks or spaces, like the values that
+/// are read by [`crate::from_str`] with `Vec<Value>`.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, PrintValues, Value};
+/// let values = from_str::<Vec<Value>>("(a 1) (b 2)").unwrap();
+/// assert_eq!(values.compact(), "(a 1) (b 2)");
+/// assert_eq!(values.pretty(8), "(a 1)\n(b 2)");
+/// ```
+pub trait PrintValues {
+    /// Pretty prints the values into a string with the given width, as by [`to_string_pretty`].
+    fn pretty(&self, width: usize) -> String;
+
+    /// Prints the values into a string on a single line, as by [`to_string_compact`].
+    fn compact(&self) -> String;
+}
+
+impl PrintValues for Vec<Value> {
+    fn pretty(&self, width: usize) -> String {
+        to_string_pretty(self, width)
+    }
+
+    fn compact(&self) -> String {
+        to_string_compact(self)
+    }
+}
+
+/// Render a document into an [`std::io::Write`] as it is laid out,
+/// without first rendering the entire document into memory.
+pub fn render_io<W>(doc: BoxDoc<'static>, width: usize, writer: &mut W) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    doc.render(width, writer)
+}
+
+/// Pretty prints a value of type `T` into an s-expression by writing into an
+/// [`std::io::Write`].
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, pretty::to_io_writer_pretty, Value};
+/// let value = from_str::<Value>("(define x [1 2])").unwrap();
+/// let mut bytes = Vec::new();
+/// to_io_writer_pretty(&value, 80, &mut bytes).unwrap();
+/// assert_eq!(bytes, b"(define x [1 2])");
+/// ```
+pub fn to_io_writer_pretty<W, T>(value: T, width: usize, writer: &mut W) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    T: ToParens<Pretty>,
+{
+    let doc = document(value).map_err(write_error_to_io)?;
+    render_io(doc, width, writer)
+}
+
+/// Prints a value of type `T` into an s-expression on a single line by
+/// writing into an [`std::io::Write`].
+pub fn to_io_writer_compact<W, T>(value: T, writer: &mut W) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    T: ToParens<Pretty>,
+{
+    let doc = document(value).map_err(write_error_to_io)?;
+    render_io(doc.group(), usize::MAX, writer)
+}
+
+/// Convert an error while writing a value into an [`std::io::Error`].
+fn write_error_to_io(error: WriteError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+/// Output stream used by [`to_string_pretty`], [`to_string_compact`] and their variants.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{escape::EscapeMode, pretty::Pretty, ToParens};
+/// let mut pretty = Pretty::new().with_escape_mode(EscapeMode::AsciiOnly);
+/// "café".to_string().to_parens(&mut pretty).unwrap();
+/// assert_eq!(pretty.finish().pretty(80).to_string(), r#""caf\u{00e9}""#);
+/// ```
+#[derive(Default)]
 pub struct Pretty {
-    stack: Vec<Vec<BoxDoc<'static>>>,
-    current: Vec<BoxDoc<'static>>,
+    stack: Vec<Vec<Item>>,
+    current: Vec<Item>,
+    escape_mode: EscapeMode,
+    #[cfg(feature = "unicode-normalization")]
+    unicode_normalization: Option<NormalizationForm>,
 }
 
 impl Pretty {
-    fn new() -> Self {
-        Self {
-            stack: Vec::new(),
-            current: Vec::new(),
+    /// Create a new [`Pretty`] output stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how characters outside of ASCII are written in strings.
+    pub fn with_escape_mode(mut self, escape_mode: EscapeMode) -> Self {
+        self.escape_mode = escape_mode;
+        self
+    }
+
+    /// Set the unicode normalization form of the strings and symbols that are written.
+    #[cfg(feature = "unicode-normalization")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    pub fn with_unicode_normalization(
+        mut self,
+        unicode_normalization: Option<NormalizationForm>,
+    ) -> Self {
+        self.unicode_normalization = unicode_normalization;
+        self
+    }
+
+    /// Normalize a string or symbol with the normalization form, if any.
+    fn normalize<'a>(&self, str: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(form) = self.unicode_normalization {
+            return Cow::Owned(form.normalize(str));
         }
+
+        Cow::Borrowed(str)
     }
 
-    fn finish(self) -> BoxDoc<'static> {
-        BoxDoc::intersperse(self.current, BoxDoc::line())
+    /// Finish writing and return the document of the values that have been written.
+    pub fn finish(self) -> BoxDoc<'static> {
+        let ends_with_comment = ends_with_comment(&self.current);
+        let doc = join(self.current);
+
+        match ends_with_comment {
+            true => doc.append(BoxDoc::hardline()),
+            false => doc,
+        }
     }
-}
 
-impl OutputStream for Pretty {
-    type Error = Infallible;
+    /// Write a value that is not a comment.
+    fn push(&mut self, doc: BoxDoc<'static>) {
+        self.current.push(Item {
+            doc,
+            comment: false,
+        });
+    }
 
-    fn list<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    /// Write the values written by `f` between the given delimiters.
+    fn nested<F, R>(
+        &mut self,
+        f: F,
+        open: &'static str,
+        close: &'static str,
+    ) -> Result<R, WriteError>
     where
-        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+        F: FnOnce(&mut Self) -> Result<R, WriteError>,
     {
         self.stack.push(std::mem::take(&mut self.current));
         let result = f(self);
-        let docs = std::mem::replace(&mut self.current, self.stack.pop().unwrap());
+        let items = std::mem::replace(&mut self.current, self.stack.pop().unwrap());
 
-        self.current.push(
-            BoxDoc::text("(")
-                .append(BoxDoc::intersperse(docs, BoxDoc::line()).nest(2).group())
-                .append(BoxDoc::text(")")),
+        // A comment extends to the end of the line, so a closing delimiter
+        // after a comment has to start on a new line.
+        let close = match ends_with_comment(&items) {
+            true => BoxDoc::hardline().append(BoxDoc::text(close)),
+            false => BoxDoc::text(close),
+        };
+
+        self.push(
+            BoxDoc::text(open)
+                .append(join(items).nest(2).group())
+                .append(close),
         );
 
         result
     }
+}
+
+impl OutputStream for Pretty {
+    type Error = WriteError;
+
+    fn list<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f, "(", ")")
+    }
+
+    fn seq<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f, "[", "]")
+    }
+
+    fn map<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f, "{", "}")
+    }
 
     fn string(&mut self, string: impl AsRef<str>) -> Result<(), Self::Error> {
-        let escaped = escape_string(string.as_ref());
-        self.current.push(BoxDoc::text(format!(r#""{}""#, escaped)));
+        let string = self.normalize(string.as_ref());
+        let escaped = escape_string_with_mode(&string, self.escape_mode);
+        self.push(BoxDoc::text(format!(r#""{}""#, escaped)));
         Ok(())
     }
 
     fn symbol(&mut self, symbol: impl AsRef<str>) -> Result<(), Self::Error> {
-        let escaped = escape_symbol(symbol.as_ref());
-        self.current.push(BoxDoc::text(escaped));
+        let symbol = self.normalize(symbol.as_ref());
+        let escaped = escape_symbol(&symbol);
+        self.push(BoxDoc::text(escaped));
         Ok(())
     }
 
     fn bool(&mut self, bool: bool) -> Result<(), Self::Error> {
-        self.current.push(BoxDoc::text(match bool {
+        self.push(BoxDoc::text(match bool {
             true => "#t",
             false => "#f",
         }));
@@ -90,26 +342,270 @@ impl OutputStream for Pretty {
     }
 
     fn int(&mut self, int: i64) -> Result<(), Self::Error> {
-        self.current.push(BoxDoc::text(int.to_string()));
+        self.push(BoxDoc::text(int.to_string()));
         Ok(())
     }
 
     fn float(&mut self, float: f64) -> Result<(), Self::Error> {
-        let text = if float.is_nan() {
-            "#nan".to_string()
-        } else if float == f64::INFINITY {
-            "#+inf".to_string()
-        } else if float == -f64::INFINITY {
-            "#-inf".to_string()
-        } else if float == float.ceil() {
-            // To ensure that floats are not confused with ints after printing
-            // we always include a decimal point.
-            format!("{}.0", float)
-        } else {
-            float.to_string()
-        };
+        let mut text = String::new();
+        let _ = write_float(&mut text, float);
+        self.push(BoxDoc::text(text));
+        Ok(())
+    }
+
+    fn comment(&mut self, comment: impl AsRef<str>) -> Result<(), Self::Error> {
+        // Comments that span several lines are written as one comment per line.
+        for line in comment.as_ref().split('\n') {
+            self.current.push(Item {
+                doc: BoxDoc::text(format!(";{}", line)),
+                comment: true,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn custom_error(message: impl Display) -> Self::Error {
+        WriteError::new(message)
+    }
+}
+
+/// A document written into [`Pretty`], together with whether it is a comment.
+struct Item {
+    doc: BoxDoc<'static>,
+    comment: bool,
+}
+
+/// Join documents with line breaks that may be laid out as spaces,
+/// except after comments where a line break is required.
+fn join(items: Vec<Item>) -> BoxDoc<'static> {
+    let mut doc = BoxDoc::nil();
+    let mut separator = None;
+
+    for item in items {
+        if let Some(separator) = separator {
+            doc = doc.append(separator);
+        }
+
+        doc = doc.append(item.doc);
+        separator = Some(match item.comment {
+            true => BoxDoc::hardline(),
+            false => BoxDoc::line(),
+        });
+    }
+
+    doc
+}
+
+fn ends_with_comment(items: &[Item]) -> bool {
+    items.last().is_some_and(|item| item.comment)
+}
+
+/// Write a float in the form that is used by [`Pretty`].
+fn write_float(f: &mut impl Write, float: f64) -> std::fmt::Result {
+    if float.is_nan() {
+        f.write_str("#nan")
+    } else if float == f64::INFINITY {
+        f.write_str("#+inf")
+    } else if float == -f64::INFINITY {
+        f.write_str("#-inf")
+    } else if float == float.ceil() {
+        // To ensure that floats are not confused with ints after printing
+        // we always include a decimal point.
+        write!(f, "{}.0", float)
+    } else {
+        write!(f, "{}", float)
+    }
+}
+
+/// Estimate the number of bytes of the s-expression that a value is printed as on a single line.
+///
+/// The estimate is exact for [`to_string_compact`] unless comments are written,
+/// in which case it is an upper bound. For [`to_string_pretty`], see [`size_hint_pretty`].
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, pretty::size_hint, to_string_compact, Value};
+/// let value = from_str::<Value>(r#"(define x [1 2.5 "a\nb"])"#).unwrap();
+/// assert_eq!(size_hint(&value), to_string_compact(&value).len());
+/// ```
+pub fn size_hint<T>(value: &T) -> usize
+where
+    T: ToParens<SizeEstimatingOutputStream> + ?Sized,
+{
+    let mut output = SizeEstimatingOutputStream::new();
+    let _ = value.to_parens(&mut output);
+    output.size_estimate()
+}
+
+/// Estimate the number of bytes of the s-expression that a value is pretty printed as.
+///
+/// The estimate is an upper bound for [`to_string_pretty`] with any width, since it
+/// assumes that every list is broken across lines. It is exact for a width of zero
+/// unless comments are written.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, pretty::size_hint_pretty, to_string_pretty, Value};
+/// let value = from_str::<Value>(r#"(define x [1 2.5 "a\nb"])"#).unwrap();
+/// assert_eq!(size_hint_pretty(&value), to_string_pretty(&value, 0).len());
+/// assert!(size_hint_pretty(&value) >= to_string_pretty(&value, 10).len());
+/// ```
+pub fn size_hint_pretty<T>(value: &T) -> usize
+where
+    T: ToParens<SizeEstimatingOutputStream> + ?Sized,
+{
+    let mut output = SizeEstimatingOutputStream::new();
+    let _ = value.to_parens(&mut output);
+    output.pretty_size_estimate()
+}
+
+/// Output stream that counts the bytes that would be printed by [`Pretty`],
+/// without writing them.
+///
+/// Used by [`size_hint`] and [`size_hint_pretty`].
+#[derive(Debug)]
+pub struct SizeEstimatingOutputStream {
+    size: usize,
+    empty: bool,
+    depth: usize,
+    indentation: usize,
+}
+
+impl Default for SizeEstimatingOutputStream {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            empty: true,
+            depth: 0,
+            indentation: 0,
+        }
+    }
+}
+
+impl SizeEstimatingOutputStream {
+    /// Create a new [`SizeEstimatingOutputStream`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of bytes of the values that have been written so far,
+    /// when printed on a single line.
+    pub fn size_estimate(&self) -> usize {
+        self.size
+    }
+
+    /// The number of bytes of the values that have been written so far,
+    /// when every list is broken across lines.
+    pub fn pretty_size_estimate(&self) -> usize {
+        self.size + self.indentation
+    }
+
+    /// Count a value of the given length, together with the space that separates
+    /// it from the previous value. When the line is broken there, the space
+    /// becomes a line break followed by the indentation of the enclosing lists.
+    fn item(&mut self, len: usize) {
+        if !self.empty {
+            self.size += 1;
+            self.indentation += 2 * self.depth;
+        }
+
+        self.empty = false;
+        self.size += len;
+    }
+
+    /// Count the values written by `f` together with the surrounding delimiters.
+    fn nested<F, R>(&mut self, f: F) -> Result<R, WriteError>
+    where
+        F: FnOnce(&mut Self) -> Result<R, WriteError>,
+    {
+        self.item(2);
+        self.empty = true;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        self.empty = false;
+        result
+    }
+}
+
+impl OutputStream for SizeEstimatingOutputStream {
+    type Error = WriteError;
+
+    fn list<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f)
+    }
+
+    fn seq<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f)
+    }
+
+    fn map<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f)
+    }
+
+    fn string(&mut self, string: impl AsRef<str>) -> Result<(), Self::Error> {
+        self.item(escaped_string_len(string.as_ref()) + 2);
+        Ok(())
+    }
+
+    fn symbol(&mut self, symbol: impl AsRef<str>) -> Result<(), Self::Error> {
+        self.item(escaped_symbol_len(symbol.as_ref()));
+        Ok(())
+    }
+
+    fn bool(&mut self, _bool: bool) -> Result<(), Self::Error> {
+        self.item(2);
+        Ok(())
+    }
+
+    fn int(&mut self, int: i64) -> Result<(), Self::Error> {
+        let mut count = ByteCount(0);
+        let _ = write!(count, "{}", int);
+        self.item(count.0);
+        Ok(())
+    }
+
+    fn float(&mut self, float: f64) -> Result<(), Self::Error> {
+        let mut count = ByteCount(0);
+        let _ = write_float(&mut count, float);
+        self.item(count.0);
+        Ok(())
+    }
+
+    fn comment(&mut self, comment: impl AsRef<str>) -> Result<(), Self::Error> {
+        // Each line of the comment is followed by a line break, which may be
+        // indented like the next value or the closing delimiter.
+        for line in comment.as_ref().split('\n') {
+            self.item(line.len() + 2);
+            self.indentation += 2 * self.depth;
+        }
+
+        Ok(())
+    }
+
+    fn custom_error(message: impl Display) -> Self::Error {
+        WriteError::new(message)
+    }
+}
+
+/// [`std::fmt::Write`] that only counts the bytes written into it.
+struct ByteCount(usize);
 
-        self.current.push(BoxDoc::text(text));
+impl Write for ByteCount {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0 += s.len();
         Ok(())
     }
 }
@@ -4,23 +4,66 @@ use smol_str::SmolStr;
 use std::ops::Range;
 use thiserror::Error;
 
-use crate::escape::unescape;
-use crate::from_parens::{FromParens, InputStream, ParseError, TokenTree};
+use crate::escape::{unescape, UnescapeError};
+use crate::from_parens::{FromParens, InputStream, ParseError, TokenKind, TokenTree};
+#[cfg(feature = "unicode-normalization")]
+use crate::normalize::NormalizationForm;
+use crate::span::SpanConverter;
 use crate::Symbol;
 
+/// Kind of delimiter that encloses a list, sequence or map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delimiter {
+    Paren,
+    Bracket,
+    Brace,
+}
+
+/// Error of the lexer for a token that could not be read.
+#[derive(Debug, Clone, PartialEq, Default)]
+enum LexError {
+    /// The input is not a token.
+    #[default]
+    Syntax,
+    /// A string or delimited symbol contains an invalid escape sequence
+    /// at the given range, relative to the start of the token.
+    Unescape(Span),
+}
+
+impl LexError {
+    /// Lexer error for an escape sequence in a token that is delimited by one character on either side.
+    fn unescape(error: UnescapeError, escaped: &str) -> Self {
+        let span = error.span(escaped);
+        LexError::Unescape(span.start + 1..span.end + 1)
+    }
+}
+
+/// Replace the escape sequences in a delimited string or symbol token.
+fn unescape_token(slice: &str) -> Result<SmolStr, LexError> {
+    let escaped = &slice[1..slice.len() - 1];
+    unescape(escaped)
+        .map(SmolStr::from)
+        .map_err(|error| LexError::unescape(error, escaped))
+}
+
 #[derive(Debug, Clone, PartialEq, Logos)]
-#[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"[ \t\n\f]+", error = LexError)]
 enum Token {
-    #[token("(", |_| 0)]
-    OpenList(usize),
+    /// Opening delimiter, together with the distance to the closing delimiter.
+    #[token("(", |_| { (Delimiter::Paren, 0) })]
+    #[token("[", |_| { (Delimiter::Bracket, 0) })]
+    #[token("{", |_| { (Delimiter::Brace, 0) })]
+    Open((Delimiter, usize)),
 
-    #[token(")")]
-    CloseList,
+    #[token(")", |_| Delimiter::Paren)]
+    #[token("]", |_| Delimiter::Bracket)]
+    #[token("}", |_| Delimiter::Brace)]
+    Close(Delimiter),
 
-    #[regex(
-        r#""([^"\\]|\\["\\tnr]|u\{[a-fA-F0-9]+\})*""#,
-        |lex| Some(unescape(&lex.slice()[1..lex.slice().len() - 1])?.into())
-    )]
+    #[token("'")]
+    Quote,
+
+    #[regex(r#""([^"\\]|\\(.|\n))*""#, |lex| unescape_token(lex.slice()))]
     String(SmolStr),
 
     #[regex(
@@ -32,24 +75,25 @@ enum Token {
         |lex| Symbol::new(lex.slice())
     )]
     #[regex(
-        r#"\|([^\|\\]|\\u\{[a-fA-F0-9]{1,4}\};|\\[\|\\tnr])*\|"#,
-        |lex| Some(unescape(&lex.slice()[1..lex.slice().len() - 1])?.into())
+        r#"\|([^\|\\]|\\(.|\n))*\|"#,
+        |lex| unescape_token(lex.slice()).map(Symbol::from)
     )]
     Symbol(Symbol),
 
-    #[regex(";[^\n]*\n")]
-    Comment,
+    /// Comment, without the leading `;` and the trailing line break.
+    #[regex(";[^\n]*\n", |lex| { let slice = lex.slice(); SmolStr::from(&slice[1..slice.len() - 1]) })]
+    Comment(SmolStr),
 
     #[token("#t", |_| Some(true))]
     #[token("#f", |_| Some(false))]
     Bool(bool),
 
-    #[regex("[+-]?[0-9]+", |lex| lex.slice().parse().map_err(|_| ()), priority = 0)]
+    #[regex("[+-]?[0-9]+", |lex| lex.slice().parse().map_err(|_| LexError::Syntax), priority = 0)]
     Int(i64),
 
     #[regex(
         r#"[+-]?[0-9]+\.[0-9]*([eE][+-]?[0-9]+)?"#r,
-        |lex| lex.slice().parse().map_err(|_| ()),
+        |lex| lex.slice().parse().map_err(|_| LexError::Syntax),
         priority = 1
     )]
     #[token("#+inf", |_| f64::INFINITY)]
@@ -73,88 +117,753 @@ pub enum ReadError {
     UnexpectedClose { span: Span },
     #[error("expected whitespace")]
     ExpectedWhitespace { after: Span, before: Span },
+    #[error("lists nested too deeply")]
+    NestingLimitExceeded { span: Span },
     #[error(transparent)]
     Parse(#[from] ParseError<Span>),
 }
 
+impl ReadError {
+    /// The span in the source string where the error occured, if known.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ReadError::Syntax { span } => Some(span),
+            ReadError::EndOfFile => None,
+            ReadError::UnexpectedClose { span } => Some(span),
+            ReadError::ExpectedWhitespace { before, .. } => Some(before),
+            ReadError::NestingLimitExceeded { span } => Some(span),
+            ReadError::Parse(error) => error.span(),
+        }
+    }
+
+    /// Move the spans of the error by `offset` bytes, for errors that are
+    /// read from a part of a larger string.
+    pub(crate) fn offset(self, offset: usize) -> Self {
+        let shift = |span: Span| span.start + offset..span.end + offset;
+
+        match self {
+            ReadError::Syntax { span } => ReadError::Syntax { span: shift(span) },
+            ReadError::EndOfFile => ReadError::EndOfFile,
+            ReadError::UnexpectedClose { span } => ReadError::UnexpectedClose { span: shift(span) },
+            ReadError::ExpectedWhitespace { after, before } => ReadError::ExpectedWhitespace {
+                after: shift(after),
+                before: shift(before),
+            },
+            ReadError::NestingLimitExceeded { span } => {
+                ReadError::NestingLimitExceeded { span: shift(span) }
+            }
+            ReadError::Parse(error) => ReadError::Parse(error.map_span(shift)),
+        }
+    }
+
+    /// Suggest a fix for common mistakes that lead to this error.
+    ///
+    /// The `source` must be the string that the error was read from.
+    /// Suggestions are heuristics intended for tooling and are therefore
+    /// not included in the [`Display`](std::fmt::Display) output.
+    pub fn suggestion(&self, source: &str) -> Option<String> {
+        match self {
+            // Misspelled literals such as `#True` are either not recognized at
+            // all or are read as a valid literal followed by a symbol.
+            ReadError::Syntax { span } | ReadError::ExpectedWhitespace { after: span, .. } => {
+                let rest = source.get(span.start..)?.strip_prefix('#')?;
+                let word: String = rest
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '+' | '-'))
+                    .collect();
+
+                let fixed = match word.to_lowercase().as_str() {
+                    "true" | "yes" => "#t",
+                    "false" | "no" => "#f",
+                    "inf" | "+inf" | "infinity" | "+infinity" => "#+inf",
+                    "-infinity" => "#-inf",
+                    "nan" if word != "nan" => "#nan",
+                    "t" if word != "t" => "#t",
+                    "f" if word != "f" => "#f",
+                    _ => return None,
+                };
+
+                Some(format!("did you mean {}?", fixed))
+            }
+            ReadError::Parse(ParseError::UnexpectedToken {
+                found: TokenKind::Symbol,
+                span,
+                ..
+            }) => {
+                // A symbol that directly follows another symbol on the same
+                // line may have been intended as a single symbol with spaces.
+                let current = source.get(span.clone())?;
+                let before = source.get(..span.start)?;
+                let trimmed = before.trim_end_matches(' ');
+
+                if trimmed.len() == before.len() {
+                    return None;
+                }
+
+                let start = trimmed
+                    .rfind(|c: char| c.is_whitespace() || matches!(c, '(' | ')'))
+                    .map_or(0, |index| index + 1);
+                let previous = &trimmed[start..];
+
+                if !is_plain_symbol(previous) || !is_plain_symbol(current) {
+                    return None;
+                }
+
+                Some(format!(
+                    "use |{}{}{}| to include spaces in a symbol",
+                    previous,
+                    &before[trimmed.len()..],
+                    current
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check whether a string consists of a single symbol that is not delimited.
+fn is_plain_symbol(text: &str) -> bool {
+    let mut lexer = Token::lexer(text);
+
+    matches!(lexer.next(), Some(Ok(Token::Symbol(_))))
+        && lexer.span() == (0..text.len())
+        && !text.starts_with('|')
+}
+
+/// Number of columns that a tab is expanded to by [`render_error`].
+const TAB_WIDTH: usize = 4;
+
+/// Maximum number of characters of a source line shown by [`render_error`].
+const SNIPPET_WIDTH: usize = 80;
+
+/// Render a [`ReadError`] together with a snippet of the source that caused it.
+///
+/// The snippet starts with the error message, followed by each affected line
+/// with its line number in a gutter and a line that underlines the error span.
+/// Tabs are expanded and lines that are too long are truncated with `…`.
+/// Both spans of [`ReadError::ExpectedWhitespace`] are marked, the preceding
+/// token with `-` and the token that should have been separated with `^`.
+/// [`ReadError::EndOfFile`] points just past the last non-whitespace character.
+///
+/// The `source` must be the string that the error was read from.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, read::render_error, Value};
+/// let source = "(a\n  b))";
+/// let error = from_str::<Vec<Value>>(source).unwrap_err();
+/// assert_eq!(
+///     render_error(source, &error),
+///     "error: unexpected closing delimiter\n2 |   b))\n  |     ^"
+/// );
+/// ```
+pub fn render_error(source: &str, error: &ReadError) -> String {
+    let converter = SpanConverter::new(source);
+
+    let spans = match error {
+        ReadError::ExpectedWhitespace { after, before } => {
+            vec![(after.clone(), '-'), (before.clone(), '^')]
+        }
+        ReadError::EndOfFile => {
+            let end = source.trim_end().len();
+            vec![(end..end, '^')]
+        }
+        _ => error
+            .span()
+            .map(|span| (span.clone(), '^'))
+            .into_iter()
+            .collect(),
+    };
+
+    // Group the spans by line. Spans that cross a line break are underlined
+    // up to the end of the line on which they start.
+    let mut lines: Vec<(usize, Vec<(Span, char)>)> = Vec::new();
+
+    for (span, marker) in spans {
+        let (line, start) = converter.offset_to_line_col(span.start);
+        let (end_line, end) = converter.offset_to_line_col(span.end);
+        let end = if end_line > line { usize::MAX } else { end };
+
+        match lines.iter_mut().find(|(other, _)| *other == line) {
+            Some((_, markers)) => markers.push((start..end, marker)),
+            None => lines.push((line, vec![(start..end, marker)])),
+        }
+    }
+
+    let gutter = lines
+        .iter()
+        .map(|(line, _)| (line + 1).to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut output = vec![format!("error: {}", error)];
+
+    for (line, markers) in lines {
+        let (text, underline) = render_line(converter.line(line), &markers);
+        output.push(
+            format!("{:>gutter$} | {}", line + 1, text)
+                .trim_end()
+                .to_string(),
+        );
+        output.push(
+            format!("{:gutter$} | {}", "", underline)
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    output.join("\n")
+}
+
+/// Render a line of source text and a line underlining the byte ranges in `markers`.
+fn render_line(text: &str, markers: &[(Span, char)]) -> (String, String) {
+    let mut chars = Vec::new();
+
+    // The column of every byte offset into the line after expanding tabs.
+    let mut columns = Vec::with_capacity(text.len() + 1);
+
+    for (offset, c) in text.char_indices() {
+        columns.resize(offset + 1, chars.len());
+
+        match c {
+            '\t' => chars.extend(std::iter::repeat_n(' ', TAB_WIDTH)),
+            c => chars.push(c),
+        }
+    }
+
+    columns.resize(text.len() + 1, chars.len());
+
+    let markers: Vec<_> = markers
+        .iter()
+        .map(|(span, marker)| {
+            let start = columns[span.start.min(text.len())];
+            let end = columns[span.end.min(text.len())];
+            (start..end, *marker)
+        })
+        .collect();
+
+    // Choose a window that shows the start of the first marker with some context.
+    let (window_start, window_end) = if chars.len() > SNIPPET_WIDTH {
+        let first = markers
+            .iter()
+            .map(|(span, _)| span.start)
+            .min()
+            .unwrap_or(0);
+        let start = first
+            .saturating_sub(SNIPPET_WIDTH / 4)
+            .min(chars.len() - SNIPPET_WIDTH);
+        (start, start + SNIPPET_WIDTH)
+    } else {
+        (0, chars.len())
+    };
+
+    let mut rendered = String::new();
+    let shift = usize::from(window_start > 0);
+
+    if window_start > 0 {
+        rendered.push('…');
+    }
+
+    rendered.extend(&chars[window_start..window_end]);
+
+    if window_end < chars.len() {
+        rendered.push('…');
+    }
+
+    let mut underline = vec![' '; window_end - window_start + 2 * shift + 1];
+
+    for (span, marker) in markers {
+        let start = span.start.clamp(window_start, window_end) - window_start + shift;
+        let end = span.end.clamp(window_start, window_end) - window_start + shift;
+
+        for c in &mut underline[start..end.max(start + 1)] {
+            *c = marker;
+        }
+    }
+
+    (rendered, underline.into_iter().collect())
+}
+
+/// Default for the maximum number of nested lists, see [`ReadOptions::with_max_depth`].
+pub const MAX_NESTING_DEPTH: usize = 1000;
+
+/// Options for reading s-expressions.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str_with_opts, ReadOptions, Value};
+/// let options = ReadOptions::new().with_expand_shorthands(true);
+/// let value: Value = from_str_with_opts("'a", &options).unwrap();
+/// assert_eq!(value, parenthesis::from_str::<Value>("(quote a)").unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    max_depth: usize,
+    strict_whitespace: bool,
+    expand_shorthands: bool,
+    fold_case: bool,
+    max_errors: usize,
+    recovery: Recovery,
+    preserve_comments: bool,
+    #[cfg(feature = "unicode-normalization")]
+    unicode_normalization: Option<NormalizationForm>,
+}
+
+impl ReadOptions {
+    /// Create [`ReadOptions`] with the default settings.
+    pub fn new() -> Self {
+        Self {
+            max_depth: MAX_NESTING_DEPTH,
+            strict_whitespace: true,
+            expand_shorthands: false,
+            fold_case: false,
+            max_errors: usize::MAX,
+            recovery: Recovery::default(),
+            preserve_comments: false,
+            #[cfg(feature = "unicode-normalization")]
+            unicode_normalization: None,
+        }
+    }
+
+    /// Set the maximum number of nested lists.
+    ///
+    /// Reading deeply nested input can overflow the stack, since [`FromParens`]
+    /// implementations recurse into nested lists. Input that exceeds the limit
+    /// fails with [`ReadError::NestingLimitExceeded`] before it is parsed.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set whether adjacent tokens must be separated by whitespace.
+    ///
+    /// This is enabled by default. When disabled, input such as `a"b"` is
+    /// read as two values instead of failing with [`ReadError::ExpectedWhitespace`].
+    pub fn with_strict_whitespace(mut self, strict_whitespace: bool) -> Self {
+        self.strict_whitespace = strict_whitespace;
+        self
+    }
+
+    /// Set whether the quote shorthand is expanded.
+    ///
+    /// This is disabled by default. When enabled, `'x` is read as `(quote x)`
+    /// for any value `x`. Otherwise a quote is a syntax error.
+    pub fn with_expand_shorthands(mut self, expand_shorthands: bool) -> Self {
+        self.expand_shorthands = expand_shorthands;
+        self
+    }
+
+    /// Set whether symbols are converted to lowercase while reading.
+    ///
+    /// This is disabled by default. When enabled, all symbols including
+    /// delimited ones such as `|Foo Bar|` are folded to lowercase, so that
+    /// `(OPERATION ...)` is read as `(operation ...)`. Strings are left unchanged.
+    /// Only ASCII letters are folded; other characters are kept as they are.
+    pub fn with_fold_case(mut self, fold_case: bool) -> Self {
+        self.fold_case = fold_case;
+        self
+    }
+
+    /// Set the maximum number of errors reported by [`from_str_all_errors_with_opts`].
+    ///
+    /// At least one error is always reported. There is no limit by default.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Set the strategy used by [`from_str_all_errors_with_opts`] to recover from malformed tokens.
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Set whether comments are preserved as tokens.
+    ///
+    /// This is disabled by default, in which case comments are skipped while
+    /// reading. When enabled, the [`ReaderStream`] returns each comment as a
+    /// [`TokenTree::Comment`] in the position where it occurs. Since most
+    /// [`FromParens`] implementations do not expect comments, they can use
+    /// [`InputStream::skip_comments`] to advance past them.
+    pub fn with_preserve_comments(mut self, preserve_comments: bool) -> Self {
+        self.preserve_comments = preserve_comments;
+        self
+    }
+
+    /// Set the unicode normalization form of strings and symbols.
+    ///
+    /// This is disabled by default. When enabled, all strings and symbols
+    /// are normalized to the given form while reading, before case folding.
+    #[cfg(feature = "unicode-normalization")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    pub fn with_unicode_normalization(
+        mut self,
+        unicode_normalization: Option<NormalizationForm>,
+    ) -> Self {
+        self.unicode_normalization = unicode_normalization;
+        self
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Strategy to recover from malformed input when collecting all errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Recovery {
+    /// Skip only the offending token.
+    #[default]
+    SkipToken,
+    /// Skip the offending token and all following tokens up to the next
+    /// opening or closing delimiter of a list.
+    SkipToNextList,
+}
+
 /// Read a value of type `T` from an s-expression string.
 pub fn from_str<T>(str: &str) -> Result<T, ReadError>
 where
     T: for<'a> FromParens<ReaderStream<'a>>,
 {
-    let mut tokens: Vec<_> = Token::lexer(str)
-        .spanned()
-        .filter(|(token, _)| !matches!(token, Ok(Token::Comment)))
-        .map(|(token, span)| match token {
-            Ok(token) => Ok((token, span)),
-            Err(()) => Err(ReadError::Syntax { span: span.clone() }),
-        })
-        .collect::<Result<_, _>>()?;
+    from_str_with_opts(str, &ReadOptions::default())
+}
+
+/// Read a value of type `T` from the front of an s-expression string and
+/// return it together with the remaining input.
+///
+/// Unlike [`from_str`], the remaining input starts at the first value that has
+/// not been consumed. The entire string is still lexed, so the remaining input
+/// must consist of valid tokens with balanced delimiters.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str_partial, Value};
+/// let (value, rest) = from_str_partial::<Value>("(a b) (c d)").unwrap();
+/// assert_eq!(value.to_string(), "(a b)");
+/// assert_eq!(rest, "(c d)");
+/// ```
+pub fn from_str_partial<T>(str: &str) -> Result<(T, &str), ReadError>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    match read_partial(str, &ReadOptions::default()) {
+        Ok((value, offset)) => Ok((value, &str[offset..])),
+        Err(mut errors) => Err(errors.swap_remove(0)),
+    }
+}
+
+/// Read a value of type `T` from an s-expression string with the given options.
+pub fn from_str_with_opts<T>(str: &str, opts: &ReadOptions) -> Result<T, ReadError>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    from_str_all_errors_with_opts(str, opts).map_err(|mut errors| errors.swap_remove(0))
+}
+
+/// Read a value of type `T` from an s-expression string, collecting all errors.
+///
+/// Instead of stopping at the first error, malformed tokens and unbalanced
+/// delimiters are reported and skipped so that the remaining input can still
+/// be checked. This is intended for tooling such as editor integrations.
+/// Equivalent to [`from_str_all_errors_with`] with [`Recovery::SkipToken`].
+pub fn from_str_all_errors<T>(str: &str) -> Result<T, Vec<ReadError>>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    from_str_all_errors_with(str, Recovery::default())
+}
+
+/// Read a value of type `T` from an s-expression string, collecting all errors
+/// and recovering from malformed tokens with the given strategy.
+///
+/// The errors are ordered by the phase in which they are found: syntax errors
+/// first, followed by missing whitespace, unbalanced delimiters and finally the
+/// error of [`FromParens`] if any. Since [`FromParens`] can not recover from
+/// errors, there is at most one [`ReadError::Parse`] which is reported for the
+/// recovered input. It may therefore be a consequence of an earlier error.
+pub fn from_str_all_errors_with<T>(str: &str, recovery: Recovery) -> Result<T, Vec<ReadError>>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    from_str_all_errors_with_opts(str, &ReadOptions::new().with_recovery(recovery))
+}
+
+/// Read a value of type `T` from an s-expression string with the given options,
+/// collecting all errors as in [`from_str_all_errors_with`].
+pub fn from_str_all_errors_with_opts<T>(str: &str, opts: &ReadOptions) -> Result<T, Vec<ReadError>>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    read(str, opts).map_err(|mut errors| {
+        errors.truncate(opts.max_errors.max(1));
+        errors
+    })
+}
+
+/// Read a value of type `T`, returning the value or the list of errors.
+fn read<T>(str: &str, options: &ReadOptions) -> Result<T, Vec<ReadError>>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    read_partial(str, options).map(|(value, _)| value)
+}
+
+/// Read a value of type `T`, returning the value together with the offset of
+/// the first token that was not consumed, or the list of errors.
+fn read_partial<T>(str: &str, options: &ReadOptions) -> Result<(T, usize), Vec<ReadError>>
+where
+    T: for<'a> FromParens<ReaderStream<'a>>,
+{
+    let mut errors = Vec::new();
+    let mut tokens = Vec::new();
+    let mut skipping = false;
 
-    check_whitespace(&tokens)?;
-    balance_lists(&mut tokens)?;
+    for (token, span) in Token::lexer(str).spanned() {
+        match token {
+            Ok(Token::Comment(_)) if !options.preserve_comments => {}
+            Ok(Token::Quote) if !options.expand_shorthands => {
+                errors.push(ReadError::Syntax { span });
+                skipping = options.recovery == Recovery::SkipToNextList;
+            }
+            Ok(token @ (Token::Open(_) | Token::Close(_))) => {
+                skipping = false;
+                tokens.push((token, span));
+            }
+            Ok(_) if skipping => {}
+            #[cfg(feature = "unicode-normalization")]
+            Ok(Token::String(string)) => {
+                let string = match options.unicode_normalization {
+                    Some(form) => form.normalize(&string).into(),
+                    None => string,
+                };
+
+                tokens.push((Token::String(string), span));
+            }
+            Ok(Token::Symbol(symbol)) => {
+                #[cfg(feature = "unicode-normalization")]
+                let symbol = match options.unicode_normalization {
+                    Some(form) => Symbol::new(form.normalize(symbol.as_ref())),
+                    None => symbol,
+                };
+
+                let symbol = match options.fold_case {
+                    true => Symbol::new(symbol.as_ref().to_ascii_lowercase()),
+                    false => symbol,
+                };
+
+                #[cfg(feature = "interning")]
+                let symbol = crate::intern::intern_global(symbol);
+
+                tokens.push((Token::Symbol(symbol), span));
+            }
+            Ok(token) => tokens.push((token, span)),
+            Err(error) => {
+                // Invalid escape sequences are reported at the escape sequence
+                // instead of the entire string or symbol.
+                let span = match error {
+                    LexError::Syntax => span,
+                    LexError::Unescape(escape) => {
+                        span.start + escape.start..span.start + escape.end
+                    }
+                };
+
+                errors.push(ReadError::Syntax { span });
+                skipping = options.recovery == Recovery::SkipToNextList;
+            }
+        }
+    }
+
+    if options.strict_whitespace {
+        check_whitespace(&tokens, &mut errors);
+    }
+
+    if options.expand_shorthands {
+        tokens = expand_shorthands(tokens);
+    }
 
-    let result = T::from_parens(&mut ReaderStream {
+    // Input that is nested too deeply can not be parsed safely.
+    if let Err(error) = balance_lists(&mut tokens, options.max_depth, &mut errors) {
+        errors.push(error);
+        return Err(errors);
+    }
+
+    let mut stream = ReaderStream {
         tokens: &tokens,
         cur_span: 0..0,
         parent_span: 0..str.len(),
-    })?;
+    };
+    let result = T::from_parens(&mut stream);
+    let offset = stream
+        .tokens
+        .first()
+        .map_or(str.len(), |(_, span)| span.start);
 
-    Ok(result)
+    match result {
+        Ok(result) if errors.is_empty() => Ok((result, offset)),
+        Ok(_) => Err(errors),
+        Err(error) => {
+            errors.push(error.into());
+            Err(errors)
+        }
+    }
 }
 
-fn check_whitespace(tokens: &[(Token, Span)]) -> Result<(), ReadError> {
+fn check_whitespace(tokens: &[(Token, Span)], errors: &mut Vec<ReadError>) {
     for window in tokens.windows(2) {
         let (token_a, span_a) = &window[0];
         let (token_b, span_b) = &window[1];
 
         match token_a {
-            Token::OpenList(_) => continue,
-            Token::Comment => continue,
+            Token::Open(_) => continue,
+            Token::Quote => continue,
+            Token::Comment(_) => continue,
             _ => {}
         }
 
         match token_b {
-            Token::CloseList => continue,
-            Token::Comment => continue,
+            Token::Close(_) => continue,
+            Token::Comment(_) => continue,
             _ => {}
         }
 
         if span_a.end == span_b.start {
-            return Err(ReadError::ExpectedWhitespace {
+            errors.push(ReadError::ExpectedWhitespace {
                 after: span_a.clone(),
                 before: span_b.clone(),
             });
         }
     }
+}
 
-    Ok(())
+/// Expand each `'x` into `(quote x)`.
+///
+/// Incomplete shorthands at the end of a list or the input remain unclosed,
+/// so that they are reported when balancing the lists.
+fn expand_shorthands(tokens: Vec<(Token, Span)>) -> Vec<(Token, Span)> {
+    let mut expanded = Vec::with_capacity(tokens.len());
+
+    // Current nesting depth including the lists introduced by expansion.
+    let mut depth = 0;
+
+    // Depths of the lists introduced by expansion that are still open.
+    let mut pending = Vec::new();
+
+    for (token, span) in tokens {
+        let completes_value = match token {
+            Token::Quote => {
+                expanded.push((Token::Open((Delimiter::Paren, 0)), span.clone()));
+                expanded.push((Token::Symbol(Symbol::new("quote")), span));
+                depth += 1;
+                pending.push(depth);
+                false
+            }
+            Token::Open(_) => {
+                expanded.push((token, span));
+                depth += 1;
+                false
+            }
+            Token::Comment(_) => {
+                expanded.push((token, span));
+                false
+            }
+            Token::Close(_) => {
+                expanded.push((token, span));
+                depth -= 1;
+                true
+            }
+            _ => {
+                expanded.push((token, span));
+                true
+            }
+        };
+
+        // Close the lists of all shorthands whose value is now complete.
+        if completes_value {
+            let end = expanded.last().map_or(0, |(_, span)| span.end);
+
+            while pending.last() == Some(&depth) {
+                expanded.push((Token::Close(Delimiter::Paren), end..end));
+                pending.pop();
+                depth -= 1;
+            }
+        }
+    }
+
+    expanded
 }
 
-/// Check that the parentheses are well-balanced and make the OpenList
-/// tokens reflect the distance to their associated CloseList tokens.
-fn balance_lists(tokens: &mut [(Token, Span)]) -> Result<(), ReadError> {
-    // Stack that holds the indices of all currently unclosed `(`s.
-    let mut stack = Vec::new();
+/// Check that the delimiters are well-balanced and make the opening
+/// delimiters reflect the distance to their associated closing delimiters.
+///
+/// Unbalanced delimiters are reported and removed from the tokens. A closing
+/// delimiter that does not match the innermost open delimiter is unbalanced.
+/// Exceeding the maximum depth is fatal and reported as an error immediately.
+fn balance_lists(
+    tokens: &mut Vec<(Token, Span)>,
+    max_depth: usize,
+    errors: &mut Vec<ReadError>,
+) -> Result<(), ReadError> {
+    // Stack that holds the indices and kinds of all currently unclosed delimiters.
+    let mut stack: Vec<(usize, Delimiter)> = Vec::new();
 
-    for i in 0..tokens.len() {
-        let (token, span) = &tokens[i];
+    // Indices of the delimiters that do not have a partner.
+    let mut unbalanced = Vec::new();
 
+    for (i, (token, span)) in tokens.iter().enumerate() {
         match token {
-            Token::OpenList(_) => stack.push(i),
-            Token::CloseList => {
-                let Some(j) = stack.pop() else {
-                    return Err(ReadError::UnexpectedClose { span: span.clone() });
-                };
+            Token::Open((delimiter, _)) => {
+                if stack.len() >= max_depth {
+                    return Err(ReadError::NestingLimitExceeded { span: span.clone() });
+                }
 
-                tokens[j].0 = Token::OpenList(i - j);
+                stack.push((i, *delimiter));
+            }
+            Token::Close(delimiter) => {
+                if stack.last().map(|(_, open)| open) == Some(delimiter) {
+                    stack.pop();
+                } else {
+                    errors.push(ReadError::UnexpectedClose { span: span.clone() });
+                    unbalanced.push(i);
+                }
             }
             _ => {}
         }
     }
 
     if !stack.is_empty() {
-        return Err(ReadError::EndOfFile);
+        errors.push(ReadError::EndOfFile);
+        unbalanced.extend(stack.into_iter().map(|(i, _)| i));
+    }
+
+    if !unbalanced.is_empty() {
+        unbalanced.sort_unstable();
+        let mut unbalanced = unbalanced.into_iter().peekable();
+        let mut index = 0;
+
+        tokens.retain(|_| {
+            let keep = unbalanced.next_if_eq(&index).is_none();
+            index += 1;
+            keep
+        });
+    }
+
+    // Now that all delimiters are balanced, record the distances.
+    let mut stack = Vec::new();
+
+    for i in 0..tokens.len() {
+        match tokens[i].0 {
+            Token::Open(_) => stack.push(i),
+            Token::Close(delimiter) => {
+                let j = stack.pop().expect("delimiters are balanced");
+                tokens[j].0 = Token::Open((delimiter, i - j));
+            }
+            _ => {}
+        }
     }
 
     Ok(())
@@ -170,41 +879,86 @@ pub struct ReaderStream<'a> {
 
 impl<'a> InputStream for ReaderStream<'a> {
     type Span = Span;
+    type Checkpoint = ReaderCheckpoint<'a>;
 
     fn next(&mut self) -> Option<TokenTree<Self>> {
-        match self.peek()? {
-            TokenTree::List(inner) => {
-                self.cur_span = inner.parent_span.clone();
-                self.tokens = &self.tokens[inner.tokens.len() + 2..];
-                Some(TokenTree::List(inner))
-            }
-            token_tree => {
-                self.cur_span = self.tokens[0].1.clone();
+        let token_tree = self.peek()?;
+
+        match &self.tokens[0] {
+            (Token::Open((_, skip)), span) => {
+                self.cur_span = span.start..self.tokens[*skip].1.end;
+                self.tokens = &self.tokens[skip + 1..];
+            }
+            (_, span) => {
+                self.cur_span = span.clone();
                 self.tokens = &self.tokens[1..];
-                Some(token_tree)
             }
         }
+
+        Some(token_tree)
     }
 
     fn peek(&self) -> Option<TokenTree<Self>> {
         let (token, span) = self.tokens.first()?;
 
         match token {
-            Token::OpenList(skip) => Some(TokenTree::List(ReaderStream {
-                tokens: &self.tokens[1..*skip],
-                cur_span: span.end..span.end,
-                parent_span: span.end..self.tokens[*skip].1.end,
-            })),
-            Token::CloseList => None,
+            Token::Open((delimiter, skip)) => {
+                let inner = ReaderStream {
+                    tokens: &self.tokens[1..*skip],
+                    cur_span: span.end..span.end,
+                    parent_span: span.start..self.tokens[*skip].1.end,
+                };
+
+                Some(match delimiter {
+                    Delimiter::Paren => TokenTree::List(inner),
+                    Delimiter::Bracket => TokenTree::Seq(inner),
+                    Delimiter::Brace => TokenTree::Map(inner),
+                })
+            }
+            Token::Close(_) => None,
+            Token::Quote => unreachable!("shorthands have been expanded before"),
             Token::String(string) => Some(TokenTree::String(string.clone())),
             Token::Symbol(symbol) => Some(TokenTree::Symbol(symbol.clone())),
-            Token::Comment => unreachable!("comments have been stripped before"),
+            Token::Comment(comment) => Some(TokenTree::Comment(comment.clone())),
             Token::Bool(bool) => Some(TokenTree::Bool(*bool)),
             Token::Int(int) => Some(TokenTree::Int(*int)),
             Token::Float(float) => Some(TokenTree::Float(*float)),
         }
     }
 
+    fn peek_n(&self, n: usize) -> Option<TokenTree<Self>> {
+        // Skip over `n` tokens, using the distance recorded in `Open`
+        // to skip over a nested list in a single step.
+        let mut index = 0;
+
+        for _ in 0..n {
+            index += match self.tokens.get(index)? {
+                (Token::Open((_, skip)), _) => skip + 1,
+                (Token::Close(_), _) => return None,
+                _ => 1,
+            };
+        }
+
+        ReaderStream {
+            tokens: self.tokens.get(index..)?,
+            cur_span: self.cur_span.clone(),
+            parent_span: self.parent_span.clone(),
+        }
+        .peek()
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        ReaderCheckpoint {
+            tokens: self.tokens,
+            cur_span: self.cur_span.clone(),
+        }
+    }
+
+    fn restore(&mut self, checkpoint: Self::Checkpoint) {
+        self.tokens = checkpoint.tokens;
+        self.cur_span = checkpoint.cur_span;
+    }
+
     fn span(&self) -> Self::Span {
         self.cur_span.clone()
     }
@@ -213,15 +967,39 @@ impl<'a> InputStream for ReaderStream<'a> {
         self.parent_span.clone()
     }
 
+    fn byte_range(span: &Self::Span) -> Option<Range<usize>> {
+        Some(span.clone())
+    }
+
     fn is_end(&self) -> bool {
         self.tokens.is_empty()
     }
 }
 
+/// Position in a [`ReaderStream`], see [`InputStream::checkpoint`].
+#[derive(Debug, Clone)]
+pub struct ReaderCheckpoint<'a> {
+    tokens: &'a [(Token, Span)],
+    cur_span: Span,
+}
+
+impl<'a> PartialEq for ReaderCheckpoint<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.tokens, other.tokens)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::from_str;
-    use crate::Value;
+    use super::{
+        from_str, from_str_all_errors, from_str_all_errors_with, from_str_all_errors_with_opts,
+        from_str_partial, from_str_with_opts, ReadError, ReadOptions, Recovery, MAX_NESTING_DEPTH,
+    };
+    use super::{ReaderStream, Span};
+    use crate::from_parens::{FromParens, InputStream, ParseError, TokenTree, ValueStream};
+    use crate::pretty::Pretty;
+    use crate::to_parens::{OutputStream, ToParens};
+    use crate::{to_string_compact, to_string_pretty, Symbol, Value};
     use rstest::rstest;
 
     #[rstest]
@@ -231,4 +1009,573 @@ mod test {
     fn require_whitespace(#[case] text: &str) {
         assert!(from_str::<Vec<Value>>(text).is_err());
     }
+
+    #[rstest]
+    #[case(r#""a\qb""#, 2..4)]
+    #[case(r#"x "\u{d800}""#, 3..11)]
+    #[case(r#""\u{110000}" y"#, 1..11)]
+    #[case(r#""\u{41""#, 1..6)]
+    #[case(r#"|a\q|"#, 2..4)]
+    fn invalid_escape(#[case] text: &str, #[case] expected: Span) {
+        let Err(ReadError::Syntax { span }) = from_str::<Vec<Value>>(text) else {
+            panic!("expected syntax error");
+        };
+
+        assert_eq!(span, expected);
+    }
+
+    #[rstest]
+    #[case("(a b) (c d)", "(a b)", "(c d)")]
+    #[case("(a b)", "(a b)", "")]
+    #[case("x ; comment\n  [1 2] ", "x", "[1 2] ")]
+    #[case("1 2 3", "1", "2 3")]
+    fn partial(#[case] text: &str, #[case] expected: &str, #[case] rest: &str) {
+        let expected = from_str::<Value>(expected).unwrap();
+        assert_eq!(from_str_partial::<Value>(text).unwrap(), (expected, rest));
+    }
+
+    #[test]
+    fn partial_error() {
+        assert!(from_str_partial::<Value>("(a b) (c").is_err());
+        assert!(from_str_partial::<i64>("a 1").is_err());
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let value = from_str::<Value>(r#""caf\u{e9} \u{2615}""#).unwrap();
+        assert_eq!(value, Value::from("café ☕"));
+
+        let value = from_str::<Value>(r"|caf\u{e9}|").unwrap();
+        assert_eq!(value, Value::Symbol(Symbol::new("café")));
+    }
+
+    /// Either a comment or a value, used to test that comments are preserved.
+    #[derive(Debug, PartialEq)]
+    enum Commented {
+        Comment(String),
+        Value(Value),
+    }
+
+    impl<I: InputStream> FromParens<I> for Commented {
+        fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+            match stream.peek() {
+                Some(TokenTree::Comment(comment)) => {
+                    stream.next();
+                    Ok(Commented::Comment(comment.into()))
+                }
+                _ => Ok(Commented::Value(Value::from_parens(stream)?)),
+            }
+        }
+    }
+
+    impl<O: OutputStream> ToParens<O> for Commented {
+        fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+            match self {
+                Commented::Comment(comment) => output.comment(comment),
+                Commented::Value(value) => value.to_parens(output),
+            }
+        }
+    }
+
+    #[test]
+    fn preserve_comments() {
+        let text = "; header\n(a b) ; trailing\nc ;; end\n";
+        let options = ReadOptions::new().with_preserve_comments(true);
+        let items = from_str_with_opts::<Vec<Commented>>(text, &options).unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                Commented::Comment(" header".into()),
+                Commented::Value(from_str("(a b)").unwrap()),
+                Commented::Comment(" trailing".into()),
+                Commented::Value(from_str("c").unwrap()),
+                Commented::Comment("; end".into()),
+            ]
+        );
+
+        assert_eq!(
+            to_string_pretty(&items, 80),
+            "; header\n(a b)\n; trailing\nc\n;; end\n"
+        );
+        assert_eq!(
+            from_str_with_opts::<Vec<Commented>>(&to_string_compact(&items), &options).unwrap(),
+            items
+        );
+
+        // Without the option, comments are skipped.
+        assert_eq!(
+            from_str::<Vec<Commented>>(text).unwrap(),
+            vec![
+                Commented::Value(from_str("(a b)").unwrap()),
+                Commented::Value(from_str("c").unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn preserve_comments_nested() {
+        let options = ReadOptions::new().with_preserve_comments(true);
+
+        let Err(ReadError::Parse(error)) =
+            from_str_with_opts::<Value>("(a ; comment\n b)", &options)
+        else {
+            panic!("expected parse error");
+        };
+        assert_eq!(error.to_string(), "expected value, found comment");
+
+        struct Skip(Vec<i64>);
+
+        impl<I: InputStream> FromParens<I> for Skip {
+            fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                let mut list = stream.skip_comments().expect_list()?;
+                let mut values = Vec::new();
+
+                while !list.skip_comments().is_end() {
+                    values.push(i64::from_parens(&mut list)?);
+                }
+
+                Ok(Skip(values))
+            }
+        }
+
+        let text = "; numbers\n(1 ; one\n 2 ; two\n)";
+        let Skip(values) = from_str_with_opts(text, &options).unwrap();
+        assert_eq!(values, vec![1, 2]);
+
+        // A closing delimiter after a comment is moved to the next line.
+        let nested = vec![
+            Commented::Value(Value::from(1)),
+            Commented::Comment(" one".into()),
+        ];
+        let mut pretty = Pretty::new();
+        pretty.list(|output| nested.to_parens(output)).unwrap();
+        assert_eq!(pretty.finish().pretty(80).to_string(), "(1 ; one\n)");
+    }
+
+    #[test]
+    fn custom_error() {
+        struct Even;
+
+        impl<I: InputStream> FromParens<I> for Even {
+            fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+                let int = i64::from_parens(stream)?;
+
+                if int % 2 != 0 {
+                    return Err(stream.error(format!("expected even number, found {}", int)));
+                }
+
+                Ok(Even)
+            }
+        }
+
+        let Err(ReadError::Parse(error)) = from_str::<Vec<Even>>("2 4 7") else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), "expected even number, found 7");
+        assert!(matches!(error, ParseError::Custom { span, .. } if span == (4..5)));
+    }
+
+    #[test]
+    fn all_errors() {
+        let errors = from_str_all_errors::<Vec<Value>>("a #x (b) #y) (c c\"d\"").unwrap_err();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                ReadError::Syntax { span: first },
+                ReadError::Syntax { span: second },
+                ReadError::ExpectedWhitespace { .. },
+                ReadError::UnexpectedClose { span: close },
+                ReadError::EndOfFile,
+            ] if *first == (2..3) && *second == (9..10) && *close == (11..12)
+        ));
+    }
+
+    #[test]
+    fn all_errors_parse() {
+        let errors = from_str_all_errors::<Vec<i64>>("1 #x 2 a").unwrap_err();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [ReadError::Syntax { .. }, ReadError::Parse(_)]
+        ));
+        assert_eq!(errors[1].to_string(), "expected int, found symbol");
+    }
+
+    #[test]
+    fn all_errors_recovery() {
+        let errors = from_str_all_errors_with::<[Value; 2]>("# a b", Recovery::SkipToken);
+        assert!(matches!(
+            errors.unwrap_err().as_slice(),
+            [ReadError::Syntax { .. }]
+        ));
+
+        let errors = from_str_all_errors_with::<[Value; 2]>("# a b", Recovery::SkipToNextList);
+        let errors = errors.unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [ReadError::Syntax { .. }, ReadError::Parse(_)]
+        ));
+        assert_eq!(errors[1].to_string(), "expected 2 elements, found 0");
+    }
+
+    #[test]
+    fn all_errors_ok() {
+        let values = from_str_all_errors::<Vec<Value>>("(a) b ; comment\n").unwrap();
+        assert_eq!(values.len(), 2);
+    }
+
+    #[rstest]
+    #[case("#True", "did you mean #t?")]
+    #[case("(a #false)", "did you mean #f?")]
+    #[case("#inf", "did you mean #+inf?")]
+    #[case("#NaN", "did you mean #nan?")]
+    fn suggest_literal(#[case] text: &str, #[case] suggestion: &str) {
+        let error = from_str::<Vec<Value>>(text).unwrap_err();
+        assert_eq!(error.suggestion(text).as_deref(), Some(suggestion));
+        assert!(!error.to_string().contains(suggestion));
+    }
+
+    #[test]
+    fn suggest_symbol_with_spaces() {
+        let text = r#"foo bar "baz""#;
+        let error = from_str::<(Symbol, String)>(text).unwrap_err();
+
+        assert_eq!(
+            error.suggestion(text).as_deref(),
+            Some("use |foo bar| to include spaces in a symbol")
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_literal("#x")]
+    #[case::unbalanced(")")]
+    #[case::missing_space("#t#f")]
+    fn suggest_none(#[case] text: &str) {
+        let error = from_str::<Vec<Value>>(text).unwrap_err();
+        assert_eq!(error.suggestion(text), None);
+    }
+
+    fn nested(depth: usize) -> String {
+        format!("{}{}", "(".repeat(depth), ")".repeat(depth))
+    }
+
+    #[test]
+    fn nesting_limit() {
+        assert!(from_str::<Value>(&nested(MAX_NESTING_DEPTH)).is_ok());
+
+        let result = from_str::<Value>(&nested(MAX_NESTING_DEPTH + 1));
+        assert!(matches!(
+            result,
+            Err(ReadError::NestingLimitExceeded { span }) if span == (MAX_NESTING_DEPTH..MAX_NESTING_DEPTH + 1)
+        ));
+
+        let errors = from_str_all_errors::<Value>(&nested(MAX_NESTING_DEPTH + 1)).unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [ReadError::NestingLimitExceeded { .. }]
+        ));
+    }
+
+    #[test]
+    fn nesting_limit_options() {
+        let options = ReadOptions::new().with_max_depth(2);
+
+        assert!(from_str_with_opts::<Vec<Value>>("(()) (a (b))", &options).is_ok());
+        assert!(matches!(
+            from_str_with_opts::<Vec<Value>>("(()) (a (b ()))", &options),
+            Err(ReadError::NestingLimitExceeded { .. })
+        ));
+        assert!(
+            from_str_with_opts::<Vec<Value>>("", &ReadOptions::new().with_max_depth(0)).is_ok()
+        );
+        assert!(
+            from_str_with_opts::<Vec<Value>>("()", &ReadOptions::new().with_max_depth(0)).is_err()
+        );
+    }
+
+    #[test]
+    fn options_strict_whitespace() {
+        let options = ReadOptions::new().with_strict_whitespace(false);
+        let values: Vec<Value> = from_str_with_opts(r#"a"b"()c"#, &options).unwrap();
+        assert_eq!(values, from_str::<Vec<Value>>(r#"a "b" () c"#).unwrap());
+    }
+
+    #[rstest]
+    #[case("'a", "(quote a)")]
+    #[case("'(a 'b) c", "(quote (a (quote b))) c")]
+    #[case("(''a)", "((quote (quote a)))")]
+    #[case("'()", "(quote ())")]
+    fn options_expand_shorthands(#[case] text: &str, #[case] expanded: &str) {
+        let options = ReadOptions::new().with_expand_shorthands(true);
+        let values: Vec<Value> = from_str_with_opts(text, &options).unwrap();
+        assert_eq!(values, from_str::<Vec<Value>>(expanded).unwrap());
+
+        assert!(matches!(
+            from_str::<Vec<Value>>(text),
+            Err(ReadError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn options_unicode_normalization() {
+        use crate::normalize::NormalizationForm;
+        use crate::ToParens;
+
+        let composed = "(|caf\u{e9}| \"caf\u{e9}\")";
+        let decomposed = "(|cafe\u{301}| \"cafe\u{301}\")";
+
+        let options = ReadOptions::new().with_unicode_normalization(Some(NormalizationForm::Nfc));
+        let value: Value = from_str_with_opts(decomposed, &options).unwrap();
+        assert_eq!(value, from_str::<Value>(composed).unwrap());
+
+        let options = ReadOptions::new().with_unicode_normalization(Some(NormalizationForm::Nfd));
+        let value: Value = from_str_with_opts(composed, &options).unwrap();
+        assert_eq!(value, from_str::<Value>(decomposed).unwrap());
+
+        assert_ne!(
+            from_str::<Value>(composed).unwrap(),
+            from_str::<Value>(decomposed).unwrap()
+        );
+
+        let mut pretty =
+            crate::pretty::Pretty::new().with_unicode_normalization(Some(NormalizationForm::Nfc));
+        value.to_parens(&mut pretty).unwrap();
+        assert_eq!(pretty.finish().pretty(80).to_string(), composed);
+    }
+
+    #[test]
+    fn options_expand_shorthands_incomplete() {
+        let options = ReadOptions::new().with_expand_shorthands(true);
+        assert!(from_str_with_opts::<Vec<Value>>("(a ')", &options).is_err());
+        assert!(from_str_with_opts::<Vec<Value>>("'", &options).is_err());
+    }
+
+    #[test]
+    fn options_fold_case() {
+        let options = ReadOptions::new().with_fold_case(true);
+        let values: Vec<Value> =
+            from_str_with_opts(r#"(OPERATION Add |Ünïcode X|) "KEEP""#, &options).unwrap();
+        assert_eq!(
+            values,
+            from_str::<Vec<Value>>(r#"(operation add |Ünïcode x|) "KEEP""#).unwrap()
+        );
+    }
+
+    #[test]
+    fn options_max_errors() {
+        let options = ReadOptions::new().with_max_errors(2);
+        let errors = from_str_all_errors_with_opts::<Vec<Value>>("# # # #", &options).unwrap_err();
+        assert_eq!(errors.len(), 2);
+
+        let options = ReadOptions::new().with_max_errors(0);
+        let errors = from_str_all_errors_with_opts::<Vec<Value>>("# #", &options).unwrap_err();
+        assert_eq!(errors.len(), 1);
+    }
+
+    /// Checks `peek_n` against advancing a clone of the stream.
+    fn check_peek_n<I: InputStream + Clone>(stream: &mut I) -> usize {
+        let mut count = 0;
+
+        loop {
+            let before = stream.clone();
+
+            for n in 0..5 {
+                let mut advanced = stream.clone();
+                let expected = (0..n).try_for_each(|_| advanced.next().map(drop));
+                let expected = expected.and_then(|_| advanced.peek());
+                let peeked = stream.peek_n(n);
+
+                assert_eq!(
+                    peeked.as_ref().map(TokenTree::kind),
+                    expected.as_ref().map(TokenTree::kind)
+                );
+
+                if let Some(TokenTree::List(mut list)) = peeked {
+                    count += check_peek_n(&mut list);
+                }
+            }
+
+            // Peeking does not advance the stream.
+            assert_eq!(stream.is_end(), before.is_end());
+
+            if stream.next().is_none() {
+                return count;
+            }
+
+            count += 1;
+        }
+    }
+
+    #[test]
+    fn peek_n() {
+        struct PeekN;
+
+        impl<'a> FromParens<ReaderStream<'a>> for PeekN {
+            fn from_parens(stream: &mut ReaderStream<'a>) -> Result<Self, ParseError<Span>> {
+                assert!(check_peek_n(stream) > 0);
+                assert!(stream.peek_n(0).is_none());
+                Ok(PeekN)
+            }
+        }
+
+        from_str::<PeekN>(r#"(a (b c) "d") e (f)"#).unwrap();
+
+        let values: Vec<Value> = from_str(r#"(a (b c) "d") e (f)"#).unwrap();
+        let mut stream = values.as_slice();
+        assert!(check_peek_n(&mut stream) > 0);
+        assert!(stream.peek_n(3).is_none());
+
+        let mut stream = ValueStream::new(&values);
+        assert!(check_peek_n(&mut stream) > 0);
+        assert!(stream.peek_n(3).is_none());
+    }
+
+    /// Untagged alternatives, parsed by trying each in turn.
+    #[derive(Debug, PartialEq)]
+    enum Untagged {
+        Pair(i64, i64),
+        Int(i64),
+        Name(Symbol),
+    }
+
+    impl<I: InputStream> FromParens<I> for Untagged {
+        fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+            if let Ok((a, b)) = stream.try_parse() {
+                return Ok(Untagged::Pair(a, b));
+            }
+
+            if let Ok(int) = stream.try_parse() {
+                return Ok(Untagged::Int(int));
+            }
+
+            Ok(Untagged::Name(stream.try_parse()?))
+        }
+    }
+
+    #[test]
+    fn try_parse() {
+        let expected = (
+            Untagged::Pair(1, 2),
+            Untagged::Int(3),
+            Untagged::Name("x".into()),
+        );
+        let text = "1 2 3 x";
+        assert_eq!(
+            from_str::<(Untagged, Untagged, Untagged)>(text).unwrap(),
+            expected
+        );
+
+        let values: Vec<Value> = from_str(text).unwrap();
+        let mut stream = values.as_slice();
+        assert_eq!(
+            Untagged::from_parens(&mut stream).unwrap(),
+            Untagged::Pair(1, 2)
+        );
+        assert_eq!(
+            Untagged::from_parens(&mut stream).unwrap(),
+            Untagged::Int(3)
+        );
+        assert_eq!(
+            Untagged::from_parens(&mut stream).unwrap(),
+            Untagged::Name("x".into())
+        );
+        assert!(stream.is_end());
+
+        let mut stream = ValueStream::new(&values);
+        let parsed = <(Untagged, Untagged, Untagged)>::from_parens(&mut stream).unwrap();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn checkpoint_restore() {
+        struct Restore;
+
+        impl<'a> FromParens<ReaderStream<'a>> for Restore {
+            fn from_parens(stream: &mut ReaderStream<'a>) -> Result<Self, ParseError<Span>> {
+                let checkpoint = stream.checkpoint();
+                let span = stream.span();
+
+                assert!(matches!(stream.next(), Some(TokenTree::List(_))));
+                assert!(matches!(stream.next(), Some(TokenTree::Int(2))));
+                assert!(stream.checkpoint() != checkpoint);
+
+                stream.restore(checkpoint.clone());
+                assert!(stream.checkpoint() == checkpoint);
+                assert_eq!(stream.span(), span);
+                assert!(matches!(stream.next(), Some(TokenTree::List(_))));
+
+                let error = stream.parse_recoverable::<(i64, i64)>().unwrap_err();
+                assert!(!error.is_recoverable());
+                assert!(stream.is_end());
+
+                let error = stream.parse_recoverable::<Symbol>().unwrap_err();
+                assert!(error.is_recoverable());
+
+                Ok(Restore)
+            }
+        }
+
+        from_str::<Restore>("(a b) 2").unwrap();
+    }
+
+    #[rstest]
+    #[case::syntax("(a #q b)", "error: unrecognized syntax\n1 | (a #q b)\n  |    ^")]
+    #[case::end_of_file("(a\n  b\n", "error: unexpected end of file\n2 |   b\n  |    ^")]
+    #[case::unexpected_close(
+        "\n\n\n\n\n\n\n\n\n(a\n  b))",
+        "error: unexpected closing delimiter\n11 |   b))\n   |     ^"
+    )]
+    #[case::expected_whitespace(
+        "(a \"b\"c)",
+        "error: expected whitespace\n1 | (a \"b\"c)\n  |    ---^"
+    )]
+    #[case::nesting_limit_exceeded(
+        "((((((a))))))",
+        "error: lists nested too deeply\n1 | ((((((a))))))\n  |    ^"
+    )]
+    #[case::tab("(x)\n(y\n\t#q)", "error: unrecognized syntax\n3 |     #q)\n  |     ^")]
+    fn render_error(#[case] source: &str, #[case] expected: &str) {
+        let options = ReadOptions::default().with_max_depth(3);
+        let error = from_str_with_opts::<Vec<Value>>(source, &options).unwrap_err();
+        assert_eq!(super::render_error(source, &error), expected);
+    }
+
+    #[test]
+    fn render_error_parse() {
+        let source = "(a\n 1.5)";
+        let error = from_str::<(Value, Symbol)>(source).unwrap_err();
+        assert_eq!(
+            super::render_error(source, &error),
+            "error: expected 2nd tuple element\n1 | (a\n  | ^^"
+        );
+
+        let source = "a 1.5";
+        let error = from_str::<(Symbol, Symbol)>(source).unwrap_err();
+        assert_eq!(
+            super::render_error(source, &error),
+            "error: expected symbol, found float\n1 | a 1.5\n  |   ^^^"
+        );
+    }
+
+    #[test]
+    fn render_error_long_line() {
+        let source = format!("({} #q {})", "a ".repeat(40), "b ".repeat(40));
+        let error = from_str::<Vec<Value>>(&source).unwrap_err();
+        let rendered = super::render_error(&source, &error);
+        let lines: Vec<_> = rendered.lines().collect();
+
+        assert_eq!(
+            lines[1],
+            format!(
+                "1 | … {}  #q {}b…",
+                "a ".repeat(9).trim_end(),
+                "b ".repeat(28)
+            )
+        );
+        assert_eq!(lines[2], format!("  | {}^", " ".repeat(21)));
+    }
 }
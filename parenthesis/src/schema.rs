@@ -0,0 +1,416 @@
+//! Validation of the structure of [`Value`]s.
+//!
+//! A [`Schema`] describes the expected shape of a value, so that a value can be
+//! checked with [`validate`] before it is taken apart by hand. Schemas can be
+//! built from their variants or written compactly with the [`schema!`] macro.
+//!
+//! [`schema!`]: crate::schema!
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::from_parens::TokenKind;
+use crate::{Symbol, Value};
+
+/// The expected structure of a [`Value`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Schema {
+    /// Any value.
+    Any,
+    /// The empty list `()`.
+    Nil,
+    /// A boolean.
+    Bool,
+    /// An integer.
+    Int,
+    /// A float.
+    Float,
+    /// A string.
+    Str,
+    /// A symbol.
+    Symbol,
+    /// Exactly the given value.
+    Literal(Value),
+    /// A list or sequence whose elements match the given schemas in order.
+    ///
+    /// Elements with an [`Schema::Optional`] schema may be missing at the end of the list.
+    List(Vec<Schema>),
+    /// A list or sequence whose elements all match the given schema.
+    ListOf(Box<Schema>),
+    /// A map from symbols to values that match the schema of their key.
+    ///
+    /// Entries with an [`Schema::Optional`] schema may be missing,
+    /// while keys that are not listed are rejected.
+    Map(Vec<(Symbol, Schema)>),
+    /// A value that matches the given schema, or that may be missing when it is
+    /// an element of a [`Schema::List`] or an entry of a [`Schema::Map`].
+    Optional(Box<Schema>),
+}
+
+impl Display for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schema::Any => f.write_str("any value"),
+            Schema::Nil => f.write_str("`()`"),
+            Schema::Bool => f.write_str("bool"),
+            Schema::Int => f.write_str("int"),
+            Schema::Float => f.write_str("float"),
+            Schema::Str => f.write_str("string"),
+            Schema::Symbol => f.write_str("symbol"),
+            Schema::Literal(value) => write!(f, "`{}`", value),
+            Schema::List(_) | Schema::ListOf(_) => f.write_str("list"),
+            Schema::Map(_) => f.write_str("map"),
+            Schema::Optional(schema) => schema.fmt(f),
+        }
+    }
+}
+
+/// Error when a value does not match a [`Schema`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("expected {expected}, found {found}{}", at_path(.path))]
+pub struct SchemaError {
+    /// The path to the value that does not match, as list indices and map
+    /// keys separated by `.`, or the empty string for the value itself.
+    pub path: String,
+    /// Description of what was expected.
+    pub expected: String,
+    /// Description of what was found.
+    pub found: String,
+}
+
+fn at_path(path: &str) -> String {
+    match path.is_empty() {
+        true => String::new(),
+        false => format!(" at `{}`", path),
+    }
+}
+
+/// Check that a value matches a schema.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, schema::{validate, Schema}, Symbol, Value};
+/// let schema = Schema::List(vec![
+///     Schema::Literal(Value::Symbol(Symbol::new("operation"))),
+///     Schema::Symbol,
+///     Schema::ListOf(Box::new(Schema::Int)),
+/// ]);
+///
+/// let value = from_str::<Value>("(operation add [1 2])").unwrap();
+/// assert!(validate(&value, &schema).is_ok());
+///
+/// let value = from_str::<Value>("(operation add [1 x])").unwrap();
+/// let error = validate(&value, &schema).unwrap_err();
+/// assert_eq!(error.to_string(), "expected int, found symbol at `2.1`");
+/// ```
+pub fn validate(value: &Value, schema: &Schema) -> Result<(), SchemaError> {
+    let mut path = String::new();
+    validate_at(value, schema, &mut path)
+}
+
+fn validate_at(value: &Value, schema: &Schema, path: &mut String) -> Result<(), SchemaError> {
+    let matches = match (schema, value) {
+        (Schema::Any, _) => true,
+        (Schema::Nil, Value::List(values)) => values.is_empty(),
+        (Schema::Bool, Value::Bool(_)) => true,
+        (Schema::Int, Value::Int(_)) => true,
+        (Schema::Float, Value::Float(_)) => true,
+        (Schema::Str, Value::String(_)) => true,
+        (Schema::Symbol, Value::Symbol(_)) => true,
+        (Schema::Literal(expected), value) => {
+            if expected != value {
+                return Err(error(path, schema, format!("`{}`", value)));
+            }
+
+            true
+        }
+        (Schema::List(schemas), Value::List(values) | Value::Seq(values)) => {
+            for (index, value) in values.iter().enumerate() {
+                let Some(schema) = schemas.get(index) else {
+                    return Err(error_in(path, index, "end of list", kind(value)));
+                };
+
+                with_segment(path, index, |path| validate_at(value, schema, path))?;
+            }
+
+            for (index, schema) in schemas.iter().enumerate().skip(values.len()) {
+                if !matches!(schema, Schema::Optional(_)) {
+                    return Err(error_in(path, index, schema, "end of list"));
+                }
+            }
+
+            true
+        }
+        (Schema::ListOf(schema), Value::List(values) | Value::Seq(values)) => {
+            for (index, value) in values.iter().enumerate() {
+                with_segment(path, index, |path| validate_at(value, schema, path))?;
+            }
+
+            true
+        }
+        (Schema::Map(entries), Value::Map(values)) => {
+            validate_map(values, entries, path)?;
+            true
+        }
+        (Schema::Optional(schema), value) => {
+            validate_at(value, schema, path)?;
+            true
+        }
+        _ => false,
+    };
+
+    match matches {
+        true => Ok(()),
+        false => Err(error(path, schema, kind(value))),
+    }
+}
+
+fn validate_map(
+    values: &[Value],
+    entries: &[(Symbol, Schema)],
+    path: &mut String,
+) -> Result<(), SchemaError> {
+    let mut seen = vec![false; entries.len()];
+
+    for (index, entry) in values.chunks(2).enumerate() {
+        let Value::Symbol(key) = &entry[0] else {
+            return Err(error_in(path, index * 2, "symbol key", kind(&entry[0])));
+        };
+
+        let Some(position) = entries.iter().position(|(name, _)| name == key) else {
+            let keys: Vec<_> = entries
+                .iter()
+                .map(|(name, _)| format!("`{}`", name))
+                .collect();
+            return Err(error(
+                path,
+                format!("one of the keys {}", keys.join(", ")),
+                format!("`{}`", key),
+            ));
+        };
+
+        if std::mem::replace(&mut seen[position], true) {
+            return Err(error(
+                path,
+                "each key at most once",
+                format!("duplicate key `{}`", key),
+            ));
+        }
+
+        let Some(value) = entry.get(1) else {
+            return Err(error_in(path, key, &entries[position].1, "end of map"));
+        };
+
+        with_segment(path, key, |path| {
+            validate_at(value, &entries[position].1, path)
+        })?;
+    }
+
+    for ((name, schema), seen) in entries.iter().zip(seen) {
+        if !seen && !matches!(schema, Schema::Optional(_)) {
+            return Err(error(path, format!("key `{}`", name), "end of map"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a function with a segment appended to the path.
+fn with_segment<T>(
+    path: &mut String,
+    segment: impl Display,
+    f: impl FnOnce(&mut String) -> T,
+) -> T {
+    let len = path.len();
+
+    if !path.is_empty() {
+        path.push('.');
+    }
+
+    path.push_str(&segment.to_string());
+    let result = f(path);
+    path.truncate(len);
+    result
+}
+
+fn error(path: &str, expected: impl Display, found: impl Display) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        expected: expected.to_string(),
+        found: found.to_string(),
+    }
+}
+
+fn error_in(
+    path: &mut String,
+    segment: impl Display,
+    expected: impl Display,
+    found: impl Display,
+) -> SchemaError {
+    with_segment(path, segment, |path| error(path, expected, found))
+}
+
+fn kind(value: &Value) -> TokenKind {
+    match value {
+        Value::List(_) => TokenKind::List,
+        Value::Seq(_) => TokenKind::Seq,
+        Value::Map(_) => TokenKind::Map,
+        Value::String(_) => TokenKind::String,
+        Value::Symbol(_) => TokenKind::Symbol,
+        Value::Bool(_) => TokenKind::Bool,
+        Value::Int(_) => TokenKind::Int,
+        Value::Float(_) => TokenKind::Float,
+    }
+}
+
+/// Construct a [`Schema`] from a compact description.
+///
+/// - `any`, `nil`, `bool`, `int`, `float`, `str` and `symbol` stand for the
+///   corresponding variants of [`Schema`].
+/// - `(a b c)` is a list whose elements match the schemas `a`, `b` and `c`.
+/// - `[a]` is a list whose elements all match the schema `a`.
+/// - `{key: a, "other-key": b}` is a map with the given keys. Keys that are
+///   not identifiers are written as string literals.
+/// - `?a` is an optional `a`.
+///
+/// Schemas that match literal values are constructed with [`Schema::Literal`].
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{schema, schema::Schema, Symbol};
+/// assert_eq!(
+///     schema!({ name: symbol, "input-types": [str], size: ?(int int) }),
+///     Schema::Map(vec![
+///         (Symbol::new("name"), Schema::Symbol),
+///         (Symbol::new("input-types"), Schema::ListOf(Box::new(Schema::Str))),
+///         (
+///             Symbol::new("size"),
+///             Schema::Optional(Box::new(Schema::List(vec![Schema::Int, Schema::Int])))
+///         ),
+///     ])
+/// );
+/// ```
+#[macro_export]
+macro_rules! schema {
+    (any) => { $crate::schema::Schema::Any };
+    (nil) => { $crate::schema::Schema::Nil };
+    (bool) => { $crate::schema::Schema::Bool };
+    (int) => { $crate::schema::Schema::Int };
+    (float) => { $crate::schema::Schema::Float };
+    (str) => { $crate::schema::Schema::Str };
+    (symbol) => { $crate::schema::Schema::Symbol };
+    (? $($schema:tt)+) => {
+        $crate::schema::Schema::Optional(::std::boxed::Box::new($crate::schema!($($schema)+)))
+    };
+    ([$($schema:tt)+]) => {
+        $crate::schema::Schema::ListOf(::std::boxed::Box::new($crate::schema!($($schema)+)))
+    };
+    (($($elements:tt)*)) => {
+        $crate::schema!(@list [] $($elements)*)
+    };
+    ({$($entries:tt)*}) => {
+        $crate::schema!(@map [] $($entries)*)
+    };
+
+    // The elements of a list are single token trees, possibly preceded by `?`.
+    (@list [$($acc:expr,)*]) => {
+        $crate::schema::Schema::List(::std::vec![$($acc),*])
+    };
+    (@list [$($acc:expr,)*] ? $schema:tt $($rest:tt)*) => {
+        $crate::schema!(@list [$($acc,)* $crate::schema!(? $schema),] $($rest)*)
+    };
+    (@list [$($acc:expr,)*] $schema:tt $($rest:tt)*) => {
+        $crate::schema!(@list [$($acc,)* $crate::schema!($schema),] $($rest)*)
+    };
+
+    // The entries of a map are separated by commas.
+    (@map [$($acc:expr,)*] $(,)?) => {
+        $crate::schema::Schema::Map(::std::vec![$($acc),*])
+    };
+    (@map [$($acc:expr,)*] $key:tt : ? $schema:tt $(, $($rest:tt)*)?) => {
+        $crate::schema!(@map [$($acc,)* (
+            $crate::Symbol::new($crate::schema!(@key $key)),
+            $crate::schema!(? $schema)
+        ),] $($($rest)*)?)
+    };
+    (@map [$($acc:expr,)*] $key:tt : $schema:tt $(, $($rest:tt)*)?) => {
+        $crate::schema!(@map [$($acc,)* (
+            $crate::Symbol::new($crate::schema!(@key $key)),
+            $crate::schema!($schema)
+        ),] $($($rest)*)?)
+    };
+    (@key $key:ident) => { stringify!($key) };
+    (@key $key:literal) => { $key };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate, Schema};
+    use crate::{from_str, Symbol, Value};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("#t", schema!(bool))]
+    #[case("1", schema!(int))]
+    #[case("1.5", schema!(float))]
+    #[case(r#""a""#, schema!(str))]
+    #[case("a", schema!(symbol))]
+    #[case("()", schema!(nil))]
+    #[case("(a [1 2])", schema!(any))]
+    #[case("(a 1)", schema!((symbol int)))]
+    #[case("[a 1]", schema!((symbol int)))]
+    #[case("(a)", schema!((symbol ?int)))]
+    #[case("()", schema!([int]))]
+    #[case("[1 2 3]", schema!([int]))]
+    #[case("{a 1}", schema!({a: int, b: ?str}))]
+    #[case(r#"{b "x" a 1}"#, schema!({a: int, b: ?str}))]
+    #[case("((a 1) (b 2))", schema!([(symbol int)]))]
+    fn valid(#[case] text: &str, #[case] schema: Schema) {
+        let value = from_str::<Value>(text).unwrap();
+        assert_eq!(validate(&value, &schema), Ok(()));
+    }
+
+    #[rstest]
+    #[case("1", schema!(float), "expected float, found int")]
+    #[case("(1)", schema!(nil), "expected `()`, found list")]
+    #[case("(a 1)", schema!((symbol str)), "expected string, found int at `1`")]
+    #[case("(a)", schema!((symbol int)), "expected int, found end of list at `1`")]
+    #[case("(a 1 2)", schema!((symbol int)), "expected end of list, found int at `2`")]
+    #[case("{a 1}", schema!([int]), "expected list, found map")]
+    #[case("[1 a]", schema!([int]), "expected int, found symbol at `1`")]
+    #[case("{a x}", schema!({a: int}), "expected int, found symbol at `a`")]
+    #[case("{}", schema!({a: int}), "expected key `a`, found end of map")]
+    #[case("{a}", schema!({a: int}), "expected int, found end of map at `a`")]
+    #[case("{b 1}", schema!({a: int, c: int}), "expected one of the keys `a`, `c`, found `b`")]
+    #[case("{a 1 a 2}", schema!({a: int}), "expected each key at most once, found duplicate key `a`")]
+    #[case(r#"{"a" 1}"#, schema!({a: int}), "expected symbol key, found string at `0`")]
+    #[case("(x ((a 1) (b c)))", schema!((symbol [(symbol int)])), "expected int, found symbol at `1.1.1`")]
+    #[case(r#"{"in-types" [a]}"#, schema!({"in-types": [str]}), "expected symbol key, found string at `0`")]
+    #[case("{in-types [a]}", schema!({"in-types": [str]}), "expected string, found symbol at `in-types.0`")]
+    fn invalid(#[case] text: &str, #[case] schema: Schema, #[case] message: &str) {
+        let value = from_str::<Value>(text).unwrap();
+        let error = validate(&value, &schema).unwrap_err();
+        assert_eq!(error.to_string(), message);
+    }
+
+    #[test]
+    fn literal() {
+        let schema = Schema::List(vec![
+            Schema::Literal(Value::Symbol(Symbol::new("operation"))),
+            Schema::Any,
+        ]);
+
+        let value = from_str::<Value>("(operation 1)").unwrap();
+        assert_eq!(validate(&value, &schema), Ok(()));
+
+        let value = from_str::<Value>("(other 1)").unwrap();
+        let error = validate(&value, &schema).unwrap_err();
+        assert_eq!(error.path, "0");
+        assert_eq!(
+            error.to_string(),
+            "expected `operation`, found `other` at `0`"
+        );
+    }
+}
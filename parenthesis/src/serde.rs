@@ -0,0 +1,977 @@
+//! Conversion between [`Value`]s and types that implement serde's traits.
+//!
+//! Since s-expressions do not cleanly map onto the serde data model, the
+//! conversion follows the conventions of the derive macros where possible:
+//!
+//! - Sequences are written as `[...]` and tuples as `(...)`.
+//! - Maps are written as `{key value ...}`.
+//! - Structs are written as lists of `(field value)` lists.
+//! - Newtype structs are written as their inner value.
+//! - Unit variants are written as a symbol and other variants as a list that
+//!   starts with the name of the variant, followed by the fields as above.
+//! - `None` and `()` are written as `()`, and `Some(value)` as `(value)`.
+//! - Characters are written as strings and bytes as sequences of integers.
+use std::fmt::Display;
+
+use ::serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+use ::serde::ser::{self, Serialize};
+use thiserror::Error;
+
+use crate::{Symbol, Value};
+
+/// Convert a value into a [`Value`].
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, serde::to_value, Value};
+/// let value = to_value(&(1, vec!["a", "b"])).unwrap();
+/// assert_eq!(value, from_str::<Value>(r#"(1 ["a" "b"])"#).unwrap());
+/// ```
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, SerializeError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Convert a [`Value`] into a value of type `T`.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, serde::from_value, Value};
+/// let value = from_str::<Value>(r#"(1 ["a" "b"])"#).unwrap();
+/// let (int, strings): (u8, Vec<String>) = from_value(value).unwrap();
+/// assert_eq!(int, 1);
+/// assert_eq!(strings, ["a", "b"]);
+/// ```
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, DeserializeError> {
+    T::deserialize(value)
+}
+
+/// Error while converting a value into a [`Value`].
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct SerializeError {
+    message: String,
+}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Error while converting a [`Value`] into a value.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct DeserializeError {
+    message: String,
+}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Serializer that produces a [`Value`].
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    type SerializeSeq = SerializeItems;
+    type SerializeTuple = SerializeItems;
+    type SerializeTupleStruct = SerializeItems;
+    type SerializeTupleVariant = SerializeItems;
+    type SerializeMap = SerializeItems;
+    type SerializeStruct = SerializeItems;
+    type SerializeStructVariant = SerializeItems;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, SerializeError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, SerializeError> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::Int(v)),
+            Err(_) => Err(ser::Error::custom(format!("integer `{}` out of range", v))),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, SerializeError> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, SerializeError> {
+        self.serialize_u128(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, SerializeError> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::Int(v)),
+            Err(_) => Err(ser::Error::custom(format!("integer `{}` out of range", v))),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, SerializeError> {
+        Ok(Value::from(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, SerializeError> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, SerializeError> {
+        Ok(Value::String(v.to_string().into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, SerializeError> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, SerializeError> {
+        Ok(Value::Seq(
+            v.iter().map(|byte| Value::Int(*byte as i64)).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value, SerializeError> {
+        Ok(Value::List(Vec::new()))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, SerializeError> {
+        Ok(Value::List(vec![value.serialize(self)?]))
+    }
+
+    fn serialize_unit(self) -> Result<Value, SerializeError> {
+        Ok(Value::List(Vec::new()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, SerializeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, SerializeError> {
+        Ok(Value::Symbol(Symbol::from_static(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, SerializeError> {
+        Ok(Value::List(vec![
+            Value::Symbol(Symbol::from_static(variant)),
+            value.serialize(self)?,
+        ]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::Seq, None, len.unwrap_or(0)))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::List, None, len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::List, None, len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::List, Some(variant), len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::Map, None, len.unwrap_or(0) * 2))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::List, None, len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, SerializeError> {
+        Ok(SerializeItems::new(Value::List, Some(variant), len))
+    }
+}
+
+/// Serializer for the elements of a list, sequence or map.
+struct SerializeItems {
+    constructor: fn(Vec<Value>) -> Value,
+    items: Vec<Value>,
+}
+
+impl SerializeItems {
+    /// Create a new serializer that starts with the name of the variant, if any.
+    fn new(
+        constructor: fn(Vec<Value>) -> Value,
+        variant: Option<&'static str>,
+        len: usize,
+    ) -> Self {
+        let mut items = Vec::with_capacity(len + 1);
+        items.extend(variant.map(|variant| Value::Symbol(Symbol::from_static(variant))));
+        Self { constructor, items }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn push_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.items.push(Value::List(vec![
+            Value::Symbol(Symbol::from_static(key)),
+            value.serialize(ValueSerializer)?,
+        ]));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerializeError> {
+        Ok((self.constructor)(self.items))
+    }
+}
+
+impl ser::SerializeSeq for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.push(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeItems {
+    type Ok = Value;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerializeError> {
+        self.finish()
+    }
+}
+
+impl Value {
+    /// Describe the value for error messages of serde.
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::List(_) => Unexpected::Other("list"),
+            Value::Seq(_) => Unexpected::Seq,
+            Value::Map(_) => Unexpected::Map,
+            Value::String(string) => Unexpected::Str(string),
+            Value::Symbol(_) => Unexpected::Other("symbol"),
+            Value::Bool(bool) => Unexpected::Bool(*bool),
+            Value::Int(int) => Unexpected::Signed(*int),
+            Value::Float(float) => Unexpected::Float(float.0),
+        }
+    }
+
+    fn invalid_type(&self, expected: &dyn de::Expected) -> DeserializeError {
+        de::Error::invalid_type(self.unexpected(), expected)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::List(items) | Value::Seq(items) => visit_seq(items, visitor),
+            Value::Map(items) => visitor.visit_map(MapDeserializer::new(items)),
+            Value::String(string) => visitor.visit_string(string.into()),
+            Value::Symbol(symbol) => visitor.visit_string(symbol.into()),
+            Value::Bool(bool) => visitor.visit_bool(bool),
+            Value::Int(int) => visitor.visit_i64(int),
+            Value::Float(float) => visitor.visit_f64(float.0),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self {
+            Value::String(string) => {
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(self.invalid_type(&visitor)),
+                }
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Seq(items) => {
+                let bytes = items
+                    .iter()
+                    .map(|item| match item {
+                        Value::Int(int) => u8::try_from(*int)
+                            .map_err(|_| de::Error::invalid_value(item.unexpected(), &"a byte")),
+                        _ => Err(item.invalid_type(&"a byte")),
+                    })
+                    .collect::<Result<Vec<u8>, _>>()?;
+                visitor.visit_byte_buf(bytes)
+            }
+            Value::String(string) => visitor.visit_byte_buf(string.as_bytes().to_vec()),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::List(items) if items.is_empty() => visitor.visit_none(),
+            Value::List(items) if items.len() == 1 => {
+                visitor.visit_some(items.into_iter().next().unwrap())
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::List(items) if items.is_empty() => visitor.visit_unit(),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::List(items) | Value::Seq(items) => visit_seq(items, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Map(items) => visitor.visit_map(MapDeserializer::new(items)),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::List(items) => visit_fields(items, visitor),
+            Value::Map(items) => visitor.visit_map(MapDeserializer::new(items)),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Symbol(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                fields: None,
+            }),
+            Value::List(items) => {
+                let mut items = items.into_iter();
+                match items.next() {
+                    Some(Value::Symbol(variant)) => visitor.visit_enum(EnumDeserializer {
+                        variant,
+                        fields: Some(items.collect()),
+                    }),
+                    _ => Err(de::Error::invalid_value(
+                        Unexpected::Other("list"),
+                        &"a list that starts with the name of a variant",
+                    )),
+                }
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 str string identifier
+    }
+}
+
+impl IntoDeserializer<'_, DeserializeError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Visit the items of a list or sequence, checking that all items are consumed.
+fn visit_seq<'de, V: Visitor<'de>>(
+    items: Vec<Value>,
+    visitor: V,
+) -> Result<V::Value, DeserializeError> {
+    let len = items.len();
+    let mut deserializer = SeqDeserializer {
+        items: items.into_iter(),
+    };
+    let value = visitor.visit_seq(&mut deserializer)?;
+
+    match deserializer.items.len() {
+        0 => Ok(value),
+        remaining => Err(de::Error::invalid_length(
+            len,
+            &format!("{} elements", len - remaining).as_str(),
+        )),
+    }
+}
+
+/// Visit a list of `(field value)` lists as a map from field names to values.
+fn visit_fields<'de, V: Visitor<'de>>(
+    items: Vec<Value>,
+    visitor: V,
+) -> Result<V::Value, DeserializeError> {
+    let items = items
+        .into_iter()
+        .map(|item| match item {
+            Value::List(pair) if pair.len() == 2 && matches!(pair[0], Value::Symbol(_)) => Ok(pair),
+            _ => Err(de::Error::invalid_value(
+                item.unexpected(),
+                &"a `(field value)` list",
+            )),
+        })
+        .collect::<Result<Vec<_>, DeserializeError>>()?;
+
+    visitor.visit_map(MapDeserializer::new(items.into_iter().flatten().collect()))
+}
+
+struct SeqDeserializer {
+    items: std::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.items
+            .next()
+            .map(|item| seed.deserialize(item))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// Deserializer for a map that is given as alternating keys and values.
+struct MapDeserializer {
+    items: std::vec::IntoIter<Value>,
+    value: Option<Value>,
+}
+
+impl MapDeserializer {
+    fn new(items: Vec<Value>) -> Self {
+        Self {
+            items: items.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some(key) = self.items.next() else {
+            return Ok(None);
+        };
+
+        match self.items.next() {
+            Some(value) => self.value = Some(value),
+            None => return Err(de::Error::custom("map is missing a value for its last key")),
+        }
+
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len() / 2)
+    }
+}
+
+/// Deserializer for an enum variant together with its fields, if any.
+struct EnumDeserializer {
+    variant: Symbol,
+    fields: Option<Vec<Value>>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = DeserializeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(Value::Symbol(self.variant))?;
+        Ok((variant, VariantDeserializer(self.fields)))
+    }
+}
+
+/// Deserializer for the fields of an enum variant, which are absent for unit variants.
+struct VariantDeserializer(Option<Vec<Value>>);
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.0 {
+            None => Ok(()),
+            Some(_) => Err(de::Error::invalid_type(
+                Unexpected::Other("list"),
+                &"unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.0 {
+            Some(fields) if fields.len() == 1 => {
+                seed.deserialize(fields.into_iter().next().unwrap())
+            }
+            _ => Err(de::Error::invalid_type(
+                Unexpected::Other("variant"),
+                &"newtype variant with a single field",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(fields) => visit_seq(fields, visitor),
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &visitor)),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(fields) => visit_fields(fields, visitor),
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &visitor)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    use ::serde::de::{self, MapAccess, SeqAccess, Visitor};
+    use ::serde::ser::{SerializeStruct, SerializeTupleVariant};
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{from_value, to_value};
+    use crate::{from_str, Value};
+
+    // The serde derive macros are not available here, so the following
+    // implementations are written by hand as the derive macros would generate them.
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        name: String,
+        retries: Option<u32>,
+        tags: Vec<String>,
+        shape: Shape,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Rect(f64, f64),
+    }
+
+    impl Serialize for Config {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Config", 4)?;
+            state.serialize_field("name", &self.name)?;
+            state.serialize_field("retries", &self.retries)?;
+            state.serialize_field("tags", &self.tags)?;
+            state.serialize_field("shape", &self.shape)?;
+            state.end()
+        }
+    }
+
+    impl Serialize for Shape {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Shape::Point => serializer.serialize_unit_variant("Shape", 0, "point"),
+                Shape::Rect(width, height) => {
+                    let mut state = serializer.serialize_tuple_variant("Shape", 1, "rect", 2)?;
+                    state.serialize_field(width)?;
+                    state.serialize_field(height)?;
+                    state.end()
+                }
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Config {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ConfigVisitor;
+
+            impl<'de> Visitor<'de> for ConfigVisitor {
+                type Value = Config;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("struct Config")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Config, A::Error> {
+                    let mut name = None;
+                    let mut retries = None;
+                    let mut tags = None;
+                    let mut shape = None;
+
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            "name" => name = Some(map.next_value()?),
+                            "retries" => retries = Some(map.next_value()?),
+                            "tags" => tags = Some(map.next_value()?),
+                            "shape" => shape = Some(map.next_value()?),
+                            _ => return Err(de::Error::unknown_field(&key, &[])),
+                        }
+                    }
+
+                    Ok(Config {
+                        name: name.ok_or_else(|| de::Error::missing_field("name"))?,
+                        retries: retries.unwrap_or_default(),
+                        tags: tags.ok_or_else(|| de::Error::missing_field("tags"))?,
+                        shape: shape.ok_or_else(|| de::Error::missing_field("shape"))?,
+                    })
+                }
+            }
+
+            deserializer.deserialize_struct(
+                "Config",
+                &["name", "retries", "tags", "shape"],
+                ConfigVisitor,
+            )
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Shape {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ShapeVisitor;
+
+            impl<'de> Visitor<'de> for ShapeVisitor {
+                type Value = Shape;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("enum Shape")
+                }
+
+                fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Shape, A::Error> {
+                    use de::VariantAccess;
+                    let (variant, access): (String, _) = data.variant()?;
+
+                    match variant.as_str() {
+                        "point" => access.unit_variant().map(|()| Shape::Point),
+                        "rect" => access.tuple_variant(2, RectVisitor),
+                        _ => Err(de::Error::unknown_variant(&variant, &["point", "rect"])),
+                    }
+                }
+            }
+
+            struct RectVisitor;
+
+            impl<'de> Visitor<'de> for RectVisitor {
+                type Value = Shape;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("tuple variant Shape::Rect")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Shape, A::Error> {
+                    let width = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                    let height = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                    Ok(Shape::Rect(width, height))
+                }
+            }
+
+            deserializer.deserialize_enum("Shape", &["point", "rect"], ShapeVisitor)
+        }
+    }
+
+    #[test]
+    fn round_trip_struct() {
+        let config = Config {
+            name: "server".into(),
+            retries: Some(3),
+            tags: vec!["a".into(), "b".into()],
+            shape: Shape::Rect(1.5, 2.0),
+        };
+
+        let value = to_value(&config).unwrap();
+        let expected: Value =
+            from_str(r#"((name "server") (retries (3)) (tags ["a" "b"]) (shape (rect 1.5 2.0)))"#)
+                .unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(from_value::<Config>(value).unwrap(), config);
+
+        let config = Config {
+            retries: None,
+            shape: Shape::Point,
+            ..config
+        };
+        let value = to_value(&config).unwrap();
+        let expected: Value =
+            from_str(r#"((name "server") (retries ()) (tags ["a" "b"]) (shape point))"#).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(from_value::<Config>(value).unwrap(), config);
+    }
+
+    #[test]
+    fn round_trip_std() {
+        let map = BTreeMap::from([(1, 'x'), (2, 'y')]);
+        let value = to_value(&map).unwrap();
+        assert_eq!(value, from_str::<Value>(r#"{1 "x" 2 "y"}"#).unwrap());
+        assert_eq!(from_value::<BTreeMap<i32, char>>(value).unwrap(), map);
+
+        let tuple = ((), true, -4i8, 0.5f32);
+        let value = to_value(&tuple).unwrap();
+        assert_eq!(value, from_str::<Value>("(() #t -4 0.5)").unwrap());
+        assert_eq!(from_value::<((), bool, i8, f32)>(value).unwrap(), tuple);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(to_value(&u64::MAX).is_err());
+        assert!(from_value::<u8>(Value::Int(256)).is_err());
+        assert!(from_value::<(i64, i64)>(from_str("(1 2 3)").unwrap()).is_err());
+        assert!(from_value::<Config>(from_str(r#"((name "a" "b"))"#).unwrap()).is_err());
+        assert!(from_value::<Shape>(from_str("(circle 1.0)").unwrap()).is_err());
+    }
+}
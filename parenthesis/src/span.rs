@@ -0,0 +1,260 @@
+//! Spans of parsed values and their conversion to lines and columns.
+use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
+
+use crate::from_parens::{FromParens, InputStream, ParseError};
+use crate::read::{ReadError, Span};
+use crate::to_parens::{OutputStream, ToParens};
+
+/// Converts byte offsets within a source string to line and column numbers.
+///
+/// The start offsets of all lines are computed once on construction,
+/// so that each conversion only takes logarithmic time.
+/// Lines and columns are counted from zero and columns are measured in bytes.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::span::SpanConverter;
+/// let converter = SpanConverter::new("(a\n  b)");
+/// assert_eq!(converter.offset_to_line_col(5), (1, 2));
+/// assert_eq!(converter.span_to_range(&(1..6)), ((0, 1), (1, 3)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpanConverter<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SpanConverter<'a> {
+    /// Create a new [`SpanConverter`] for the given source string.
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The source string.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Convert a byte offset to a line and column.
+    ///
+    /// Offsets past the end of the source are clamped to the end.
+    pub fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        (line, offset - self.line_starts[line])
+    }
+
+    /// The text of a line without its line terminator.
+    ///
+    /// Lines past the end of the source are empty.
+    pub fn line(&self, line: usize) -> &'a str {
+        let Some(start) = self.line_starts.get(line) else {
+            return "";
+        };
+
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.source.len(), |next| next - 1);
+
+        self.source[*start..end].trim_end_matches('\r')
+    }
+
+    /// Convert a span to the lines and columns of its start and end.
+    pub fn span_to_range(&self, span: &Span) -> ((usize, usize), (usize, usize)) {
+        (
+            self.offset_to_line_col(span.start),
+            self.offset_to_line_col(span.end),
+        )
+    }
+
+    /// Display a [`ReadError`] prefixed with the line and column where it occured.
+    ///
+    /// The line and column are counted from one, as is common for error messages.
+    pub fn display_error<'b>(&'b self, error: &'b ReadError) -> impl Display + 'b {
+        DisplayError {
+            converter: self,
+            error,
+        }
+    }
+}
+
+struct DisplayError<'a, 'b> {
+    converter: &'b SpanConverter<'a>,
+    error: &'b ReadError,
+}
+
+impl Display for DisplayError<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let offset = match self.error.span() {
+            Some(span) => span.start,
+            None => self.converter.source.len(),
+        };
+
+        let (line, col) = self.converter.offset_to_line_col(offset);
+        write!(f, "{}:{}: {}", line + 1, col + 1, self.error)
+    }
+}
+
+/// A value together with the span of the input it was parsed from.
+///
+/// The span covers all tokens consumed while parsing the value. When used as
+/// a named field in a derived struct, the span covers the `(name value)` list
+/// instead. Streams without source positions, such as slices of [`Value`]s,
+/// produce empty spans. Printing a [`Spanned`] value only prints the inner value.
+///
+/// # Examples
+///
+/// ```
+/// # use parenthesis::{from_str, span::Spanned, Symbol};
+/// let (_, name): (Symbol, Spanned<Symbol>) = from_str("define name").unwrap();
+/// assert_eq!(name.value, Symbol::from("name"));
+/// assert_eq!(name.span, 7..11);
+/// ```
+///
+/// [`Value`]: crate::Value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    /// The value.
+    pub value: T,
+    /// The span of the value.
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    /// Create a new [`Spanned`] value.
+    pub fn new(value: T, span: Span) -> Self {
+        Self { value, span }
+    }
+
+    /// Discard the span and return the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<I, T> FromParens<I> for Spanned<T>
+where
+    I: InputStream,
+    T: FromParens<I>,
+{
+    fn from_parens(stream: &mut I) -> Result<Self, ParseError<I::Span>> {
+        // Advance over the first token to find out where it starts.
+        let checkpoint = stream.checkpoint();
+        let start = stream.next().and_then(|_| I::byte_range(&stream.span()));
+        stream.restore(checkpoint);
+
+        let value = T::from_parens(stream)?;
+        let end = I::byte_range(&stream.span());
+
+        let span = match (start, end) {
+            (Some(start), Some(end)) => start.start..end.end.max(start.end),
+            (None, Some(end)) => end,
+            (_, None) => Span::default(),
+        };
+
+        Ok(Self { value, span })
+    }
+}
+
+impl<O, T> ToParens<O> for Spanned<T>
+where
+    O: OutputStream,
+    T: ToParens<O>,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        self.value.to_parens(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SpanConverter, Spanned};
+    use crate::{from_str, to_values, FromParens, Symbol, Value};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, (0, 0))]
+    #[case(3, (0, 3))]
+    #[case(4, (1, 0))]
+    #[case(5, (2, 0))]
+    #[case(8, (2, 3))]
+    #[case(100, (2, 3))]
+    fn offset_to_line_col(#[case] offset: usize, #[case] expected: (usize, usize)) {
+        let converter = SpanConverter::new("abc\n\nxyz");
+        assert_eq!(converter.offset_to_line_col(offset), expected);
+    }
+
+    #[test]
+    fn empty_source() {
+        let converter = SpanConverter::new("");
+        assert_eq!(converter.span_to_range(&(0..0)), ((0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn display_error() {
+        let source = "(a\n  b))";
+        let converter = SpanConverter::new(source);
+
+        let error = from_str::<Vec<Value>>(source).unwrap_err();
+        assert_eq!(
+            converter.display_error(&error).to_string(),
+            "2:5: unexpected closing delimiter"
+        );
+
+        let source = "(a\n  b";
+        let converter = SpanConverter::new(source);
+
+        let error = from_str::<Vec<Value>>(source).unwrap_err();
+        assert_eq!(
+            converter.display_error(&error).to_string(),
+            "2:4: unexpected end of file"
+        );
+    }
+
+    #[rstest]
+    #[case("x", 0..1)]
+    #[case("  (a (b c))  ", 2..11)]
+    #[case("(a) (b)", 0..7)]
+    fn spanned_values(#[case] source: &str, #[case] expected: std::ops::Range<usize>) {
+        let spanned = from_str::<Spanned<Vec<Value>>>(source).unwrap();
+        assert_eq!(spanned.span, expected);
+    }
+
+    #[test]
+    fn spanned_write() {
+        let values: Vec<Value> = from_str("a (b)").unwrap();
+        let spanned = from_str::<(Spanned<Symbol>, Spanned<Value>)>("a (b)").unwrap();
+        assert_eq!(spanned.1.span, 2..5);
+        assert_eq!(to_values(&spanned), values);
+
+        let mut stream = values.as_slice();
+        let symbol = Spanned::<Symbol>::from_parens(&mut stream).unwrap();
+        assert_eq!(symbol.value, Symbol::from("a"));
+        assert_eq!(symbol.span, 0..0);
+    }
+}
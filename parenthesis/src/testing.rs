@@ -0,0 +1,135 @@
+//! Helpers for testing that types round trip through s-expressions.
+//!
+//! [`Value`] implements proptest's [`Arbitrary`], so random values can be generated
+//! with `any::<Value>()`. The size of the generated values can be configured with
+//! [`ValueParameters`]. Generated symbols and strings may contain any characters,
+//! since the printer escapes them so that they are read back unchanged.
+//!
+//! # Examples
+//!
+//! ```
+//! # use parenthesis::testing::{assert_roundtrip, ValueParameters};
+//! # use parenthesis::Value;
+//! use proptest::prelude::*;
+//!
+//! let parameters = ValueParameters {
+//!     depth: 16,
+//!     ..ValueParameters::default()
+//! };
+//!
+//! proptest!(|(value in any_with::<Value>(parameters))| {
+//!     assert_roundtrip(&value);
+//! });
+//! ```
+//!
+//! [`Value`]: crate::Value
+//! [`Arbitrary`]: proptest::arbitrary::Arbitrary
+use std::fmt::Debug;
+
+use crate::pretty::Pretty;
+use crate::read::ReaderStream;
+use crate::{from_str, to_string_compact, to_string_pretty, FromParens, ToParens};
+
+/// The widths at which [`assert_roundtrip`] prints values with [`to_string_pretty`].
+///
+/// A width of zero breaks every list that can be broken.
+pub const ROUNDTRIP_WIDTHS: [usize; 4] = [0, 20, 80, 200];
+
+/// Parameters for generating arbitrary [`Value`]s.
+///
+/// These are passed to [`Strategy::prop_recursive`], see there for details.
+///
+/// [`Value`]: crate::Value
+/// [`Strategy::prop_recursive`]: proptest::strategy::Strategy::prop_recursive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueParameters {
+    /// The maximum nesting depth of lists, sequences and maps.
+    pub depth: u32,
+    /// The desired total number of atoms in a value.
+    pub size: u32,
+    /// The maximum number of elements in a list, sequence or map.
+    pub branch: u32,
+}
+
+impl Default for ValueParameters {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            size: 256,
+            branch: 10,
+        }
+    }
+}
+
+/// Assert that a value is read back unchanged after it has been printed.
+///
+/// The value is printed with [`to_string_compact`] and with [`to_string_pretty`] at
+/// each of the [`ROUNDTRIP_WIDTHS`], and each output is read again with [`from_str`].
+///
+/// # Panics
+///
+/// Panics with the printed text if reading fails or the result differs from the value.
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: for<'a> FromParens<ReaderStream<'a>> + PartialEq + Debug,
+    for<'a> &'a T: ToParens<Pretty>,
+{
+    let outputs = std::iter::once(to_string_compact(value)).chain(
+        ROUNDTRIP_WIDTHS
+            .iter()
+            .map(|width| to_string_pretty(value, *width)),
+    );
+
+    for text in outputs {
+        match from_str::<T>(&text) {
+            Ok(parsed) => assert_eq!(&parsed, value, "round trip changed value via {:?}", text),
+            Err(error) => panic!("failed to read {:?}: {}", text, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{assert_roundtrip, ValueParameters};
+    use crate::{Symbol, Value};
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    /// The nesting depth of a value, where atoms have depth zero.
+    fn depth(value: &Value) -> u32 {
+        match value {
+            Value::List(values) | Value::Seq(values) | Value::Map(values) => {
+                1 + values.iter().map(depth).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_deep_values(
+            value in any_with::<Value>(ValueParameters { depth: 32, size: 64, branch: 2 })
+        ) {
+            assert_roundtrip(&value);
+        }
+
+        #[test]
+        fn roundtrip_collections(map: BTreeMap<Symbol, Vec<i64>>) {
+            assert_roundtrip(&map);
+        }
+
+        #[test]
+        fn parameters_bound_depth(
+            value in any_with::<Value>(ValueParameters { depth: 2, ..ValueParameters::default() })
+        ) {
+            prop_assert!(depth(&value) <= 2);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "round trip changed value")]
+    fn roundtrip_mismatch() {
+        // Nested vectors are written in place, so an empty inner vector is lost.
+        assert_roundtrip(&vec![Vec::<i64>::new()]);
+    }
+}
@@ -2,10 +2,21 @@
 use smol_str::SmolStr;
 use std::{
     borrow::{Borrow, Cow},
-    convert::Infallible,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32,
+        NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    rc::Rc,
+    sync::Arc,
 };
 
-use crate::{Symbol, Value};
+use thiserror::Error;
+
+use crate::{Bounded, Either, OrDefault, Rest, Symbol, Value};
 
 /// Output stream that s-expressions can be written to.
 pub trait OutputStream {
@@ -17,6 +28,16 @@ pub trait OutputStream {
     where
         F: FnOnce(&mut Self) -> Result<R, Self::Error>;
 
+    /// Write a sequence to the output stream, whose elements are written by the given function.
+    fn seq<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>;
+
+    /// Write a map to the output stream, whose elements are written by the given function.
+    fn map<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>;
+
     /// Write a string to the output stream.
     fn string(&mut self, string: impl AsRef<str>) -> Result<(), Self::Error>;
 
@@ -31,6 +52,35 @@ pub trait OutputStream {
 
     /// Write a float to the output stream.
     fn float(&mut self, float: f64) -> Result<(), Self::Error>;
+
+    /// Write a comment to the output stream, without the leading `;`.
+    ///
+    /// Output streams that can not represent comments ignore them,
+    /// which is what the default implementation does.
+    fn comment(&mut self, comment: impl AsRef<str>) -> Result<(), Self::Error> {
+        let _ = comment;
+        Ok(())
+    }
+
+    /// Create an error for a value that can not be written to the output stream,
+    /// such as an integer that exceeds the range of s-expression integers.
+    fn custom_error(message: impl Display) -> Self::Error;
+}
+
+/// Error for a value that can not be written by the output streams of this crate.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message}")]
+pub struct WriteError {
+    message: String,
+}
+
+impl WriteError {
+    /// Create a new error with the given message.
+    pub fn new(message: impl Display) -> Self {
+        Self {
+            message: message.to_string(),
+        }
+    }
 }
 
 /// Types that can be converted to an s-expression.
@@ -42,6 +92,19 @@ where
     fn to_parens(&self, output: &mut O) -> Result<(), O::Error>;
 }
 
+/// Types that are written as a list that starts with a tag symbol.
+///
+/// The derive macro for [`ToParens`] implements this trait for structs with
+/// `#[sexpr(tag = "...")]`. A named field with `#[sexpr(tagged)]` uses this trait
+/// to print its value, so that the name of the field takes the place of the tag.
+pub trait ToParensTagged<O>: ToParens<O>
+where
+    O: OutputStream,
+{
+    /// Print the contents of the list that follow the tag.
+    fn to_parens_untagged(&self, output: &mut O) -> Result<(), O::Error>;
+}
+
 impl<O> ToParens<O> for Value
 where
     O: OutputStream,
@@ -49,6 +112,8 @@ where
     fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
         match self {
             Value::List(list) => output.list(|output| list.to_parens(output)),
+            Value::Seq(seq) => output.seq(|output| seq.to_parens(output)),
+            Value::Map(map) => output.map(|output| map.to_parens(output)),
             Value::String(string) => output.string(string),
             Value::Symbol(symbol) => output.symbol(symbol),
             Value::Bool(bool) => output.bool(*bool),
@@ -78,6 +143,16 @@ where
     }
 }
 
+impl<O> ToParens<O> for str
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.string(self)
+    }
+}
+
 impl<O> ToParens<O> for Symbol
 where
     O: OutputStream,
@@ -102,6 +177,33 @@ where
     }
 }
 
+impl<O, V> ToParens<O> for VecDeque<V>
+where
+    O: OutputStream,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for value in self.iter() {
+            value.to_parens(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "smallvec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "smallvec")))]
+impl<O, A> ToParens<O> for smallvec::SmallVec<A>
+where
+    O: OutputStream,
+    A: smallvec::Array,
+    A::Item: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        self.as_slice().to_parens(output)
+    }
+}
+
 impl<O, V> ToParens<O> for [V]
 where
     O: OutputStream,
@@ -116,6 +218,326 @@ where
     }
 }
 
+impl<O, V, const N: usize> ToParens<O> for [V; N]
+where
+    O: OutputStream,
+    V: ToParens<O>,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        self.as_slice().to_parens(output)
+    }
+}
+
+/// Writes the entries of the map in insertion order.
+#[cfg(feature = "indexmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexmap")))]
+impl<O, K, V, S> ToParens<O> for indexmap::IndexMap<K, V, S>
+where
+    O: OutputStream,
+    K: ToParens<O>,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for (key, value) in self.iter() {
+            output.list(|output| {
+                key.to_parens(output)?;
+                value.to_parens(output)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the values of the set in order.
+#[cfg(feature = "indexmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexmap")))]
+impl<O, V, S> ToParens<O> for indexmap::IndexSet<V, S>
+where
+    O: OutputStream,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for value in self.iter() {
+            value.to_parens(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the values of the set in ascending order.
+impl<O, V> ToParens<O> for BTreeSet<V>
+where
+    O: OutputStream,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for value in self.iter() {
+            value.to_parens(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the values of the set in unspecified order.
+///
+/// See [`sorted`] for deterministic output.
+impl<O, V, S> ToParens<O> for HashSet<V, S>
+where
+    O: OutputStream,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for value in self.iter() {
+            value.to_parens(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write the values of a set ordered by their s-expression representation.
+///
+/// This gives deterministic output for sets with unspecified iteration order
+/// such as [`HashSet`]. It can be used with the `serialize_with` attribute
+/// of the derive macros.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use parenthesis::{to_values, ToParens, Value};
+/// # #[cfg(feature = "macros")]
+/// # pub fn main() {
+/// #[derive(ToParens)]
+/// struct Capabilities {
+///     #[sexpr(serialize_with = "parenthesis::to_parens::sorted")]
+///     names: HashSet<String>,
+/// }
+///
+/// let capabilities = Capabilities {
+///     names: HashSet::from(["b".to_string(), "c".to_string(), "a".to_string()]),
+/// };
+///
+/// assert_eq!(
+///     to_values(&capabilities),
+///     [Value::from("a"), Value::from("b"), Value::from("c")]
+/// );
+/// # }
+/// # #[cfg(not(feature = "macros"))]
+/// # pub fn main() {}
+/// ```
+pub fn sorted<'a, O, V>(
+    values: impl IntoIterator<Item = &'a V>,
+    output: &mut O,
+) -> Result<(), O::Error>
+where
+    O: OutputStream,
+    V: ToParens<ValueOutputStream> + 'a,
+{
+    let mut values: Vec<Vec<Value>> = values.into_iter().map(to_values).collect();
+    values.sort();
+
+    for value in values.into_iter().flatten() {
+        value.to_parens(output)?;
+    }
+
+    Ok(())
+}
+
+/// Maps are written as a sequence of lists which contain the key followed by the value.
+/// The entries are written in the order of the keys.
+impl<O, K, V> ToParens<O> for BTreeMap<K, V>
+where
+    O: OutputStream,
+    K: ToParens<O>,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for (key, value) in self.iter() {
+            output.list(|output| {
+                key.to_parens(output)?;
+                value.to_parens(output)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps are written as a sequence of lists which contain the key followed by the value.
+/// The order of the entries is unspecified.
+impl<O, K, V, S> ToParens<O> for HashMap<K, V, S>
+where
+    O: OutputStream,
+    K: ToParens<O>,
+    V: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        for (key, value) in self.iter() {
+            output.list(|output| {
+                key.to_parens(output)?;
+                value.to_parens(output)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<O> ToParens<O> for Rest
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        self.0.to_parens(output)
+    }
+}
+
+impl<O, T> ToParens<O> for OrDefault<T>
+where
+    O: OutputStream,
+    T: ToParens<O>,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        self.0.to_parens(output)
+    }
+}
+
+/// Options are written in place: `Some` writes the value and `None` writes nothing.
+impl<O, T> ToParens<O> for Option<T>
+where
+    O: OutputStream,
+    T: ToParens<O>,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        match self {
+            Some(value) => value.to_parens(output),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Write a value unless it is equal to its default value.
+///
+/// This can be used with the `serialize_with` attribute of the derive macros
+/// for a trailing [`OrDefault`] field, which then reads back the same value.
+pub fn skip_default<O, T>(value: &T, output: &mut O) -> Result<(), O::Error>
+where
+    O: OutputStream,
+    T: ToParens<O> + Default + PartialEq,
+{
+    if *value == T::default() {
+        return Ok(());
+    }
+
+    value.to_parens(output)
+}
+
+/// Write a value as the contents of a sequence `[...]`.
+///
+/// This is used by the `seq` attribute of the derive macros, so that a field
+/// such as a `Vec` is written as a single sequence instead of as values in place.
+pub fn seq<O, T>(value: &T, output: &mut O) -> Result<(), O::Error>
+where
+    O: OutputStream,
+    T: ToParens<O> + ?Sized,
+{
+    output.seq(|output| value.to_parens(output))
+}
+
+/// Write a collection of key value pairs as a map `{key value ...}`.
+///
+/// This is used by the `map` attribute of the derive macros, since maps such as
+/// [`BTreeMap`] are otherwise written as lists that contain a key and a value.
+pub fn map<'a, O, T, K, V>(value: &'a T, output: &mut O) -> Result<(), O::Error>
+where
+    O: OutputStream,
+    &'a T: IntoIterator<Item = (&'a K, &'a V)>,
+    K: ToParens<O> + 'a,
+    V: ToParens<O> + 'a,
+{
+    output.map(|output| {
+        for (key, value) in value {
+            key.to_parens(output)?;
+            value.to_parens(output)?;
+        }
+
+        Ok(())
+    })
+}
+
+impl<O, A, B> ToParens<O> for Either<A, B>
+where
+    O: OutputStream,
+    A: ToParens<O>,
+    B: ToParens<O>,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), O::Error> {
+        match self {
+            Either::Left(value) => value.to_parens(output),
+            Either::Right(value) => value.to_parens(output),
+        }
+    }
+}
+
+/// Writing `()` produces no tokens.
+impl<O> ToParens<O> for ()
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, _output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        Ok(())
+    }
+}
+
+/// Like `()`, writing a [`PhantomData`] produces no tokens, regardless of `T`.
+impl<O, T: ?Sized> ToParens<O> for PhantomData<T>
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, _output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        Ok(())
+    }
+}
+
+/// Implements [`ToParens`] for a tuple whose elements are written positionally in order.
+macro_rules! impl_to_parens_tuple {
+    ($($index:tt $name:ident),+) => {
+        impl<O, $($name),+> ToParens<O> for ($($name,)+)
+        where
+            O: OutputStream,
+            $($name: ToParens<O>),+
+        {
+            fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+                $(self.$index.to_parens(output)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_to_parens_tuple!(0 A);
+impl_to_parens_tuple!(0 A, 1 B);
+impl_to_parens_tuple!(0 A, 1 B, 2 C);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K, 10 L);
+impl_to_parens_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 J, 9 K, 10 L, 11 M);
+
 impl<O> ToParens<O> for f64
 where
     O: OutputStream,
@@ -126,6 +548,138 @@ where
     }
 }
 
+/// Writes the `f64` with the shortest decimal representation that reads back
+/// as the same `f32`, instead of the exact but noisy widened value.
+impl<O> ToParens<O> for f32
+where
+    O: OutputStream,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        let float = match self.to_string().parse::<f64>() {
+            Ok(float) => float,
+            Err(_) => f64::from(*self),
+        };
+
+        output.float(float)
+    }
+}
+
+impl<O> ToParens<O> for bool
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.bool(*self)
+    }
+}
+
+impl<O> ToParens<O> for char
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.string(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+/// Implements [`ToParens`] for a network address type by writing it as a string.
+macro_rules! impl_to_parens_addr {
+    ($($addr:ty),*) => {
+        $(
+            impl<O> ToParens<O> for $addr
+            where
+                O: OutputStream,
+            {
+                #[inline]
+                fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+                    output.string(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_to_parens_addr!(
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+    SocketAddrV4,
+    SocketAddrV6
+);
+
+/// Writes a UUID as a string in lowercase hyphenated form.
+#[cfg(feature = "uuid")]
+#[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+impl<O> ToParens<O> for uuid::Uuid
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.string(
+            self.hyphenated()
+                .encode_lower(&mut uuid::Uuid::encode_buffer()),
+        )
+    }
+}
+
+/// Writes a timestamp as an RFC 3339 string in UTC with a `Z` suffix.
+///
+/// Sub-second digits are written in groups of three as far as needed,
+/// so that the timestamp reads back without loss, including leap seconds.
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl<O> ToParens<O> for chrono::DateTime<chrono::Utc>
+where
+    O: OutputStream,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.string(self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+    }
+}
+
+/// Writes a date as an RFC 3339 full-date string such as `2024-02-29`.
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl<O> ToParens<O> for chrono::NaiveDate
+where
+    O: OutputStream,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.string(self.format("%Y-%m-%d").to_string())
+    }
+}
+
+/// Writes a timestamp as an RFC 3339 string with its offset.
+///
+/// Only as many sub-second digits are written as needed to read back the
+/// same timestamp.
+///
+/// Writing a timestamp that can not be represented in RFC 3339, i.e. whose year is
+/// outside of `0..=9999` or whose offset has non-zero seconds, fails with an error
+/// created by [`OutputStream::custom_error`].
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl<O> ToParens<O> for time::OffsetDateTime
+where
+    O: OutputStream,
+{
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        let string = self
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(|error| {
+                O::custom_error(format_args!(
+                    "timestamp `{}` can not be represented in RFC 3339: {}",
+                    self, error
+                ))
+            })?;
+        output.string(string)
+    }
+}
+
 impl<O> ToParens<O> for i64
 where
     O: OutputStream,
@@ -136,9 +690,99 @@ where
     }
 }
 
+/// Implements [`ToParens`] for an integer type that can be losslessly widened to `i64`.
+macro_rules! impl_to_parens_int {
+    ($($int:ty),*) => {
+        $(
+            impl<O> ToParens<O> for $int
+            where
+                O: OutputStream,
+            {
+                #[inline]
+                fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+                    output.int(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_parens_int!(i8, i16, i32, u8, u16, u32);
+
+/// Implements [`ToParens`] for an integer type whose values may exceed the range of `i64`.
+///
+/// Writing a value that does not fit into an `i64` fails with an error created by
+/// [`OutputStream::custom_error`], since s-expression integers are limited to
+/// 64bit signed integers.
+macro_rules! impl_to_parens_int_checked {
+    ($($int:ty),*) => {
+        $(
+            impl<O> ToParens<O> for $int
+            where
+                O: OutputStream,
+            {
+                #[inline]
+                fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+                    let Ok(int) = i64::try_from(*self) else {
+                        return Err(O::custom_error(format_args!(
+                            "integer `{}` of type `{}` exceeds the range of s-expression integers",
+                            self,
+                            stringify!($int)
+                        )));
+                    };
+
+                    output.int(int)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_parens_int_checked!(isize, u64, usize);
+
+/// Implements [`ToParens`] for a nonzero integer type via its underlying integer type.
+macro_rules! impl_to_parens_nonzero {
+    ($($nonzero:ty),*) => {
+        $(
+            impl<O> ToParens<O> for $nonzero
+            where
+                O: OutputStream,
+            {
+                #[inline]
+                fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+                    self.get().to_parens(output)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_parens_nonzero!(
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroIsize,
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroUsize
+);
+
+impl<O, const MIN: i64, const MAX: i64> ToParens<O> for Bounded<MIN, MAX>
+where
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        output.int(self.get())
+    }
+}
+
 impl<O, T> ToParens<O> for &T
 where
-    T: ToParens<O>,
+    T: ToParens<O> + ?Sized,
     O: OutputStream,
 {
     #[inline]
@@ -147,9 +791,42 @@ where
     }
 }
 
+impl<O, T> ToParens<O> for Box<T>
+where
+    T: ToParens<O> + ?Sized,
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        T::to_parens(self, output)
+    }
+}
+
+impl<O, T> ToParens<O> for Rc<T>
+where
+    T: ToParens<O> + ?Sized,
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        T::to_parens(self, output)
+    }
+}
+
+impl<O, T> ToParens<O> for Arc<T>
+where
+    T: ToParens<O> + ?Sized,
+    O: OutputStream,
+{
+    #[inline]
+    fn to_parens(&self, output: &mut O) -> Result<(), <O as OutputStream>::Error> {
+        T::to_parens(self, output)
+    }
+}
+
 impl<'a, O, T> ToParens<O> for Cow<'a, T>
 where
-    T: ToParens<O> + Clone,
+    T: ToParens<O> + ToOwned + ?Sized,
     O: OutputStream,
 {
     #[inline]
@@ -163,12 +840,20 @@ where
 pub use parenthesis_macros::ToParens;
 
 /// Convert a value of type `T` to a vector of [`Value`]s.
+///
+/// # Panics
+///
+/// Panics if the value can not be written, see [`WriteError`].
 pub fn to_values<T>(value: T) -> Vec<Value>
 where
     T: ToParens<ValueOutputStream>,
 {
     let mut output = ValueOutputStream::new();
-    let _ = value.to_parens(&mut output);
+
+    if let Err(error) = value.to_parens(&mut output) {
+        panic!("{}", error);
+    }
+
     output.finish()
 }
 
@@ -189,20 +874,42 @@ impl ValueOutputStream {
     fn finish(self) -> Vec<Value> {
         self.current
     }
+
+    /// Collect the values written by `f` and wrap them into a single value.
+    fn nested<F, R>(&mut self, f: F, wrap: fn(Vec<Value>) -> Value) -> Result<R, WriteError>
+    where
+        F: FnOnce(&mut Self) -> Result<R, WriteError>,
+    {
+        self.stack.push(std::mem::take(&mut self.current));
+        let result = f(self);
+        let values = std::mem::replace(&mut self.current, self.stack.pop().unwrap());
+        self.current.push(wrap(values));
+        result
+    }
 }
 
 impl OutputStream for ValueOutputStream {
-    type Error = Infallible;
+    type Error = WriteError;
 
     fn list<F, R>(&mut self, f: F) -> Result<R, Self::Error>
     where
         F: FnOnce(&mut Self) -> Result<R, Self::Error>,
     {
-        self.stack.push(std::mem::take(&mut self.current));
-        let result = f(self);
-        let list = std::mem::replace(&mut self.current, self.stack.pop().unwrap());
-        self.current.push(Value::List(list));
-        result
+        self.nested(f, Value::List)
+    }
+
+    fn seq<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f, Value::Seq)
+    }
+
+    fn map<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Self::Error>,
+    {
+        self.nested(f, Value::Map)
     }
 
     fn string(&mut self, string: impl AsRef<str>) -> Result<(), Self::Error> {
@@ -229,4 +936,8 @@ impl OutputStream for ValueOutputStream {
         self.current.push(Value::from(float));
         Ok(())
     }
+
+    fn custom_error(message: impl Display) -> Self::Error {
+        WriteError::new(message)
+    }
 }
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use parenthesis::from_parens::{FromParens, InputStream, ParseError};
+use parenthesis::read::Span;
+use parenthesis::{Describe, Rest, Symbol, Syntax};
+
+#[cfg(feature = "macros")]
+fn parse_symbol<I: InputStream>(stream: &mut I) -> Result<Symbol, ParseError<I::Span>> {
+    Symbol::from_parens(stream)
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn all_field_kinds() {
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(tag = "node")]
+    struct Node {
+        name: Symbol,
+        #[sexpr(positional_optional)]
+        arity: Option<u32>,
+        #[sexpr(required)]
+        kind: Kind,
+        #[sexpr(required, default)]
+        weight: f64,
+        #[sexpr(optional, rename = "doc")]
+        title: Option<String>,
+        #[sexpr(repeated, rename = "input")]
+        inputs: Vec<Symbol>,
+        #[sexpr(repeated, tagged, rename = "port")]
+        ports: Vec<Port>,
+        #[sexpr(required, seq)]
+        path: Vec<Symbol>,
+        #[sexpr(required, map)]
+        meta: BTreeMap<Symbol, i64>,
+        #[sexpr(required, deserialize_with = "parse_symbol")]
+        custom: Symbol,
+        #[sexpr(flatten)]
+        position: Position,
+        #[sexpr(skip)]
+        cache: Vec<u8>,
+        #[sexpr(span)]
+        span: Span,
+        rest: Rest,
+    }
+
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    enum Kind {
+        Leaf,
+        Branch(u8, u8),
+        #[sexpr(other)]
+        Other(Symbol, Vec<parenthesis::Value>),
+    }
+
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(tag = "port")]
+    struct Port {
+        name: Symbol,
+        #[sexpr(optional)]
+        ty: Option<Symbol>,
+    }
+
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    struct Position {
+        #[sexpr(required)]
+        x: i32,
+        #[sexpr(required)]
+        y: i32,
+    }
+
+    assert_eq!(
+        Node::syntax().to_string(),
+        "(node <symbol> <int>? (kind <kind>) (weight <float>)? (doc <string>)? \
+        (input <symbol>)... (port <symbol> (ty <symbol>)?)... (path [<symbol>...]) \
+        (meta {⟦<symbol> <int>⟧...}) (custom <value>) (x <int>) (y <int>) <value>...)"
+    );
+    assert_eq!(Node::reference(), Syntax::placeholder("node"));
+    assert_eq!(
+        Kind::syntax().to_string(),
+        "leaf | (branch <int> <int>) | (<symbol> <value>...)"
+    );
+    assert_eq!(Position::syntax().to_string(), "(x <int>) (y <int>)");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn containers() {
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(transparent)]
+    struct Name(Symbol);
+
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(untagged)]
+    enum Literal {
+        Int(i64),
+        Pair(Name, Name),
+        Nil,
+    }
+
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(seq, rename_all = "snake_case")]
+    struct Point {
+        #[sexpr(required)]
+        first_coordinate: f32,
+        #[sexpr(required)]
+        second_coordinate: f32,
+    }
+
+    // Recursive types are described by reference.
+    #[derive(FromParens, Describe, PartialEq, Debug)]
+    #[sexpr(tag = "tree")]
+    struct Tree {
+        value: Literal,
+        children: Vec<Tree>,
+    }
+
+    assert_eq!(Name::syntax().to_string(), "<symbol>");
+    assert_eq!(Name::reference().to_string(), "<name>");
+    assert_eq!(Literal::syntax().to_string(), "<int> | <name> <name> | nil");
+    assert_eq!(
+        Point::syntax().to_string(),
+        "[(first_coordinate <float>) (second_coordinate <float>)]"
+    );
+    assert_eq!(Tree::syntax().to_string(), "(tree <literal> <tree>...)");
+}
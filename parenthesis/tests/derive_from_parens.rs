@@ -1,4 +1,32 @@
-use parenthesis::{from_str, read::ReadError, FromParens, Symbol, Value};
+use parenthesis::{
+    from_parens::{ParseError, ValueStream},
+    read::{ReadError, ReaderStream},
+    to_values, FromParens, OrDefault, Rest, Symbol, ToParens, Value,
+};
+
+/// Read a value from a string and check that reading it from the values in
+/// the string via a [`ValueStream`] agrees. If reading succeeds, the value
+/// read from the [`ValueStream`] is returned so that it is checked as well.
+fn from_str<T>(text: &str) -> Result<T, ReadError>
+where
+    T: for<'a> FromParens<ReaderStream<'a>> + for<'a> FromParens<ValueStream<'a>>,
+{
+    let result = parenthesis::from_str::<T>(text);
+
+    let Ok(values) = parenthesis::from_str::<Vec<Value>>(text) else {
+        return result;
+    };
+
+    let value_result = T::from_parens(&mut ValueStream::new(&values));
+
+    match result {
+        Ok(_) => Ok(value_result.expect("reading from values should succeed")),
+        Err(error) => {
+            assert!(value_result.is_err(), "reading from values should fail");
+            Err(error)
+        }
+    }
+}
 
 #[test]
 #[cfg(feature = "macros")]
@@ -126,6 +154,62 @@ pub fn repeated() {
     }
 }
 
+#[test]
+#[cfg(feature = "macros")]
+pub fn named_fields_any_order() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Test {
+        name: Symbol,
+        #[sexpr(required)]
+        title: String,
+        #[sexpr(optional)]
+        description: Option<String>,
+        #[sexpr(repeated)]
+        tag: Vec<Symbol>,
+    }
+
+    let expected = Test {
+        name: "op".into(),
+        title: "Title".into(),
+        description: Some("Description".into()),
+        tag: vec!["a".into(), "b".into()],
+    };
+
+    let forms = [
+        r#"(title "Title")"#,
+        r#"(description "Description")"#,
+        "(tag a) (tag b)",
+    ];
+
+    let permutations = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+
+    for permutation in permutations {
+        let forms: Vec<_> = permutation.iter().map(|index| forms[*index]).collect();
+        let text = format!("op {}", forms.join(" "));
+        assert_eq!(from_str::<Test>(&text).unwrap(), expected, "{}", text);
+    }
+
+    // Forms of repeated fields may be interleaved with other fields and
+    // duplicates are still rejected in any order.
+    let text = r#"op (tag a) (title "Title") (description "Description") (tag b)"#;
+    assert_eq!(from_str::<Test>(text).unwrap(), expected);
+    assert!(from_str::<Test>(r#"op (title "a") (tag a) (title "b")"#).is_err());
+
+    // Fields are printed in the order in which they are declared.
+    assert_eq!(
+        to_values(&expected),
+        from_str::<Vec<Value>>(r#"op (title "Title") (description "Description") (tag a) (tag b)"#)
+            .unwrap()
+    );
+}
+
 #[test]
 #[cfg(feature = "macros")]
 pub fn resursive_field() {
@@ -163,3 +247,2264 @@ pub fn resursive_field() {
     let test = from_str::<Outer>(text).unwrap();
     assert_eq!(test, expected);
 }
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn rest_round_trip() {
+    #[derive(FromParens, ToParens, PartialEq, Debug)]
+    struct Test {
+        name: Symbol,
+        #[sexpr(required)]
+        version: i64,
+        #[sexpr(optional)]
+        comment: Option<String>,
+        extra: Rest,
+    }
+
+    let text = r#"node (version 2) (comment "c") (unknown 1 2) (version 3) "trailing""#;
+    let test = from_str::<Test>(text).unwrap();
+
+    assert_eq!(test.name, Symbol::new("node"));
+    assert_eq!(test.version, 2);
+    assert_eq!(test.comment.as_deref(), Some("c"));
+    assert_eq!(
+        test.extra,
+        Rest(from_str::<Vec<Value>>(r#"(unknown 1 2) (version 3) "trailing""#).unwrap())
+    );
+
+    let expected = from_str::<Vec<Value>>(text).unwrap();
+    assert_eq!(to_values(&test), expected);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn rest_empty() {
+    #[derive(FromParens)]
+    struct Test {
+        #[sexpr(optional)]
+        field: Option<String>,
+        rest: Rest,
+    }
+
+    let test = from_str::<Test>(r#"(field "string")"#).unwrap();
+
+    assert_eq!(test.field.unwrap(), "string");
+    assert!(test.rest.0.is_empty());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn default_absent() {
+    fn default_version() -> i64 {
+        1
+    }
+
+    #[derive(FromParens)]
+    struct Test {
+        #[sexpr(default)]
+        name: String,
+        #[sexpr(default = "default_version")]
+        version: i64,
+    }
+
+    let test = from_str::<Test>(r#""#).unwrap();
+
+    assert_eq!(test.name, "");
+    assert_eq!(test.version, 1);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn default_given() {
+    #[derive(FromParens)]
+    struct Test {
+        #[sexpr(required, default)]
+        name: String,
+        #[sexpr(default)]
+        version: i64,
+    }
+
+    let test = from_str::<Test>(r#"(version 2) (name "string")"#).unwrap();
+
+    assert_eq!(test.name, "string");
+    assert_eq!(test.version, 2);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn skip() {
+    #[derive(FromParens)]
+    struct Test {
+        first: String,
+        #[sexpr(skip)]
+        cache: Vec<String>,
+        #[sexpr(skip_deserializing)]
+        #[sexpr(required)]
+        computed: i64,
+        #[sexpr(required)]
+        field: String,
+    }
+
+    let test = from_str::<Test>(r#""a" (field "b")"#).unwrap();
+
+    assert_eq!(test.first, "a");
+    assert!(test.cache.is_empty());
+    assert_eq!(test.computed, 0);
+    assert_eq!(test.field, "b");
+
+    // Skipped fields are unknown fields, whose forms are ignored.
+    let test = from_str::<Test>(r#""a" (field "b") (computed 1)"#).unwrap();
+    assert_eq!(test.computed, 0);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn skip_serializing() {
+    #[derive(FromParens)]
+    struct Test {
+        #[sexpr(skip_serializing)]
+        #[sexpr(required)]
+        field: String,
+    }
+
+    let test = from_str::<Test>(r#"(field "a")"#).unwrap();
+
+    assert_eq!(test.field, "a");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn flatten() {
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    struct Outer {
+        name: Symbol,
+        #[sexpr(required)]
+        first: String,
+        #[sexpr(flatten)]
+        inner: Inner,
+        #[sexpr(repeated)]
+        last: Vec<String>,
+    }
+
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    struct Inner {
+        #[sexpr(required)]
+        field: String,
+        #[sexpr(optional)]
+        other: Option<String>,
+    }
+
+    let text = r#"
+        outer
+        (last "a")
+        (field "b")
+        (first "c")
+        (last "d")
+    "#;
+
+    let expected = Outer {
+        name: "outer".into(),
+        first: "c".into(),
+        inner: Inner {
+            field: "b".into(),
+            other: None,
+        },
+        last: vec!["a".into(), "d".into()],
+    };
+
+    assert_eq!(from_str::<Outer>(text).unwrap(), expected);
+
+    // Missing and duplicate fields of the flattened struct are reported,
+    // while unknown fields are skipped.
+    assert!(from_str::<Outer>(r#"outer (first "c")"#).is_err());
+    assert!(from_str::<Outer>(r#"outer (first "c") (field "b") (field "b")"#).is_err());
+    assert!(from_str::<Outer>(r#"outer (first "c") (field "b") (unknown)"#).is_ok());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn flatten_positional() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Form {
+        kind: Symbol,
+        #[sexpr(flatten)]
+        metadata: Metadata,
+        #[sexpr(required)]
+        body: String,
+    }
+
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Metadata {
+        id: i64,
+        #[sexpr(required)]
+        title: String,
+        #[sexpr(optional)]
+        description: Option<String>,
+    }
+
+    let text = r#"define 7 (body "b") (title "t") (description "d")"#;
+
+    let expected = Form {
+        kind: "define".into(),
+        metadata: Metadata {
+            id: 7,
+            title: "t".into(),
+            description: Some("d".into()),
+        },
+        body: "b".into(),
+    };
+
+    let form = from_str::<Form>(text).unwrap();
+    assert_eq!(form, expected);
+
+    // The positional fields of the flattened struct are printed at its point of declaration.
+    let printed = to_values(&form);
+    assert_eq!(
+        printed,
+        from_str::<Vec<Value>>(r#"define 7 (title "t") (description "d") (body "b")"#).unwrap()
+    );
+    assert_eq!(
+        Form::from_parens(&mut printed.as_slice()).unwrap(),
+        expected
+    );
+
+    // The positional field of the flattened struct is required.
+    assert!(from_str::<Form>(r#"define (title "t") (body "b")"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn flatten_positional_after_named() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Form {
+        kind: Symbol,
+        #[sexpr(required)]
+        body: String,
+        #[sexpr(flatten)]
+        metadata: Metadata,
+        #[sexpr(flatten)]
+        position: Position,
+    }
+
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Metadata {
+        id: i64,
+        #[sexpr(required)]
+        title: String,
+    }
+
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Position {
+        line: u32,
+        column: u32,
+    }
+
+    let expected = Form {
+        kind: "define".into(),
+        body: "b".into(),
+        metadata: Metadata {
+            id: 7,
+            title: "t".into(),
+        },
+        position: Position { line: 3, column: 4 },
+    };
+
+    // The positional fields of a flattened struct follow the named fields declared before it.
+    let printed = to_values(&expected);
+    assert_eq!(
+        printed,
+        from_str::<Vec<Value>>(r#"define (body "b") 7 (title "t") 3 4"#).unwrap()
+    );
+    assert_eq!(
+        Form::from_parens(&mut printed.as_slice()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        from_str::<Form>(r#"define (body "b") 7 (title "t") 3 4"#).unwrap(),
+        expected
+    );
+
+    // Named fields may still appear in any order around the positional values.
+    assert_eq!(
+        from_str::<Form>(r#"define (title "t") 7 3 4 (body "b")"#).unwrap(),
+        expected
+    );
+    assert!(from_str::<Form>(r#"define (body "b") 7 (title "t") 3"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn flatten_conflict() {
+    #[allow(dead_code)]
+    #[derive(FromParens, Debug)]
+    struct Outer {
+        #[sexpr(flatten)]
+        first: First,
+        #[sexpr(flatten)]
+        second: Second,
+    }
+
+    #[allow(dead_code)]
+    #[derive(FromParens, Debug)]
+    struct First {
+        #[sexpr(required)]
+        title: String,
+        #[sexpr(optional)]
+        name: Option<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(FromParens, Debug)]
+    struct Second {
+        #[sexpr(optional)]
+        name: Option<String>,
+    }
+
+    let result = from_str::<Outer>(r#"(title "a") (name "b")"#);
+    let Err(ReadError::Parse(error)) = result else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "field `name` is claimed by more than one flattened field"
+    );
+
+    assert!(from_str::<Outer>(r#"(title "a")"#).is_ok());
+}
+
+#[cfg(feature = "macros")]
+mod duration_secs {
+    use parenthesis::from_parens::{FromParens, InputStream, ParseError};
+    use parenthesis::to_parens::OutputStream;
+    use std::time::Duration;
+
+    pub fn to_parens<O: OutputStream>(value: &Duration, output: &mut O) -> Result<(), O::Error> {
+        output.float(value.as_secs_f64())
+    }
+
+    pub fn from_parens<I: InputStream>(stream: &mut I) -> Result<Duration, ParseError<I::Span>> {
+        let secs = f64::from_parens(stream)?;
+        Duration::try_from_secs_f64(secs).map_err(|err| stream.error(err))
+    }
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn with() {
+    use std::time::Duration;
+
+    fn symbol_to_parens<O: parenthesis::to_parens::OutputStream>(
+        value: &Symbol,
+        output: &mut O,
+    ) -> Result<(), O::Error> {
+        output.symbol(value)
+    }
+
+    #[derive(FromParens, ToParens, PartialEq, Debug)]
+    struct Test {
+        #[sexpr(with = "duration_secs")]
+        timeout: Duration,
+        #[sexpr(optional, with = "duration_secs")]
+        delay: Option<Duration>,
+        #[sexpr(repeated, with = "duration_secs")]
+        retry: Vec<Duration>,
+        #[sexpr(optional, serialize_with = "symbol_to_parens")]
+        #[sexpr(deserialize_with = "Symbol::from_parens")]
+        name: Option<Symbol>,
+    }
+
+    let text = r#"1.5 (delay 0.25) (retry 1.0) (retry 2.0) (name n)"#;
+    let test = from_str::<Test>(text).unwrap();
+
+    let expected = Test {
+        timeout: Duration::from_millis(1500),
+        delay: Some(Duration::from_millis(250)),
+        retry: vec![Duration::from_secs(1), Duration::from_secs(2)],
+        name: Some(Symbol::new("n")),
+    };
+
+    assert_eq!(test, expected);
+    assert_eq!(to_values(&test), from_str::<Vec<Value>>(text).unwrap());
+    assert!(from_str::<Test>("-1.0").is_err());
+}
+
+#[cfg(feature = "macros")]
+mod hex {
+    use parenthesis::from_parens::{FromParens, InputStream, ParseError};
+    use parenthesis::to_parens::OutputStream;
+
+    pub fn to_parens<O: OutputStream>(value: &[u8], output: &mut O) -> Result<(), O::Error> {
+        let hex: String = value.iter().map(|byte| format!("{:02x}", byte)).collect();
+        output.string(&hex)
+    }
+
+    pub fn from_parens<I: InputStream>(stream: &mut I) -> Result<Vec<u8>, ParseError<I::Span>> {
+        let hex = String::from_parens(stream)?;
+
+        if hex.len() % 2 != 0 {
+            return Err(stream.error("hex string must have an even number of digits"));
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                let digits = hex.get(index..index + 2);
+                digits
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| stream.error(format!("invalid hex string `{}`", hex)))
+            })
+            .collect()
+    }
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn with_hex() {
+    #[derive(FromParens, ToParens, PartialEq, Debug)]
+    struct Blob {
+        #[sexpr(required, with = "hex")]
+        data: Vec<u8>,
+    }
+
+    let text = r#"(data "00ff7a")"#;
+    let blob = from_str::<Blob>(text).unwrap();
+
+    assert_eq!(blob.data, vec![0x00, 0xff, 0x7a]);
+    assert_eq!(to_values(&blob), from_str::<Vec<Value>>(text).unwrap());
+    assert!(from_str::<Blob>(r#"(data "0ff")"#).is_err());
+    assert!(from_str::<Blob>(r#"(data "zz")"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn transparent() {
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    #[sexpr(transparent)]
+    struct NodeId(Symbol);
+
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    #[sexpr(transparent)]
+    struct Name {
+        name: String,
+    }
+
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    struct Node {
+        id: NodeId,
+        #[sexpr(required)]
+        name: Name,
+    }
+
+    let test = from_str::<Node>(r#"node-1 (name "first")"#).unwrap();
+
+    let expected = Node {
+        id: NodeId("node-1".into()),
+        name: Name {
+            name: "first".into(),
+        },
+    };
+
+    assert_eq!(test, expected);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn header() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    #[sexpr(header = "my-type")]
+    struct Test {
+        positional: Symbol,
+        #[sexpr(required)]
+        required: String,
+    }
+
+    let text = r#"(my-type positional (required "val"))"#;
+    let test = from_str::<Test>(text).unwrap();
+
+    let expected = Test {
+        positional: "positional".into(),
+        required: "val".into(),
+    };
+
+    assert_eq!(test, expected);
+    assert_eq!(to_values(&test), from_str::<Vec<Value>>(text).unwrap());
+
+    let Err(ReadError::Parse(error)) =
+        from_str::<Test>(r#"(other-type positional (required "val"))"#)
+    else {
+        panic!("expected parse error");
+    };
+
+    assert_eq!(error.to_string(), "expected `my-type`, found `other-type`");
+    assert!(from_str::<Test>(r#"positional (required "val")"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn tag() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    #[sexpr(tag = "operation")]
+    struct Operation {
+        name: Symbol,
+        #[sexpr(required)]
+        title: String,
+    }
+
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Module {
+        #[sexpr(repeated, tagged)]
+        operation: Vec<Operation>,
+        #[sexpr(optional, tagged, rename = "operation-main")]
+        main: Option<Operation>,
+    }
+
+    let text = r#"
+        (operation @core-f32/mul (title "Multiply"))
+        (operation @core-f32/add (title "Add"))
+        (operation-main main (title "Main"))
+    "#;
+
+    let expected = Module {
+        operation: vec![
+            Operation {
+                name: "@core-f32/mul".into(),
+                title: "Multiply".into(),
+            },
+            Operation {
+                name: "@core-f32/add".into(),
+                title: "Add".into(),
+            },
+        ],
+        main: Some(Operation {
+            name: "main".into(),
+            title: "Main".into(),
+        }),
+    };
+
+    let module = from_str::<Module>(text).unwrap();
+    assert_eq!(module, expected);
+    assert_eq!(to_values(&module), from_str::<Vec<Value>>(text).unwrap());
+
+    let operation = from_str::<Operation>(r#"(operation @core-f32/add (title "Add"))"#).unwrap();
+    assert_eq!(operation, expected.operation[1]);
+
+    let Err(ReadError::Parse(error)) = from_str::<Operation>(r#"(other add (title "Add"))"#) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected `operation`, found `other`");
+
+    let Err(ReadError::Parse(error)) = from_str::<Operation>(r#"("operation" add)"#) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected `operation`, found string");
+
+    let Err(ReadError::Parse(error)) = from_str::<Operation>("operation") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected list, found symbol");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn tuple_struct() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Ports(Symbol, Vec<Symbol>);
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Name(Symbol);
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Op {
+        Add(i64, i64),
+    }
+
+    let ports = Ports("in".into(), vec!["p0".into(), "p1".into()]);
+    assert_eq!(
+        to_values(&ports),
+        parenthesis::from_str::<Vec<Value>>("in p0 p1").unwrap()
+    );
+    assert_eq!(from_str::<Ports>("in p0 p1").unwrap(), ports);
+    assert_eq!(from_str::<Ports>("in").unwrap(), Ports("in".into(), vec![]));
+    assert_eq!(from_str::<Name>("x").unwrap(), Name("x".into()));
+    assert_eq!(from_str::<Op>("(add 1 2)").unwrap(), Op::Add(1, 2));
+
+    let Err(ReadError::Parse(error)) = from_str::<Ports>("") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.innermost().to_string(),
+        "expected 1st field of `Ports`"
+    );
+    assert_eq!(
+        error.to_string(),
+        "expected 1st field of `Ports`, in 1st field of `Ports`"
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Name>("") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.innermost().to_string(),
+        "expected 1st field of `Name`"
+    );
+    assert_eq!(
+        error.to_string(),
+        "expected 1st field of `Name`, in 1st field of `Name`"
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Op>("(add 1)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.innermost().to_string(), "expected 2nd field of `Add`");
+    assert_eq!(
+        error.to_string(),
+        "expected 2nd field of `Add`, in 2nd field of `Add`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn variadic() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Call {
+        function: Symbol,
+        #[sexpr(variadic)]
+        args: Vec<i64>,
+        #[sexpr(required)]
+        returns: Symbol,
+    }
+
+    for args in [vec![], vec![1], vec![1, 2, 3]] {
+        let call = Call {
+            function: Symbol::new("f"),
+            args,
+            returns: Symbol::new("int"),
+        };
+
+        let values = to_values(&call);
+        let text = values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(values.len(), call.args.len() + 2);
+        assert_eq!(from_str::<Call>(&text).unwrap(), call);
+    }
+
+    let result = from_str::<Call>("f 1 (returns int) 2");
+    assert!(matches!(result, Err(ReadError::Parse(_))));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn variadic_lists() {
+    #[derive(Debug, PartialEq, FromParens)]
+    struct Test {
+        #[sexpr(variadic)]
+        items: Vec<Value>,
+        #[sexpr(optional)]
+        name: Option<String>,
+    }
+
+    let test = from_str::<Test>(r#"(a b) () (c) (name "n")"#).unwrap();
+    assert_eq!(test.items, from_str::<Vec<Value>>("(a b) () (c)").unwrap());
+    assert_eq!(test.name.as_deref(), Some("n"));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn positional_optional() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "wire")]
+    struct Wire {
+        source: Symbol,
+        #[sexpr(positional_optional)]
+        target: Option<Symbol>,
+        #[sexpr(optional)]
+        label: Option<String>,
+    }
+
+    for (text, target, label) in [
+        ("(wire %a)", None, None),
+        ("(wire %a %b)", Some("%b"), None),
+        (r#"(wire %a (label "x"))"#, None, Some("x")),
+        (r#"(wire %a %b (label "x"))"#, Some("%b"), Some("x")),
+    ] {
+        let wire = Wire {
+            source: Symbol::new("%a"),
+            target: target.map(Symbol::new),
+            label: label.map(String::from),
+        };
+
+        assert_eq!(from_str::<Wire>(text).unwrap(), wire);
+        assert_eq!(to_values(&wire), from_str::<Vec<Value>>(text).unwrap());
+    }
+
+    // A value that does not parse is left for the remaining fields.
+    let result = from_str::<Wire>("(wire %a 1)");
+    assert!(matches!(result, Err(ReadError::Parse(_))));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn option_positional() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "port")]
+    struct Port {
+        name: Symbol,
+        ty: Option<Symbol>,
+        width: Option<u32>,
+    }
+
+    for (text, ty, width) in [
+        ("(port a b 8)", Some("b"), Some(8)),
+        ("(port a 8)", None, Some(8)),
+        ("(port a)", None, None),
+    ] {
+        let port = from_str::<Port>(text).unwrap();
+        assert_eq!(
+            port,
+            Port {
+                name: Symbol::from("a"),
+                ty: ty.map(Symbol::from),
+                width,
+            }
+        );
+        assert_eq!(to_values(&port), from_str::<Vec<Value>>(text).unwrap());
+    }
+
+    // A value that matches neither field is left for the end of the list.
+    let error = from_str::<Port>(r#"(port a "b")"#).unwrap_err();
+    assert_eq!(error.to_string(), "expected no more values, found string");
+
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(tag = "widths")]
+    struct Widths {
+        #[sexpr(variadic)]
+        widths: Vec<Option<u32>>,
+    }
+
+    let widths = from_str::<Widths>("(widths 1 2)").unwrap();
+    assert_eq!(widths.widths, vec![Some(1), Some(2)]);
+
+    let error = from_str::<Widths>("(widths 1 a)").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected element, found symbol, in 2nd value of field `widths` of `Widths`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn primitives_round_trip() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Glyph {
+        char: char,
+        #[sexpr(required)]
+        visible: bool,
+        #[sexpr(required)]
+        weight: f32,
+    }
+
+    let glyph = Glyph {
+        char: '"',
+        visible: true,
+        weight: 0.3,
+    };
+
+    let text = to_values(&glyph)
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    assert_eq!(text, r#""\"" (visible #t) (weight 0.3)"#);
+    assert_eq!(from_str::<Glyph>(&text).unwrap(), glyph);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn validate() {
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(validate = "Range::validate")]
+    struct Range {
+        #[sexpr(validate = "non_negative")]
+        start: i64,
+        #[sexpr(required)]
+        #[sexpr(validate = "non_negative")]
+        end: i64,
+    }
+
+    impl Range {
+        fn validate(&self) -> Result<(), String> {
+            if self.start > self.end {
+                return Err(format!("range {} to {} is empty", self.start, self.end));
+            }
+
+            Ok(())
+        }
+    }
+
+    fn non_negative(value: &i64) -> Result<(), &'static str> {
+        if *value < 0 {
+            return Err("expected non-negative integer");
+        }
+
+        Ok(())
+    }
+
+    assert_eq!(
+        from_str::<Range>("1 (end 2)").unwrap(),
+        Range { start: 1, end: 2 }
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Range>("3 (end 2)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "range 3 to 2 is empty");
+
+    let Err(ReadError::Parse(error)) = from_str::<Range>("1 (end -2)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected non-negative integer");
+    assert!(matches!(error, ParseError::Custom { span, .. } if span == (2..10)));
+
+    let Err(ReadError::Parse(error)) = from_str::<Range>("-1 (end 2)") else {
+        panic!("expected parse error");
+    };
+    assert!(matches!(error, ParseError::Custom { span, .. } if span == (0..2)));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn validate_parse_error() {
+    #[derive(Debug, FromParens)]
+    struct Test {
+        #[allow(dead_code)]
+        #[sexpr(validate = "not_empty")]
+        name: String,
+    }
+
+    fn not_empty<S>(value: &str) -> Result<(), ParseError<S>> {
+        if value.is_empty() {
+            return Err(ParseError::Other("empty name".into()));
+        }
+
+        Ok(())
+    }
+
+    assert!(from_str::<Test>(r#""name""#).is_ok());
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>(r#""""#) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "empty name");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn error_messages() {
+    #[derive(Debug, FromParens)]
+    #[allow(dead_code)]
+    struct Test {
+        #[sexpr(required)]
+        name: String,
+    }
+
+    let cases = [
+        ("", "missing required field `name`"),
+        ("name", "expected named field `(name ...)`, found symbol"),
+        ("(1)", "expected field name, found int"),
+        ("()", "expected field name, found end of input"),
+        (
+            "(name 1)",
+            "expected string, found int, in field `name` of `Test`",
+        ),
+    ];
+
+    for (text, message) in cases {
+        let Err(ReadError::Parse(error)) = from_str::<Test>(text) else {
+            panic!("expected parse error");
+        };
+
+        assert_eq!(error.to_string(), message);
+    }
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>("") else {
+        panic!("expected parse error");
+    };
+    assert!(matches!(
+        error,
+        ParseError::MissingField { field: "name", .. }
+    ));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn unknown_fields() {
+    #[derive(Debug, PartialEq, FromParens)]
+    struct Lenient {
+        name: Symbol,
+        #[sexpr(optional)]
+        title: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(deny_unknown_fields)]
+    struct Strict {
+        name: Symbol,
+        #[sexpr(optional)]
+        title: Option<String>,
+        #[sexpr(flatten)]
+        meta: Meta,
+    }
+
+    #[derive(Debug, PartialEq, FromParens)]
+    struct Meta {
+        #[sexpr(optional)]
+        description: Option<String>,
+    }
+
+    let text = r#"op (titel "typo") (title "Title")"#;
+
+    assert_eq!(
+        from_str::<Lenient>(text).unwrap(),
+        Lenient {
+            name: "op".into(),
+            title: Some("Title".into()),
+        }
+    );
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Strict>(text) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "unknown field `titel`, expected one of `title`, `description`"
+    );
+    assert!(matches!(error, ParseError::Error { span, .. } if span == (3..17)));
+
+    let Err(ReadError::Parse(error)) = from_str::<Lenient>("op title") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected named field `(title ...)`, found symbol"
+    );
+}
+
+#[test]
+#[cfg(all(feature = "macros", feature = "uuid"))]
+pub fn uuid_field() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Module {
+        id: uuid::Uuid,
+        #[sexpr(optional)]
+        parent: Option<uuid::Uuid>,
+    }
+
+    let module = Module {
+        id: uuid::Uuid::from_u128(1),
+        parent: Some(uuid::Uuid::from_u128(2)),
+    };
+
+    let text = to_values(&module)
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    assert_eq!(
+        text,
+        r#""00000000-0000-0000-0000-000000000001" (parent "00000000-0000-0000-0000-000000000002")"#
+    );
+    assert_eq!(from_str::<Module>(&text).unwrap(), module);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn or_default() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(header = "point")]
+    struct Point {
+        x: i64,
+        y: i64,
+        #[sexpr(serialize_with = "parenthesis::to_parens::skip_default")]
+        z: OrDefault<i64>,
+    }
+
+    let point = from_str::<Point>("(point 1 2)").unwrap();
+    assert_eq!(
+        point,
+        Point {
+            x: 1,
+            y: 2,
+            z: OrDefault(0)
+        }
+    );
+    assert_eq!(
+        to_values(&point),
+        from_str::<Vec<Value>>("(point 1 2)").unwrap()
+    );
+
+    let point = from_str::<Point>("(point 1 2 3)").unwrap();
+    assert_eq!(point.z, OrDefault(3));
+    assert_eq!(
+        to_values(&point),
+        from_str::<Vec<Value>>("(point 1 2 3)").unwrap()
+    );
+
+    assert!(from_str::<Point>("(point 1 2 three)").is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn spanned() {
+    use parenthesis::span::Spanned;
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Param {
+        name: Spanned<Symbol>,
+        #[sexpr(required)]
+        ty: Spanned<Symbol>,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(header = "define")]
+    struct Define {
+        name: Spanned<Symbol>,
+        #[sexpr(repeated)]
+        param: Vec<Spanned<Param>>,
+        #[sexpr(optional)]
+        doc: Option<Spanned<String>>,
+    }
+
+    let text = r#"(define f (param x (ty int)) (param y (ty bool)) (doc "text"))"#;
+    let define = parenthesis::from_str::<Define>(text).unwrap();
+
+    assert_eq!(&text[define.name.span.clone()], "f");
+    assert_eq!(&text[define.param[0].span.clone()], "(param x (ty int))");
+    assert_eq!(&text[define.param[1].name.span.clone()], "y");
+    assert_eq!(&text[define.param[1].ty.span.clone()], "(ty bool)");
+    assert_eq!(&text[define.doc.unwrap().span], r#"(doc "text")"#);
+    assert_eq!(
+        to_values(parenthesis::from_str::<Define>(text).unwrap()),
+        from_str::<Vec<Value>>(text).unwrap()
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn seq() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(seq)]
+    struct Ports(Vec<Symbol>);
+
+    let ports = Ports(vec!["p0".into(), "p1".into()]);
+    assert_eq!(parenthesis::to_string_pretty(&ports, 80), "[p0 p1]");
+    assert_eq!(from_str::<Ports>("[p0 p1]").unwrap(), ports);
+    assert_eq!(from_str::<Ports>("[]").unwrap(), Ports(vec![]));
+
+    let Err(ReadError::Parse(error)) = from_str::<Ports>("(p0 p1)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected sequence, found list");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn map() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(map)]
+    struct Entry {
+        key: Symbol,
+        value: i64,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(seq, header = "entries")]
+    struct Entries {
+        #[sexpr(variadic)]
+        entries: Vec<Entry>,
+        #[sexpr(optional)]
+        default: Option<i64>,
+    }
+
+    let text = "[entries {a 1} {b 2} (default 0)]";
+    let entries = Entries {
+        entries: vec![
+            Entry {
+                key: "a".into(),
+                value: 1,
+            },
+            Entry {
+                key: "b".into(),
+                value: 2,
+            },
+        ],
+        default: Some(0),
+    };
+
+    assert_eq!(from_str::<Entries>(text).unwrap(), entries);
+    assert_eq!(to_values(&entries), from_str::<Vec<Value>>(text).unwrap());
+
+    assert!(from_str::<Entries>("(entries {a 1})").is_err());
+    assert!(from_str::<Entries>("[entries [a 1]]").is_err());
+    assert!(from_str::<Entry>("(a 1)").is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn case_insensitive() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(header = "operation", case_insensitive)]
+    struct Operation {
+        name: Symbol,
+        #[sexpr(required)]
+        arity: i64,
+        #[sexpr(repeated, rename = "Input")]
+        inputs: Vec<Symbol>,
+    }
+
+    let text = "(OPERATION Add (Arity 2) (INPUT a) (input B))";
+    let operation = from_str::<Operation>(text).unwrap();
+
+    assert_eq!(
+        operation,
+        Operation {
+            name: "Add".into(),
+            arity: 2,
+            inputs: vec!["a".into(), "B".into()],
+        }
+    );
+    assert_eq!(
+        to_values(&operation),
+        from_str::<Vec<Value>>("(operation Add (arity 2) (Input a) (Input B))").unwrap()
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Operation>("(OPERATION Add (input a))") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "missing required field `arity`");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn fold_case() {
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(header = "operation")]
+    struct Operation {
+        name: Symbol,
+        #[sexpr(required)]
+        arity: i64,
+    }
+
+    let options = parenthesis::ReadOptions::new().with_fold_case(true);
+    let text = "(OPERATION Add (ARITY 2))";
+
+    assert_eq!(
+        parenthesis::from_str_with_opts::<Operation>(text, &options).unwrap(),
+        Operation {
+            name: "add".into(),
+            arity: 2,
+        }
+    );
+    assert!(from_str::<Operation>(text).is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_variants() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Node {
+        Operation {
+            name: Symbol,
+            #[sexpr(repeated, rename = "input")]
+            inputs: Vec<Symbol>,
+            #[sexpr(optional)]
+            region: Option<Box<Node>>,
+        },
+        Region(#[sexpr(variadic)] Vec<Node>),
+        ConstInt(i64),
+        #[sexpr(rename = "nop")]
+        Empty,
+    }
+
+    let text = r#"
+        (operation add (input a) (input b)
+            (region (region (const-int 1) nop)))
+    "#;
+    let node = from_str::<Node>(text).unwrap();
+
+    assert_eq!(
+        node,
+        Node::Operation {
+            name: "add".into(),
+            inputs: vec!["a".into(), "b".into()],
+            region: Some(Box::new(Node::Region(vec![Node::ConstInt(1), Node::Empty]))),
+        }
+    );
+    assert_eq!(to_values(&node), from_str::<Vec<Value>>(text).unwrap());
+    assert_eq!(
+        from_str::<Node>(&parenthesis::to_string_compact(&node)).unwrap(),
+        node
+    );
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Node>("(const 1)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "unknown variant `const`, expected `operation`, `region`, `const-int`"
+    );
+    assert!(matches!(error, ParseError::Error { span, .. } if span == (1..6)));
+
+    assert!(from_str::<Node>("const-int").is_err());
+    assert!(from_str::<Node>("(const-int)").is_err());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_case_insensitive() {
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(case_insensitive)]
+    enum Const {
+        Int(i64),
+        Bool {
+            #[sexpr(required)]
+            value: bool,
+        },
+    }
+
+    assert_eq!(from_str::<Const>("(INT 1)").unwrap(), Const::Int(1));
+    assert_eq!(
+        from_str::<Const>("(Bool (VALUE #t))").unwrap(),
+        Const::Bool { value: true }
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_untagged() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(header = "ref")]
+    struct Ref {
+        name: Symbol,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(untagged)]
+    enum Operand {
+        Pair(i64, i64),
+        Int(i64),
+        String(String),
+        Ref(Ref),
+        Unknown,
+    }
+
+    let text = r#"1 2 3 "s" (ref x) unknown"#;
+    let operands = from_str::<Vec<Operand>>(text).unwrap();
+
+    assert_eq!(
+        operands,
+        vec![
+            Operand::Pair(1, 2),
+            Operand::Int(3),
+            Operand::String("s".into()),
+            Operand::Ref(Ref { name: "x".into() }),
+            Operand::Unknown,
+        ]
+    );
+    assert_eq!(to_values(&operands), from_str::<Vec<Value>>(text).unwrap());
+
+    // When variants match a common prefix, the first variant in order wins.
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(untagged)]
+    enum IntFirst {
+        Int(i64),
+        Pair(i64, i64),
+    }
+
+    assert_eq!(
+        from_str::<Vec<IntFirst>>("1 2").unwrap(),
+        vec![IntFirst::Int(1), IntFirst::Int(2)]
+    );
+
+    // The error of the variant that got furthest is reported.
+    #[derive(Debug, PartialEq, FromParens)]
+    #[sexpr(untagged)]
+    enum PairOrName {
+        Pair(i64, i64),
+        Name(Symbol),
+    }
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<PairOrName>("1 x") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected int, found symbol, in 2nd field of `Pair`"
+    );
+    assert!(matches!(
+        error.innermost(),
+        ParseError::UnexpectedToken { span, .. } if *span == (2..3)
+    ));
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<PairOrName>("#t") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected int or symbol, found bool");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_unit_variants() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Direction {
+        North,
+        South,
+        #[sexpr(rename = "E")]
+        East,
+        West,
+    }
+
+    let text = "north south E west";
+    let directions = from_str::<Vec<Direction>>(text).unwrap();
+
+    assert_eq!(
+        directions,
+        vec![
+            Direction::North,
+            Direction::South,
+            Direction::East,
+            Direction::West
+        ]
+    );
+    assert_eq!(
+        to_values(&directions),
+        from_str::<Vec<Value>>(text).unwrap()
+    );
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Vec<Direction>>("north up") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "unknown variant `up`, expected `north`, `south`, `E`, `west`"
+    );
+    assert!(matches!(error, ParseError::Error { span, .. } if span == (6..8)));
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Direction>("(north)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "expected symbol, found list");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn alias() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Node {
+        #[sexpr(alias = "op", alias = "operator")]
+        Operation {
+            #[sexpr(required, rename = "input-ports", alias = "ports")]
+            inputs: Vec<Symbol>,
+            #[sexpr(optional, alias = "desc")]
+            description: Option<String>,
+        },
+        #[sexpr(alias = "none")]
+        Empty,
+    }
+
+    let expected = Node::Operation {
+        inputs: vec!["a".into(), "b".into()],
+        description: Some("add".into()),
+    };
+    let canonical = r#"(operation (input-ports a b) (description "add"))"#;
+
+    for text in [
+        canonical,
+        r#"(op (ports a b) (desc "add"))"#,
+        r#"(operator (description "add") (ports a b))"#,
+    ] {
+        let node = from_str::<Node>(text).unwrap();
+        assert_eq!(node, expected);
+        assert_eq!(to_values(&node), from_str::<Vec<Value>>(canonical).unwrap());
+    }
+
+    assert_eq!(from_str::<Node>("none").unwrap(), Node::Empty);
+    assert_eq!(
+        to_values(&Node::Empty),
+        from_str::<Vec<Value>>("empty").unwrap()
+    );
+
+    let Err(ReadError::Parse(error)) =
+        parenthesis::from_str::<Node>("(operation (ports a) (input-ports b))")
+    else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "duplicate field `input-ports`");
+    assert!(matches!(
+        error,
+        ParseError::DuplicateField { span, previous, .. }
+            if previous == (11..20) && span == (21..36)
+    ));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn rename_keywords() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Node {
+        #[sexpr(rename = "@core/const")]
+        Const(i64),
+        Operation {
+            #[sexpr(repeated, rename = "input-ports")]
+            inputs: Vec<Symbol>,
+            #[sexpr(optional, rename = "@core/meta")]
+            meta: Option<String>,
+        },
+    }
+
+    let text = r#"(@core/const 1) (operation (input-ports a) (input-ports b) (@core/meta "m"))"#;
+    let nodes = from_str::<Vec<Node>>(text).unwrap();
+
+    assert_eq!(
+        nodes,
+        vec![
+            Node::Const(1),
+            Node::Operation {
+                inputs: vec!["a".into(), "b".into()],
+                meta: Some("m".into()),
+            }
+        ]
+    );
+    assert_eq!(to_values(&nodes), from_str::<Vec<Value>>(text).unwrap());
+    assert_eq!(parenthesis::to_string_compact(&nodes), text);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn rename_all() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(rename_all = "kebab-case")]
+    struct Port {
+        #[sexpr(required)]
+        port_name: Symbol,
+        #[sexpr(repeated)]
+        input_port_types: Vec<Symbol>,
+        #[sexpr(optional, rename = "out")]
+        output_port_type: Option<Symbol>,
+    }
+
+    let text = "(port-name p) (input-port-types a) (input-port-types b) (out c)";
+    let port = from_str::<Port>(text).unwrap();
+
+    assert_eq!(
+        port,
+        Port {
+            port_name: "p".into(),
+            input_port_types: vec!["a".into(), "b".into()],
+            output_port_type: Some("c".into()),
+        }
+    );
+    assert_eq!(to_values(&port), from_str::<Vec<Value>>(text).unwrap());
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(rename_all = "camelCase")]
+    struct Camel {
+        #[sexpr(required)]
+        max_arity: i64,
+    }
+
+    let text = "(maxArity 2)";
+    assert_eq!(
+        to_values(from_str::<Camel>(text).unwrap()),
+        from_str::<Vec<Value>>(text).unwrap()
+    );
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Screaming {
+        ConstInt(i64),
+        IOPort {
+            #[sexpr(required)]
+            port_index: i64,
+        },
+        #[sexpr(rename = "none")]
+        NoValue,
+    }
+
+    let text = "(CONST_INT 1) (IO_PORT (PORT_INDEX 2)) none";
+    let values = from_str::<Vec<Screaming>>(text).unwrap();
+
+    assert_eq!(
+        values,
+        vec![
+            Screaming::ConstInt(1),
+            Screaming::IOPort { port_index: 2 },
+            Screaming::NoValue
+        ]
+    );
+    assert_eq!(to_values(&values), from_str::<Vec<Value>>(text).unwrap());
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(rename_all = "snake_case")]
+    enum Snake {
+        FirstValue,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(rename_all = "lowercase")]
+    enum Lower {
+        FirstValue,
+    }
+
+    assert_eq!(from_str::<Snake>("first_value").unwrap(), Snake::FirstValue);
+    assert_eq!(from_str::<Lower>("firstvalue").unwrap(), Lower::FirstValue);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn generics() {
+    use std::marker::PhantomData;
+
+    /// A type that can neither be parsed nor printed.
+    #[derive(Debug, PartialEq)]
+    struct Opaque;
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Pair<T> {
+        left: T,
+        #[sexpr(required)]
+        right: T,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    enum Either<L, R> {
+        Left(L),
+        Right(R),
+    }
+
+    // `T` only occurs within `PhantomData`, so it requires no bounds.
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Tagged<T> {
+        value: i64,
+        #[sexpr(skip)]
+        marker: PhantomData<T>,
+    }
+
+    #[derive(Debug, PartialEq, ToParens)]
+    struct Borrowed<'a, T>
+    where
+        T: Clone,
+    {
+        name: &'a Symbol,
+        value: T,
+    }
+
+    trait Holder {
+        type Item;
+    }
+
+    impl Holder for Opaque {
+        type Item = i64;
+    }
+
+    // The inferred bound `T: FromParens` is wrong, since only `T::Item` is parsed.
+    #[derive(FromParens, ToParens)]
+    #[sexpr(bound(
+        from_parens = "T::Item: FromParens<__I>",
+        to_parens = "T::Item: ToParens<__O>"
+    ))]
+    struct Held<T: Holder> {
+        item: T::Item,
+    }
+
+    let pair = from_str::<Pair<i64>>("1 (right 2)").unwrap();
+    assert_eq!(pair, Pair { left: 1, right: 2 });
+    assert_eq!(
+        to_values(&pair),
+        parenthesis::from_str::<Vec<Value>>("1 (right 2)").unwrap()
+    );
+
+    let values = from_str::<Vec<Either<i64, Symbol>>>("(left 1) (right a)").unwrap();
+    assert_eq!(values, [Either::Left(1), Either::Right("a".into())]);
+
+    let tagged = from_str::<Tagged<Opaque>>("3").unwrap();
+    assert_eq!(tagged.value, 3);
+    assert_eq!(to_values(&tagged), [Value::Int(3)]);
+
+    let name = Symbol::new("x");
+    let borrowed = Borrowed {
+        name: &name,
+        value: 1,
+    };
+    assert_eq!(
+        to_values(&borrowed),
+        parenthesis::from_str::<Vec<Value>>("x 1").unwrap()
+    );
+
+    let held = from_str::<Held<Opaque>>("4").unwrap();
+    assert_eq!(held.item, 4);
+    assert_eq!(to_values(&held), [Value::Int(4)]);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn span_field() {
+    use parenthesis::read::Span;
+
+    #[derive(FromParens, ToParens, Debug)]
+    #[sexpr(header = "outer")]
+    struct Outer {
+        #[sexpr(span)]
+        span: Span,
+        inner: Inner,
+        #[sexpr(required)]
+        leaf: Leaf,
+    }
+
+    #[derive(FromParens, ToParens, Debug)]
+    #[sexpr(header = "inner")]
+    struct Inner {
+        name: Symbol,
+        #[sexpr(span)]
+        span: Span,
+    }
+
+    #[derive(FromParens, ToParens, Debug)]
+    struct Leaf {
+        name: Symbol,
+        #[sexpr(span)]
+        span: Span,
+    }
+
+    let text = "  (outer (inner a) (leaf b))";
+    let outer = parenthesis::from_str::<Outer>(text).unwrap();
+
+    let range = |part: &str| {
+        let start = text.find(part).unwrap();
+        start..start + part.len()
+    };
+
+    assert_eq!(outer.span, range("(outer (inner a) (leaf b))"));
+    assert_eq!(outer.inner.span, range("(inner a)"));
+    assert_eq!(outer.leaf.span, range("(leaf b)"));
+
+    // The span field is not printed.
+    assert_eq!(
+        to_values(&outer),
+        parenthesis::from_str::<Vec<Value>>("(outer (inner a) (leaf b))").unwrap()
+    );
+
+    // Values without source positions have empty spans.
+    let outer = from_str::<Outer>(text).unwrap();
+    assert_eq!(outer.span, Span::default());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn seq_field() {
+    #[derive(FromParens, ToParens, PartialEq, Eq, Debug)]
+    struct Test {
+        #[sexpr(seq)]
+        args: Vec<Symbol>,
+        #[sexpr(repeated, seq, rename = "inputs")]
+        input_vars: Vec<Symbol>,
+        #[sexpr(optional, seq)]
+        outputs: Option<Vec<Symbol>>,
+    }
+
+    let test = from_str::<Test>("[%a %b] (inputs [%c %d %e]) (outputs [])").unwrap();
+    assert_eq!(
+        test,
+        Test {
+            args: vec!["%a".into(), "%b".into()],
+            input_vars: vec!["%c".into(), "%d".into(), "%e".into()],
+            outputs: Some(Vec::new()),
+        }
+    );
+
+    // A repeated field in a sequence is a single form that may be absent.
+    let test = from_str::<Test>("[]").unwrap();
+    assert_eq!(
+        test,
+        Test {
+            args: Vec::new(),
+            input_vars: Vec::new(),
+            outputs: None,
+        }
+    );
+
+    for text in [
+        "[] (inputs [])",
+        "[%a] (inputs [%b])",
+        "[%a %b %c] (inputs [%d %e]) (outputs [%f %g])",
+    ] {
+        let values = parenthesis::from_str::<Vec<Value>>(text).unwrap();
+        let test = from_str::<Test>(text).unwrap();
+        assert_eq!(to_values(&test), values);
+    }
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>("(%a %b)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected sequence, found list, in field `args` of `Test`"
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>("[] (inputs %a %b)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected sequence, found symbol, in field `inputs` of `Test`"
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>("[] (inputs [%a]) (inputs [%b])") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "duplicate field `inputs`");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn seq_repeated_default() {
+    fn default_inputs() -> Vec<Symbol> {
+        vec!["%in".into()]
+    }
+
+    #[derive(FromParens, PartialEq, Eq, Debug)]
+    struct Test {
+        #[sexpr(repeated, seq, default = "default_inputs")]
+        inputs: Vec<Symbol>,
+    }
+
+    // An explicit default is used when the form is absent.
+    assert_eq!(from_str::<Test>("").unwrap().inputs, default_inputs());
+    assert_eq!(from_str::<Test>("(inputs [])").unwrap().inputs, Vec::new());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn map_field() {
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(FromParens, ToParens, PartialEq, Debug)]
+    struct Test {
+        #[sexpr(map)]
+        attrs: BTreeMap<Symbol, Value>,
+        #[sexpr(required, map, rename = "meta")]
+        metadata: BTreeMap<Symbol, Value>,
+        #[sexpr(optional, map)]
+        counts: Option<HashMap<String, i64>>,
+    }
+
+    let test = from_str::<Test>(r#"{a 1 b {c 2}} (meta {author "x" version 2}) (counts {"x" 1})"#)
+        .unwrap();
+    assert_eq!(
+        test,
+        Test {
+            attrs: BTreeMap::from([
+                ("a".into(), Value::from(1)),
+                (
+                    "b".into(),
+                    Value::Map(vec![Value::Symbol("c".into()), Value::from(2)])
+                ),
+            ]),
+            metadata: BTreeMap::from([
+                ("author".into(), Value::from("x")),
+                ("version".into(), Value::from(2)),
+            ]),
+            counts: Some(HashMap::from([("x".into(), 1)])),
+        }
+    );
+
+    for text in [
+        "{} (meta {})",
+        r#"{a {b {c 1}}} (meta {author "x" version 2}) (counts {"x" 1})"#,
+    ] {
+        let values = parenthesis::from_str::<Vec<Value>>(text).unwrap();
+        let test = from_str::<Test>(text).unwrap();
+        assert_eq!(to_values(&test), values);
+    }
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Test>("{a 1 b} (meta {})") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "missing value for key, in field `attrs` of `Test`"
+    );
+    assert_eq!(error.span(), Some(&(5..6)));
+
+    let Err(ReadError::Parse(error)) = parenthesis::from_str::<Test>("{a 1 a 2} (meta {})") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "duplicate key, in field `attrs` of `Test`"
+    );
+    assert_eq!(error.span(), Some(&(5..6)));
+
+    let Err(ReadError::Parse(error)) = from_str::<Test>("[a 1] (meta {})") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected map, found sequence, in field `attrs` of `Test`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_other_variant() {
+    #[derive(FromParens, ToParens, PartialEq, Debug)]
+    enum Op {
+        Add(i64, i64),
+        Nop,
+        #[sexpr(other)]
+        Unknown(Symbol, Vec<Value>),
+    }
+
+    let text = "(add 1 2) (future-op x [y] {z 1}) nop";
+    let ops = from_str::<Vec<Op>>(text).unwrap();
+    assert_eq!(
+        ops,
+        vec![
+            Op::Add(1, 2),
+            Op::Unknown(
+                "future-op".into(),
+                parenthesis::from_str::<Vec<Value>>("x [y] {z 1}").unwrap()
+            ),
+            Op::Nop,
+        ]
+    );
+    assert_eq!(
+        to_values(&ops),
+        parenthesis::from_str::<Vec<Value>>(text).unwrap()
+    );
+
+    // Errors within known variants are not hidden by the `other` variant.
+    let Err(ReadError::Parse(error)) = from_str::<Op>("(add 1 x)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected int, found symbol, in 2nd field of `Add`"
+    );
+
+    // Unknown symbols are still rejected.
+    let Err(ReadError::Parse(error)) = from_str::<Op>("other") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "unknown variant `other`, expected `nop`");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn from_into_conversion() {
+    #[derive(FromParens, ToParens)]
+    #[sexpr(tag = "range")]
+    struct RawRange {
+        start: i64,
+        end: i64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, FromParens, ToParens)]
+    #[sexpr(from = "RawRange", into = "RawRange")]
+    struct Range {
+        start: i64,
+        len: u64,
+    }
+
+    impl TryFrom<RawRange> for Range {
+        type Error = String;
+
+        fn try_from(raw: RawRange) -> Result<Self, Self::Error> {
+            match u64::try_from(raw.end - raw.start) {
+                Ok(len) => Ok(Self {
+                    start: raw.start,
+                    len,
+                }),
+                Err(_) => Err(format!("range {} to {} is empty", raw.start, raw.end)),
+            }
+        }
+    }
+
+    impl From<Range> for RawRange {
+        fn from(range: Range) -> Self {
+            Self {
+                start: range.start,
+                end: range.start + range.len as i64,
+            }
+        }
+    }
+
+    let ranges = from_str::<Vec<Range>>("(range 1 3) (range 2 2)").unwrap();
+    assert_eq!(
+        ranges,
+        vec![Range { start: 1, len: 2 }, Range { start: 2, len: 0 }]
+    );
+    assert_eq!(
+        to_values(&ranges),
+        parenthesis::from_str::<Vec<Value>>("(range 1 3) (range 2 2)").unwrap()
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Vec<Range>>("(range 1 3) (range 3 1)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(error.to_string(), "range 3 to 1 is empty");
+    assert!(matches!(error, ParseError::Custom { span, .. } if span == (12..23)));
+
+    // Errors of the raw type are passed on unchanged.
+    let Err(ReadError::Parse(error)) = from_str::<Range>("(range 1 x)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected int, found symbol, in field `end` of `RawRange`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn repeated_count_bounds() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "func")]
+    struct Func {
+        name: Symbol,
+        #[sexpr(repeated, min = 1, max = 4, rename = "param")]
+        params: Vec<Symbol>,
+    }
+
+    let text = "(func f (param a) (param b))";
+    let func = from_str::<Func>(text).unwrap();
+    assert_eq!(func.params, vec![Symbol::new("a"), Symbol::new("b")]);
+    assert_eq!(
+        to_values(&func),
+        parenthesis::from_str::<Vec<Value>>(text).unwrap()
+    );
+
+    let Err(ReadError::Parse(error)) = from_str::<Vec<Func>>("(func g (param a)) (func f)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected between 1 and 4 occurrences of field `param`, found 0"
+    );
+    assert_eq!(error.span(), Some(&(19..27)));
+
+    let text = "(func f (param a) (param b) (param c) (param d) (param e))";
+    let Err(ReadError::Parse(error)) = from_str::<Func>(text) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected between 1 and 4 occurrences of field `param`, found 5"
+    );
+    let start = text.find("(param e)").unwrap();
+    assert_eq!(error.span(), Some(&(start..start + 9)));
+
+    #[derive(Debug, FromParens)]
+    #[sexpr(tag = "func")]
+    #[allow(dead_code)]
+    struct Single {
+        #[sexpr(repeated, min = 1, max = 1, rename = "param")]
+        params: Vec<Symbol>,
+    }
+
+    let Err(ReadError::Parse(error)) = from_str::<Single>("(func)") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected exactly 1 occurrence of field `param`, found 0"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "expected at most 1 occurrence of field `param`, found 2")]
+pub fn repeated_count_bounds_print() {
+    #[derive(ToParens)]
+    struct Func {
+        #[sexpr(repeated, max = 1, rename = "param")]
+        params: Vec<Symbol>,
+    }
+
+    to_values(&Func {
+        params: vec![Symbol::new("a"), Symbol::new("b")],
+    });
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn error_context() {
+    #[derive(Debug, FromParens)]
+    #[allow(dead_code)]
+    struct Signature {
+        #[sexpr(variadic)]
+        inputs: Vec<Symbol>,
+        #[sexpr(required)]
+        output: Symbol,
+    }
+
+    #[derive(Debug, FromParens)]
+    #[allow(dead_code)]
+    struct Operation {
+        name: Symbol,
+        #[sexpr(required)]
+        signature: Signature,
+    }
+
+    #[derive(Debug, FromParens)]
+    #[allow(dead_code)]
+    struct Module {
+        #[sexpr(repeated, rename = "op")]
+        operations: Vec<Operation>,
+    }
+
+    let text = "(op add (signature a b (output c))) (op mul (signature a 1 (output c)))";
+    let Err(ReadError::Parse(error)) = from_str::<Module>(text) else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected symbol, found int, in 2nd value of field `inputs` of `Signature`, \
+         in field `signature` of `Operation`, in 2nd occurrence of field `op` of `Module`"
+    );
+    let start = text.find(" 1 ").unwrap() + 1;
+    assert!(matches!(
+        error.innermost(),
+        ParseError::UnexpectedToken { span, .. } if *span == (start..start + 1)
+    ));
+    assert_eq!(error.span(), Some(&(start..start + 1)));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn integer_fields() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct OpDef {
+        name: Symbol,
+        #[sexpr(required)]
+        arity: u8,
+        #[sexpr(required)]
+        size: usize,
+        #[sexpr(optional)]
+        offset: Option<i32>,
+    }
+
+    let text = "add (arity 2) (size 4096) (offset -12)";
+    let op_def = from_str::<OpDef>(text).unwrap();
+    assert_eq!(
+        op_def,
+        OpDef {
+            name: Symbol::new("add"),
+            arity: 2,
+            size: 4096,
+            offset: Some(-12),
+        }
+    );
+    assert_eq!(to_values(&op_def), from_str::<Vec<Value>>(text).unwrap());
+
+    // Values that do not fit are reported at the literal, together with the field.
+    for (text, literal, message) in [
+        (
+            "add (arity 300) (size 1)",
+            "300",
+            "integer `300` out of range for `u8`, in field `arity` of `OpDef`",
+        ),
+        (
+            "add (arity 1) (size -1)",
+            "-1",
+            "integer `-1` out of range for `usize`, in field `size` of `OpDef`",
+        ),
+        (
+            "add (arity 1) (size 1) (offset 4294967296)",
+            "4294967296",
+            "integer `4294967296` out of range for `i32`, in field `offset` of `OpDef`",
+        ),
+    ] {
+        let Err(ReadError::Parse(error)) = from_str::<OpDef>(text) else {
+            panic!("expected parse error");
+        };
+        assert_eq!(error.to_string(), message);
+
+        let start = text.find(literal).unwrap();
+        assert_eq!(error.span(), Some(&(start..start + literal.len())));
+    }
+
+    #[derive(Debug, PartialEq, FromParens)]
+    struct Counts(u8, i32);
+
+    let Err(ReadError::Parse(error)) = from_str::<Counts>("1 -2147483649") else {
+        panic!("expected parse error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "integer `-2147483649` out of range for `i32`, in 2nd field of `Counts`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn lifetime_parameters() {
+    use std::borrow::Cow;
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "op")]
+    struct Op<'a> {
+        name: Cow<'a, str>,
+        #[sexpr(variadic)]
+        args: Vec<Cow<'a, str>>,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    struct Block<'a, T> {
+        #[sexpr(repeated, tagged, rename = "op")]
+        ops: Vec<Op<'a>>,
+        #[sexpr(optional)]
+        meta: Option<T>,
+    }
+
+    let text = (0..100)
+        .map(|i| format!(r#"(op "op{}" "a" "b\n")"#, i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let block = from_str::<Block<i64>>(&text).unwrap();
+
+    assert_eq!(block.ops.len(), 100);
+    assert_eq!(block.ops[7].name, "op7");
+    assert_eq!(block.ops[7].args, ["a", "b\n"]);
+    assert_eq!(to_values(&block), from_str::<Vec<Value>>(&text).unwrap());
+
+    let op = Op {
+        name: Cow::Borrowed("add"),
+        args: vec![Cow::Borrowed("x")],
+    };
+    assert_eq!(from_str::<Op>(r#"(op "add" "x")"#).unwrap(), op);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn bool_fields() {
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "feature")]
+    struct Feature {
+        enabled: bool,
+        #[sexpr(optional)]
+        default: Option<bool>,
+    }
+
+    let text = "(feature #t (default #f))";
+    let feature = from_str::<Feature>(text).unwrap();
+    assert_eq!(
+        feature,
+        Feature {
+            enabled: true,
+            default: Some(false),
+        }
+    );
+    assert_eq!(to_values(&feature), from_str::<Vec<Value>>(text).unwrap());
+
+    let error = from_str::<Feature>("(feature yes)").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected bool, found symbol, in field `enabled` of `Feature`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn default_if_empty() {
+    #[derive(Debug, Default, PartialEq, FromParens, ToParens)]
+    struct Meta {
+        #[sexpr(required)]
+        version: i64,
+        #[sexpr(optional)]
+        author: Option<String>,
+    }
+
+    #[derive(Debug, Default, PartialEq, FromParens, ToParens)]
+    struct Options {
+        #[sexpr(optional)]
+        verbose: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "module")]
+    struct Module {
+        #[sexpr(optional, default_if_empty)]
+        meta: Option<Meta>,
+        #[sexpr(required, default_if_empty, default)]
+        limits: Vec<i64>,
+        #[sexpr(optional)]
+        options: Option<Options>,
+    }
+
+    // An absent form is `None` or the default of a required field.
+    let module = from_str::<Module>("(module)").unwrap();
+    assert_eq!(module.meta, None);
+    assert_eq!(module.limits, Vec::<i64>::new());
+    assert_eq!(module.options, None);
+
+    // An empty form is the default value, even though `version` is required.
+    let module = from_str::<Module>("(module (meta) (limits) (options))").unwrap();
+    assert_eq!(module.meta, Some(Meta::default()));
+    assert_eq!(module.options, Some(Options::default()));
+
+    let text = r#"(module (meta (version 2) (author "a")) (limits 1 2) (options (verbose #t)))"#;
+    let module = from_str::<Module>(text).unwrap();
+    assert_eq!(
+        module,
+        Module {
+            meta: Some(Meta {
+                version: 2,
+                author: Some("a".to_owned()),
+            }),
+            limits: vec![1, 2],
+            options: Some(Options {
+                verbose: Some(true)
+            }),
+        }
+    );
+    assert_eq!(to_values(&module), from_str::<Vec<Value>>(text).unwrap());
+
+    // A form with some values is parsed as usual.
+    let error = from_str::<Module>(r#"(module (meta (author "a")))"#).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "missing required field `version`, in field `meta` of `Module`"
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn unit_and_phantom_fields() {
+    use std::marker::PhantomData;
+
+    // `T` is only used as a marker, so it needs no bounds.
+    #[derive(Debug, PartialEq, FromParens, ToParens)]
+    #[sexpr(tag = "id")]
+    struct Id<T> {
+        value: i64,
+        marker: PhantomData<T>,
+        unit: (),
+        #[sexpr(optional)]
+        label: Option<String>,
+    }
+
+    struct NotParsable;
+
+    let text = r#"(id 3 (label "a"))"#;
+    let id = from_str::<Id<NotParsable>>(text).unwrap();
+    assert_eq!(id.value, 3);
+    assert_eq!(id.label.as_deref(), Some("a"));
+    assert_eq!(to_values(&id), from_str::<Vec<Value>>(text).unwrap());
+}
@@ -1,4 +1,4 @@
-use parenthesis::{from_str, to_values, ToParens, Value};
+use parenthesis::{from_str, to_values, FromParens, ToParens, Value};
 
 #[test]
 #[cfg(feature = "macros")]
@@ -106,3 +106,238 @@ pub fn repeated() {
         expected_sexpr.push_str(&format!(r#" (field "{}")"#, i));
     }
 }
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn default() {
+    #[derive(ToParens)]
+    pub struct Test {
+        #[sexpr(default)]
+        field: String,
+    }
+
+    let expected = from_str::<Vec<Value>>(r#"(field "a")"#).unwrap();
+    let exported = to_values(Test { field: "a".into() });
+
+    assert_eq!(expected, exported);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn skip_default() {
+    fn default_version() -> i64 {
+        1
+    }
+
+    #[derive(Debug, PartialEq, ToParens, FromParens)]
+    pub struct Test {
+        #[sexpr(default, skip_default)]
+        name: String,
+        #[sexpr(default = "default_version", skip_default)]
+        version: i64,
+        #[sexpr(default)]
+        count: i64,
+    }
+
+    let test = Test {
+        name: String::new(),
+        version: 1,
+        count: 0,
+    };
+    let expected = from_str::<Vec<Value>>("(count 0)").unwrap();
+    assert_eq!(expected, to_values(&test));
+    assert_eq!(from_str::<Test>("(count 0)").unwrap(), test);
+
+    let test = Test {
+        name: "a".into(),
+        version: 2,
+        count: 3,
+    };
+    let expected = from_str::<Vec<Value>>(r#"(name "a") (version 2) (count 3)"#).unwrap();
+    assert_eq!(expected, to_values(&test));
+    assert_eq!(
+        from_str::<Test>(r#"(name "a") (version 2) (count 3)"#).unwrap(),
+        test
+    );
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn skip_round_trip() {
+    fn default_cache() -> Vec<String> {
+        vec!["cached".into()]
+    }
+
+    #[derive(Debug, PartialEq, ToParens, FromParens)]
+    pub struct Test {
+        name: String,
+        #[sexpr(skip)]
+        computed: usize,
+        #[sexpr(skip, default = "default_cache")]
+        cache: Vec<String>,
+        #[sexpr(skip_printing, default)]
+        parsed_only: i64,
+        #[sexpr(skip_parsing, required)]
+        printed_only: i64,
+    }
+
+    #[derive(ToParens)]
+    pub struct Plain {
+        name: String,
+        #[sexpr(required)]
+        printed_only: i64,
+    }
+
+    let test = Test {
+        name: "a".into(),
+        computed: 3,
+        cache: Vec::new(),
+        parsed_only: 0,
+        printed_only: 2,
+    };
+    let plain = Plain {
+        name: "a".into(),
+        printed_only: 2,
+    };
+    assert_eq!(to_values(&test), to_values(&plain));
+
+    let parsed: Test = from_str(r#""a" (parsed_only 5)"#).unwrap();
+    assert_eq!(parsed.computed, 0);
+    assert_eq!(parsed.cache, default_cache());
+    assert_eq!(parsed.parsed_only, 5);
+    assert_eq!(parsed.printed_only, 0);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn skip() {
+    #[derive(ToParens)]
+    pub struct Test {
+        first: String,
+        #[allow(dead_code)]
+        #[sexpr(skip)]
+        cache: Vec<String>,
+        #[allow(dead_code)]
+        #[sexpr(skip_serializing)]
+        #[sexpr(required)]
+        computed: String,
+        #[sexpr(skip_deserializing)]
+        #[sexpr(required)]
+        field: String,
+    }
+
+    let expected = from_str::<Vec<Value>>(r#""a" (field "d")"#).unwrap();
+
+    let exported = to_values(Test {
+        first: "a".into(),
+        cache: vec!["b".into()],
+        computed: "c".into(),
+        field: "d".into(),
+    });
+
+    assert_eq!(expected, exported);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn flatten() {
+    #[derive(ToParens)]
+    pub struct Outer {
+        first: String,
+        #[sexpr(flatten)]
+        inner: Inner,
+        #[sexpr(required)]
+        last: String,
+    }
+
+    #[derive(ToParens)]
+    pub struct Inner {
+        #[sexpr(required)]
+        field: String,
+    }
+
+    let expected = from_str::<Vec<Value>>(r#""a" (field "b") (last "c")"#).unwrap();
+
+    let exported = to_values(Outer {
+        first: "a".into(),
+        inner: Inner { field: "b".into() },
+        last: "c".into(),
+    });
+
+    assert_eq!(expected, exported);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn transparent() {
+    #[derive(ToParens)]
+    #[sexpr(transparent)]
+    struct NodeId(String);
+
+    #[derive(ToParens)]
+    #[sexpr(transparent)]
+    struct Name {
+        name: String,
+    }
+
+    #[derive(ToParens)]
+    struct Node {
+        id: NodeId,
+        #[sexpr(required)]
+        name: Name,
+    }
+
+    let expected = from_str::<Vec<Value>>(r#""node-1" (name "first")"#).unwrap();
+
+    let exported = to_values(Node {
+        id: NodeId("node-1".into()),
+        name: Name {
+            name: "first".into(),
+        },
+    });
+
+    assert_eq!(expected, exported);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn enum_variants() {
+    #[derive(ToParens)]
+    pub enum Const {
+        SmallInt(i64),
+        IOPort {
+            #[sexpr(required)]
+            index: i64,
+        },
+        #[sexpr(rename = "unit")]
+        Unit,
+    }
+
+    let expected = from_str::<Vec<Value>>("(small-int 1) (io-port (index 2)) unit").unwrap();
+    let exported = to_values((Const::SmallInt(1), Const::IOPort { index: 2 }, Const::Unit));
+
+    assert_eq!(expected, exported);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn borrowed_fields() {
+    #[derive(ToParens)]
+    pub struct Op<'a> {
+        name: &'a str,
+        #[sexpr(variadic)]
+        args: Vec<&'a str>,
+        #[sexpr(optional)]
+        label: Option<Box<str>>,
+    }
+
+    let args = ["x".to_string(), "y".to_string()];
+    let op = Op {
+        name: "add",
+        args: args.iter().map(String::as_str).collect(),
+        label: Some("sum".into()),
+    };
+
+    let expected = from_str::<Vec<Value>>(r#""add" "x" "y" (label "sum")"#).unwrap();
+    assert_eq!(expected, to_values(&op));
+}
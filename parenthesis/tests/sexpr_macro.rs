@@ -0,0 +1,96 @@
+#[cfg(feature = "macros")]
+use parenthesis::{from_str, sexpr, sexpr_vec, Symbol, Value};
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn list() {
+    let value = sexpr!((foo 1 "bar"));
+    let expected = Value::List(vec![
+        Value::Symbol(Symbol::new("foo")),
+        Value::Int(1),
+        Value::String("bar".into()),
+    ]);
+    assert_eq!(value, expected);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn matches_reader() {
+    let value = sexpr!((@core/define input-ports |with space| [-1 +2.5 1.0e3]
+        ; comment
+        {a #t b #f} #+inf -sym "esc\"aped\n" 1.5e-3));
+    let expected: Value = from_str(
+        r#"(@core/define input-ports |with space| [-1 +2.5 1.0e3]
+        ; comment
+        {a #t b #f} #+inf -sym "esc\"aped\n" 1.5e-3)"#,
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn top_level_atoms() {
+    assert_eq!(sexpr!(foo), Value::Symbol(Symbol::new("foo")));
+    assert_eq!(
+        sexpr!(input - ports),
+        Value::Symbol(Symbol::new("input-ports"))
+    );
+    assert_eq!(sexpr!(-1), Value::Int(-1));
+    assert_eq!(sexpr!(#t), Value::Bool(true));
+    assert_eq!(sexpr!("bar"), Value::String("bar".into()));
+    assert_eq!(sexpr!(#nan), Value::from(f64::NAN));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn multiple() {
+    let values = sexpr_vec!((a) [b] c 2);
+    assert_eq!(values, from_str::<Vec<Value>>("(a) [b] c 2").unwrap());
+    assert!(sexpr_vec!().is_empty());
+}
+
+#[cfg(feature = "macros")]
+fn describe(value: &Value) -> String {
+    parenthesis::match_sexpr!(value,
+        (add $x:int $y:int) => format!("add {}", x + y),
+        (define $name:symbol [$params:seq] ..) => format!("define {} {}", name, params.len()),
+        (input-ports ($port:string ..) |the end|) => format!("port {}", port),
+        (nested (deep #t $f:float)) => format!("float {}", f),
+        ($head:symbol ..) => { format!("call {}", head) }
+        $other => format!("other {}", other),
+    )
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn match_patterns() {
+    assert_eq!(describe(&sexpr!((add 1 2))), "add 3");
+    assert_eq!(describe(&sexpr!((add 1 x))), "call add");
+    assert_eq!(describe(&sexpr!((define f [[a b]] 1 2))), "define f 2");
+    assert_eq!(describe(&sexpr!((define f [a b]))), "call define");
+    assert_eq!(describe(&sexpr!((input-ports ("a" 1) |the end|))), "port a");
+    assert_eq!(describe(&sexpr!((nested (deep #t 1.5)))), "float 1.5");
+    assert_eq!(describe(&sexpr!((nested (deep #f 1.5)))), "call nested");
+    assert_eq!(describe(&sexpr!(42)), "other 42");
+}
+
+#[test]
+#[cfg(feature = "macros")]
+pub fn match_control_flow() {
+    fn first_int(values: Vec<Value>) -> Option<i64> {
+        for value in values {
+            parenthesis::match_sexpr!(value,
+                (int $x:int) => return Some(x),
+                (stop) => break,
+                _ => continue,
+            );
+        }
+
+        None
+    }
+
+    assert_eq!(first_int(sexpr_vec!((a) (int 3) (int 4))), Some(3));
+    assert_eq!(first_int(sexpr_vec!((a) (stop) (int 4))), None);
+    assert_eq!(parenthesis::match_sexpr!(sexpr!(x), x => 1, _ => 2), 1);
+}
@@ -0,0 +1,15 @@
+use parenthesis::{FromParens, Rest};
+
+#[derive(FromParens)]
+struct Optional {
+    #[sexpr(optional)]
+    rest: Rest,
+}
+
+#[derive(FromParens)]
+struct Seq {
+    #[sexpr(seq)]
+    rest: Rest,
+}
+
+fn main() {}